@@ -0,0 +1,40 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Measures how long it takes to parse the messages received from a stream.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use egg_mode::stream::StreamMessage;
+
+fn load(path: &str) -> String {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join(path);
+    std::fs::read_to_string(path).unwrap()
+}
+
+fn parse_messages(c: &mut Criterion) {
+    let messages = [
+        ("tweet", load("sample_payloads/sample-stream.json")),
+        ("tweet_v2", load("sample_payloads/v2-sample-stream.json")),
+        (
+            "delete",
+            r#"{"delete":{"status":{"id":1234,"id_str":"1234","user_id":3,"user_id_str":"3"}}}"#
+                .to_string(),
+        ),
+        (
+            "unknown",
+            r#"{"warning":{"code":"FALLING_BEHIND","percent_full":60}}"#.to_string(),
+        ),
+    ];
+
+    let mut group = c.benchmark_group("stream_message");
+    for (name, message) in &messages {
+        group.bench_function(*name, |b| {
+            b.iter(|| black_box(message).parse::<StreamMessage>().unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parse_messages);
+criterion_main!(benches);
@@ -15,12 +15,10 @@ use egg_mode::user;
 async fn main() {
     let config = common::Config::load().await;
 
-    println!("");
+    println!();
     println!("Heterogeneous multi-user lookup:");
 
-    let mut users: Vec<egg_mode::user::UserID> = vec![];
-    users.push(config.user_id.into());
-    users.push("SwiftOnSecurity".into());
+    let users: Vec<egg_mode::user::UserID> = vec![config.user_id.into(), "SwiftOnSecurity".into()];
 
     for user in user::lookup(users, &config.token)
         .await
@@ -31,7 +29,7 @@ async fn main() {
         print_user(user)
     }
 
-    println!("");
+    println!();
     println!("Searching based on a term: (here, it's 'rustlang')");
     user::search("rustlang", &config.token)
         .with_page_size(5)
@@ -43,7 +41,7 @@ async fn main() {
         })
         .await;
 
-    println!("");
+    println!();
     println!("Who do you follow?");
     user::friends_of(config.user_id, &config.token)
         .with_page_size(5)
@@ -55,7 +53,7 @@ async fn main() {
         })
         .await;
 
-    println!("");
+    println!();
     println!("Who follows you?");
     user::followers_of(config.user_id, &config.token)
         .with_page_size(5)
@@ -69,7 +67,7 @@ async fn main() {
 }
 
 fn print_user(user: &user::TwitterUser) {
-    println!("");
+    println!();
     println!("{} (@{})", user.name, user.screen_name);
     println!("Created at {}", user.created_at);
     println!(
@@ -82,9 +80,9 @@ fn print_user(user: &user::TwitterUser) {
         println!("(no description provided)");
     }
     match (&user.location, &user.url) {
-        (&Some(ref loc), &Some(ref link)) => println!("{} | {}", loc, link),
-        (&None, &Some(ref link)) => println!("{}", link),
-        (&Some(ref loc), &None) => println!("{}", loc),
+        (Some(loc), Some(link)) => println!("{} | {}", loc, link),
+        (&None, Some(link)) => println!("{}", link),
+        (Some(loc), &None) => println!("{}", loc),
         (&None, &None) => (),
     }
 }
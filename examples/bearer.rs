@@ -22,7 +22,7 @@ async fn main() -> Result<()> {
 
     let (_timeline, feed) = timeline.start().await?;
     for tweet in feed.response {
-        println!("");
+        println!();
         common::print_tweet(&tweet);
     }
     Ok(())
@@ -16,7 +16,7 @@ use std::collections::HashSet;
 async fn main() -> Result<()> {
     let config = common::Config::load().await;
 
-    println!("");
+    println!();
     let friends: HashSet<u64> = user::friends_ids(config.user_id, &config.token)
         .map_ok(|r| r.response)
         .try_collect()
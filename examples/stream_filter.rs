@@ -14,9 +14,10 @@ async fn main() {
     println!("Ctrl-C to quit\n");
 
     let stream = egg_mode::stream::filter()
-        .track(&["rustlang", "python", "java", "javascript"])
-        .language(&["en"])
+        .track(["rustlang", "python", "java", "javascript"])
+        .language(["en"])
         .start(&config.token)
+        .expect("invalid stream parameters")
         .try_for_each(|m| {
             if let StreamMessage::Tweet(tweet) = m {
                 common::print_tweet(&tweet);
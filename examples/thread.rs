@@ -79,11 +79,11 @@ async fn main() -> Result<()> {
     }
 
     for tweet in &thread {
-        println!("");
+        println!();
         if tweet.id == start_id {
             println!("-- this is our starting tweet");
         }
-        common::print_tweet(&tweet);
+        common::print_tweet(tweet);
     }
     Ok(())
 }
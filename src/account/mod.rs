@@ -1,13 +1,23 @@
-//! Functionality to alter a user's public profile.
+//! Functionality to alter a user's public profile and account settings.
 //!
-//! Specifically, this module contains functions which update the information
-//! that is publically visible on a user's timeline (e.g. name, location). This module does *not*
-//! modify a user's private account settings (e.g. email, password).
+//! Most of this module contains functions which update the information that is publically visible
+//! on a user's timeline (e.g. name, location). The [`settings`] function and the [`update_settings`]
+//! builder also allow you to load and change some of the user's account settings, like their
+//! language, time zone, or who can send them Direct Messages. This module does *not* modify a
+//! user's login details (e.g. email, password).
+//!
+//! [`settings`]: fn.settings.html
+//! [`update_settings`]: fn.update_settings.html
+
+use serde::Deserialize;
 
 use crate::{
     auth,
-    common::{post, request_with_empty_response, request_with_json_response, ParamList},
+    common::{
+        get, post, raw_request, request_with_empty_response, request_with_json_response, ParamList,
+    },
     error, links,
+    trend::TrendLocation,
     user::TwitterUser,
     Response,
 };
@@ -57,9 +67,12 @@ pub async fn update_profile_image(
     image: &[u8],
     token: &auth::Token,
 ) -> error::Result<Response<TwitterUser>> {
+    request_with_json_response(profile_image_request(image, token)).await
+}
+
+fn profile_image_request(image: &[u8], token: &auth::Token) -> hyper::Request<hyper::Body> {
     let params = ParamList::new().add_param("image", base64::encode(image));
-    let req = post(links::account::UPDATE_PROFILE_IMAGE, token, Some(&params));
-    request_with_json_response(req).await
+    post(links::account::UPDATE_PROFILE_IMAGE, token, Some(&params))
 }
 
 /// Uploads a profile banner on behalf of the authenticating user.
@@ -89,6 +102,36 @@ pub async fn update_profile_banner(
     request_with_empty_response(req).await
 }
 
+/// Resets the authenticating user's profile image to Twitter's default image.
+///
+/// Twitter has no way to remove a profile image, so this downloads the default image Twitter
+/// gives to new accounts and uploads it with [`update_profile_image`]. As with that function, the
+/// new image is processed asynchronously, so the returned user may still show the old image.
+///
+/// [`update_profile_image`]: fn.update_profile_image.html
+pub async fn reset_profile_image(token: &auth::Token) -> error::Result<Response<TwitterUser>> {
+    let (_, image) = raw_request(default_image_request()).await?;
+
+    update_profile_image(&image, token).await
+}
+
+///The default image isn't served by the API, so it's loaded without signing the request.
+fn default_image_request() -> hyper::Request<hyper::Body> {
+    hyper::Request::get(links::account::DEFAULT_PROFILE_IMAGE)
+        .body(hyper::Body::empty())
+        .unwrap()
+}
+
+/// Removes the authenticating user's profile banner.
+///
+/// The banner is removed right away. Calling this when the user has no banner succeeds without
+/// changing anything.
+pub async fn remove_profile_banner(token: &auth::Token) -> error::Result<Response<()>> {
+    let req = post(links::account::REMOVE_PROFILE_BANNER, token, None);
+
+    request_with_empty_response(req).await
+}
+
 /// Sets some values that users are able to set under the "Account" tab of their settings page.
 /// Only the parameters specified will be updated.
 pub async fn update_profile(
@@ -106,3 +149,266 @@ pub async fn update_profile(
 
     request_with_json_response(req).await
 }
+
+/// The account settings of the authenticated user, as returned by [`settings`] and
+/// [`SettingsUpdate::send`].
+///
+/// [`settings`]: fn.settings.html
+/// [`SettingsUpdate::send`]: struct.SettingsUpdate.html#method.send
+#[derive(Debug, Clone, Deserialize)]
+pub struct Settings {
+    /// The screen name of the authenticated user.
+    pub screen_name: String,
+    /// Whether the user's tweets are protected.
+    #[serde(default)]
+    pub protected: bool,
+    /// Whether the user has enabled attaching locations to their tweets.
+    #[serde(default)]
+    pub geo_enabled: bool,
+    /// The language the user has selected for the Twitter interface, as a [BCP 47][] language tag.
+    ///
+    /// [BCP 47]: https://tools.ietf.org/html/bcp47
+    pub language: String,
+    /// Whether other users can find this account by its email address.
+    #[serde(default)]
+    pub discoverable_by_email: bool,
+    /// Whether other users can find this account by its phone number.
+    #[serde(default)]
+    pub discoverable_by_mobile_phone: bool,
+    /// Whether the user has chosen to see media that may be sensitive without a warning.
+    #[serde(default)]
+    pub display_sensitive_media: bool,
+    /// Who is allowed to send Direct Messages to this user, if Twitter returned it.
+    #[serde(default)]
+    pub allow_dms_from: Option<AllowDmsFrom>,
+    /// The hours during which Twitter will not send notifications to the user's phone.
+    #[serde(default)]
+    pub sleep_time: SleepTime,
+    /// The time zone the user has selected, if any.
+    #[serde(default)]
+    pub time_zone: Option<TimeZone>,
+    /// The location the user has selected to load trending topics from.
+    #[serde(default)]
+    pub trend_location: Vec<TrendLocation>,
+}
+
+/// Represents the "sleep time" setting, when Twitter will not send notifications to the user's
+/// phone.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SleepTime {
+    /// Whether sleep time is enabled.
+    pub enabled: bool,
+    /// The hour of the day (from 0 to 23) when sleep time begins, if enabled.
+    pub start_time: Option<u32>,
+    /// The hour of the day (from 0 to 23) when sleep time ends, if enabled.
+    pub end_time: Option<u32>,
+}
+
+/// Represents the time zone a user has selected in their settings.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TimeZone {
+    /// The display name of the time zone, e.g. "Pacific Time (US & Canada)".
+    pub name: String,
+    /// The name of the time zone in the tz database, e.g. "America/Los_Angeles".
+    pub tzinfo_name: String,
+    /// The offset from UTC of the time zone, in seconds.
+    pub utc_offset: i32,
+}
+
+/// Represents who is allowed to send Direct Messages to a user.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AllowDmsFrom {
+    /// Anyone can send the user a Direct Message.
+    All,
+    /// Only accounts the user follows can send them a Direct Message.
+    Following,
+    /// Only verified accounts and accounts the user follows can send them a Direct Message.
+    Verified,
+}
+
+impl AllowDmsFrom {
+    fn as_str(self) -> &'static str {
+        match self {
+            AllowDmsFrom::All => "all",
+            AllowDmsFrom::Following => "following",
+            AllowDmsFrom::Verified => "verified",
+        }
+    }
+}
+
+/// Loads the account settings of the authenticated user.
+pub async fn settings(token: &auth::Token) -> error::Result<Response<Settings>> {
+    let req = get(links::account::SETTINGS, token, None);
+    request_with_json_response(req).await
+}
+
+/// Begins building a request to update the account settings of the authenticated user.
+///
+/// Only the settings that are set on the returned builder will be changed.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # use egg_mode::Token;
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let token: Token = unimplemented!();
+/// use egg_mode::account::{self, AllowDmsFrom};
+///
+/// let settings = account::update_settings()
+///     .time_zone("Pacific Time (US & Canada)")
+///     .allow_dms_from(AllowDmsFrom::Following)
+///     .send(&token)
+///     .await
+///     .unwrap();
+/// # }
+/// ```
+pub fn update_settings() -> SettingsUpdate {
+    SettingsUpdate::default()
+}
+
+/// Represents a pending update to the authenticated user's account settings.
+///
+/// This is returned by [`update_settings`]; see that function's documentation for details.
+///
+/// [`update_settings`]: fn.update_settings.html
+#[derive(Debug, Clone, Default)]
+#[must_use = "SettingsUpdate is a builder; call `send` to update the settings"]
+pub struct SettingsUpdate {
+    trend_location_woeid: Option<u32>,
+    sleep_time: Option<Option<(u32, u32)>>,
+    time_zone: Option<String>,
+    lang: Option<String>,
+    allow_dms_from: Option<AllowDmsFrom>,
+}
+
+impl SettingsUpdate {
+    /// Sets the location to load trending topics from, by its "where on earth identifier".
+    ///
+    /// The locations Twitter has trends for can be loaded with `trend::available`.
+    pub fn trend_location(self, woeid: u32) -> Self {
+        SettingsUpdate {
+            trend_location_woeid: Some(woeid),
+            ..self
+        }
+    }
+
+    /// Enables sleep time, during which Twitter will not send notifications to the user's phone.
+    ///
+    /// `start` and `end` are hours of the day, from 0 to 23, in the user's time zone.
+    pub fn sleep_time(self, start: u32, end: u32) -> Self {
+        SettingsUpdate {
+            sleep_time: Some(Some((start, end))),
+            ..self
+        }
+    }
+
+    /// Disables sleep time.
+    pub fn disable_sleep_time(self) -> Self {
+        SettingsUpdate {
+            sleep_time: Some(None),
+            ..self
+        }
+    }
+
+    /// Sets the user's time zone. This should be one of the [Rails time zone names][rails], e.g.
+    /// "Pacific Time (US & Canada)".
+    ///
+    /// [rails]: https://api.rubyonrails.org/classes/ActiveSupport/TimeZone.html
+    pub fn time_zone(self, time_zone: impl Into<String>) -> Self {
+        SettingsUpdate {
+            time_zone: Some(time_zone.into()),
+            ..self
+        }
+    }
+
+    /// Sets the language used in the Twitter interface, as a [BCP 47][] language tag.
+    ///
+    /// [BCP 47]: https://tools.ietf.org/html/bcp47
+    pub fn lang(self, lang: impl Into<String>) -> Self {
+        SettingsUpdate {
+            lang: Some(lang.into()),
+            ..self
+        }
+    }
+
+    /// Sets who is allowed to send Direct Messages to the user.
+    pub fn allow_dms_from(self, allow_dms_from: AllowDmsFrom) -> Self {
+        SettingsUpdate {
+            allow_dms_from: Some(allow_dms_from),
+            ..self
+        }
+    }
+
+    /// Sends the update to Twitter, returning the user's new settings.
+    pub async fn send(self, token: &auth::Token) -> error::Result<Response<Settings>> {
+        let mut params = ParamList::new()
+            .add_opt_param(
+                "trend_location_woeid",
+                self.trend_location_woeid.map(|id| id.to_string()),
+            )
+            .add_opt_param("time_zone", self.time_zone)
+            .add_opt_param("lang", self.lang)
+            .add_opt_param("allow_dms_from", self.allow_dms_from.map(|a| a.as_str()));
+
+        match self.sleep_time {
+            Some(Some((start, end))) => {
+                params = params
+                    .add_param("sleep_time_enabled", "true")
+                    .add_param("start_sleep_time", format!("{:02}", start))
+                    .add_param("end_sleep_time", format!("{:02}", end));
+            }
+            Some(None) => {
+                params = params.add_param("sleep_time_enabled", "false");
+            }
+            None => (),
+        }
+
+        let req = post(links::account::SETTINGS, token, Some(&params));
+        request_with_json_response(req).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{default_image_request, profile_image_request, AllowDmsFrom, Settings};
+    use crate::common::tests::load_file;
+    use crate::{auth::Token, links};
+
+    use futures::executor::block_on;
+
+    #[test]
+    fn parse_settings() {
+        let content = load_file("sample_payloads/account-settings.json");
+        let settings = ::serde_json::from_str::<Settings>(&content).unwrap();
+
+        assert_eq!(settings.screen_name, "theSeanCook");
+        assert_eq!(settings.language, "en");
+        assert_eq!(settings.allow_dms_from, Some(AllowDmsFrom::Following));
+        assert!(!settings.sleep_time.enabled);
+        assert_eq!(settings.sleep_time.start_time, None);
+        assert_eq!(
+            settings.time_zone.unwrap().tzinfo_name,
+            "America/Los_Angeles"
+        );
+        assert_eq!(settings.trend_location[0].woeid, 2357024);
+    }
+
+    #[test]
+    fn profile_image_requests() {
+        let token = Token::Bearer("token".to_string());
+
+        let req = default_image_request();
+        assert_eq!(req.method(), hyper::Method::GET);
+        assert_eq!(req.uri(), links::account::DEFAULT_PROFILE_IMAGE);
+        assert!(req.headers().get(hyper::header::AUTHORIZATION).is_none());
+
+        let req = profile_image_request(b"\x89PNG", &token);
+        assert_eq!(req.method(), hyper::Method::POST);
+        assert_eq!(req.uri(), links::account::UPDATE_PROFILE_IMAGE);
+        assert!(req.headers().get(hyper::header::AUTHORIZATION).is_some());
+        let body = block_on(hyper::body::to_bytes(req.into_body())).unwrap();
+        assert_eq!(&body[..], b"image=iVBORw%3D%3D");
+    }
+}
@@ -156,6 +156,33 @@
 //! # }
 //! ```
 //!
+//! ### Protecting the callback
+//!
+//! A web app that receives the verifier through a callback URL should make sure the callback
+//! belongs to a sign-in the user started, so another site can't sign the user in to someone else's
+//! account by sending them to the callback. Create a random value with [`new_state`], save it in
+//! the user's session, and add it to the callback URL with [`callback_with_state`] before calling
+//! `request_token`. When the user comes back, load the callback's parameters into a [`Callback`]
+//! and call `verify`, which checks the state and request token and returns the verifier.
+//!
+//! [`new_state`]: fn.new_state.html
+//! [`callback_with_state`]: fn.callback_with_state.html
+//! [`Callback`]: struct.Callback.html
+//!
+//! ```rust,no_run
+//! # #[tokio::main]
+//! # async fn main() {
+//! # let con_token = egg_mode::KeyPair::new("consumer key", "consumer secret");
+//! # let request_token = egg_mode::KeyPair::new("request key", "request secret");
+//! # let session_state = String::new();
+//! # let redirected_to = "";
+//! let callback = egg_mode::auth::Callback::from_url(redirected_to).unwrap();
+//! let verifier = callback.verify(&request_token, &session_state).unwrap();
+//! let (token, user_id, screen_name) =
+//!     egg_mode::auth::access_token(con_token, &request_token, verifier).await.unwrap();
+//! # }
+//! ```
+//!
 //! **WARNING**: The consumer token and preset access token mentioned below are as privileged as
 //! passwords! If your consumer key pair leaks or is visible to the public, anyone can impersonate
 //! your app! If you use a fixed token for your app, it's recommended to set them in separate files
@@ -183,9 +210,28 @@
 //!
 //! For more information on the individual steps of the authentication process, see the
 //! documentation for the functions in this module.
+//!
+//! ## Debugging signatures
+//!
+//! If Twitter rejects your requests with 401 errors even though your keys are correct, the
+//! `debug` submodule (available with the `auth-debug` feature) can show you the signature base
+//! string egg-mode created for each request, to compare against Twitter's OAuth tool.
+//!
+//! ## Delegating to other services
+//!
+//! Services that act on behalf of Twitter users, like image hosts, can verify who a request came
+//! from with [OAuth Echo][echo], without the user signing in to them separately. [`OAuthEcho`]
+//! creates the headers to send to such a service, and [`verify_echo`] checks them on the
+//! service's end.
+//!
+//! [echo]: https://developer.twitter.com/en/docs/authentication/oauth-echo
+//! [`OAuthEcho`]: struct.OAuthEcho.html
+//! [`verify_echo`]: fn.verify_echo.html
 
 use std::borrow::Cow;
+use std::fmt;
 
+use hyper::header::{HeaderMap, HeaderValue, AUTHORIZATION};
 use hyper::Method;
 use serde::{Deserialize, Serialize};
 use serde_json;
@@ -196,8 +242,12 @@ use crate::{
     links,
 };
 
+mod callback;
+#[cfg(feature = "auth-debug")]
+pub mod debug;
 pub(crate) mod raw;
 
+pub use self::callback::{callback_with_state, new_state, Callback, CallbackError};
 use raw::RequestBuilder;
 
 /// A key/secret pair representing the app that is sending a request or an authorization from a user.
@@ -219,7 +269,14 @@ use raw::RequestBuilder;
 /// ```rust
 /// let con_token = egg_mode::KeyPair::new("consumer key", "consumer token");
 /// ```
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// The `Debug` output of a `KeyPair` leaves out its `secret`, so it can be logged without leaking
+/// the secret. With the `zeroize_secrets` feature on, `KeyPair` also implements
+/// `zeroize::Zeroize`, so its strings can be overwritten with zeroes once they're no longer
+/// needed, for example by holding it in a `zeroize::Zeroizing`. Keep in mind that this only covers
+/// the `KeyPair` itself: clones of it, or copies of its fields made elsewhere, are not cleared with
+/// it.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct KeyPair {
     ///A key used to identify an application or user.
     pub key: Cow<'static, str>,
@@ -252,6 +309,55 @@ impl KeyPair {
     }
 }
 
+impl fmt::Debug for KeyPair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("KeyPair")
+            .field("key", &self.key)
+            .field("secret", &REDACTED)
+            .finish()
+    }
+}
+
+#[cfg(feature = "zeroize_secrets")]
+impl zeroize::Zeroize for KeyPair {
+    fn zeroize(&mut self) {
+        zeroize_cow(&mut self.key);
+        zeroize_cow(&mut self.secret);
+    }
+}
+
+/// Placeholder printed in place of secrets in `Debug` output.
+struct Redacted;
+
+const REDACTED: Redacted = Redacted;
+
+impl fmt::Debug for Redacted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+/// Returns the first few characters of the given key, for identifying it in logs.
+fn key_prefix(key: &str) -> String {
+    let prefix = key.chars().take(4).collect::<String>();
+    if prefix.len() < key.len() {
+        format!("{}...", prefix)
+    } else {
+        prefix
+    }
+}
+
+/// Overwrites the given string with zeroes, if it's owned, and leaves it empty.
+///
+/// Borrowed strings are `'static`, and can't be cleared.
+#[cfg(feature = "zeroize_secrets")]
+fn zeroize_cow(s: &mut Cow<'static, str>) {
+    if let Cow::Owned(s) = s {
+        zeroize::Zeroize::zeroize(s);
+    }
+    *s = Cow::Borrowed("");
+}
+
 /// A token that can be used to sign requests to Twitter.
 ///
 /// Conceptually, a Token represents your authorization to call the Twitter API. It can either be a
@@ -273,7 +379,12 @@ impl KeyPair {
 ///
 /// [apps]: https://developer.twitter.com/en/apps
 /// [invalidate]: fn.invalidate_bearer.html
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// To keep tokens out of logs, the `Debug` output of a `Token` leaves out the Bearer token and the
+/// secrets of its key pairs. If you need to tell tokens apart in logs, `redacted_debug` goes
+/// further and only prints the start of each key. With the `zeroize_secrets` feature on, `Token`
+/// implements `zeroize::Zeroize` the same way as `KeyPair`.
+#[derive(Clone, Serialize, Deserialize)]
 pub enum Token {
     /// An OAuth Access token indicating the request is coming from a specific user.
     Access {
@@ -287,6 +398,66 @@ pub enum Token {
     Bearer(String),
 }
 
+impl Token {
+    /// Returns a description of this token that is safe to write to logs.
+    ///
+    /// Unlike the `Debug` output, which prints the keys of an Access token in full, this only
+    /// prints the first few characters of each key, and leaves out the secrets and Bearer token
+    /// entirely.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use egg_mode::{KeyPair, Token};
+    ///
+    /// let token = Token::Access {
+    ///     consumer: KeyPair::new("consumer key", "consumer secret"),
+    ///     access: KeyPair::new("12345-access key", "access secret"),
+    /// };
+    /// assert!(!format!("{:?}", token).contains("access secret"));
+    /// assert_eq!(
+    ///     token.redacted_debug(),
+    ///     "Token::Access { consumer: \"cons...\", access: \"1234...\" }"
+    /// );
+    /// ```
+    pub fn redacted_debug(&self) -> String {
+        match self {
+            Token::Access { consumer, access } => format!(
+                "Token::Access {{ consumer: {:?}, access: {:?} }}",
+                key_prefix(&consumer.key),
+                key_prefix(&access.key)
+            ),
+            Token::Bearer(_) => "Token::Bearer(<redacted>)".to_string(),
+        }
+    }
+}
+
+impl fmt::Debug for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Access { consumer, access } => f
+                .debug_struct("Access")
+                .field("consumer", consumer)
+                .field("access", access)
+                .finish(),
+            Token::Bearer(_) => f.debug_tuple("Bearer").field(&REDACTED).finish(),
+        }
+    }
+}
+
+#[cfg(feature = "zeroize_secrets")]
+impl zeroize::Zeroize for Token {
+    fn zeroize(&mut self) {
+        match self {
+            Token::Access { consumer, access } => {
+                consumer.zeroize();
+                access.zeroize();
+            }
+            Token::Bearer(token) => token.zeroize(),
+        }
+    }
+}
+
 /// With the given consumer KeyPair, ask Twitter for a request KeyPair that can be used to request
 /// access to the user's account.
 ///
@@ -642,3 +813,221 @@ pub async fn verify_tokens(token: &Token) -> Result<Response<crate::user::Twitte
     let req = get(links::auth::VERIFY_CREDENTIALS, token, None);
     request_with_json_response(req).await
 }
+
+/// The headers that let a third-party service verify a user's identity with Twitter, using
+/// [OAuth Echo][echo].
+///
+/// [echo]: https://developer.twitter.com/en/docs/authentication/oauth-echo
+///
+/// Some services, like image hosts, accept uploads on behalf of Twitter users without asking them
+/// to sign in separately. Instead, the app uploading the image signs a call to Twitter's
+/// `account/verify_credentials` endpoint, and sends it to the service in two headers. The service
+/// then makes that call itself, and if it succeeds, knows which user the upload came from.
+///
+/// An app delegating to such a service creates an `OAuthEcho` from the user's `Token` with `new`,
+/// and adds its headers to the request for the service with `add_headers`. A service receiving
+/// the request loads the headers with `from_headers`, and checks them with `verify_echo`.
+///
+/// ```rust,no_run
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let token: egg_mode::Token = unimplemented!();
+/// use egg_mode::auth::OAuthEcho;
+///
+/// let mut request = hyper::Request::post("https://images.example.com/upload")
+///     .body(hyper::Body::from("image data"))
+///     .unwrap();
+/// OAuthEcho::new(&token).add_headers(request.headers_mut());
+///
+/// // on the receiving end:
+/// let echo = OAuthEcho::from_headers(request.headers()).unwrap();
+/// let user = egg_mode::auth::verify_echo(&echo).await.unwrap();
+/// println!("upload from @{}", user.screen_name);
+/// # }
+/// ```
+///
+/// The signed credentials are only valid for a short time after they're created, so create a new
+/// `OAuthEcho` for each request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OAuthEcho {
+    service_provider: String,
+    credentials: String,
+}
+
+impl OAuthEcho {
+    /// The header containing the URL the service should call to verify the credentials.
+    pub const SERVICE_PROVIDER_HEADER: &'static str = "x-auth-service-provider";
+    /// The header containing the signed `Authorization` header for the verification call.
+    pub const CREDENTIALS_HEADER: &'static str = "x-verify-credentials-authorization";
+
+    /// Signs a call to `account/verify_credentials` with the given token, for a third-party
+    /// service to verify.
+    ///
+    /// # Panics
+    ///
+    /// OAuth Echo requires the signature of a specific user, so if this function is handed a
+    /// `Token` that is not an Access token, it will panic.
+    pub fn new(token: &Token) -> OAuthEcho {
+        if let Token::Bearer(_) = token {
+            panic!("non-access token passed to OAuthEcho::new");
+        }
+
+        let req = get(links::auth::VERIFY_CREDENTIALS, token, None);
+        let credentials = req.headers()[AUTHORIZATION]
+            .to_str()
+            .expect("OAuth headers are ASCII")
+            .to_string();
+
+        OAuthEcho {
+            service_provider: links::auth::VERIFY_CREDENTIALS.to_string(),
+            credentials,
+        }
+    }
+
+    /// Loads the OAuth Echo headers sent by an app delegating to this service, if both are
+    /// present.
+    pub fn from_headers(headers: &HeaderMap) -> Option<OAuthEcho> {
+        let header = |name| headers.get(name)?.to_str().ok().map(String::from);
+        Some(OAuthEcho {
+            service_provider: header(Self::SERVICE_PROVIDER_HEADER)?,
+            credentials: header(Self::CREDENTIALS_HEADER)?,
+        })
+    }
+
+    /// Adds the OAuth Echo headers to the given set of request headers, replacing any that are
+    /// already there.
+    pub fn add_headers(&self, headers: &mut HeaderMap) {
+        let value = |s: &str| HeaderValue::from_str(s).expect("OAuth Echo headers are valid");
+        headers.insert(Self::SERVICE_PROVIDER_HEADER, value(&self.service_provider));
+        headers.insert(Self::CREDENTIALS_HEADER, value(&self.credentials));
+    }
+
+    /// Returns the URL the service should call to verify the credentials.
+    pub fn service_provider(&self) -> &str {
+        &self.service_provider
+    }
+
+    /// Returns the signed `Authorization` header for the verification call.
+    pub fn credentials(&self) -> &str {
+        &self.credentials
+    }
+}
+
+/// Checks the given OAuth Echo credentials with Twitter, returning the user who signed them.
+///
+/// This is for services that receive requests delegated with OAuth Echo; see [`OAuthEcho`] for
+/// details. The call is made with the delegating app's credentials, so it doesn't need a token of
+/// its own.
+///
+/// [`OAuthEcho`]: struct.OAuthEcho.html
+///
+/// Since the service provider URL comes from the delegating app, this only makes the call if it's
+/// Twitter's `account/verify_credentials` endpoint, and returns `Error::BadUrl` otherwise.
+/// Twitter returns an error if the credentials have expired or weren't signed correctly.
+pub async fn verify_echo(echo: &OAuthEcho) -> Result<Response<crate::user::TwitterUser>> {
+    if echo.service_provider != links::auth::VERIFY_CREDENTIALS {
+        return Err(error::Error::BadUrl);
+    }
+
+    let req = hyper::Request::get(links::auth::VERIFY_CREDENTIALS)
+        .header(AUTHORIZATION, &echo.credentials)
+        .body(hyper::Body::empty())
+        .unwrap();
+    request_with_json_response(req).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug_redacts_secrets() {
+        let keys = KeyPair::new("consumer key", "consumer secret".to_string());
+        let debug = format!("{:?}", keys);
+        assert_eq!(
+            debug,
+            "KeyPair { key: \"consumer key\", secret: <redacted> }"
+        );
+
+        let access = Token::Access {
+            consumer: keys,
+            access: KeyPair::new("12345-access key", "access secret"),
+        };
+        let debug = format!("{:?}", access);
+        assert!(debug.contains("consumer key"));
+        assert!(debug.contains("12345-access key"));
+        assert!(!debug.contains("secret\""));
+        assert_eq!(
+            access.redacted_debug(),
+            "Token::Access { consumer: \"cons...\", access: \"1234...\" }"
+        );
+
+        let bearer = Token::Bearer("AAAAAAAAAAAAAAAAAAAAAbearer".to_string());
+        assert_eq!(format!("{:?}", bearer), "Bearer(<redacted>)");
+        assert_eq!(format!("{:#?}", bearer), "Bearer(\n    <redacted>,\n)");
+        assert_eq!(bearer.redacted_debug(), "Token::Bearer(<redacted>)");
+
+        assert_eq!(key_prefix("abc"), "abc");
+        assert_eq!(key_prefix("ééééé"), "éééé...");
+    }
+
+    #[cfg(feature = "zeroize_secrets")]
+    #[test]
+    fn zeroize_tokens() {
+        use zeroize::Zeroize;
+
+        let mut token = Token::Access {
+            consumer: KeyPair::new("consumer key", "consumer secret".to_string()),
+            access: KeyPair::new("access key".to_string(), "access secret"),
+        };
+        token.zeroize();
+        match &token {
+            Token::Access { consumer, access } => {
+                assert!(consumer.key.is_empty() && consumer.secret.is_empty());
+                assert!(access.key.is_empty() && access.secret.is_empty());
+            }
+            Token::Bearer(_) => unreachable!(),
+        }
+
+        let mut bearer = Token::Bearer("bearer".to_string());
+        bearer.zeroize();
+        assert!(matches!(&bearer, Token::Bearer(token) if token.is_empty()));
+
+        // without a Drop impl, fields can still be moved out
+        let KeyPair { key, secret } = KeyPair::new("key", "secret");
+        assert_eq!((&*key, &*secret), ("key", "secret"));
+    }
+
+    #[test]
+    fn oauth_echo_headers() {
+        let token = Token::Access {
+            consumer: KeyPair::new("consumer key", "consumer secret"),
+            access: KeyPair::new("12345-access key", "access secret"),
+        };
+        let echo = OAuthEcho::new(&token);
+        assert_eq!(echo.service_provider(), links::auth::VERIFY_CREDENTIALS);
+        assert!(echo.credentials().starts_with("OAuth "));
+        assert!(echo
+            .credentials()
+            .contains("oauth_token=\"12345-access%20key\""));
+        assert!(!echo.credentials().contains("secret"));
+
+        let mut headers = HeaderMap::new();
+        echo.add_headers(&mut headers);
+        assert_eq!(
+            headers["X-Auth-Service-Provider"],
+            links::auth::VERIFY_CREDENTIALS
+        );
+        assert_eq!(OAuthEcho::from_headers(&headers), Some(echo.clone()));
+
+        headers.remove(OAuthEcho::CREDENTIALS_HEADER);
+        assert_eq!(OAuthEcho::from_headers(&headers), None);
+
+        let forged = OAuthEcho {
+            service_provider: "https://example.com/verify".into(),
+            ..echo
+        };
+        let res = futures::executor::block_on(verify_echo(&forged));
+        assert!(matches!(res, Err(error::Error::BadUrl)));
+    }
+}
@@ -0,0 +1,211 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Carrying a `state` value through the sign-in callback.
+
+use std::borrow::Cow;
+
+use super::KeyPair;
+
+/// The query parameter that `callback_with_state` adds to the callback URL.
+const STATE_PARAM: &str = "state";
+
+/// Creates a random value to pass through the sign-in flow with `callback_with_state`.
+///
+/// The value is 32 random letters and numbers, so it can't be guessed by a third party. Save it
+/// in the user's session before sending them to Twitter, and compare it against the callback with
+/// `Callback::verify` when they come back.
+pub fn new_state() -> String {
+    super::raw::random_token(32)
+}
+
+/// Adds the given `state` value to a callback URL, to be given to `request_token`.
+///
+/// Twitter passes the callback URL back unchanged when it redirects the user, so the `state` comes
+/// back along with the `oauth_token` and `oauth_verifier`. If it matches the value saved in the
+/// user's session, the callback came from a sign-in the user started, rather than from a link
+/// planted by someone else to sign the user in to the wrong account (a cross-site request
+/// forgery). `Callback::verify` performs this check.
+///
+/// The `state` is added as a `state` query parameter, after any query parameters the callback URL
+/// already has. Since the state is carried in the URL, the same approach works no matter which of
+/// the app's registered callback URLs is used.
+///
+/// This returns `None` if the callback isn't a valid absolute URL, for example if it's the `"oob"`
+/// value used for PIN-based authorization, which has no callback to carry the state through.
+///
+/// ```rust,no_run
+/// # #[tokio::main]
+/// # async fn main() {
+/// use egg_mode::auth;
+///
+/// let con_token = egg_mode::KeyPair::new("consumer key", "consumer secret");
+/// let state = auth::new_state();
+/// // save `state` in the user's session, then:
+/// let callback = auth::callback_with_state("https://myapp.io/auth", &state).unwrap();
+/// let req_token = auth::request_token(&con_token, callback).await.unwrap();
+/// let url = auth::authenticate_url(&req_token);
+/// # }
+/// ```
+pub fn callback_with_state(callback: &str, state: &str) -> Option<String> {
+    let mut url = url::Url::parse(callback).ok()?;
+    if url.cannot_be_a_base() {
+        return None;
+    }
+    url.query_pairs_mut().append_pair(STATE_PARAM, state);
+    Some(url.into())
+}
+
+/// The query parameters Twitter gives to the callback URL at the end of the sign-in flow.
+///
+/// Load this from the URL the user was redirected to with `from_url`, or from just its query
+/// string with `from_query`, then call `verify` to check it against the sign-in the user started.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Callback {
+    /// The `key` of the request token the user signed in with.
+    pub oauth_token: Option<String>,
+    /// The verifier to give to `access_token`, if the user accepted the app's access.
+    pub oauth_verifier: Option<String>,
+    /// If the user declined to give the app access, contains the `key` of the request token.
+    pub denied: Option<String>,
+    /// The value that was added to the callback URL by `callback_with_state`, if any.
+    pub state: Option<String>,
+}
+
+impl Callback {
+    /// Loads the callback parameters from the full URL the user was redirected to.
+    ///
+    /// This returns `None` if the URL can't be parsed.
+    pub fn from_url(url: &str) -> Option<Callback> {
+        let url = url::Url::parse(url).ok()?;
+        Some(Callback::from_pairs(url.query_pairs()))
+    }
+
+    /// Loads the callback parameters from the query string of the URL the user was redirected to,
+    /// with or without the leading `?`.
+    pub fn from_query(query: &str) -> Callback {
+        let query = query.strip_prefix('?').unwrap_or(query);
+        Callback::from_pairs(url::form_urlencoded::parse(query.as_bytes()))
+    }
+
+    fn from_pairs<'a>(pairs: impl Iterator<Item = (Cow<'a, str>, Cow<'a, str>)>) -> Callback {
+        let mut callback = Callback::default();
+        for (key, value) in pairs {
+            let field = match key.as_ref() {
+                "oauth_token" => &mut callback.oauth_token,
+                "oauth_verifier" => &mut callback.oauth_verifier,
+                "denied" => &mut callback.denied,
+                STATE_PARAM => &mut callback.state,
+                _ => continue,
+            };
+            // keep the first value, so parameters appended after the callback's own can't
+            // override it
+            if field.is_none() {
+                *field = Some(value.into_owned());
+            }
+        }
+        callback
+    }
+
+    /// Checks that this callback finishes the sign-in that was started with the given request
+    /// token and `state`, and returns the verifier to give to `access_token`.
+    ///
+    /// The `state` is compared in constant time, so its value can't be found by timing repeated
+    /// attempts. An empty `state` never matches, even if the callback's is also empty, since it
+    /// would let any callback through.
+    pub fn verify(&self, request_token: &KeyPair, state: &str) -> Result<&str, CallbackError> {
+        if self.denied.is_some() {
+            return Err(CallbackError::Denied);
+        }
+        match self.state {
+            Some(ref given)
+                if !state.is_empty() && constant_time_eq(given.as_bytes(), state.as_bytes()) => {}
+            _ => return Err(CallbackError::StateMismatch),
+        }
+        if self.oauth_token.as_deref() != Some(&*request_token.key) {
+            return Err(CallbackError::TokenMismatch);
+        }
+        self.oauth_verifier
+            .as_deref()
+            .ok_or(CallbackError::MissingVerifier)
+    }
+}
+
+/// Represents a reason that `Callback::verify` rejected a sign-in callback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum CallbackError {
+    /// The user declined to give the app access to their account.
+    #[error("User declined the authorization request")]
+    Denied,
+    /// The `state` in the callback is missing or doesn't match the one the sign-in was started
+    /// with. The callback may have been forged, so it shouldn't be used to sign the user in.
+    #[error("Callback state is missing or does not match")]
+    StateMismatch,
+    /// The request token in the callback isn't the one the sign-in was started with.
+    #[error("Callback is for a different request token")]
+    TokenMismatch,
+    /// The callback doesn't include an `oauth_verifier`.
+    #[error("Callback has no verifier")]
+    MissingVerifier,
+}
+
+/// Compares two byte strings without returning early at the first difference.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_roundtrip() {
+        let state = new_state();
+        assert_eq!(state.len(), 32);
+        assert_ne!(state, new_state());
+
+        let callback = callback_with_state("https://myapp.io/auth?next=%2Fhome", &state).unwrap();
+        assert_eq!(
+            callback,
+            format!("https://myapp.io/auth?next=%2Fhome&state={}", state)
+        );
+        assert_eq!(callback_with_state("oob", &state), None);
+
+        let req_token = KeyPair::new("request key", "request secret");
+        let redirect = format!("{}&oauth_token=request+key&oauth_verifier=abc", callback);
+        let returned = Callback::from_url(&redirect).unwrap();
+        assert_eq!(returned.verify(&req_token, &state), Ok("abc"));
+        assert_eq!(
+            returned.verify(&req_token, "forged"),
+            Err(CallbackError::StateMismatch)
+        );
+        let other_token = KeyPair::new("other key", "other secret");
+        assert_eq!(
+            returned.verify(&other_token, &state),
+            Err(CallbackError::TokenMismatch)
+        );
+
+        let forged = Callback::from_query("?oauth_token=request%20key&oauth_verifier=abc");
+        assert_eq!(
+            forged.verify(&req_token, &state),
+            Err(CallbackError::StateMismatch)
+        );
+
+        let empty = Callback::from_query("oauth_token=request+key&oauth_verifier=abc&state=");
+        assert_eq!(
+            empty.verify(&req_token, ""),
+            Err(CallbackError::StateMismatch)
+        );
+        assert_eq!(
+            forged.verify(&req_token, ""),
+            Err(CallbackError::StateMismatch)
+        );
+
+        let denied = Callback::from_query(&format!("denied=request+key&state={}", state));
+        assert_eq!(
+            denied.verify(&req_token, &state),
+            Err(CallbackError::Denied)
+        );
+    }
+}
@@ -0,0 +1,102 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Inspection of the OAuth signatures egg-mode creates.
+//!
+//! When Twitter rejects a request with a 401 error, the cause is often a mismatch between the
+//! signature egg-mode calculated and the one Twitter expected. This module lets you see every
+//! signature as it's created: install a hook with [`set_signature_hook`], and it will be called
+//! with a [`SignatureDebug`] for each request signed with an Access token (or while requesting
+//! one), containing the signature base string and the normalized parameters that went into it.
+//! These can be compared against Twitter's OAuth tool or another OAuth implementation.
+//!
+//! The consumer and token secrets are never given to the hook; the masked signing key only shows
+//! how long each of them is. The keys themselves, the nonce, and the timestamp are part of the
+//! base string and are given as-is.
+//!
+//! This module is only available with the `auth-debug` feature enabled.
+//!
+//! [`set_signature_hook`]: fn.set_signature_hook.html
+//! [`SignatureDebug`]: struct.SignatureDebug.html
+//!
+//! # Example
+//!
+//! ```rust
+//! egg_mode::auth::debug::set_signature_hook(|sig| {
+//!     eprintln!("signed {} {}", sig.method, sig.uri);
+//!     eprintln!("  base string: {}", sig.base_string);
+//! });
+//! ```
+
+use std::sync::{Arc, RwLock};
+
+use lazy_static::lazy_static;
+
+/// The parts of an OAuth signature, as given to the hook set with `set_signature_hook`.
+#[derive(Debug, Clone)]
+pub struct SignatureDebug {
+    /// The HTTP method of the request.
+    pub method: String,
+    /// The URL of the request, without its query string.
+    pub uri: String,
+    /// The percent-encoded parameters that were signed, sorted into the order OAuth requires.
+    ///
+    /// This includes the request's query and body parameters as well as the `oauth_*` parameters
+    /// that go in the `Authorization` header.
+    pub params: Vec<(String, String)>,
+    /// The signature base string: the method, URL, and normalized parameters, joined with `&`.
+    pub base_string: String,
+    /// The key used to sign the base string, with each secret replaced by its length, like
+    /// `<41 chars>&<37 chars>`. A secret that's empty, like the token secret when requesting a
+    /// request token, is left empty.
+    pub masked_signing_key: String,
+    /// The resulting signature, base64-encoded, as sent in the `oauth_signature` parameter.
+    pub signature: String,
+}
+
+type Hook = Arc<dyn Fn(&SignatureDebug) + Send + Sync>;
+
+lazy_static! {
+    static ref HOOK: RwLock<Option<Hook>> = RwLock::new(None);
+}
+
+/// Sets the function to call with each OAuth signature egg-mode creates, replacing any hook
+/// already set.
+///
+/// The hook is called on the thread that signs the request, before the request is sent. It
+/// applies to every request made through egg-mode in this process.
+pub fn set_signature_hook<F>(hook: F)
+where
+    F: Fn(&SignatureDebug) + Send + Sync + 'static,
+{
+    *HOOK.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(hook));
+}
+
+/// Removes the hook set with `set_signature_hook`, if any.
+pub fn clear_signature_hook() {
+    *HOOK.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Returns whether a hook is set, so the details only need to be collected if someone will see
+/// them.
+pub(crate) fn has_hook() -> bool {
+    HOOK.read().map(|hook| hook.is_some()).unwrap_or(false)
+}
+
+/// Gives the given signature details to the hook, if one is set.
+pub(crate) fn report(sig: SignatureDebug) {
+    let hook = HOOK.read().ok().and_then(|hook| hook.clone());
+    if let Some(hook) = hook {
+        hook(&sig);
+    }
+}
+
+/// Replaces a secret with its length, for display in the masked signing key.
+pub(crate) fn mask(secret: &str) -> String {
+    if secret.is_empty() {
+        String::new()
+    } else {
+        format!("<{} chars>", secret.chars().count())
+    }
+}
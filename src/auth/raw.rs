@@ -20,6 +20,25 @@ use crate::common::*;
 
 use super::{KeyPair, Token};
 
+/// A string holding secrets, which is overwritten with zeroes when it's dropped if the
+/// `zeroize_secrets` feature is on.
+#[cfg(feature = "zeroize_secrets")]
+type SecretString = zeroize::Zeroizing<String>;
+#[cfg(not(feature = "zeroize_secrets"))]
+type SecretString = String;
+
+/// Wraps the given string so it's overwritten with zeroes when it's dropped, if the
+/// `zeroize_secrets` feature is on.
+#[cfg(feature = "zeroize_secrets")]
+fn secret(s: String) -> SecretString {
+    zeroize::Zeroizing::new(s)
+}
+
+#[cfg(not(feature = "zeroize_secrets"))]
+fn secret(s: String) -> SecretString {
+    s
+}
+
 // n.b. this type is exported in `raw::auth` - these docs are public!
 /// Builder struct to assemble and sign an API request.
 ///
@@ -30,7 +49,7 @@ pub struct RequestBuilder<'a> {
     method: Method,
     params: Option<ParamList>,
     query: Option<String>,
-    body: Option<(Body, &'static str)>,
+    body: Option<(Body, Cow<'static, str>)>,
     addon: OAuthAddOn,
 }
 
@@ -91,7 +110,7 @@ impl<'a> RequestBuilder<'a> {
         RequestBuilder {
             body: Some((
                 Body::from(params.to_urlencoded()),
-                "application/x-www-form-urlencoded",
+                "application/x-www-form-urlencoded".into(),
             )),
             params: Some(total_params),
             ..self
@@ -119,7 +138,26 @@ impl<'a> RequestBuilder<'a> {
     /// sent with the request.
     pub fn with_body(self, body: impl Into<Body>, content: &'static str) -> Self {
         RequestBuilder {
-            body: Some((body.into(), content)),
+            body: Some((body.into(), content.into())),
+            ..self
+        }
+    }
+
+    /// Includes the given parts as the request body, formatted as `multipart/form-data`. Data
+    /// given this way will *not* be included in the OAuth signature, as required by the OAuth
+    /// specification for multipart bodies.
+    ///
+    /// Note that the functions that specify a request body each overwrite the body. For example,
+    /// if you specify `with_body_params` and also `with_body_multipart`, only the one you call
+    /// last will be sent with the request.
+    pub fn with_body_multipart(self, parts: impl IntoIterator<Item = FormPart>) -> Self {
+        let boundary = random_token(32);
+        let body = encode_multipart(parts, &boundary);
+        RequestBuilder {
+            body: Some((
+                Body::from(body),
+                format!("multipart/form-data; boundary={}", boundary).into(),
+            )),
             ..self
         }
     }
@@ -155,7 +193,7 @@ impl<'a> RequestBuilder<'a> {
         let oauth = OAuthParams::from_keys(consumer_key.clone(), token.cloned())
             .with_addon(self.addon.clone())
             .sign_request(self.method.clone(), self.base_uri, self.params.as_ref());
-        self.request_authorization(oauth.to_string())
+        self.request_authorization(secret(oauth.to_string()))
     }
 
     /// Formats this `RequestBuilder` into a complete `Request`, signing it with the given token.
@@ -165,10 +203,18 @@ impl<'a> RequestBuilder<'a> {
     ///
     /// If the given `Token` is a Bearer token, the request will be authenticated using OAuth 2.0,
     /// specifying the given Bearer token as authorization.
+    ///
+    /// If a `TokenPool` is set with `dispatch::set_token_pool` and the given Bearer token is in it,
+    /// a read-only request is signed with the pool's token with the most calls left for this
+    /// endpoint instead.
     pub fn request_token(self, token: &Token) -> Request<Body> {
-        match token {
+        let pooled = crate::dispatch::current_token_pool()
+            .and_then(|pool| pool.select(token, &self.method, self.base_uri));
+        match pooled.as_ref().unwrap_or(token) {
             Token::Access { consumer, access } => self.request_keys(consumer, Some(access)),
-            Token::Bearer(bearer) => self.request_authorization(format!("Bearer {}", bearer)),
+            Token::Bearer(bearer) => {
+                self.request_authorization(secret(format!("Bearer {}", bearer)))
+            }
         }
     }
 
@@ -190,7 +236,7 @@ impl<'a> RequestBuilder<'a> {
     /// Assembles the final `Request` with the given Authorization header. This is private to
     /// require that a well-formed header is constructed given, as constructed from the other
     /// `request_*` methods.
-    fn request_authorization(self, authorization: String) -> Request<Body> {
+    fn request_authorization(self, authorization: SecretString) -> Request<Body> {
         let full_url = if let Some(query) = self.query {
             format!("{}?{}", self.base_uri, query)
         } else {
@@ -199,16 +245,114 @@ impl<'a> RequestBuilder<'a> {
         let request = Request::builder()
             .method(self.method)
             .uri(full_url)
-            .header(AUTHORIZATION, authorization);
+            .header(AUTHORIZATION, &*authorization);
 
         if let Some((body, content)) = self.body {
-            request.header(CONTENT_TYPE, content).body(body).unwrap()
+            request.header(CONTENT_TYPE, &*content).body(body).unwrap()
         } else {
             request.body(Body::empty()).unwrap()
         }
     }
 }
 
+// n.b. this type is exported in `raw` - these docs are public!
+/// A single part of a `multipart/form-data` request body.
+///
+/// Parts can either be plain text fields, created with `FormPart::text`, or files, created with
+/// `FormPart::file`. Use `raw::request_multipart` or `RequestBuilder::with_body_multipart` to send
+/// them.
+#[derive(Debug, Clone)]
+pub struct FormPart {
+    name: Cow<'static, str>,
+    filename: Option<Cow<'static, str>>,
+    content_type: Option<mime::Mime>,
+    data: Vec<u8>,
+}
+
+impl FormPart {
+    /// Creates a plain text field with the given name and value.
+    pub fn text(name: impl Into<Cow<'static, str>>, value: impl Into<String>) -> FormPart {
+        FormPart {
+            name: name.into(),
+            filename: None,
+            content_type: None,
+            data: value.into().into_bytes(),
+        }
+    }
+
+    /// Creates a file field with the given name, contents, and content type.
+    ///
+    /// Twitter's endpoints generally don't care about the file's name, so it defaults to the name
+    /// of the field. Use `filename` to set a different one.
+    pub fn file(
+        name: impl Into<Cow<'static, str>>,
+        data: impl Into<Vec<u8>>,
+        content_type: mime::Mime,
+    ) -> FormPart {
+        let name = name.into();
+        FormPart {
+            filename: Some(name.clone()),
+            name,
+            content_type: Some(content_type),
+            data: data.into(),
+        }
+    }
+
+    /// Sets the file name sent with this part.
+    pub fn filename(self, filename: impl Into<Cow<'static, str>>) -> FormPart {
+        FormPart {
+            filename: Some(filename.into()),
+            ..self
+        }
+    }
+}
+
+/// Formats the given parts as a `multipart/form-data` body, separated by the given boundary.
+fn encode_multipart(parts: impl IntoIterator<Item = FormPart>, boundary: &str) -> Vec<u8> {
+    // quotes and line breaks would end the header early, so they're percent-encoded the way
+    // browsers do
+    fn quote(value: &str) -> String {
+        value
+            .replace('"', "%22")
+            .replace('\r', "%0D")
+            .replace('\n', "%0A")
+    }
+
+    let mut body = Vec::new();
+    for part in parts {
+        body.extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
+        body.extend_from_slice(
+            format!(
+                "Content-Disposition: form-data; name=\"{}\"",
+                quote(&part.name)
+            )
+            .as_bytes(),
+        );
+        if let Some(filename) = &part.filename {
+            body.extend_from_slice(format!("; filename=\"{}\"", quote(filename)).as_bytes());
+        }
+        body.extend_from_slice(b"\r\n");
+        if let Some(content_type) = &part.content_type {
+            body.extend_from_slice(format!("Content-Type: {}\r\n", content_type).as_bytes());
+        }
+        body.extend_from_slice(b"\r\n");
+        body.extend_from_slice(&part.data);
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+    body
+}
+
+/// Creates a random alphanumeric string of the given length.
+pub(crate) fn random_token(len: usize) -> String {
+    let mut rng = rand::thread_rng();
+    ::std::iter::repeat(())
+        .map(|()| rng.sample(rand::distributions::Alphanumeric))
+        .map(char::from)
+        .take(len)
+        .collect()
+}
+
 /// OAuth header set used to create an OAuth signature.
 #[derive(Clone, Debug)]
 struct OAuthParams {
@@ -238,12 +382,7 @@ impl OAuthParams {
             Err(err) => err.duration(),
         }
         .as_secs();
-        let mut rng = rand::thread_rng();
-        let nonce = ::std::iter::repeat(())
-            .map(|()| rng.sample(rand::distributions::Alphanumeric))
-            .map(char::from)
-            .take(32)
-            .collect::<String>();
+        let nonce = random_token(32);
         OAuthParams {
             consumer_key: KeyPair::empty(),
             token: None,
@@ -273,7 +412,7 @@ impl OAuthParams {
     /// Uses the parameters in this `OAuthParams` instance to generate a signature for the given
     /// request, returning it as a `SignedHeader`.
     fn sign_request(self, method: Method, uri: &str, params: Option<&ParamList>) -> SignedHeader {
-        let query_string = {
+        let normalized = {
             let sig_params = params
                 .cloned()
                 .unwrap_or_default()
@@ -282,7 +421,7 @@ impl OAuthParams {
                 .add_param("oauth_signature_method", "HMAC-SHA1")
                 .add_param("oauth_timestamp", format!("{}", self.timestamp.clone()))
                 .add_param("oauth_version", "1.0")
-                .add_opt_param("oauth_token", self.token.clone().map(|k| k.key))
+                .add_opt_param("oauth_token", self.token.as_ref().map(|k| k.key.clone()))
                 .add_opt_param(
                     "oauth_callback",
                     self.addon.as_callback().map(|s| s.to_string()),
@@ -292,14 +431,22 @@ impl OAuthParams {
                     self.addon.as_verifier().map(|s| s.to_string()),
                 );
 
+            // OAuth sorts the encoded parameters by key, then by value for repeated keys. Sorting
+            // the joined `key=value` strings instead would put `a-b` before `a`, since `-` sorts
+            // before `=`.
             let mut query = sig_params
                 .iter()
-                .map(|(k, v)| format!("{}={}", percent_encode(k), percent_encode(v)))
+                .map(|(k, v)| (percent_encode(k).to_string(), percent_encode(v).to_string()))
                 .collect::<Vec<_>>();
             query.sort();
 
-            query.join("&")
+            query
         };
+        let query_string = normalized
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join("&");
 
         let base_str = format!(
             "{}&{}&{}",
@@ -307,11 +454,11 @@ impl OAuthParams {
             percent_encode(uri),
             percent_encode(&query_string)
         );
-        let key = format!(
+        let key = secret(format!(
             "{}&{}",
             percent_encode(&self.consumer_key.secret),
             percent_encode(&self.token.as_ref().unwrap_or(&KeyPair::new("", "")).secret)
-        );
+        ));
 
         // TODO check if key is correct length? Can this fail?
         let mut digest = Hmac::<Sha1>::new_from_slice(key.as_bytes()).expect("Wrong key length");
@@ -321,9 +468,9 @@ impl OAuthParams {
         params.insert("oauth_signature_method", "HMAC-SHA1".into());
         params.insert("oauth_version", "1.0".into());
 
-        params.insert("oauth_consumer_key", self.consumer_key.key);
-        if let Some(token) = self.token {
-            params.insert("oauth_token", token.key);
+        params.insert("oauth_consumer_key", self.consumer_key.key.clone());
+        if let Some(token) = &self.token {
+            params.insert("oauth_token", token.key.clone());
         }
 
         params.insert("oauth_nonce", self.nonce.into());
@@ -339,10 +486,28 @@ impl OAuthParams {
             OAuthAddOn::None => (),
         }
 
-        params.insert(
-            "oauth_signature",
-            base64::encode(&digest.finalize().into_bytes()).into(),
-        );
+        let signature = base64::encode(digest.finalize().into_bytes());
+
+        #[cfg(feature = "auth-debug")]
+        {
+            if super::debug::has_hook() {
+                let token_secret = self.token.as_ref().map(|t| &*t.secret).unwrap_or_default();
+                super::debug::report(super::debug::SignatureDebug {
+                    method: method.to_string(),
+                    uri: uri.to_string(),
+                    params: normalized,
+                    base_string: base_str,
+                    masked_signing_key: format!(
+                        "{}&{}",
+                        super::debug::mask(&self.consumer_key.secret),
+                        super::debug::mask(token_secret)
+                    ),
+                    signature: signature.clone(),
+                });
+            }
+        }
+
+        params.insert("oauth_signature", signature.into());
 
         SignedHeader { params }
     }
@@ -409,14 +574,38 @@ impl fmt::Display for SignedHeader {
     }
 }
 
+/// Signs a GET request to the given URL with the given keys, and returns the URL with the OAuth
+/// parameters added to its query string instead of an `Authorization` header.
+///
+/// The returned URL is made of the given `base_uri`, the given parameters, and the OAuth
+/// parameters, including the signature. The secrets of the keys are not included.
+#[cfg(feature = "media")]
+pub(crate) fn signed_get_url(
+    base_uri: &str,
+    params: &ParamList,
+    consumer: &KeyPair,
+    access: &KeyPair,
+) -> String {
+    let signed = OAuthParams::from_keys(consumer.clone(), Some(access.clone())).sign_request(
+        Method::GET,
+        base_uri,
+        Some(params),
+    );
+    let mut query = params.clone();
+    for (key, value) in signed.params {
+        query.add_param_ref(key, value);
+    }
+    format!("{}?{}", base_uri, query.to_urlencoded())
+}
+
 /// Creates a basic `Authorization` header based on the given consumer token.
 ///
 /// The authorization created by this function can only be used with requests to generate or
 /// invalidate a bearer token. Using this authorization with any other endpoint will result in an
 /// invalid request.
-fn bearer_request(con_token: &KeyPair) -> String {
-    let text = format!("{}:{}", con_token.key, con_token.secret);
-    format!("Basic {}", base64::encode(&text))
+fn bearer_request(con_token: &KeyPair) -> SecretString {
+    let text = secret(format!("{}:{}", con_token.key, con_token.secret));
+    secret(format!("Basic {}", base64::encode(&*text)))
 }
 
 // n.b. this function is re-exported in the `raw` module - these docs are public!
@@ -474,18 +663,146 @@ pub fn post_json<B: serde::Serialize>(uri: &str, token: &Token, body: B) -> Requ
         .request_token(token)
 }
 
+// n.b. this function is re-exported in the `raw` module - these docs are public!
+/// Assemble a signed POST request to the given URL with the given parts as a multipart body.
+///
+/// This method of building requests allows you to use endpoints that take a
+/// `multipart/form-data` body, like the non-chunked form of `POST media/upload`. As required for
+/// multipart bodies, only the OAuth parameters themselves are used in the OAuth signature; the
+/// parts are not signed.
+///
+/// ```rust,no_run
+/// use egg_mode::raw::{request_multipart, response_json, FormPart};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let token: egg_mode::Token = unimplemented!();
+/// # let image: Vec<u8> = unimplemented!();
+/// let req = request_multipart(
+///     "https://upload.twitter.com/1.1/media/upload.json",
+///     &token,
+///     vec![
+///         FormPart::file("media", image, mime::IMAGE_PNG),
+///         FormPart::text("media_category", "tweet_image"),
+///     ],
+/// );
+/// let json: egg_mode::Response<serde_json::Value> = response_json(req).await.unwrap();
+/// # }
+/// ```
+pub fn multipart(
+    uri: &str,
+    token: &Token,
+    parts: impl IntoIterator<Item = FormPart>,
+) -> Request<Body> {
+    RequestBuilder::new(Method::POST, uri)
+        .with_body_multipart(parts)
+        .request_token(token)
+}
+
 #[cfg(test)]
 mod tests {
-    use super::bearer_request;
+    use super::{bearer_request, encode_multipart, FormPart, KeyPair, OAuthParams};
+    use crate::common::ParamList;
+    use hyper::Method;
+
+    #[test]
+    fn signature_example() {
+        // the worked example from Twitter's "Creating a signature" documentation
+        let consumer = KeyPair::new(
+            "xvz1evFS4wEEPTGEFPHBog",
+            "kAcSOqF21Fu85e7zjz7ZN2U4ZRhfV3WpwPAoE3Z7kBw",
+        );
+        let token = KeyPair::new(
+            "370773112-GmHxMAgYyLbNEtIKZeRNFsMKPR9EyMZeS9weJAEb",
+            "LswwdoUaIvS8ltyTt5jkRh4J50vUPVVHtR2YPi5kE",
+        );
+        let nonce = "kYjzVBB8Y0ZFabxSWbWovY3uYSQ2pTgmZeNu2VS4cg";
+        let oauth = OAuthParams {
+            nonce: nonce.to_string(),
+            timestamp: 1318622958,
+            ..OAuthParams::from_keys(consumer, Some(token))
+        };
+        let params = ParamList::new()
+            .add_param("include_entities", "true")
+            .add_param(
+                "status",
+                "Hello Ladies + Gentlemen, a signed OAuth request!",
+            );
+
+        #[cfg(feature = "auth-debug")]
+        let reported = {
+            let reported = std::sync::Arc::new(std::sync::Mutex::new(None));
+            let sink = reported.clone();
+            crate::auth::debug::set_signature_hook(move |sig| {
+                if sig.base_string.contains(nonce) {
+                    *sink.lock().unwrap() = Some(sig.clone());
+                }
+            });
+            reported
+        };
+
+        let header = oauth.sign_request(
+            Method::POST,
+            "https://api.twitter.com/1.1/statuses/update.json",
+            Some(&params),
+        );
+        assert_eq!(
+            header.params["oauth_signature"],
+            "hCtSmYh+iHYCEqBWrE7C7hYmtUk="
+        );
+
+        #[cfg(feature = "auth-debug")]
+        {
+            crate::auth::debug::clear_signature_hook();
+            let sig = reported.lock().unwrap().take().unwrap();
+            assert_eq!(
+                sig.base_string,
+                "POST&https%3A%2F%2Fapi.twitter.com%2F1.1%2Fstatuses%2Fupdate.json&\
+                 include_entities%3Dtrue%26\
+                 oauth_consumer_key%3Dxvz1evFS4wEEPTGEFPHBog%26\
+                 oauth_nonce%3DkYjzVBB8Y0ZFabxSWbWovY3uYSQ2pTgmZeNu2VS4cg%26\
+                 oauth_signature_method%3DHMAC-SHA1%26\
+                 oauth_timestamp%3D1318622958%26\
+                 oauth_token%3D370773112-GmHxMAgYyLbNEtIKZeRNFsMKPR9EyMZeS9weJAEb%26\
+                 oauth_version%3D1.0%26\
+                 status%3DHello%2520Ladies%2520%252B%2520Gentlemen%252C%2520a%2520signed%2520OAuth%2520request%2521"
+            );
+            assert_eq!(sig.masked_signing_key, "<43 chars>&<41 chars>");
+            assert_eq!(sig.signature, "hCtSmYh+iHYCEqBWrE7C7hYmtUk=");
+        }
+    }
+
+    #[test]
+    fn multipart_body() {
+        let parts = vec![
+            FormPart::text("media_category", "tweet_image"),
+            FormPart::file("media", &b"\x89PNG"[..], mime::IMAGE_PNG).filename("a \"cat\".png"),
+        ];
+        let body = encode_multipart(parts, "XyZ");
+
+        let mut expected = Vec::new();
+        expected.extend_from_slice(
+            b"--XyZ\r\n\
+              Content-Disposition: form-data; name=\"media_category\"\r\n\
+              \r\n\
+              tweet_image\r\n\
+              --XyZ\r\n\
+              Content-Disposition: form-data; name=\"media\"; filename=\"a %22cat%22.png\"\r\n\
+              Content-Type: image/png\r\n\
+              \r\n",
+        );
+        expected.extend_from_slice(b"\x89PNG\r\n--XyZ--\r\n");
+        assert_eq!(body, expected);
+    }
 
     #[test]
     fn bearer_header() {
         let con_key = "xvz1evFS4wEEPTGEFPHBog";
         let con_secret = "L8qq9PZyRg6ieKGEKhZolGC0vJWLw8iEJ88DRdyOg";
-        let con_token = super::KeyPair::new(con_key, con_secret);
+        let con_token = KeyPair::new(con_key, con_secret);
 
         let output = bearer_request(&con_token);
 
-        assert_eq!(output, "Basic eHZ6MWV2RlM0d0VFUFRHRUZQSEJvZzpMOHFxOVBaeVJnNmllS0dFS2hab2xHQzB2SldMdzhpRUo4OERSZHlPZw==");
+        assert_eq!(&*output, "Basic eHZ6MWV2RlM0d0VFUFRHRUZQSEJvZzpMOHFxOVBaeVJnNmllS0dFS2hab2xHQzB2SldMdzhpRUo4OERSZHlPZw==");
     }
 }
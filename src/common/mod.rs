@@ -21,8 +21,9 @@
 //!
 //! ## `ParamList`
 //!
-//! `ParamList` is a type alias for use as a collection of parameters to a given web call. It's
-//! consumed in the auth module, and provides some easy wrappers to consistently handle some types.
+//! `ParamList` is an ordered list of parameters to a given web call, which can hold a key more than
+//! once. It's consumed in the auth module, and provides some easy wrappers to consistently handle
+//! some types.
 //!
 //! `add_param` is a basic function that turns its arguments into `Cow<'static, str>`, then inserts them
 //! as a parameter into the given `ParamList`.
@@ -54,8 +55,9 @@
 //!
 //! `serde_datetime` and `serde_via_string` are helper modules to use with derived
 //! `Serialize`/`Deserialize` implementations. `serde_datetime` loads and saves `DateTime`s with
-//! the format Twitter uses for timestamps, and `serde_via_string` uses `Display` and `FromStr` to
-//! save a string representation of the original type.
+//! the format Twitter uses for timestamps (also accepting the ISO 8601 timestamps from the v2
+//! API), and `serde_via_string` uses `Display` and `FromStr` to save a string representation of
+//! the original type. `serde_opt_via_string` does the same for `Option`s of such a type.
 //!
 //! `merge_by` and its companion type `MergeBy` is a copy of the iterator adapter of the same name
 //! from itertools, because i didn't want to add another dependency onto the great towering pile
@@ -91,7 +93,7 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::future::Future;
-use std::iter::Peekable;
+use std::iter::{FromIterator, Peekable};
 use std::pin::Pin;
 
 use hyper::header::{HeaderMap, HeaderValue};
@@ -99,7 +101,9 @@ use percent_encoding::{utf8_percent_encode, AsciiSet, PercentEncode};
 
 mod response;
 
-pub use crate::auth::raw::{get, post, post_json};
+#[cfg(any(feature = "dm", feature = "media"))]
+pub use crate::auth::raw::post_json;
+pub use crate::auth::raw::{get, post};
 
 pub use crate::common::response::*;
 use crate::{error, list, user};
@@ -161,7 +165,16 @@ macro_rules! round_trip {
         pub struct $struct_name { $(
             $(#[$attr])*
             $v $f: $t
-        ),+ }
+        ),+ ,
+            /// Any fields returned by Twitter that egg-mode doesn't load into the other fields of
+            /// this struct.
+            ///
+            /// This is only available with the `raw-fields` feature enabled. It can be used to
+            /// access data that Twitter has added to its responses before egg-mode supports it.
+            #[cfg(feature = "raw-fields")]
+            #[serde(flatten)]
+            pub extra: serde_json::Map<String, serde_json::Value>,
+        }
 
         #[allow(unused_qualifications)]
         impl crate::common::RoundTrip for $struct_name {
@@ -182,24 +195,32 @@ macro_rules! round_trip {
         struct SerCopy { $(
             $(#[$attr])*
             $v $f: $t
-        ),+ }
+        ),+ ,
+            #[cfg(feature = "raw-fields")]
+            #[serde(flatten)]
+            extra: serde_json::Map<String, serde_json::Value>,
+        }
 
         impl From<SerCopy> for $struct_name {
             fn from(src: SerCopy) -> $struct_name {
                 $struct_name { $(
                     $f: src.$f
-                ),+ }
+                ),+ ,
+                    #[cfg(feature = "raw-fields")]
+                    extra: src.extra,
+                }
             }
         }
 
         #[derive(serde::Deserialize)]
         #[serde(untagged)]
+        #[allow(clippy::large_enum_variant)]
         enum SerEnum {
             Raw($raw_name),
             Ser(SerCopy),
         }
 
-        #[allow(unused_qualifications)]
+        #[allow(unused_qualifications, clippy::infallible_try_from)]
         impl std::convert::TryFrom<SerEnum> for $struct_name
         where
             $struct_name: std::convert::TryFrom<$raw_name>,
@@ -272,12 +293,11 @@ pub type CowStr = Cow<'static, str>;
 // n.b. this type is re-exported in the `raw` module - these docs are public!
 /// Represents a list of parameters to a Twitter API call.
 ///
-/// This type is a wrapper around a `HashMap<Cow<'static, str>, Cow<'static, str>>` to collect a
-/// set of parameter key/value pairs. These are then used to assemble and sign a Twitter API
-/// request. The `Cow` type is used to avoid having to allocate a `String` if a string literal is
-/// used for a parameter. All the functions that add parameters to this `ParamList` accept `impl
-/// Into<Cow<'static, str>>`, meaning that either a string literal or an owned `String` may be
-/// used.
+/// This type collects a list of parameter key/value pairs, kept in the order they were added.
+/// These are then used to assemble and sign a Twitter API request. The `Cow` type is used to avoid
+/// having to allocate a `String` if a string literal is used for a parameter. All the functions
+/// that add parameters to this `ParamList` accept `impl Into<Cow<'static, str>>`, meaning that
+/// either a string literal or an owned `String` may be used.
 ///
 /// Most of the functions to add parameters follow a builder pattern, so that you can assemble a
 /// `ParamList` in a single statement:
@@ -291,13 +311,25 @@ pub type CowStr = Cow<'static, str>;
 ///     .extended_tweets()
 ///     .add_user_param("rustlang".into());
 /// ```
-#[derive(Debug, Clone, Default, derive_more::Deref, derive_more::DerefMut, derive_more::From)]
-pub struct ParamList(HashMap<Cow<'static, str>, Cow<'static, str>>);
+///
+/// `add_param` replaces any value already set for its key, like inserting into a map. For the
+/// endpoints that accept a parameter more than once, `add_param_multi` adds a value without
+/// replacing the existing ones:
+///
+/// ```
+/// use egg_mode::raw::ParamList;
+///
+/// let params = ParamList::new().add_param_multi("id", ["1", "2"]);
+/// assert_eq!(params.to_urlencoded(), "id=1&id=2");
+/// assert_eq!(params.get_all("id").count(), 2);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParamList(Vec<(Cow<'static, str>, Cow<'static, str>)>);
 
 impl ParamList {
     /// Creates a new, empty `ParamList`.
     pub fn new() -> Self {
-        Self(HashMap::new())
+        Self(Vec::new())
     }
 
     /// Adds the `tweet_mode=extended` parameter to this `ParamList`. Not including this parameter
@@ -309,7 +341,27 @@ impl ParamList {
         self.add_param("tweet_mode", "extended")
     }
 
-    /// Adds the given key/value parameter to this `ParamList`.
+    /// Adds the parameters that ask Twitter to include link preview cards with the loaded tweets.
+    ///
+    /// `include_cards` and `cards_platform` aren't part of Twitter's documented API; they're what
+    /// Twitter's own web client sends, and Twitter may change or drop them at any time. If that
+    /// happens, tweets will simply come back without a `card`.
+    pub(crate) fn cards(self) -> Self {
+        self.add_param("include_cards", "true")
+            .add_param("include_card_uri", "true")
+            .add_param("cards_platform", "Web-12")
+    }
+
+    /// Adds the parameters for the options set in the given `PayloadOptions`.
+    pub(crate) fn payload_options(self, options: &PayloadOptions) -> Self {
+        let flag = |value: Option<bool>| value.map(|v| if v { "true" } else { "false" });
+        self.add_opt_param("trim_user", flag(options.trim_user))
+            .add_opt_param("skip_status", flag(options.skip_status))
+            .add_opt_param("include_entities", flag(options.include_entities))
+    }
+
+    /// Adds the given key/value parameter to this `ParamList`, replacing any values already set
+    /// for that key.
     pub fn add_param(
         mut self,
         key: impl Into<Cow<'static, str>>,
@@ -342,7 +394,27 @@ impl ParamList {
         key: impl Into<Cow<'static, str>>,
         value: impl Into<Cow<'static, str>>,
     ) {
-        self.0.insert(key.into(), value.into());
+        self.insert(key.into(), value.into());
+    }
+
+    /// Adds the given key once for each of the given values, keeping any values already set for
+    /// that key.
+    ///
+    /// This is for endpoints that accept a parameter more than once, like `id=1&id=2`. All the
+    /// values are sent and signed in the order they were added.
+    pub fn add_param_multi<V>(
+        mut self,
+        key: impl Into<Cow<'static, str>>,
+        values: impl IntoIterator<Item = V>,
+    ) -> Self
+    where
+        V: Into<Cow<'static, str>>,
+    {
+        let key = key.into();
+        for value in values {
+            self.0.push((key.clone(), value.into()));
+        }
+        self
     }
 
     /// Adds the given `UserID` as a parameter to this `ParamList` by adding either a `user_id` or
@@ -373,15 +445,138 @@ impl ParamList {
         }
     }
 
+    /// Sets the given key to the given value, replacing any values already set for it. Returns the
+    /// first value that was replaced, if any.
+    ///
+    /// If the key was already present, the value takes the place of its first occurrence;
+    /// otherwise it's added at the end.
+    pub fn insert(
+        &mut self,
+        key: impl Into<Cow<'static, str>>,
+        value: impl Into<Cow<'static, str>>,
+    ) -> Option<Cow<'static, str>> {
+        let key = key.into();
+        let value = value.into();
+        match self.0.iter().position(|(k, _)| *k == key) {
+            Some(first) => {
+                let old = std::mem::replace(&mut self.0[first].1, value);
+                let mut index = 0;
+                self.0.retain(|(k, _)| {
+                    let keep = index <= first || *k != key;
+                    index += 1;
+                    keep
+                });
+                Some(old)
+            }
+            None => {
+                self.0.push((key, value));
+                None
+            }
+        }
+    }
+
+    /// Removes every value set for the given key, returning the first one, if any.
+    pub fn remove(&mut self, key: &str) -> Option<Cow<'static, str>> {
+        let first = self.0.iter().position(|(k, _)| k == key)?;
+        let (_, value) = self.0.remove(first);
+        self.0.retain(|(k, _)| k != key);
+        Some(value)
+    }
+
+    /// Returns the first value set for the given key, if any.
+    pub fn get(&self, key: &str) -> Option<&Cow<'static, str>> {
+        self.0.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// Returns every value set for the given key, in the order they were added.
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a Cow<'static, str>> {
+        self.0.iter().filter(move |(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// Returns a mutable reference to the first value set for the given key, if any.
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Cow<'static, str>> {
+        self.0.iter_mut().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// Returns whether any value is set for the given key.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.0.iter().any(|(k, _)| k == key)
+    }
+
+    /// Returns the entry for the given key, to read or change its first value in place.
+    pub fn entry(&mut self, key: impl Into<Cow<'static, str>>) -> ParamEntry<'_> {
+        ParamEntry {
+            params: self,
+            key: key.into(),
+        }
+    }
+
+    /// Returns an iterator over the key/value pairs in this `ParamList`, in the order they were
+    /// added.
+    pub fn iter(&self) -> impl Iterator<Item = (&Cow<'static, str>, &Cow<'static, str>)> {
+        self.0.iter().map(|(k, v)| (k, v))
+    }
+
+    /// Returns an iterator over the key/value pairs in this `ParamList`, in the order they were
+    /// added, with mutable references to the values.
+    pub fn iter_mut(
+        &mut self,
+    ) -> impl Iterator<Item = (&Cow<'static, str>, &mut Cow<'static, str>)> {
+        self.0.iter_mut().map(|(k, v)| (&*k, v))
+    }
+
+    /// Returns an iterator over the keys in this `ParamList`, in the order they were added. Keys
+    /// that were added more than once are returned once for each value.
+    pub fn keys(&self) -> impl Iterator<Item = &Cow<'static, str>> {
+        self.0.iter().map(|(k, _)| k)
+    }
+
+    /// Returns an iterator over the values in this `ParamList`, in the order they were added.
+    pub fn values(&self) -> impl Iterator<Item = &Cow<'static, str>> {
+        self.0.iter().map(|(_, v)| v)
+    }
+
+    /// Returns an iterator over mutable references to the values in this `ParamList`, in the
+    /// order they were added.
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut Cow<'static, str>> {
+        self.0.iter_mut().map(|(_, v)| v)
+    }
+
+    /// Keeps only the key/value pairs for which the given function returns `true`.
+    pub fn retain(
+        &mut self,
+        mut keep: impl FnMut(&Cow<'static, str>, &mut Cow<'static, str>) -> bool,
+    ) {
+        self.0.retain_mut(|(k, v)| keep(k, v));
+    }
+
+    /// Removes every parameter from this `ParamList`.
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    /// Returns the number of key/value pairs in this `ParamList`.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns whether this `ParamList` is empty.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
     /// Merge the parameters from the given `ParamList` into this one.
+    ///
+    /// Keys present in both lists keep all their values, since both sets of parameters are sent
+    /// with the request.
     pub(crate) fn combine(&mut self, other: ParamList) {
         self.0.extend(other.0);
     }
 
     /// Renders this `ParamList` as an `application/x-www-form-urlencoded` string.
     ///
-    /// The key/value pairs are printed as `key1=value1&key2=value2`, with all keys and values
-    /// being percent-encoded according to Twitter's requirements.
+    /// The key/value pairs are printed as `key1=value1&key2=value2`, in the order they were added,
+    /// with all keys and values being percent-encoded according to Twitter's requirements.
     pub fn to_urlencoded(&self) -> String {
         self.0
             .iter()
@@ -391,6 +586,185 @@ impl ParamList {
     }
 }
 
+/// Creates a `ParamList` from the given map. Since the map has no order, the parameters are
+/// sorted by key.
+impl From<HashMap<Cow<'static, str>, Cow<'static, str>>> for ParamList {
+    fn from(map: HashMap<Cow<'static, str>, Cow<'static, str>>) -> ParamList {
+        let mut params = map.into_iter().collect::<Vec<_>>();
+        params.sort();
+        ParamList(params)
+    }
+}
+
+impl<K, V> FromIterator<(K, V)> for ParamList
+where
+    K: Into<Cow<'static, str>>,
+    V: Into<Cow<'static, str>>,
+{
+    /// Collects the given pairs into a `ParamList`, keeping repeated keys.
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> ParamList {
+        ParamList(
+            iter.into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        )
+    }
+}
+
+impl<K, V> Extend<(K, V)> for ParamList
+where
+    K: Into<Cow<'static, str>>,
+    V: Into<Cow<'static, str>>,
+{
+    /// Adds the given pairs to the end of this `ParamList`, keeping repeated keys.
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        self.0
+            .extend(iter.into_iter().map(|(k, v)| (k.into(), v.into())));
+    }
+}
+
+impl IntoIterator for ParamList {
+    type Item = (Cow<'static, str>, Cow<'static, str>);
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+// n.b. this type is re-exported in the `raw` module - these docs are public!
+/// The entry for a key in a `ParamList`, returned by `ParamList::entry`.
+///
+/// Like the entries of a `HashMap`, this reads or changes the value of a key in place. If the key
+/// is set more than once, only its first value is used.
+#[derive(Debug)]
+pub struct ParamEntry<'a> {
+    params: &'a mut ParamList,
+    key: Cow<'static, str>,
+}
+
+impl<'a> ParamEntry<'a> {
+    /// Returns the key of this entry.
+    pub fn key(&self) -> &Cow<'static, str> {
+        &self.key
+    }
+
+    /// Returns the first value for this key, setting it to the given value if the key isn't set.
+    pub fn or_insert(self, default: Cow<'static, str>) -> &'a mut Cow<'static, str> {
+        self.or_insert_with(|| default)
+    }
+
+    /// Returns the first value for this key, setting it to the result of the given function if the
+    /// key isn't set.
+    pub fn or_insert_with<F>(self, default: F) -> &'a mut Cow<'static, str>
+    where
+        F: FnOnce() -> Cow<'static, str>,
+    {
+        let ParamEntry { params, key } = self;
+        let params = &mut params.0;
+        let index = match params.iter().position(|(k, _)| *k == key) {
+            Some(index) => index,
+            None => {
+                params.push((key, default()));
+                params.len() - 1
+            }
+        };
+        &mut params[index].1
+    }
+
+    /// Calls the given function with the first value for this key, if it's set.
+    pub fn and_modify<F>(self, modify: F) -> Self
+    where
+        F: FnOnce(&mut Cow<'static, str>),
+    {
+        if let Some(value) = self.params.get_mut(&self.key) {
+            modify(value);
+        }
+        self
+    }
+}
+
+/// Options to leave parts of a response out, to cut down on the data loaded from Twitter.
+///
+/// Many endpoints that return tweets or users accept flags that trim the response. Programs that
+/// poll Twitter often, and only need part of each tweet or user, can set these to save bandwidth
+/// on every call. A `PayloadOptions` can be given to `Timeline::with_payload_options`,
+/// `CursorIter::with_payload_options`, `tweet::lookup_with_options`, and
+/// `user::lookup_with_options`.
+///
+/// Options that aren't set are left to Twitter's defaults. Endpoints that don't support an option
+/// ignore it.
+///
+/// ```rust,no_run
+/// # use egg_mode::Token;
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let token: Token = unimplemented!();
+/// use egg_mode::PayloadOptions;
+///
+/// let timeline = egg_mode::tweet::home_timeline(&token)
+///     .with_payload_options(PayloadOptions::new().trim_user(true).include_entities(false));
+/// let (timeline, feed) = timeline.start().await.unwrap();
+///
+/// for tweet in feed.iter() {
+///     // the full user is left out, but their ID is still available
+///     println!("{:?}: {}", tweet.user_id, tweet.text);
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PayloadOptions {
+    trim_user: Option<bool>,
+    skip_status: Option<bool>,
+    include_entities: Option<bool>,
+}
+
+impl PayloadOptions {
+    /// Creates a new `PayloadOptions` that leaves every option to Twitter's defaults.
+    pub fn new() -> PayloadOptions {
+        PayloadOptions::default()
+    }
+
+    /// Creates a `PayloadOptions` that trims as much as possible: users in tweets are cut down to
+    /// their ID, statuses are left out of users, and entities are left out of both.
+    pub fn minimal() -> PayloadOptions {
+        PayloadOptions::new()
+            .trim_user(true)
+            .skip_status(true)
+            .include_entities(false)
+    }
+
+    /// Sets whether the users who posted tweets are cut down to their ID.
+    ///
+    /// When set, the `user` field of the loaded tweets is `None`, and only `user_id` is filled in.
+    pub fn trim_user(self, trim_user: bool) -> Self {
+        PayloadOptions {
+            trim_user: Some(trim_user),
+            ..self
+        }
+    }
+
+    /// Sets whether the most recent tweet of each loaded user is left out of their `status`.
+    pub fn skip_status(self, skip_status: bool) -> Self {
+        PayloadOptions {
+            skip_status: Some(skip_status),
+            ..self
+        }
+    }
+
+    /// Sets whether hashtags, links, mentions, and media are loaded into the `entities` of
+    /// tweets and users.
+    ///
+    /// When this is `false`, `entities` is empty. Note that media is still loaded into
+    /// `extended_entities`, if Twitter sends it.
+    pub fn include_entities(self, include_entities: bool) -> Self {
+        PayloadOptions {
+            include_entities: Some(include_entities),
+            ..self
+        }
+    }
+}
+
 // Helper trait to stringify the contents of an Option
 pub(crate) trait MapString {
     fn map_string(&self) -> Option<String>;
@@ -442,6 +816,23 @@ pub fn codepoints_to_bytes(&mut (ref mut start, ref mut end): &mut (usize, usize
     }
 }
 
+///Merges the two given iterators into one, taking the next item from `left` whenever `comp`
+///returns true for the next items of both.
+///
+///If both iterators are sorted according to `comp`, the result is as well.
+pub fn merge_by<Iter, Fun>(left: Iter, right: Iter, comp: Fun) -> MergeBy<Iter, Fun>
+where
+    Iter: Iterator,
+    Fun: FnMut(&Iter::Item, &Iter::Item) -> bool,
+{
+    MergeBy {
+        left: left.peekable(),
+        right: right.peekable(),
+        comp,
+        fused: None,
+    }
+}
+
 ///A clone of MergeBy from Itertools.
 pub struct MergeBy<Iter, Fun>
 where
@@ -486,21 +877,25 @@ where
 }
 
 pub mod serde_datetime {
-    use chrono::TimeZone;
     use serde::de::Error;
     use serde::{Deserialize, Deserializer, Serializer};
 
     const DATE_FORMAT: &str = "%a %b %d %T %z %Y";
 
+    /// Parses a timestamp in either the format used by the v1.1 API or the ISO 8601 format used by
+    /// the v2 API.
+    pub fn parse(s: &str) -> Result<chrono::DateTime<chrono::Utc>, chrono::ParseError> {
+        chrono::DateTime::parse_from_str(s, DATE_FORMAT)
+            .or_else(|e| chrono::DateTime::parse_from_rfc3339(s).map_err(|_| e))
+            .map(|date| date.with_timezone(&chrono::Utc))
+    }
+
     pub fn deserialize<'de, D>(ser: D) -> Result<chrono::DateTime<chrono::Utc>, D::Error>
     where
         D: Deserializer<'de>,
     {
         let s = String::deserialize(ser)?;
-        let date = (chrono::Utc)
-            .datetime_from_str(&s, DATE_FORMAT)
-            .map_err(D::Error::custom)?;
-        Ok(date)
+        parse(&s).map_err(D::Error::custom)
     }
 
     pub fn serialize<S>(src: &chrono::DateTime<chrono::Utc>, ser: S) -> Result<S::Ok, S::Error>
@@ -521,7 +916,7 @@ pub mod serde_via_string {
     where
         D: Deserializer<'de>,
         T: std::str::FromStr,
-        <T as std::str::FromStr>::Err: std::fmt::Display,
+        <T as std::str::FromStr>::Err: fmt::Display,
     {
         let str = String::deserialize(ser)?;
         str.parse().map_err(D::Error::custom)
@@ -536,6 +931,68 @@ pub mod serde_via_string {
     }
 }
 
+pub mod serde_opt_via_string {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use std::fmt;
+
+    pub fn deserialize<'de, D, T>(ser: D) -> Result<Option<T>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: std::str::FromStr,
+        <T as std::str::FromStr>::Err: fmt::Display,
+    {
+        match Option::<String>::deserialize(ser)? {
+            Some(str) => str.parse().map(Some).map_err(D::Error::custom),
+            None => Ok(None),
+        }
+    }
+
+    pub fn serialize<T, S>(src: &Option<T>, ser: S) -> Result<S::Ok, S::Error>
+    where
+        T: fmt::Display,
+        S: Serializer,
+    {
+        match src {
+            Some(src) => ser.collect_str(src),
+            None => ser.serialize_none(),
+        }
+    }
+}
+
+pub mod serde_vec_via_string {
+    use serde::de::Error;
+    use serde::ser::SerializeSeq;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use std::fmt;
+
+    pub fn deserialize<'de, D, T>(ser: D) -> Result<Vec<T>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: std::str::FromStr,
+        <T as std::str::FromStr>::Err: fmt::Display,
+    {
+        Vec::<String>::deserialize(ser)?
+            .iter()
+            .map(|str| str.parse().map_err(D::Error::custom))
+            .collect()
+    }
+
+    pub fn serialize<T, S>(src: &[T], ser: S) -> Result<S::Ok, S::Error>
+    where
+        T: fmt::Display,
+        S: Serializer,
+    {
+        let mut seq = ser.serialize_seq(Some(src.len()))?;
+        for item in src {
+            seq.serialize_element(&item.to_string())?;
+        }
+        seq.end()
+    }
+}
+
 /// Percent-encodes the given string based on the Twitter API specification.
 ///
 /// Twitter bases its encoding scheme on RFC 3986, Section 2.1. They describe the process in full
@@ -547,11 +1004,11 @@ pub mod serde_via_string {
 ///
 /// When this function was originally implemented, the `percent_encoding` crate did not have an
 /// encoding set that matched this, so it was recreated here.
-pub fn percent_encode(src: &str) -> PercentEncode {
+pub fn percent_encode(src: &str) -> PercentEncode<'_> {
     lazy_static::lazy_static! {
         static ref ENCODER: AsciiSet = percent_encoding::NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_').remove(b'~');
     }
-    utf8_percent_encode(src, &*ENCODER)
+    utf8_percent_encode(src, &ENCODER)
 }
 
 #[cfg(test)]
@@ -580,4 +1037,73 @@ pub(crate) mod tests {
         codepoints_to_bytes(&mut range, unicode);
         assert_eq!(&unicode[range.0..range.1], "Iñtërnâtiônàližætiøn ënd");
     }
+
+    #[test]
+    fn test_param_list() {
+        let mut params = ParamList::new()
+            .add_param("b", "1")
+            .add_param_multi("a", ["2", "3"])
+            .add_param("c", "4");
+        assert_eq!(params.to_urlencoded(), "b=1&a=2&a=3&c=4");
+        assert_eq!(params.get("a").unwrap(), "2");
+        assert_eq!(params.get_all("a").collect::<Vec<_>>(), ["2", "3"]);
+
+        assert_eq!(params.insert("a", "5").unwrap(), "2");
+        assert_eq!(params.to_urlencoded(), "b=1&a=5&c=4");
+        assert!(params.insert("d", "6").is_none());
+        assert_eq!(params.remove("b").unwrap(), "1");
+        assert!(!params.contains_key("b"));
+        assert_eq!(params.to_urlencoded(), "a=5&c=4&d=6");
+
+        params.combine(ParamList::new().add_param("a", "7"));
+        assert_eq!(params.len(), 4);
+        assert_eq!(params.get_all("a").count(), 2);
+
+        // the methods it had when it was a `HashMap` still work
+        params.get_mut("c").unwrap().to_mut().push('0');
+        params.entry("a").and_modify(|a| *a = "8".into());
+        params.entry("e").or_insert("9".into());
+        *params.entry("d").or_insert_with(|| "x".into()) += "1";
+        assert_eq!(params.to_urlencoded(), "a=8&c=40&d=61&a=7&e=9");
+        assert_eq!(params.keys().collect::<Vec<_>>(), ["a", "c", "d", "a", "e"]);
+        assert_eq!(
+            params.values().collect::<Vec<_>>(),
+            ["8", "40", "61", "7", "9"]
+        );
+        params.retain(|k, _| k != "a");
+        params.extend([("f", "10")]);
+        assert_eq!(params.to_urlencoded(), "c=40&d=61&e=9&f=10");
+        params.clear();
+        assert!(params.is_empty());
+    }
+
+    #[test]
+    fn test_response_combinators() {
+        let rate_limit = RateLimit {
+            limit: 15,
+            remaining: 10,
+            reset: 1234,
+        };
+        let resp = Response::from_parts(rate_limit, vec![1, 2, 3]);
+
+        let borrowed: Vec<Response<&i32>> = (&resp).into_iter().collect();
+        assert_eq!(borrowed.len(), 3);
+        assert_eq!(borrowed[1].rate_limit_status.remaining, 10);
+        assert_eq!(*borrowed[1].response, 2);
+
+        let other = Response::from_parts(rate_limit, "other");
+        let zipped = Response::zip(resp, other);
+        let (status, (nums, name)) = Response::into_parts(zipped);
+        assert_eq!(status.reset, 1234);
+        assert_eq!(nums, vec![1, 2, 3]);
+        assert_eq!(name, "other");
+    }
+
+    #[test]
+    fn test_datetime_formats() {
+        let legacy = serde_datetime::parse("Tue Dec 31 19:26:16 +0000 2019").unwrap();
+        let iso = serde_datetime::parse("2019-12-31T19:26:16.000Z").unwrap();
+        assert_eq!(legacy, iso);
+        assert!(serde_datetime::parse("yesterday").is_err());
+    }
 }
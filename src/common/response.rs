@@ -2,13 +2,25 @@
 //! Twitter.
 
 use crate::error::Error::{self, *};
-use crate::error::{Result, TwitterErrors};
+use crate::error::{DnsError, PartialError, Result, TwitterErrorCode, TwitterErrors};
 
-use hyper::client::{HttpConnector, ResponseFuture};
+use futures::future::{self, TryFutureExt};
+use futures::stream::{self, Stream, StreamExt};
+use hyper::client::connect::dns::{GaiAddrs, GaiFuture, GaiResolver, Name};
+use hyper::client::ResponseFuture;
+use hyper::service::Service;
 use hyper::{self, Body, Request};
-use serde::{de::DeserializeOwned, Deserialize};
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::Deserialize;
 
 use std::convert::TryFrom;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::RwLock;
+use std::task::{Context, Poll};
+use std::time::Duration;
 
 use super::Headers;
 
@@ -48,9 +60,12 @@ fn rate_limit_reset(headers: &Headers) -> Result<Option<i32>> {
 ///
 ///As this implements `Deref` and `DerefMut`, you can transparently use the contained `response`'s
 ///methods as if they were methods on this struct.
-#[derive(
-    Debug, Deserialize, derive_more::Constructor, derive_more::Deref, derive_more::DerefMut,
-)]
+///
+///Some endpoints return the data they could load alongside an `errors` array describing the parts
+///of the request that failed, for example when some of the requested tweets don't exist. Rather
+///than failing the whole call, these errors are collected into `partial_errors`, so the data that
+///did load can still be used.
+#[derive(Debug, Deserialize, derive_more::Deref, derive_more::DerefMut)]
 pub struct Response<T> {
     /// The latest rate-limit information returned with the request.
     #[serde(flatten)]
@@ -60,9 +75,48 @@ pub struct Response<T> {
     #[deref_mut]
     #[serde(default)]
     pub response: T,
+    /// Errors Twitter returned alongside a successful response, describing parts of the request
+    /// that couldn't be completed. This is empty if the whole request succeeded.
+    #[serde(default)]
+    pub partial_errors: Vec<PartialError>,
+    /// The family of the endpoint that was called, like `statuses` or `friendships`, if this
+    /// response came straight from a call to Twitter.
+    ///
+    /// This is the group Twitter sorts the endpoint under in `service::rate_limit_status`, so it
+    /// can be used to add up rate-limit usage without keeping a table of URLs:
+    ///
+    /// ```rust,no_run
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let token: egg_mode::Token = unimplemented!();
+    /// use std::collections::HashMap;
+    ///
+    /// let mut usage: HashMap<String, i32> = HashMap::new();
+    /// let resp = egg_mode::tweet::show(1261253754969640960, &token).await.unwrap();
+    /// if let Some(family) = &resp.family {
+    ///     *usage.entry(family.clone()).or_default() += 1;
+    ///     println!("{}: {} calls left", family, resp.rate_limit_status.remaining);
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// Responses created with `Response::new`, or loaded from several calls, have no family.
+    #[serde(skip)]
+    pub family: Option<String>,
 }
 
 impl<T> Response<T> {
+    ///Create a new `Response<T>` from rate-limit information and a response, with no partial
+    ///errors.
+    pub fn new(rate_limit_status: RateLimit, response: T) -> Response<T> {
+        Response {
+            rate_limit_status,
+            response,
+            partial_errors: Vec::new(),
+            family: None,
+        }
+    }
+
     ///Convert a `Response<T>` to a `Response<U>` by running its contained response through the
     ///given function. This preserves its rate-limit information.
     ///
@@ -75,6 +129,8 @@ impl<T> Response<T> {
         Response {
             rate_limit_status: src.rate_limit_status,
             response: fun(src.response),
+            partial_errors: src.partial_errors,
+            family: src.family,
         }
     }
 
@@ -91,6 +147,8 @@ impl<T> Response<T> {
         Ok(Response {
             rate_limit_status: src.rate_limit_status,
             response: fun(src.response)?,
+            partial_errors: src.partial_errors,
+            family: src.family,
         })
     }
 
@@ -108,6 +166,70 @@ impl<T> Response<T> {
         Response {
             rate_limit_status: src.rate_limit_status,
             response: src.response.into(),
+            partial_errors: src.partial_errors,
+            family: src.family,
+        }
+    }
+
+    ///Combine two responses into one `Response` containing both of their contents as a tuple.
+    ///
+    ///The rate-limit information and family of `src` are kept, and those of `other` are discarded. If you need
+    ///the rate-limit information of both (for example, if they came from different endpoints), use
+    ///`into_parts` on them instead. The partial errors of both responses are kept.
+    ///
+    ///Note that this is not a member function, so as to not conflict with potential methods on the
+    ///contained `T`.
+    pub fn zip<U>(src: Response<T>, other: Response<U>) -> Response<(T, U)> {
+        let mut partial_errors = src.partial_errors;
+        partial_errors.extend(other.partial_errors);
+        Response {
+            rate_limit_status: src.rate_limit_status,
+            response: (src.response, other.response),
+            partial_errors,
+            family: src.family,
+        }
+    }
+
+    ///Split a `Response<T>` into its rate-limit information and its contained response, discarding
+    ///any partial errors.
+    ///
+    ///Note that this is not a member function, so as to not conflict with potential methods on the
+    ///contained `T`.
+    pub fn into_parts(src: Response<T>) -> (RateLimit, T) {
+        (src.rate_limit_status, src.response)
+    }
+
+    ///Create a `Response<T>` from rate-limit information and a response. This is the inverse of
+    ///`into_parts`.
+    pub fn from_parts(rate_limit_status: RateLimit, response: T) -> Response<T> {
+        Response::new(rate_limit_status, response)
+    }
+
+    ///Convert a `&Response<T>` into a `Response<&T>`, copying its rate-limit information and
+    ///partial errors.
+    ///
+    ///Note that this is not a member function, so as to not conflict with potential methods on the
+    ///contained `T`.
+    pub fn as_ref(src: &Response<T>) -> Response<&T> {
+        Response {
+            rate_limit_status: src.rate_limit_status,
+            response: &src.response,
+            partial_errors: src.partial_errors.clone(),
+            family: src.family.clone(),
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Response<T>
+where
+    &'a T: IntoIterator,
+{
+    type IntoIter = ResponseIter<<&'a T as IntoIterator>::IntoIter>;
+    type Item = Response<<&'a T as IntoIterator>::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        ResponseIter {
+            it: Response::map(Response::as_ref(self), |it| it.into_iter()),
         }
     }
 }
@@ -125,9 +247,9 @@ impl<T: IntoIterator> IntoIterator for Response<T> {
 
 /// Iterator wrapper around a `Response`.
 ///
-/// This type is returned by `Response`'s `IntoIterator` implementation. It uses the `IntoIterator`
-/// implementation of the contained `T`, and copies the rate-limit information to yield individual
-/// `Response<T::Item>` instances.
+/// This type is returned by `Response`'s `IntoIterator` implementations. It uses the `IntoIterator`
+/// implementation of the contained `T` (or `&T`, when iterating over a `&Response<T>`), and copies
+/// the rate-limit information and partial errors to yield individual `Response<T::Item>` instances.
 pub struct ResponseIter<T> {
     it: Response<T>,
 }
@@ -139,6 +261,8 @@ impl<T: Iterator> Iterator for ResponseIter<T> {
         Some(Response {
             rate_limit_status: self.it.rate_limit_status,
             response: self.it.response.next()?,
+            partial_errors: self.it.partial_errors.clone(),
+            family: self.it.family.clone(),
         })
     }
 }
@@ -170,80 +294,618 @@ compile_error!(
 with more than one of these feature flags enabled at the same time"
 );
 
+type HttpConnector = hyper::client::HttpConnector<Resolver>;
+
 #[cfg(feature = "native_tls")]
-fn new_https_connector() -> hyper_tls::HttpsConnector<HttpConnector> {
-    hyper_tls::HttpsConnector::new()
+type HttpsConnector = hyper_tls::HttpsConnector<HttpConnector>;
+
+#[cfg(any(feature = "rustls", feature = "rustls_webpki"))]
+type HttpsConnector = hyper_rustls::HttpsConnector<HttpConnector>;
+
+/// hyper's own DNS resolver, with its errors wrapped in a `DnsError` so they can be recognized
+/// when they come back out of a request.
+#[derive(Clone)]
+struct Resolver(GaiResolver);
+
+impl Service<Name> for Resolver {
+    type Response = GaiAddrs;
+    type Error = DnsError;
+    type Future = future::MapErr<GaiFuture, fn(io::Error) -> DnsError>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<std::result::Result<(), DnsError>> {
+        self.0.poll_ready(cx).map_err(DnsError)
+    }
+
+    fn call(&mut self, name: Name) -> Self::Future {
+        self.0.call(name).map_err(DnsError)
+    }
 }
 
+fn new_http_connector() -> HttpConnector {
+    let mut http = HttpConnector::new_with_resolver(Resolver(GaiResolver::new()));
+    http.enforce_http(false);
+    http
+}
+
+#[cfg(feature = "native_tls")]
+fn new_https_connector() -> HttpsConnector {
+    hyper_tls::HttpsConnector::new_with_connector(new_http_connector())
+}
+
+// hyper-rustls only sets up its TLS configuration for connectors that use hyper's own resolver,
+// so the following mirror its `with_native_roots` and `with_webpki_roots` constructors
+
 #[cfg(feature = "rustls")]
-fn new_https_connector() -> hyper_rustls::HttpsConnector<HttpConnector> {
-    hyper_rustls::HttpsConnector::with_native_roots()
+fn new_https_connector() -> HttpsConnector {
+    let mut config = rustls::ClientConfig::new();
+    config.root_store = match rustls_native_certs::load_native_certs() {
+        Ok(store) | Err((Some(store), _)) => store,
+        Err((None, err)) => panic!("cannot access native cert store: {}", err),
+    };
+    if config.root_store.is_empty() {
+        panic!("no CA certificates found");
+    }
+    new_rustls_connector(config)
 }
 
 #[cfg(feature = "rustls_webpki")]
-fn new_https_connector() -> hyper_rustls::HttpsConnector<HttpConnector> {
-    hyper_rustls::HttpsConnector::with_webpki_roots()
+fn new_https_connector() -> HttpsConnector {
+    let mut config = rustls::ClientConfig::new();
+    config
+        .root_store
+        .add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
+    new_rustls_connector(config)
+}
+
+#[cfg(any(feature = "rustls", feature = "rustls_webpki"))]
+fn new_rustls_connector(mut config: rustls::ClientConfig) -> HttpsConnector {
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    config.ct_logs = Some(&ct_logs::LOGS);
+    (new_http_connector(), config).into()
+}
+
+lazy_static::lazy_static! {
+    static ref SHARED_CLIENT: RwLock<Option<hyper::Client<HttpsConnector>>> = RwLock::new(None);
+}
+
+/// The default limit on the size of a response body: 64 MiB.
+const DEFAULT_MAX_RESPONSE_SIZE: usize = 64 * 1024 * 1024;
+
+static MAX_RESPONSE_SIZE: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_RESPONSE_SIZE);
+
+/// Returns the largest response body egg-mode will read, as set by the installed `ClientConfig`.
+pub(crate) fn max_response_size() -> usize {
+    MAX_RESPONSE_SIZE.load(Ordering::Relaxed)
+}
+
+/// Reads the given response body into memory, failing with `Error::ResponseTooLarge` instead of
+/// reading more than `limit` bytes.
+pub(crate) async fn read_body(mut body: Body, limit: usize) -> Result<Vec<u8>> {
+    use hyper::body::HttpBody;
+
+    // if the response gave a Content-Length, we can fail without reading anything
+    if matches!(HttpBody::size_hint(&body).upper(), Some(len) if len > limit as u64) {
+        return Err(ResponseTooLarge(limit));
+    }
+
+    let mut data = Vec::new();
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk?;
+        if data.len() + chunk.len() > limit {
+            return Err(ResponseTooLarge(limit));
+        }
+        data.extend_from_slice(&chunk);
+    }
+    Ok(data)
+}
+
+/// Returns the client to use for a request: the shared one if a `ClientConfig` has been
+/// installed, otherwise a fresh client with the default settings.
+fn http_client() -> hyper::Client<HttpsConnector> {
+    let shared = SHARED_CLIENT.read().unwrap_or_else(|e| e.into_inner());
+    match *shared {
+        Some(ref client) => client.clone(),
+        None => ClientConfig::default().build_client(),
+    }
+}
+
+// n.b. this type is exported at the crate root - these docs are public!
+/// Settings for the HTTP client egg-mode uses to talk to Twitter.
+///
+/// By default, egg-mode creates a new HTTP client for every call it makes, which means no
+/// connections are reused between calls. This is simple and works regardless of how many async
+/// runtimes you use, but applications making a lot of calls may want to keep connections open
+/// between them. To do that, create a `ClientConfig`, tune it with its builder methods, and call
+/// `install`. From then on, every call made by egg-mode shares one client and one connection
+/// pool, configured with these settings.
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+///
+/// egg_mode::ClientConfig::new()
+///     .pool_max_idle_per_host(8)
+///     .pool_idle_timeout(Some(Duration::from_secs(30)))
+///     .install();
+/// ```
+///
+/// `ClientConfig` also sets the largest response body egg-mode will read into memory, so that a
+/// misbehaving proxy or server can't make a long-running service run out of memory. This limit
+/// applies whether or not a `ClientConfig` has been installed; by default it's 64 MiB, far more
+/// than any Twitter response needs.
+///
+/// Note that pooled connections are driven by the async runtime that opened them. If you install
+/// a `ClientConfig`, make sure all your calls to egg-mode happen on the same runtime, or
+/// connections opened on a runtime that has since shut down will fail when they're reused.
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    pool_max_idle_per_host: usize,
+    pool_idle_timeout: Option<Duration>,
+    http2_only: bool,
+    max_response_size: usize,
+}
+
+impl Default for ClientConfig {
+    fn default() -> ClientConfig {
+        ClientConfig {
+            pool_max_idle_per_host: usize::MAX,
+            pool_idle_timeout: Some(Duration::from_secs(90)),
+            http2_only: false,
+            max_response_size: DEFAULT_MAX_RESPONSE_SIZE,
+        }
+    }
+}
+
+impl ClientConfig {
+    /// Creates a new `ClientConfig` with the default settings.
+    ///
+    /// These are the same settings egg-mode uses if no configuration is installed: no limit on
+    /// idle connections per host, a 90-second idle timeout, HTTP/1.1 with HTTP/2 upgrades
+    /// negotiated per-connection, and a 64 MiB limit on the size of responses.
+    pub fn new() -> ClientConfig {
+        ClientConfig::default()
+    }
+
+    /// Sets the maximum number of idle connections kept open for each host. Setting this to 0
+    /// disables connection reuse entirely.
+    pub fn pool_max_idle_per_host(self, max_idle: usize) -> Self {
+        ClientConfig {
+            pool_max_idle_per_host: max_idle,
+            ..self
+        }
+    }
+
+    /// Sets how long an idle connection is kept open before it's closed. `None` keeps idle
+    /// connections open indefinitely.
+    pub fn pool_idle_timeout(self, timeout: Option<Duration>) -> Self {
+        ClientConfig {
+            pool_idle_timeout: timeout,
+            ..self
+        }
+    }
+
+    /// Sets whether to only use HTTP/2 when connecting to Twitter.
+    pub fn http2_only(self, http2_only: bool) -> Self {
+        ClientConfig { http2_only, ..self }
+    }
+
+    /// Sets the largest response body, in bytes, that egg-mode will read. Calls whose response is
+    /// larger fail with `Error::ResponseTooLarge` as soon as that's known, without reading the
+    /// rest of the response.
+    ///
+    /// For streams, this limits the size of a single message instead of the whole response.
+    pub fn max_response_size(self, bytes: usize) -> Self {
+        ClientConfig {
+            max_response_size: bytes,
+            ..self
+        }
+    }
+
+    /// Builds a client with these settings and sets it as the client used by every call egg-mode
+    /// makes from now on.
+    ///
+    /// Calling this again replaces the previous client. Connections already opened by the
+    /// previous client are not reused.
+    pub fn install(self) {
+        let client = self.build_client();
+        let mut shared = SHARED_CLIENT.write().unwrap_or_else(|e| e.into_inner());
+        *shared = Some(client);
+        MAX_RESPONSE_SIZE.store(self.max_response_size, Ordering::Relaxed);
+    }
+
+    /// Removes any installed `ClientConfig`, returning egg-mode to creating a new client for every
+    /// call.
+    pub fn uninstall() {
+        let mut shared = SHARED_CLIENT.write().unwrap_or_else(|e| e.into_inner());
+        *shared = None;
+        MAX_RESPONSE_SIZE.store(DEFAULT_MAX_RESPONSE_SIZE, Ordering::Relaxed);
+    }
+
+    fn build_client(&self) -> hyper::Client<HttpsConnector> {
+        hyper::Client::builder()
+            .pool_max_idle_per_host(self.pool_max_idle_per_host)
+            .pool_idle_timeout(self.pool_idle_timeout)
+            .http2_only(self.http2_only)
+            .build(new_https_connector())
+    }
 }
 
 // n.b. this function is re-exported in the `raw` module - these docs are public!
 /// Converts the given request into a raw `ResponseFuture` from hyper.
 pub fn get_response(request: Request<Body>) -> ResponseFuture {
-    let connector = new_https_connector();
-    let client = hyper::Client::builder().build(connector);
-    client.request(request)
+    http_client().request(request)
+}
+
+/// The `errors` array of a response body, along with whether the body also contained data.
+#[derive(Deserialize)]
+struct ErrorBody {
+    errors: Vec<PartialError>,
+    #[serde(default)]
+    data: Option<IgnoredAny>,
 }
 
 // n.b. this function is re-exported in the `raw` module - these docs are public!
 /// Loads the given request, parses the headers and response for potential errors given by Twitter,
 /// and returns the headers and raw bytes returned from the response.
+///
+/// If Twitter returned an `errors` array alongside the data of a successful response, the response
+/// is returned as normal, including the `errors` array in the returned bytes.
 pub async fn raw_request(request: Request<Body>) -> Result<(Headers, Vec<u8>)> {
-    let connector = new_https_connector();
-    let client = hyper::Client::builder().build(connector);
-    let resp = client.request(request).await?;
+    let (headers, body, _) = raw_request_partial(request).await?;
+    Ok((headers, body))
+}
+
+/// Performs the same work as `raw_request`, but also returns any errors Twitter returned
+/// alongside the data of a successful response.
+async fn raw_request_partial(
+    request: Request<Body>,
+) -> Result<(Headers, Vec<u8>, Vec<PartialError>)> {
+    let pooled = crate::dispatch::current_token_pool()
+        .and_then(|pool| pool.window(&request).map(|window| (pool, window)));
+
+    let dispatcher = crate::dispatch::current();
+    let permit = match &dispatcher {
+        Some(dispatcher) => Some(dispatcher.acquire(&request).await?),
+        None => None,
+    };
+    let result = send_request(request).await;
+    let rate_limit = match &result {
+        Ok((headers, _, _)) => RateLimit::try_from(headers).ok(),
+        Err(_) => None,
+    };
+    if let (Some(dispatcher), Some(permit)) = (dispatcher, permit) {
+        dispatcher.release(permit, &result, rate_limit);
+    }
+    if let Some((pool, window)) = pooled {
+        pool.record(window, &result, rate_limit);
+    }
+    result
+}
+
+/// Sends the given request and checks the response for errors.
+async fn send_request(request: Request<Body>) -> Result<(Headers, Vec<u8>, Vec<PartialError>)> {
+    let resp = http_client().request(request).await?;
     let (parts, body) = resp.into_parts();
-    let body: Vec<_> = hyper::body::to_bytes(body).await?.to_vec();
-    if let Ok(errors) = serde_json::from_slice::<TwitterErrors>(&body) {
-        if errors.errors.iter().any(|e| e.code == 88)
-            && parts.headers.contains_key(X_RATE_LIMIT_RESET)
-        {
-            return Err(RateLimit(rate_limit_reset(&parts.headers)?.unwrap()));
-        } else {
-            return Err(TwitterError(parts.headers, errors));
+    let body = read_body(body, max_response_size()).await?;
+    let (headers, partial_errors) = check_errors(parts.status, parts.headers, &body)?;
+    Ok((headers, body, partial_errors))
+}
+
+/// Reads the rate-limit information from the headers of a failed response, if Twitter included it.
+///
+/// Malformed headers are treated as missing, so that they don't hide the error itself.
+pub(crate) fn error_rate_limit(headers: &Headers) -> Option<RateLimit> {
+    if !headers.contains_key(X_RATE_LIMIT_LIMIT) {
+        return None;
+    }
+    RateLimit::try_from(headers).ok()
+}
+
+/// Checks the given response for errors, returning the errors that accompanied a successful
+/// response, if any.
+fn check_errors(
+    status: hyper::StatusCode,
+    headers: Headers,
+    body: &[u8],
+) -> Result<(Headers, Vec<PartialError>)> {
+    if let Ok(error_body) = serde_json::from_slice::<ErrorBody>(body) {
+        if status.is_success() && error_body.data.is_some() {
+            return Ok((headers, error_body.errors));
+        }
+
+        // a failed request is only reported as a `TwitterError` if it has error codes to report
+        let codes = error_body
+            .errors
+            .iter()
+            .map(|err| match err {
+                PartialError::Code(err) => Some(err.clone()),
+                PartialError::Problem(_) => None,
+            })
+            .collect::<Option<Vec<TwitterErrorCode>>>();
+        if let Some(errors) = codes {
+            if errors.iter().any(|e| e.code == 88) && headers.contains_key(X_RATE_LIMIT_RESET) {
+                return Err(RateLimit(rate_limit_reset(&headers)?.unwrap()));
+            } else {
+                return Err(TwitterError(headers, TwitterErrors { errors }));
+            }
+        } else if status.is_success() {
+            // a successful status with problems and no data means nothing could be loaded, which
+            // would otherwise surface as a confusing deserialization error
+            return Err(NoData(headers, error_body.errors.into_boxed_slice()));
         }
     }
-    if !parts.status.is_success() {
-        return Err(BadStatus(parts.status));
+    if !status.is_success() {
+        return Err(BadStatus(status, error_rate_limit(&headers)));
+    }
+    Ok((headers, Vec::new()))
+}
+
+/// The progress of a `raw_stream`.
+enum RawStreamState {
+    Opening(Pin<Box<dyn Future<Output = Result<Body>> + Send>>),
+    Body(Body),
+    Done,
+}
+
+// n.b. this function is re-exported in the `raw` module as `stream` - these docs are public!
+/// Loads the given request and returns the response body as a stream of raw chunks.
+///
+/// This uses the same connection handling as the rest of egg-mode, but leaves the response body
+/// alone, so it can be used with streaming endpoints that egg-mode doesn't otherwise support. The
+/// chunks are returned as they arrive from the connection, so a single message from the endpoint
+/// may be split across several chunks, or several messages may share one chunk.
+///
+/// If Twitter returns an error status, the response body is read in full and checked for error
+/// data, and the stream returns the resulting error and ends. The stream also ends after the first
+/// connection error.
+///
+/// ```rust,no_run
+/// use egg_mode::raw::{request_get, stream, ParamList};
+/// use futures::TryStreamExt;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let token: egg_mode::Token = unimplemented!();
+/// let params = ParamList::new().add_param("delimited", "length");
+/// let request = request_get("https://stream.twitter.com/1.1/statuses/sample.json", &token, Some(&params));
+/// let mut chunks = stream(request);
+///
+/// while let Some(chunk) = chunks.try_next().await.unwrap() {
+///     println!("received {} bytes", chunk.len());
+/// }
+/// # }
+/// ```
+pub fn raw_stream(request: Request<Body>) -> impl Stream<Item = Result<Vec<u8>>> + Unpin + Send {
+    body_chunks(open_stream(request))
+}
+
+/// Waits for the given body to be ready, then returns it as a stream of chunks, ending after the
+/// first error.
+fn body_chunks<F>(open: F) -> impl Stream<Item = Result<Vec<u8>>> + Unpin + Send
+where
+    F: Future<Output = Result<Body>> + Send + 'static,
+{
+    let chunks = stream::unfold(
+        RawStreamState::Opening(Box::pin(open)),
+        |state| async move {
+            let mut body = match state {
+                RawStreamState::Opening(open) => match open.await {
+                    Ok(body) => body,
+                    Err(e) => return Some((Err(e), RawStreamState::Done)),
+                },
+                RawStreamState::Body(body) => body,
+                RawStreamState::Done => return None,
+            };
+
+            match body.next().await {
+                Some(Ok(chunk)) => Some((Ok(chunk.to_vec()), RawStreamState::Body(body))),
+                Some(Err(e)) => Some((Err(e.into()), RawStreamState::Done)),
+                None => None,
+            }
+        },
+    );
+
+    Box::pin(chunks)
+}
+
+/// Starts the given request and returns its body, checking the response for errors if it didn't
+/// succeed.
+async fn open_stream(request: Request<Body>) -> Result<Body> {
+    let resp = http_client().request(request).await?;
+    stream_body(resp).await
+}
+
+/// Returns the body of the given streaming response, or the error it contains if it didn't
+/// succeed.
+async fn stream_body(resp: hyper::Response<Body>) -> Result<Body> {
+    if resp.status().is_success() {
+        return Ok(resp.into_body());
     }
-    Ok((parts.headers, body))
+
+    let (parts, body) = resp.into_parts();
+    let body = read_body(body, max_response_size()).await?;
+    let rate_limit = error_rate_limit(&parts.headers);
+    check_errors(parts.status, parts.headers, &body)?;
+    Err(BadStatus(parts.status, rate_limit))
 }
 
 // n.b. this function is re-exported in the `raw` module - these docs are public!
 /// Loads the given request and discards the response body after parsing it for rate-limit and
 /// error information, returning the rate-limit information from the headers.
 pub async fn request_with_empty_response(request: Request<Body>) -> Result<Response<()>> {
-    let (headers, _) = raw_request(request).await?;
+    let family = endpoint_family(request.uri().path()).map(String::from);
+    let (headers, _, partial_errors) = raw_request_partial(request).await?;
     let rate_limit_status = RateLimit::try_from(&headers)?;
     Ok(Response {
         rate_limit_status,
         response: (),
+        partial_errors,
+        family,
     })
 }
 
 // n.b. this function is re-exported in the `raw` module - these docs are public!
 /// Loads the given request and parses the response as JSON into the given type, including
 /// rate-limit headers.
+///
+/// If Twitter returned an `errors` array alongside the data of a successful response, those errors
+/// are placed in the returned `Response`'s `partial_errors`.
 pub async fn request_with_json_response<T: DeserializeOwned>(
     request: Request<Body>,
 ) -> Result<Response<T>> {
-    let (headers, body) = raw_request(request).await?;
+    let family = endpoint_family(request.uri().path()).map(String::from);
+    #[cfg(feature = "strict-fields")]
+    let strict = unknown_field_handler().map(|handler| {
+        let uri = request.uri();
+        let endpoint = format!(
+            "{}{}",
+            uri.authority().map_or("", |a| a.as_str()),
+            uri.path()
+        );
+        (handler, endpoint)
+    });
+    let (headers, body, partial_errors) = raw_request_partial(request).await?;
+    #[cfg(feature = "strict-fields")]
+    let response = match strict {
+        Some((handler, endpoint)) => parse_reporting_unknown(&body, &endpoint, &*handler)?,
+        None => serde_json::from_slice(&body)?,
+    };
+    #[cfg(not(feature = "strict-fields"))]
     let response = serde_json::from_slice(&body)?;
     let rate_limit_status = RateLimit::try_from(&headers)?;
     Ok(Response {
         rate_limit_status,
         response,
+        partial_errors,
+        family,
     })
 }
 
+#[cfg(feature = "strict-fields")]
+type UnknownFieldHandler = std::sync::Arc<dyn Fn(&UnknownField) + Send + Sync>;
+
+#[cfg(feature = "strict-fields")]
+lazy_static::lazy_static! {
+    static ref UNKNOWN_FIELD_HANDLER: RwLock<Option<UnknownFieldHandler>> = RwLock::new(None);
+}
+
+// n.b. this type is re-exported in the `raw` module - these docs are public!
+/// A field in a response from Twitter that egg-mode doesn't load, as given to the handler set
+/// with `set_unknown_field_handler`.
+///
+/// This is only available with the `strict-fields` feature enabled.
+#[cfg(feature = "strict-fields")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownField {
+    /// The endpoint that returned the field, as its host and path, like
+    /// `api.twitter.com/1.1/statuses/show.json`.
+    pub endpoint: String,
+    /// The path to the field within the response, with the names of the fields it's nested in and
+    /// the indices of the arrays it's in, separated by dots, like `user.profile_banner_color` or
+    /// `0.entities.cashtags`.
+    pub path: String,
+}
+
+// n.b. this function is re-exported in the `raw` module - these docs are public!
+/// Calls the given function with every field Twitter returns that egg-mode doesn't load,
+/// replacing any handler already set.
+///
+/// Ordinarily, fields egg-mode doesn't know about are skipped without a word, so that responses
+/// still load when Twitter adds to them. With a handler set, every response that egg-mode parses
+/// into its own types is checked for such fields, and each one is reported to the handler. The
+/// response still loads as usual. This is meant to find new fields in Twitter's responses early;
+/// it makes parsing slower, so it's best left off outside of testing and monitoring.
+///
+/// Fields that are loaded into an `extra` map by the `raw-fields` feature aren't reported, and
+/// neither are the fields of types that egg-mode loads from an intermediate JSON value, like
+/// stream messages.
+///
+/// This is only available with the `strict-fields` feature enabled.
+///
+/// ```rust
+/// egg_mode::raw::set_unknown_field_handler(|field| {
+///     eprintln!("new field in {}: {}", field.endpoint, field.path);
+/// });
+/// ```
+#[cfg(feature = "strict-fields")]
+pub fn set_unknown_field_handler(handler: impl Fn(&UnknownField) + Send + Sync + 'static) {
+    let mut current = UNKNOWN_FIELD_HANDLER
+        .write()
+        .unwrap_or_else(|e| e.into_inner());
+    *current = Some(std::sync::Arc::new(handler));
+}
+
+// n.b. this function is re-exported in the `raw` module - these docs are public!
+/// Stops reporting unknown fields to the handler set with `set_unknown_field_handler`, if any.
+///
+/// This is only available with the `strict-fields` feature enabled.
+#[cfg(feature = "strict-fields")]
+pub fn clear_unknown_field_handler() {
+    let mut current = UNKNOWN_FIELD_HANDLER
+        .write()
+        .unwrap_or_else(|e| e.into_inner());
+    *current = None;
+}
+
+#[cfg(feature = "strict-fields")]
+fn unknown_field_handler() -> Option<UnknownFieldHandler> {
+    UNKNOWN_FIELD_HANDLER
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+/// Parses the given response body as JSON, reporting every field the target type skipped to the
+/// given handler.
+#[cfg(feature = "strict-fields")]
+fn parse_reporting_unknown<T: DeserializeOwned>(
+    body: &[u8],
+    endpoint: &str,
+    handler: &dyn Fn(&UnknownField),
+) -> Result<T> {
+    let mut deser = serde_json::Deserializer::from_slice(body);
+    let response = serde_ignored::deserialize(&mut deser, |path| {
+        handler(&UnknownField {
+            endpoint: endpoint.to_string(),
+            path: path.to_string(),
+        })
+    })?;
+    deser.end()?;
+    Ok(response)
+}
+
+// n.b. this function is re-exported in the `raw` module - these docs are public!
+/// Returns the family of the endpoint at the given URL or path, as given in
+/// `Response::family`.
+///
+/// The family is the first part of the path after the API version, without a `.json` extension,
+/// so both `https://api.twitter.com/1.1/statuses/show.json` and `/1.1/statuses/lookup.json` are
+/// in the `statuses` family, and version 2 endpoints like `/2/tweets/search/recent` are in the
+/// `tweets` family. Returns `None` if the path doesn't name an endpoint.
+///
+/// ```rust
+/// use egg_mode::raw::endpoint_family;
+///
+/// assert_eq!(endpoint_family("https://api.twitter.com/1.1/friendships/show.json"), Some("friendships"));
+/// assert_eq!(endpoint_family("/2/tweets/search/recent"), Some("tweets"));
+/// assert_eq!(endpoint_family("https://api.twitter.com/"), None);
+/// ```
+pub fn endpoint_family(url: &str) -> Option<&str> {
+    let path = match url.find("://") {
+        Some(pos) => {
+            let rest = &url[pos + 3..];
+            &rest[rest.find('/').unwrap_or(rest.len())..]
+        }
+        None => url,
+    };
+    let path = path.split(&['?', '#'][..]).next().unwrap_or("");
+
+    let family = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .find(|segment| !segment.starts_with(|c: char| c.is_ascii_digit()) && *segment != "labs")?;
+    Some(family.trim_end_matches(".json"))
+}
+
 // n.b. this type is exported at the crate root - these docs are public!
 /// Rate limit information returned with a `Response`.
 ///
@@ -272,6 +934,18 @@ pub struct RateLimit {
     pub reset: i32,
 }
 
+impl RateLimit {
+    /// Returns a `RateLimit` with every field set to -1, for when a response didn't include any
+    /// rate-limit information.
+    pub(crate) fn unknown() -> RateLimit {
+        RateLimit {
+            limit: -1,
+            remaining: -1,
+            reset: -1,
+        }
+    }
+}
+
 impl TryFrom<&Headers> for RateLimit {
     type Error = Error;
     fn try_from(headers: &Headers) -> Result<Self> {
@@ -282,3 +956,232 @@ impl TryFrom<&Headers> for RateLimit {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::StatusCode;
+
+    #[test]
+    fn response_size_limit() {
+        use futures::executor::block_on;
+
+        let chunked = || {
+            let chunks: Vec<io::Result<Vec<u8>>> = vec![Ok(vec![1; 6]), Ok(vec![2; 6])];
+            Body::wrap_stream(stream::iter(chunks))
+        };
+        assert_eq!(block_on(read_body(chunked(), 12)).unwrap().len(), 12);
+        assert!(matches!(
+            block_on(read_body(chunked(), 11)),
+            Err(ResponseTooLarge(11))
+        ));
+
+        // bodies with a known length are rejected before they're read
+        assert!(matches!(
+            block_on(read_body(Body::from(vec![0; 20]), 10)),
+            Err(ResponseTooLarge(10))
+        ));
+        assert!(block_on(read_body(Body::empty(), 0)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn raw_stream_chunks() {
+        use futures::executor::block_on;
+
+        let chunks: Vec<io::Result<Vec<u8>>> =
+            vec![Ok(b"{\"a\":".to_vec()), Ok(b"1}\r\n".to_vec())];
+        let resp = hyper::Response::new(Body::wrap_stream(stream::iter(chunks)));
+        let received: Vec<_> = block_on(body_chunks(stream_body(resp)).collect());
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0].as_ref().unwrap(), b"{\"a\":");
+        assert_eq!(received[1].as_ref().unwrap(), b"1}\r\n");
+
+        let body = r#"{"errors": [{"code": 32, "message": "Could not authenticate you."}]}"#;
+        let resp = hyper::Response::builder()
+            .status(StatusCode::UNAUTHORIZED)
+            .body(Body::from(body))
+            .unwrap();
+        let received: Vec<_> = block_on(body_chunks(stream_body(resp)).collect());
+        assert_eq!(received.len(), 1);
+        match &received[0] {
+            Err(TwitterError(_, errors)) => assert_eq!(errors.errors[0].code, 32),
+            other => panic!("expected TwitterError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn client_config() {
+        let defaults = ClientConfig::new();
+        assert_eq!(defaults.pool_max_idle_per_host, usize::MAX);
+        assert_eq!(defaults.pool_idle_timeout, Some(Duration::from_secs(90)));
+        assert!(!defaults.http2_only);
+        assert_eq!(defaults.max_response_size, DEFAULT_MAX_RESPONSE_SIZE);
+
+        let config = ClientConfig::new()
+            .pool_max_idle_per_host(4)
+            .pool_idle_timeout(None)
+            .http2_only(true)
+            .max_response_size(DEFAULT_MAX_RESPONSE_SIZE * 2);
+        assert_eq!(config.pool_max_idle_per_host, 4);
+        assert_eq!(config.pool_idle_timeout, None);
+        assert!(config.http2_only);
+
+        // other tests may read the limit while this runs, so only ever raise it
+        config.install();
+        assert!(SHARED_CLIENT.read().unwrap().is_some());
+        assert_eq!(max_response_size(), DEFAULT_MAX_RESPONSE_SIZE * 2);
+
+        ClientConfig::uninstall();
+        assert!(SHARED_CLIENT.read().unwrap().is_none());
+        assert_eq!(max_response_size(), DEFAULT_MAX_RESPONSE_SIZE);
+    }
+
+    #[test]
+    fn endpoint_families() {
+        assert_eq!(
+            endpoint_family("https://api.twitter.com/1.1/statuses/show.json?id=20"),
+            Some("statuses")
+        );
+        assert_eq!(
+            endpoint_family("https://upload.twitter.com/1.1/media/upload.json"),
+            Some("media")
+        );
+        assert_eq!(
+            endpoint_family("https://api.twitter.com/labs/2/tweets/search"),
+            Some("tweets")
+        );
+        assert_eq!(
+            endpoint_family("/1.1/lists/members/show.json"),
+            Some("lists")
+        );
+        assert_eq!(endpoint_family("/oauth/request_token"), Some("oauth"));
+        assert_eq!(endpoint_family("https://api.twitter.com"), None);
+        assert_eq!(endpoint_family("/1.1/"), None);
+    }
+
+    #[test]
+    fn partial_errors() {
+        let body = br#"{
+            "data": [{"id": "20", "text": "just setting up my twttr"}],
+            "errors": [{"code": 144, "message": "No status found with that ID."}]
+        }"#;
+        let (_, errors) = check_errors(StatusCode::OK, Headers::new(), body).unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code(), Some(144));
+
+        // the response to `GET /2/tweets?ids=1261326399320715264,1276230436478386177`, from
+        // Twitter's documentation
+        let body = br#"{
+            "data": [{
+                "id": "1261326399320715264",
+                "text": "Tune in to the @MongoDB @Twitch stream featuring our very own @suhemparack to talk about Twitter Developer Labs - right now! https://t.co/fAWpYi3o5O"
+            }],
+            "errors": [{
+                "value": "1276230436478386177",
+                "detail": "Could not find tweet with ids: [1276230436478386177].",
+                "title": "Not Found Error",
+                "resource_type": "tweet",
+                "parameter": "ids",
+                "resource_id": "1276230436478386177",
+                "type": "https://api.twitter.com/2/problems/resource-not-found"
+            }]
+        }"#;
+        let (_, errors) = check_errors(StatusCode::OK, Headers::new(), body).unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code(), None);
+        match &errors[0] {
+            PartialError::Problem(problem) => {
+                assert_eq!(problem.title, "Not Found Error");
+                assert_eq!(
+                    problem.problem_type,
+                    "https://api.twitter.com/2/problems/resource-not-found"
+                );
+                assert_eq!(problem.resource_type.as_deref(), Some("tweet"));
+                assert_eq!(problem.resource_id.as_deref(), Some("1276230436478386177"));
+                assert_eq!(problem.parameter.as_deref(), Some("ids"));
+            }
+            other => panic!("expected a Problem, got {:?}", other),
+        }
+
+        let body = br#"{"errors": [{"code": 144, "message": "No status found with that ID."}]}"#;
+        match check_errors(StatusCode::NOT_FOUND, Headers::new(), body) {
+            Err(TwitterError(_, errors)) => assert_eq!(errors.errors[0].code, 144),
+            other => panic!("expected TwitterError, got {:?}", other.map(|(_, e)| e)),
+        }
+
+        let body = br#"[{"id": 20}]"#;
+        let (_, errors) = check_errors(StatusCode::OK, Headers::new(), body).unwrap();
+        assert!(errors.is_empty());
+
+        // when none of the request succeeded, v2 endpoints still return 200, with only problems
+        let body = br#"{
+            "errors": [{
+                "value": "1276230436478386177",
+                "detail": "Could not find tweet with ids: [1276230436478386177].",
+                "title": "Not Found Error",
+                "type": "https://api.twitter.com/2/problems/resource-not-found"
+            }]
+        }"#;
+        match check_errors(StatusCode::OK, Headers::new(), body) {
+            Err(NoData(_, errors)) => {
+                assert_eq!(errors.len(), 1);
+                assert!(
+                    matches!(&errors[0], PartialError::Problem(p) if p.title == "Not Found Error")
+                );
+            }
+            other => panic!("expected NoData, got {:?}", other.map(|(_, e)| e)),
+        }
+    }
+
+    #[cfg(feature = "strict-fields")]
+    #[test]
+    fn report_unknown_fields() {
+        #[derive(Deserialize)]
+        struct Known {
+            #[allow(dead_code)]
+            id: u64,
+        }
+
+        let fields = std::cell::RefCell::new(Vec::new());
+        let body = br#"[{"id": 1}, {"id": 2, "new_field": {"nested": true}}]"#;
+        let parsed: Vec<Known> = parse_reporting_unknown(body, "api.twitter.com/show", &|field| {
+            fields.borrow_mut().push(field.clone())
+        })
+        .unwrap();
+
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(
+            fields.into_inner(),
+            vec![UnknownField {
+                endpoint: "api.twitter.com/show".into(),
+                path: "1.new_field".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn error_rate_limits() {
+        let mut headers = Headers::new();
+        headers.insert(X_RATE_LIMIT_LIMIT, "900".parse().unwrap());
+        headers.insert(X_RATE_LIMIT_REMAINING, "899".parse().unwrap());
+        headers.insert(X_RATE_LIMIT_RESET, "1600000000".parse().unwrap());
+
+        let body = br#"{"errors": [{"code": 50, "message": "User not found."}]}"#;
+        let err = check_errors(StatusCode::NOT_FOUND, headers.clone(), body).unwrap_err();
+        let rate_limit = err.rate_limit().unwrap();
+        assert_eq!(rate_limit.limit, 900);
+        assert_eq!(rate_limit.remaining, 899);
+        assert_eq!(rate_limit.reset, 1600000000);
+
+        let err = check_errors(StatusCode::FORBIDDEN, headers, b"").unwrap_err();
+        match err {
+            BadStatus(StatusCode::FORBIDDEN, Some(rate_limit)) => {
+                assert_eq!(rate_limit.remaining, 899)
+            }
+            other => panic!("expected BadStatus, got {:?}", other),
+        }
+
+        let err = check_errors(StatusCode::FORBIDDEN, Headers::new(), b"").unwrap_err();
+        assert!(err.rate_limit().is_none());
+    }
+}
@@ -225,6 +225,37 @@ impl Cursor for ListCursor {
 /// }
 /// # }
 /// ```
+///
+/// ## Resuming an interrupted crawl
+///
+/// Loading every follower of a large account can take many rate-limit windows, so it's worth being
+/// able to pick up where you left off if your process restarts. The `checkpoint` method returns a
+/// cursor ID that can be saved, and `resume_from` starts a new `CursorIter` from that point:
+///
+/// ```rust,no_run
+/// # use egg_mode::Token;
+/// use futures::StreamExt;
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let token: Token = unimplemented!();
+/// # fn load_checkpoint() -> Option<i64> { None }
+/// # fn save_checkpoint(_: i64) {}
+/// let mut followers = egg_mode::user::followers_ids("rustlang", &token).with_page_size(5000);
+/// if let Some(cursor) = load_checkpoint() {
+///     followers = followers.resume_from(cursor);
+/// }
+///
+/// while let Some(id) = followers.next().await {
+///     let id = id.unwrap();
+///     // ... process the ID ...
+///     save_checkpoint(followers.checkpoint());
+/// }
+/// # }
+/// ```
+///
+/// The checkpoint refers to the page that is currently being read, so a resumed `CursorIter` will
+/// start at the beginning of that page. This means some items may be seen twice across a restart,
+/// but none will be skipped.
 #[must_use = "cursor iterators are lazy and do nothing unless consumed"]
 pub struct CursorIter<T>
 where
@@ -253,6 +284,7 @@ where
     ///implementation. It is made available for those who wish to manually manage network calls and
     ///pagination.
     pub next_cursor: i64,
+    page_cursor: i64,
     loader: Option<FutureResponse<T>>,
     iter: Option<Box<dyn Iterator<Item = Response<T::Item>> + Send>>,
 }
@@ -275,6 +307,7 @@ where
                 page_size: Some(page_size),
                 previous_cursor: -1,
                 next_cursor: -1,
+                page_cursor: -1,
                 loader: None,
                 iter: None,
                 ..self
@@ -284,21 +317,71 @@ where
         }
     }
 
+    ///Trims the results loaded by this `CursorIter` with the given `PayloadOptions`.
+    ///
+    ///This is mainly useful for the lists of users, where `skip_status` and `include_entities`
+    ///can cut down the size of each page considerably. Calling this function will invalidate any
+    ///current results, if any were previously loaded.
+    pub fn with_payload_options(self, options: PayloadOptions) -> CursorIter<T> {
+        let params = self
+            .params_base
+            .clone()
+            .unwrap_or_default()
+            .payload_options(&options);
+        CursorIter {
+            params_base: Some(params),
+            previous_cursor: -1,
+            next_cursor: -1,
+            page_cursor: -1,
+            loader: None,
+            iter: None,
+            ..self
+        }
+    }
+
+    ///Returns a cursor ID that can be passed to `resume_from` to continue loading results from
+    ///the page currently being read by the `Stream` implementation.
+    ///
+    ///Resuming from this cursor may return some results that were already yielded from the current
+    ///page, but it will never skip any. Before any results have been loaded, this returns `-1`,
+    ///which refers to the first page.
+    pub fn checkpoint(&self) -> i64 {
+        self.page_cursor
+    }
+
+    ///Sets this `CursorIter` to begin loading results from the given cursor ID, as saved from
+    ///`checkpoint` or from the `next_cursor` field of an earlier run.
+    ///
+    ///Calling this function will invalidate any current results, if any were previously loaded.
+    ///Since `with_page_size` resets the cursor, call it before calling this function.
+    pub fn resume_from(self, cursor: i64) -> CursorIter<T> {
+        CursorIter {
+            previous_cursor: -1,
+            next_cursor: cursor,
+            page_cursor: cursor,
+            loader: None,
+            iter: None,
+            ..self
+        }
+    }
+
     ///Loads the next page of results.
     ///
     ///This is intended to be used as part of this struct's Iterator implementation. It is provided
     ///as a convenience for those who wish to manage network calls and pagination manually.
     pub fn call(&self) -> impl Future<Output = Result<Response<T>>> {
-        let params = self
-            .params_base
+        let req = get(self.link, &self.token, Some(&self.page_params()));
+        request_with_json_response(req)
+    }
+
+    ///Returns the parameters for loading the page at `next_cursor`.
+    fn page_params(&self) -> ParamList {
+        self.params_base
             .as_ref()
             .cloned()
             .unwrap_or_default()
             .add_param("cursor", self.next_cursor.to_string())
-            .add_opt_param("count", self.page_size.map_string());
-
-        let req = get(self.link, &self.token, Some(&params));
-        request_with_json_response(req)
+            .add_opt_param("count", self.page_size.map_string())
     }
 
     ///Creates a new instance of CursorIter, with the given parameters and empty initial results.
@@ -318,6 +401,7 @@ where
             page_size,
             previous_cursor: -1,
             next_cursor: -1,
+            page_cursor: -1,
             loader: None,
             iter: None,
         }
@@ -343,12 +427,8 @@ where
                     self.next_cursor = resp.next_cursor_id();
 
                     let resp = Response::map(resp, |r| r.into_inner());
-                    let rate = resp.rate_limit_status;
 
-                    let mut iter = Box::new(resp.response.into_iter().map(move |item| Response {
-                        rate_limit_status: rate,
-                        response: item,
-                    }));
+                    let mut iter = Box::new(resp.into_iter());
                     let first = iter.next();
                     self.iter = Some(iter);
 
@@ -369,7 +449,55 @@ where
             }
         }
 
+        self.page_cursor = self.next_cursor;
         self.loader = Some(Box::pin(self.call()));
         self.poll_next(cx)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{CursorIter, IDCursor};
+    use crate::auth::Token;
+    use crate::common::ParamList;
+
+    #[test]
+    fn resume_cursor() {
+        let token = Token::Bearer("token".to_string());
+        let params = ParamList::new().add_param("screen_name", "rustlang");
+        let ids = CursorIter::<IDCursor>::new("ids", &token, Some(params), Some(500));
+        assert_eq!(ids.checkpoint(), -1);
+        assert_eq!(ids.page_params().get("cursor").unwrap(), "-1");
+
+        let ids = ids.with_page_size(5000).resume_from(1374004777531007833);
+        assert_eq!(ids.checkpoint(), 1374004777531007833);
+        let params = ids.page_params();
+        assert_eq!(params.get("cursor").unwrap(), "1374004777531007833");
+        assert_eq!(params.get("count").unwrap(), "5000");
+        assert_eq!(params.get("screen_name").unwrap(), "rustlang");
+
+        // changing the page size starts over from the first page
+        let ids = ids.with_page_size(100);
+        assert_eq!(ids.checkpoint(), -1);
+        assert_eq!(ids.page_params().get("cursor").unwrap(), "-1");
+    }
+
+    #[test]
+    fn parse_id_cursor() {
+        use super::Cursor;
+
+        let page: IDCursor = serde_json::from_str(
+            r#"{
+                "ids": [657693, 183709371],
+                "next_cursor": 1374004777531007833,
+                "next_cursor_str": "1374004777531007833",
+                "previous_cursor": 0,
+                "previous_cursor_str": "0"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(page.next_cursor_id(), 1374004777531007833);
+        assert_eq!(page.previous_cursor_id(), 0);
+        assert_eq!(page.into_inner(), vec![657693, 183709371]);
+    }
+}
@@ -10,7 +10,26 @@ use crate::{auth, links};
 use super::*;
 
 /// Lookup a single DM by its numeric ID.
-pub async fn show(id: u64, token: &auth::Token) -> Result<Response<DirectMessage>, error::Error> {
+pub async fn show<T: Into<DmId>>(
+    id: T,
+    token: &auth::Token,
+) -> Result<Response<DirectMessage>, error::Error> {
+    let id: DmId = id.into();
+    let params = ParamList::default().add_param("id", id.to_string());
+    let req = get(links::direct::SHOW, token, Some(&params));
+    let resp: Response<raw::SingleEvent> = request_with_json_response(req).await?;
+    Response::try_map(resp, DirectMessage::try_from)
+}
+
+/// Lookup a single Direct Message event by its numeric ID.
+///
+/// This is the same as `show`, except that if the event isn't a direct message, it's returned as
+/// `DirectMessageEvent::Other` instead of failing with an `InvalidResponse` error.
+pub async fn show_event<T: Into<DmId>>(
+    id: T,
+    token: &auth::Token,
+) -> Result<Response<DirectMessageEvent>, error::Error> {
+    let id: DmId = id.into();
     let params = ParamList::default().add_param("id", id.to_string());
     let req = get(links::direct::SHOW, token, Some(&params));
     let resp: Response<raw::SingleEvent> = request_with_json_response(req).await?;
@@ -27,6 +46,33 @@ pub fn list(token: &auth::Token) -> Timeline {
     Timeline::new(links::direct::LIST, token.clone())
 }
 
+/// Load the direct messages between the authorized user and the given user.
+///
+/// This is the same as `list(token).with_participant(user_id)`. Twitter doesn't offer a way to
+/// load a single conversation, so this still loads every page of the authorized user's messages
+/// from the last 30 days, but only returns the messages from this conversation. Stop loading pages
+/// once you've found the messages you need to avoid going through the rest. For more information,
+/// see the docs for [`Timeline::with_participant`].
+///
+/// [`Timeline::with_participant`]: struct.Timeline.html#method.with_participant
+///
+/// ```no_run
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let token: egg_mode::Token = unimplemented!();
+/// let mut timeline = egg_mode::direct::with_user(783214, &token);
+/// let mut messages = timeline.start().await.unwrap().response;
+///
+/// // keep going until at least 10 messages from this conversation have loaded
+/// while messages.len() < 10 && timeline.has_more() {
+///     messages.extend(timeline.next_page().await.unwrap().response);
+/// }
+/// # }
+/// ```
+pub fn with_user(user_id: u64, token: &auth::Token) -> Timeline {
+    list(token).with_participant(user_id)
+}
+
 /// Delete the direct message with the given ID.
 ///
 /// The authenticated user must be the sender of this DM for this call to be successful.
@@ -36,7 +82,11 @@ pub fn list(token: &auth::Token) -> Timeline {
 ///
 /// Twitter does not return anything upon a successful deletion, so this function will return an
 /// empty `Response` upon success.
-pub async fn delete(id: u64, token: &auth::Token) -> Result<Response<()>, error::Error> {
+pub async fn delete<T: Into<DmId>>(
+    id: T,
+    token: &auth::Token,
+) -> Result<Response<()>, error::Error> {
+    let id: DmId = id.into();
     let params = ParamList::new().add_param("id", id.to_string());
     let req = auth::raw::delete(links::direct::DELETE, token, Some(&params));
     request_with_empty_response(req).await
@@ -54,10 +104,11 @@ pub async fn delete(id: u64, token: &auth::Token) -> Result<Response<()>, error:
 /// separate user lookup will occur prior to sending the read receipt. To avoid this extra lookup,
 /// pass a numeric ID (or the `UserID::ID` variant of `UserID`) to this function.
 pub async fn mark_read(
-    id: u64,
+    id: impl Into<DmId>,
     sender: impl Into<UserID>,
     token: &auth::Token,
 ) -> Result<Response<()>, error::Error> {
+    let id: DmId = id.into();
     let recipient_id = match sender.into() {
         UserID::ID(id) => id,
         UserID::ScreenName(name) => {
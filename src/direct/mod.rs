@@ -17,17 +17,26 @@
 //! ## Types
 //!
 //! * `DirectMessage`: The primary representation of a DM as retrieved from Twitter. Contains the
-//!   types `DMEntities`/`Cta`/`QuickReply` as fields.
+//!   types `DMEntities`/`Cta`/`QuickReplyRequest`/`QuickReplyResponse` as fields.
 //! * `Timeline`: Returned by `list`, this is how you load a user's Direct Messages. Contains
 //!   adapters to consume the collection as a `Stream` or to load it into a `DMConversations`
 //!   collection.
 //! * `DraftMessage`: As DMs have many optional parameters when creating them, this builder struct
 //!   allows you to build up a DM before sending it.
+//! * `ConversationView`/`MessageOrder`: Returned by `Timeline::into_conversation_views`, a
+//!   conversation between the authenticated user and another user, with its messages in order.
+//! * `DirectMessageEvent`: Returned by `show_event` and `Timeline::next_page_events`, either a
+//!   `DirectMessage` or another type of event, kept as raw JSON.
+//! * `DmId`: The numeric ID of a DM. Functions that take a DM's ID accept anything that converts
+//!   into this type, including plain `u64`s.
 //!
 //! ## Functions
 //!
 //! * `list`: This creates a `Timeline` struct to load a user's Direct Messages.
+//! * `with_user`: This creates a `Timeline` struct to load the authenticated user's conversation
+//!   with one other user.
 //! * `show`: This allows you to load a single DM from its ID.
+//! * `show_event`: This allows you to load a single event from its ID, even if it's not a DM.
 //! * `delete`: This allows you to delete a DM from a user's own views. Note that it will not
 //!   delete it entirely from the system; the recipient will still have a copy of the message.
 //! * `mark_read`: This sends a read receipt for a given message to a given user. This also has the
@@ -37,6 +46,8 @@
 
 use std::borrow::Cow;
 use std::collections::{HashMap, VecDeque};
+use std::convert::TryFrom;
+use std::fmt;
 use std::future::Future;
 
 use chrono;
@@ -55,6 +66,48 @@ pub(crate) mod raw;
 
 pub use self::fun::*;
 
+/// The numeric ID of a direct message.
+///
+/// Functions in this module that take the ID of a DM accept any type that converts into `DmId`,
+/// so that it can't be confused with a tweet or user ID while it's being passed around. Since
+/// `DmId` can also be created from a `u64` or `&u64`, code that passes plain numbers to these
+/// functions continues to work. See [`TweetId`] for the equivalent type for tweets.
+///
+/// [`TweetId`]: ../tweet/struct.TweetId.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct DmId(pub u64);
+
+impl From<u64> for DmId {
+    fn from(id: u64) -> DmId {
+        DmId(id)
+    }
+}
+
+impl<'a> From<&'a u64> for DmId {
+    fn from(id: &'a u64) -> DmId {
+        DmId(*id)
+    }
+}
+
+impl<'a> From<&'a DmId> for DmId {
+    fn from(id: &'a DmId) -> DmId {
+        *id
+    }
+}
+
+impl From<DmId> for u64 {
+    fn from(id: DmId) -> u64 {
+        id.0
+    }
+}
+
+impl fmt::Display for DmId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
 // TODO is this enough? i'm not sure if i want a field-by-field breakdown like with Tweet
 /// Represents a single direct message.
 #[derive(Debug)]
@@ -71,16 +124,15 @@ pub struct DirectMessage {
     pub attachment: Option<entities::MediaEntity>,
     /// A list of "call to action" buttons attached to the DM, if present.
     pub ctas: Option<Vec<Cta>>,
-    /// A list of "Quick Replies" sent with this message to request structured input from the
-    /// recipient.
+    /// The "Quick Reply" sent with this message to request structured input from the recipient,
+    /// if present.
     ///
-    /// Note that there is no way to select a Quick Reply as a response in the public API; a
-    /// `quick_reply_response` can only be populated if the Quick Reply was selected in the Twitter
+    /// Note that there is no way to respond to a Quick Reply in the public API; a
+    /// `quick_reply_response` can only be populated if the Quick Reply was answered in the Twitter
     /// Web Client, or Twitter for iOS/Android.
-    pub quick_replies: Option<Vec<QuickReply>>,
-    /// The `metadata` accompanying a Quick Reply, if the sender selected a Quick Reply for their
-    /// response.
-    pub quick_reply_response: Option<String>,
+    pub quick_reply: Option<QuickReplyRequest>,
+    /// The sender's answer to a Quick Reply, if this message was sent in response to one.
+    pub quick_reply_response: Option<QuickReplyResponse>,
     /// The ID of the user who sent the DM.
     ///
     /// To load full user information for the sender or recipient, use `user::show`. Note that
@@ -98,27 +150,96 @@ pub struct DirectMessage {
     /// Twitter may show a message with a user that doesn't exist if that user has been suspended
     /// or has deleted their account.
     pub recipient_id: u64,
+    /// Any fields in the DM's `message_data` that egg-mode doesn't load into the other fields of
+    /// this struct.
+    ///
+    /// This is only available with the `raw-fields` feature enabled. It can be used to access data
+    /// that Twitter has added to its responses before egg-mode supports it.
+    #[cfg(feature = "raw-fields")]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
-impl From<raw::SingleEvent> for DirectMessage {
-    fn from(ev: raw::SingleEvent) -> DirectMessage {
-        let raw::SingleEvent { event, apps } = ev;
-        let raw: raw::RawDirectMessage = event.as_raw_dm();
-        raw.into_dm(&apps)
+impl TryFrom<raw::SingleEvent> for DirectMessage {
+    type Error = error::Error;
+
+    fn try_from(ev: raw::SingleEvent) -> Result<DirectMessage, error::Error> {
+        match DirectMessageEvent::from(ev) {
+            DirectMessageEvent::Message(dm) => Ok(dm),
+            DirectMessageEvent::Other(_) => Err(error::Error::InvalidResponse(
+                "expected a message_create event",
+                None,
+            )),
+        }
     }
 }
 
 impl From<raw::EventCursor> for Vec<DirectMessage> {
     fn from(evs: raw::EventCursor) -> Vec<DirectMessage> {
-        let raw::EventCursor { events, apps, .. } = evs;
-        let mut ret = vec![];
+        Vec::<DirectMessageEvent>::from(evs)
+            .into_iter()
+            .filter_map(DirectMessageEvent::into_message)
+            .collect()
+    }
+}
+
+/// An event from the Direct Message API: either a direct message, or an event egg-mode doesn't
+/// load into its own type.
+///
+/// Twitter delivers direct messages as `message_create` events, alongside which the Account
+/// Activity API can include other kinds of events, and may add new ones in the future. The
+/// functions that return `DirectMessage`s leave those out; the functions that return
+/// `DirectMessageEvent`s keep them as the JSON Twitter sent, so they aren't lost.
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum DirectMessageEvent {
+    /// A direct message, from a `message_create` event.
+    Message(DirectMessage),
+    /// Any other type of event, as the JSON object Twitter sent for it. Its `type` field names
+    /// the type of event.
+    Other(serde_json::Value),
+}
 
-        for ev in events {
-            let raw: raw::RawDirectMessage = ev.as_raw_dm();
-            ret.push(raw.into_dm(&apps));
+impl DirectMessageEvent {
+    /// Returns the direct message in this event, if it's a `message_create` event.
+    pub fn into_message(self) -> Option<DirectMessage> {
+        match self {
+            DirectMessageEvent::Message(dm) => Some(dm),
+            DirectMessageEvent::Other(_) => None,
         }
+    }
+
+    /// Returns the type of this event, like `message_create`.
+    pub fn event_type(&self) -> &str {
+        match self {
+            DirectMessageEvent::Message(_) => "message_create",
+            DirectMessageEvent::Other(event) => event
+                .get("type")
+                .and_then(|t| t.as_str())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl From<raw::SingleEvent> for DirectMessageEvent {
+    fn from(ev: raw::SingleEvent) -> DirectMessageEvent {
+        let raw::SingleEvent { event, apps } = ev;
+        match event {
+            raw::EventType::MessageCreate(dm) => DirectMessageEvent::Message(dm.into_dm(&apps)),
+            raw::EventType::Other(event) => DirectMessageEvent::Other(event),
+        }
+    }
+}
 
-        ret
+impl From<raw::EventCursor> for Vec<DirectMessageEvent> {
+    fn from(evs: raw::EventCursor) -> Vec<DirectMessageEvent> {
+        let raw::EventCursor { events, apps, .. } = evs;
+        events
+            .into_iter()
+            .map(|ev| match ev {
+                raw::EventType::MessageCreate(dm) => DirectMessageEvent::Message(dm.into_dm(&apps)),
+                raw::EventType::Other(event) => DirectMessageEvent::Other(event),
+            })
+            .collect()
     }
 }
 
@@ -172,7 +293,7 @@ struct DraftCta {
 /// [`DraftMessage`].
 ///
 /// [`DraftMessage`]: struct.DraftMessage.html
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QuickReply {
     /// The label shown to the user. When the user selects this Quick Reply, the label will be sent
     /// as the `text` of the reply message.
@@ -184,6 +305,111 @@ pub struct QuickReply {
     pub metadata: String,
 }
 
+/// The structured input requested by a Quick Reply.
+///
+/// For more information about the kinds of Quick Replies, see the `quick_reply_option`,
+/// `quick_reply_location`, and `quick_reply_text_input` functions on [`DraftMessage`].
+///
+/// [`DraftMessage`]: struct.DraftMessage.html
+#[derive(Debug, Clone)]
+pub enum QuickReplyRequest {
+    /// The recipient is asked to select one of the given options.
+    Options(Vec<QuickReply>),
+    /// The recipient is asked to share a location.
+    Location {
+        /// Metadata that accompanies this Quick Reply, returned with the response.
+        metadata: Option<String>,
+    },
+    /// The recipient is asked to type a response.
+    TextInput {
+        /// The placeholder shown in the recipient's input field.
+        label: Option<String>,
+        /// The kind of keyboard shown to the recipient.
+        keyboard: TextInputKeyboard,
+        /// Metadata that accompanies this Quick Reply, returned with the response.
+        metadata: Option<String>,
+    },
+}
+
+impl QuickReplyRequest {
+    /// Formats this Quick Reply as the `quick_reply` object of a new message.
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            QuickReplyRequest::Options(options) => serde_json::json!({
+                "type": "options",
+                "options": options,
+            }),
+            QuickReplyRequest::Location { metadata } => serde_json::json!({
+                "type": "location",
+                "location": {
+                    "metadata": metadata,
+                },
+            }),
+            QuickReplyRequest::TextInput {
+                label,
+                keyboard,
+                metadata,
+            } => serde_json::json!({
+                "type": "text_input",
+                "text_input": {
+                    "keyboard": keyboard,
+                    "label": label,
+                    "metadata": metadata,
+                },
+            }),
+        }
+    }
+}
+
+/// The kind of keyboard shown for a Text Input Quick Reply.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextInputKeyboard {
+    /// The regular keyboard for the recipient's device.
+    Default,
+    /// A keyboard for entering numbers.
+    Number,
+}
+
+/// A recipient's answer to a Quick Reply.
+#[derive(Debug, Clone, PartialEq)]
+pub enum QuickReplyResponse {
+    /// The sender selected one of the given options. The option's label is the message's `text`.
+    Options {
+        /// The `metadata` of the selected option.
+        metadata: String,
+    },
+    /// The sender shared a location.
+    Location {
+        /// The `metadata` of the Quick Reply, if it had any.
+        metadata: Option<String>,
+        /// The location the sender shared.
+        location: SharedLocation,
+    },
+    /// The sender typed a response. The typed text is the message's `text`.
+    TextInput {
+        /// The `metadata` of the Quick Reply, if it had any.
+        metadata: Option<String>,
+    },
+}
+
+/// A location shared in response to a Location Quick Reply.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SharedLocation {
+    /// The sender shared a point on the map.
+    Coordinates {
+        /// The latitude of the shared point.
+        latitude: f64,
+        /// The longitude of the shared point.
+        longitude: f64,
+    },
+    /// The sender shared a place. Use `place::show` to load its information.
+    Place {
+        /// The ID of the shared place.
+        place_id: String,
+    },
+}
+
 /// Helper struct to navigate collections of direct messages by tracking the status of Twitter's
 /// cursor references.
 ///
@@ -246,6 +472,13 @@ pub struct Timeline {
     pub next_cursor: Option<String>,
     /// Whether this `Timeline` has been called yet.
     pub loaded: bool,
+    /// The total number of messages loaded by this `Timeline` since it was created or last reset.
+    pub total_loaded: usize,
+    order: MessageOrder,
+    /// If set, only messages in the conversation with this user are returned.
+    participant: Option<u64>,
+    /// The ID of the authenticated user, once a page of messages has shown it.
+    me_id: Option<u64>,
 }
 
 impl Timeline {
@@ -256,6 +489,10 @@ impl Timeline {
             count: 20,
             next_cursor: None,
             loaded: false,
+            total_loaded: 0,
+            order: MessageOrder::NewestFirst,
+            participant: None,
+            me_id: None,
         }
     }
 
@@ -265,10 +502,56 @@ impl Timeline {
         Timeline { count, ..self }
     }
 
+    /// Builder function to set the order of the messages in each conversation loaded by
+    /// `into_conversations` or `into_conversation_views`. The default is
+    /// `MessageOrder::NewestFirst`.
+    pub fn with_message_order(self, order: MessageOrder) -> Self {
+        Timeline { order, ..self }
+    }
+
+    /// Builder function to only return the messages in the conversation between the authenticated
+    /// user and the given user. To load the authenticated user's messages to themself, give their
+    /// own ID.
+    ///
+    /// Twitter can't filter Direct Messages by user, so every page of messages is still loaded
+    /// and counts against the rate limit; the messages from other conversations are dropped as each
+    /// page arrives. This means a page can come back empty even though more pages follow, so
+    /// check `has_more` rather than the size of each page. See `with_user` for an example.
+    pub fn with_participant(self, user_id: u64) -> Self {
+        Timeline {
+            participant: Some(user_id),
+            ..self
+        }
+    }
+
+    /// Builder function to begin loading messages from the given cursor, as saved from the
+    /// `next_cursor` field of an earlier `Timeline`.
+    ///
+    /// This allows an app that archives messages to save its progress and pick up where it left
+    /// off in a later run. Keep in mind that Twitter only returns messages from the last 30 days,
+    /// so a cursor saved long ago may no longer return the messages it used to.
+    ///
+    /// Note that `start` clears the cursor, so call `next_page` or `into_stream` to use it.
+    pub fn resume_cursor(self, cursor: impl Into<String>) -> Self {
+        Timeline {
+            next_cursor: Some(cursor.into()),
+            ..self
+        }
+    }
+
+    /// Returns whether there may be more messages to load from this `Timeline`.
+    ///
+    /// This is `true` if the `Timeline` hasn't been called yet, or if the last page loaded included
+    /// a cursor for another page.
+    pub fn has_more(&self) -> bool {
+        !self.loaded || self.next_cursor.is_some()
+    }
+
     /// Clears the saved cursor information on this `Timeline`.
     pub fn reset(&mut self) {
         self.next_cursor = None;
         self.loaded = false;
+        self.total_loaded = 0;
     }
 
     fn request(&self, cursor: Option<String>) -> Request<Body> {
@@ -290,27 +573,72 @@ impl Timeline {
 
     /// Loads the next page of messages, setting the `next_cursor` to the one received from
     /// Twitter.
+    ///
+    /// Events other than direct messages are left out; use `next_page_events` to keep them.
     pub fn next_page(
         &mut self,
     ) -> impl Future<Output = Result<Response<Vec<DirectMessage>>, error::Error>> + '_ {
+        self.next_page_events().map(|resp| {
+            Ok(Response::map(resp?, |events| {
+                events
+                    .into_iter()
+                    .filter_map(DirectMessageEvent::into_message)
+                    .collect()
+            }))
+        })
+    }
+
+    /// Loads the next page of events, setting the `next_cursor` to the one received from
+    /// Twitter.
+    ///
+    /// This works like `next_page`, but also returns the events that aren't direct messages, as
+    /// `DirectMessageEvent::Other`. If `with_participant` is set, those events are always
+    /// returned, since egg-mode can't tell which conversation they belong to.
+    pub fn next_page_events(
+        &mut self,
+    ) -> impl Future<Output = Result<Response<Vec<DirectMessageEvent>>, error::Error>> + '_ {
         let next_cursor = self.next_cursor.take();
         let req = self.request(next_cursor);
         let loader = request_with_json_response(req);
         loader.map(
-            move |resp: Result<Response<raw::EventCursor>, error::Error>| {
-                let mut resp = resp?;
-                self.loaded = true;
-                self.next_cursor = resp.next_cursor.take();
-                Ok(Response::into(resp))
-            },
+            move |resp: Result<Response<raw::EventCursor>, error::Error>| Ok(self.load_page(resp?)),
         )
     }
 
+    /// Saves the cursor from the given page of events and counts them, after dropping the ones
+    /// from other conversations if `with_participant` is set.
+    fn load_page(
+        &mut self,
+        mut resp: Response<raw::EventCursor>,
+    ) -> Response<Vec<DirectMessageEvent>> {
+        self.loaded = true;
+        self.next_cursor = resp.next_cursor.take();
+        let mut resp: Response<Vec<DirectMessageEvent>> = Response::into(resp);
+        if let Some(participant) = self.participant {
+            if self.me_id.is_none() {
+                self.me_id = resp.iter().find_map(|ev| match ev {
+                    DirectMessageEvent::Message(dm) => Some(authenticated_user(dm)),
+                    DirectMessageEvent::Other(_) => None,
+                });
+            }
+            if let Some(me_id) = self.me_id {
+                resp.retain(|ev| match ev {
+                    DirectMessageEvent::Message(dm) => {
+                        conversation_partner(dm, me_id) == participant
+                    }
+                    DirectMessageEvent::Other(_) => true,
+                });
+            }
+        }
+        self.total_loaded += resp.response.len();
+        resp
+    }
+
     /// Converts this `Timeline` into a `Stream` of direct messages, which automatically loads the
     /// next page as needed.
     pub fn into_stream(self) -> impl Stream<Item = Result<Response<DirectMessage>, error::Error>> {
         stream::try_unfold(self, |mut timeline| async move {
-            if timeline.loaded && timeline.next_cursor.is_none() {
+            if !timeline.has_more() {
                 Ok::<_, error::Error>(None)
             } else {
                 let page = timeline.next_page().await?;
@@ -330,10 +658,46 @@ impl Timeline {
     /// sorts them into a set of threads by matching them against which user the authenticated user
     /// is messaging.
     ///
+    /// The messages in each conversation are sorted by when they were sent, newest first, no
+    /// matter what order Twitter returned them in. To sort them oldest first instead, set
+    /// `with_message_order` before calling this.
+    ///
     /// If there are more messages available than can be loaded without hitting the rate limit (15
     /// calls to the `list` endpoint per 15 minutes), then this function will stop once it receives
     /// a rate-limit error and sort the messages it received.
-    pub async fn into_conversations(mut self) -> Result<DMConversations, error::Error> {
+    pub async fn into_conversations(self) -> Result<DMConversations, error::Error> {
+        Ok(self.load_conversations().await?.1)
+    }
+
+    /// Loads all the direct messages from this `Timeline` and sorts them into a list of
+    /// `ConversationView`s, with the most recently active conversation first.
+    ///
+    /// This works like `into_conversations`, but each conversation also knows which user is the
+    /// authenticated user, for use with `ConversationView::unread_since` and the like.
+    pub async fn into_conversation_views(self) -> Result<Vec<ConversationView>, error::Error> {
+        let order = self.order;
+        let (me_id, conversations) = self.load_conversations().await?;
+
+        let mut views = conversations
+            .into_iter()
+            .map(|(participant_id, messages)| ConversationView {
+                me_id,
+                participant_id,
+                messages,
+                order,
+            })
+            .collect::<Vec<_>>();
+        views.sort_by(|left, right| {
+            let latest = |view: &ConversationView| view.latest().map(|dm| (dm.created_at, dm.id));
+            latest(right).cmp(&latest(left))
+        });
+
+        Ok(views)
+    }
+
+    /// Loads all the direct messages from this `Timeline` and sorts them into conversations,
+    /// returning the ID of the authenticated user along with them.
+    async fn load_conversations(mut self) -> Result<(u64, DMConversations), error::Error> {
         let mut dms: Vec<DirectMessage> = vec![];
         while !self.loaded || self.next_cursor.is_some() {
             match self.next_page().await {
@@ -342,40 +706,74 @@ impl Timeline {
                 Err(e) => return Err(e),
             }
         }
-        let mut conversations = HashMap::new();
-        let me_id = if let Some(dm) = dms.first() {
-            if dm.source_app.is_some() {
-                // since the source app info is only populated when the authenticated user sent the
-                // message, we know that this message was sent by the authenticated user
-                dm.sender_id
-            } else {
-                dm.recipient_id
+        let conversations = sort_conversations(dms, self.order)?;
+        Ok(conversations)
+    }
+}
+
+/// Sorts the given messages into conversations, returning the ID of the authenticated user along
+/// with them. If there are no messages, the returned ID is zero.
+fn sort_conversations(
+    dms: Vec<DirectMessage>,
+    order: MessageOrder,
+) -> Result<(u64, DMConversations), error::Error> {
+    let mut conversations: DMConversations = HashMap::new();
+    let me_id = if let Some(dm) = dms.first() {
+        authenticated_user(dm)
+    } else {
+        // no messages, nothing to sort
+        return Ok((0, conversations));
+    };
+
+    for dm in dms {
+        let entry = match (dm.sender_id == me_id, dm.recipient_id == me_id) {
+            (true, true) => {
+                // if the sender and recipient are the same - and they match the authenticated
+                // user - then it's the listing of "messages to self"
+                conversations.entry(me_id).or_default()
+            }
+            (true, false) => conversations.entry(dm.recipient_id).or_default(),
+            (false, true) => conversations.entry(dm.sender_id).or_default(),
+            (false, false) => {
+                return Err(error::Error::InvalidResponse(
+                    "messages activity contains disjoint conversations",
+                    None,
+                ));
             }
-        } else {
-            // no messages, nothing to sort
-            return Ok(conversations);
         };
+        entry.push(dm);
+    }
 
-        for dm in dms {
-            let entry = match (dm.sender_id == me_id, dm.recipient_id == me_id) {
-                (true, true) => {
-                    // if the sender and recipient are the same - and they match the authenticated
-                    // user - then it's the listing of "messages to self"
-                    conversations.entry(me_id).or_default()
-                }
-                (true, false) => conversations.entry(dm.recipient_id).or_default(),
-                (false, true) => conversations.entry(dm.sender_id).or_default(),
-                (false, false) => {
-                    return Err(error::Error::InvalidResponse(
-                        "messages activity contains disjoint conversations",
-                        None,
-                    ));
-                }
-            };
-            entry.push(dm);
+    for messages in conversations.values_mut() {
+        // pages can overlap if new messages arrive while the timeline is loading, so sort by the
+        // timestamp first, and then by the ID for messages sent in the same second
+        messages.sort_by_key(|dm| (dm.created_at, dm.id));
+        if order == MessageOrder::NewestFirst {
+            messages.reverse();
         }
+    }
 
-        Ok(conversations)
+    Ok((me_id, conversations))
+}
+
+/// Returns the ID of the authenticated user, as seen from one of their messages.
+fn authenticated_user(dm: &DirectMessage) -> u64 {
+    if dm.source_app.is_some() {
+        // since the source app info is only populated when the authenticated user sent the
+        // message, we know that this message was sent by the authenticated user
+        dm.sender_id
+    } else {
+        dm.recipient_id
+    }
+}
+
+/// Returns the ID of the user the authenticated user is messaging in the given message. For
+/// messages to themself, this is their own ID.
+fn conversation_partner(dm: &DirectMessage, me_id: u64) -> u64 {
+    if dm.sender_id == me_id {
+        dm.recipient_id
+    } else {
+        dm.sender_id
     }
 }
 
@@ -386,9 +784,81 @@ impl Timeline {
 /// sorted under the user's own ID. This map is returned by the `into_conversations` adapter on
 /// [`Timeline`].
 ///
+/// Each listing is sorted by when the messages were sent, in the order given to
+/// `Timeline::with_message_order`.
+///
 /// [`Timeline`]: struct.Timeline.html
 pub type DMConversations = HashMap<u64, Vec<DirectMessage>>;
 
+/// The order of the messages in a conversation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MessageOrder {
+    /// The most recent message comes first.
+    NewestFirst,
+    /// The oldest message comes first, as in most chat interfaces.
+    OldestFirst,
+}
+
+/// A conversation between the authenticated user and another user, returned by
+/// `Timeline::into_conversation_views`.
+///
+/// The messages of the conversation are sorted by when they were sent, in the order given to
+/// `Timeline::with_message_order`.
+#[derive(Debug)]
+pub struct ConversationView {
+    /// The ID of the authenticated user.
+    pub me_id: u64,
+    /// The ID of the other user in the conversation. For the authenticated user's messages to
+    /// themself, this is the same as `me_id`.
+    pub participant_id: u64,
+    /// The messages in the conversation.
+    pub messages: Vec<DirectMessage>,
+    /// The order of `messages`.
+    pub order: MessageOrder,
+}
+
+impl ConversationView {
+    /// Returns the most recent message in the conversation.
+    pub fn latest(&self) -> Option<&DirectMessage> {
+        match self.order {
+            MessageOrder::NewestFirst => self.messages.first(),
+            MessageOrder::OldestFirst => self.messages.last(),
+        }
+    }
+
+    /// Returns the messages the other user sent after the message with the given ID, in the order
+    /// of `messages`.
+    ///
+    /// This is meant to be given the ID of the last message the authenticated user has read, for
+    /// example one passed to `mark_read`. Messages sent by the authenticated user are never
+    /// considered unread. In a conversation with themself, no messages are unread.
+    pub fn unread_since(&self, last_read: impl Into<DmId>) -> Vec<&DirectMessage> {
+        let last_read = last_read.into().0;
+        self.messages
+            .iter()
+            .filter(|dm| dm.sender_id != self.me_id && dm.id > last_read)
+            .collect()
+    }
+
+    /// Returns whether this is the authenticated user's conversation with themself.
+    pub fn is_self_conversation(&self) -> bool {
+        self.me_id == self.participant_id
+    }
+
+    /// Returns the number of messages the authenticated user sent in this conversation.
+    pub fn sent_count(&self) -> usize {
+        self.messages
+            .iter()
+            .filter(|dm| dm.sender_id == self.me_id)
+            .count()
+    }
+
+    /// Returns the number of messages the other user sent in this conversation.
+    pub fn received_count(&self) -> usize {
+        self.messages.len() - self.sent_count()
+    }
+}
+
 /// Represents a direct message before it is sent.
 ///
 /// Because there are several optional items you can add to a DM, this struct allows you to add or
@@ -420,7 +890,7 @@ pub type DMConversations = HashMap<u64, Vec<DirectMessage>>;
 pub struct DraftMessage {
     text: Cow<'static, str>,
     recipient: UserID,
-    quick_reply_options: VecDeque<QuickReply>,
+    quick_reply: Option<QuickReplyRequest>,
     cta_buttons: VecDeque<DraftCta>,
     media_attachment: Option<media::MediaId>,
 }
@@ -436,7 +906,7 @@ impl DraftMessage {
         DraftMessage {
             text: text.into(),
             recipient: recipient.into(),
-            quick_reply_options: VecDeque::new(),
+            quick_reply: None,
             cta_buttons: VecDeque::new(),
             media_attachment: None,
         }
@@ -447,7 +917,7 @@ impl DraftMessage {
     /// Quick Replies allow you to request structured input from the other user. They'll have the
     /// opportunity to select from the options you add to the message when you send it. If they
     /// select one of the given options, its `metadata` will be given in the response in the
-    /// `quick_reply_response` field.
+    /// `quick_reply_response` field, as a `QuickReplyResponse::Options`.
     ///
     /// Note that while `description` is optional in this call, Twitter will not send the message
     /// if only some of the given Quick Replies have `description` fields.
@@ -459,7 +929,8 @@ impl DraftMessage {
     /// * `description` has a maximum of 72 characters, including spaces.
     ///
     /// There is a maximum of 20 Quick Reply Options on a single Direct Message. If you try to add
-    /// more, the oldest one will be removed.
+    /// more, the oldest one will be removed. A message can only have one kind of Quick Reply, so
+    /// this replaces a Location or Text Input Quick Reply added to this draft.
     ///
     /// Users can only respond to Quick Replies in the Twitter Web Client, and Twitter for
     /// iOS/Android.
@@ -472,17 +943,73 @@ impl DraftMessage {
         metadata: impl Into<String>,
         description: Option<String>,
     ) -> Self {
-        if self.quick_reply_options.len() == 20 {
-            self.quick_reply_options.pop_front();
-        }
-        self.quick_reply_options.push_back(QuickReply {
+        let option = QuickReply {
             label: label.into(),
             metadata: metadata.into(),
             description,
-        });
+        };
+        if let Some(QuickReplyRequest::Options(options)) = &mut self.quick_reply {
+            if options.len() == 20 {
+                options.remove(0);
+            }
+            options.push(option);
+        } else {
+            self.quick_reply = Some(QuickReplyRequest::Options(vec![option]));
+        }
         self
     }
 
+    /// Adds a Location-type Quick Reply to this draft message.
+    ///
+    /// A Location Quick Reply asks the other user to share a location with you, either a point on
+    /// the map or a place. If they do, their response will have a `QuickReplyResponse::Location`
+    /// in its `quick_reply_response` field, carrying the location they shared and the `metadata`
+    /// given here.
+    ///
+    /// `metadata` has a maximum of 1000 characters, including spaces.
+    ///
+    /// A message can only have one kind of Quick Reply. Calling this replaces any Quick Reply
+    /// previously added to this draft, including any options added with `quick_reply_option`.
+    pub fn quick_reply_location(self, metadata: impl Into<String>) -> Self {
+        DraftMessage {
+            quick_reply: Some(QuickReplyRequest::Location {
+                metadata: Some(metadata.into()),
+            }),
+            ..self
+        }
+    }
+
+    /// Adds a Text Input-type Quick Reply to this draft message.
+    ///
+    /// A Text Input Quick Reply asks the other user to type a response, showing the given `label`
+    /// as a placeholder in their input field and bringing up the given kind of keyboard. If they
+    /// respond, the text they typed will be the `text` of their message, and its
+    /// `quick_reply_response` field will have a `QuickReplyResponse::TextInput` carrying the
+    /// `metadata` given here.
+    ///
+    /// The fields here have the following length restrictions:
+    ///
+    /// * `label` has a maximum of 36 characters, including spaces.
+    /// * `metadata` has a maximum of 1000 characters, including spaces.
+    ///
+    /// A message can only have one kind of Quick Reply. Calling this replaces any Quick Reply
+    /// previously added to this draft, including any options added with `quick_reply_option`.
+    pub fn quick_reply_text_input(
+        self,
+        label: impl Into<String>,
+        keyboard: TextInputKeyboard,
+        metadata: impl Into<String>,
+    ) -> Self {
+        DraftMessage {
+            quick_reply: Some(QuickReplyRequest::TextInput {
+                label: Some(label.into()),
+                keyboard,
+                metadata: Some(metadata.into()),
+            }),
+            ..self
+        }
+    }
+
     /// Adds a "Call To Action" button to the message.
     ///
     /// Buttons allow you to add up to three links to a message. These links act as an extension to
@@ -545,14 +1072,11 @@ impl DraftMessage {
         let mut message_data = serde_json::json!({
             "text": self.text
         });
-        if !self.quick_reply_options.is_empty() {
-            message_data.as_object_mut().unwrap().insert(
-                "quick_reply".into(),
-                serde_json::json!({
-                    "type": "options",
-                    "options": self.quick_reply_options
-                }),
-            );
+        if let Some(quick_reply) = &self.quick_reply {
+            message_data
+                .as_object_mut()
+                .unwrap()
+                .insert("quick_reply".into(), quick_reply.to_json());
         }
         if !self.cta_buttons.is_empty() {
             message_data.as_object_mut().unwrap().insert(
@@ -595,6 +1119,305 @@ impl DraftMessage {
         });
         let req = post_json(links::direct::SEND, token, message);
         let resp: Response<raw::SingleEvent> = request_with_json_response(req).await?;
-        Ok(Response::into(resp))
+        Response::try_map(resp, DirectMessage::try_from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dm(id: u64, seconds: i64, sender_id: u64, recipient_id: u64, mine: bool) -> DirectMessage {
+        use chrono::TimeZone;
+
+        DirectMessage {
+            id,
+            created_at: chrono::Utc
+                .timestamp_opt(1_600_000_000 + seconds, 0)
+                .unwrap(),
+            text: String::new(),
+            entities: DMEntities {
+                hashtags: vec![],
+                symbols: vec![],
+                urls: vec![],
+                user_mentions: vec![],
+            },
+            attachment: None,
+            ctas: None,
+            quick_reply: None,
+            quick_reply_response: None,
+            sender_id,
+            source_app: if mine {
+                Some(TweetSource {
+                    name: "egg-mode".into(),
+                    url: "https://github.com/egg-mode-rs/egg-mode".into(),
+                })
+            } else {
+                None
+            },
+            recipient_id,
+            #[cfg(feature = "raw-fields")]
+            extra: Default::default(),
+        }
+    }
+
+    #[test]
+    fn conversation_order() {
+        let dms = || {
+            vec![
+                dm(5, 50, 1, 2, true),
+                dm(3, 30, 2, 1, false),
+                dm(7, 30, 3, 1, false),
+                dm(4, 40, 1, 2, true),
+                dm(6, 30, 2, 1, false),
+            ]
+        };
+
+        let (me_id, convos) = sort_conversations(dms(), MessageOrder::NewestFirst).unwrap();
+        assert_eq!(me_id, 1);
+        let ids = convos[&2].iter().map(|dm| dm.id).collect::<Vec<_>>();
+        assert_eq!(ids, vec![5, 4, 6, 3]);
+
+        let (_, convos) = sort_conversations(dms(), MessageOrder::OldestFirst).unwrap();
+        let view = ConversationView {
+            me_id,
+            participant_id: 2,
+            messages: convos.into_iter().find(|(id, _)| *id == 2).unwrap().1,
+            order: MessageOrder::OldestFirst,
+        };
+        let ids = view.messages.iter().map(|dm| dm.id).collect::<Vec<_>>();
+        assert_eq!(ids, vec![3, 6, 4, 5]);
+        assert_eq!(view.latest().unwrap().id, 5);
+        let unread = view
+            .unread_since(3)
+            .iter()
+            .map(|dm| dm.id)
+            .collect::<Vec<_>>();
+        assert_eq!(unread, vec![6]);
+        assert_eq!(view.sent_count(), 2);
+        assert_eq!(view.received_count(), 2);
+        assert!(!view.is_self_conversation());
+    }
+
+    #[test]
+    fn filter_participant() {
+        let dms = [
+            dm(5, 50, 1, 2, true),
+            dm(4, 40, 3, 1, false),
+            dm(3, 30, 1, 1, true),
+            dm(2, 20, 2, 1, false),
+        ];
+        let me_id = authenticated_user(&dms[0]);
+        assert_eq!(me_id, 1);
+
+        let with = |participant: u64| {
+            dms.iter()
+                .filter(|dm| conversation_partner(dm, me_id) == participant)
+                .map(|dm| dm.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(with(2), vec![5, 2]);
+        assert_eq!(with(3), vec![4]);
+        assert_eq!(with(1), vec![3]);
+        assert_eq!(with(4), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn keep_other_events() {
+        let json = serde_json::json!({
+            "events": [
+                {
+                    "type": "message_create",
+                    "id": "1234",
+                    "created_timestamp": "1600000000000",
+                    "message_create": {
+                        "target": {"recipient_id": "2"},
+                        "sender_id": "1",
+                        "message_data": {
+                            "text": "hi",
+                            "entities": {
+                                "hashtags": [], "symbols": [], "urls": [], "user_mentions": []
+                            },
+                        },
+                    },
+                },
+                {
+                    "type": "message_reaction_create",
+                    "id": "1235",
+                    "created_timestamp": "1600000001000",
+                    "message_reaction_create": {"reaction": "like"},
+                },
+            ],
+        });
+        let cursor = || serde_json::from_value::<raw::EventCursor>(json.clone()).unwrap();
+
+        let events = Vec::<DirectMessageEvent>::from(cursor());
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event_type(), "message_create");
+        assert_eq!(events[1].event_type(), "message_reaction_create");
+        match &events[1] {
+            DirectMessageEvent::Other(event) => {
+                assert_eq!(event["message_reaction_create"]["reaction"], "like")
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        let dms = Vec::<DirectMessage>::from(cursor());
+        assert_eq!(dms.len(), 1);
+        assert_eq!(dms[0].text, "hi");
+
+        let single = serde_json::json!({"event": json["events"][1].clone()});
+        let single = serde_json::from_value::<raw::SingleEvent>(single).unwrap();
+        assert!(DirectMessage::try_from(single).is_err());
+    }
+
+    #[test]
+    fn dm_ids() {
+        let dm = dm(1234, 0, 1, 2, true);
+        let id = DmId::from(dm.id);
+        assert_eq!(id, DmId::from(&dm.id));
+        assert_eq!(u64::from(id), 1234);
+        assert_eq!(id.to_string(), "1234");
+
+        let parsed: DmId = serde_json::from_str("1234").unwrap();
+        assert_eq!(parsed, id);
+        assert_eq!(serde_json::to_string(&id).unwrap(), "1234");
+    }
+
+    #[test]
+    fn resume_timeline() {
+        let message = |id: &str, sender_id: &str, recipient_id: &str| {
+            serde_json::json!({
+                "type": "message_create",
+                "id": id,
+                "created_timestamp": "1600000000000",
+                "message_create": {
+                    "target": {"recipient_id": recipient_id},
+                    "sender_id": sender_id,
+                    "message_data": {
+                        "text": "hi",
+                        "entities": {
+                            "hashtags": [], "symbols": [], "urls": [], "user_mentions": []
+                        },
+                    },
+                },
+            })
+        };
+        let page = |events: Vec<serde_json::Value>, next_cursor: Option<&str>| {
+            let mut json = serde_json::json!({ "events": events });
+            if let Some(cursor) = next_cursor {
+                json["next_cursor"] = cursor.into();
+            }
+            let cursor = serde_json::from_value::<raw::EventCursor>(json).unwrap();
+            Response::new(RateLimit::unknown(), cursor)
+        };
+
+        let token = auth::Token::Bearer("token".to_string());
+        let timeline = Timeline::new(links::direct::LIST, token);
+        assert!(timeline.has_more());
+
+        let mut timeline = timeline.resume_cursor("MTIzNA");
+        assert_eq!(timeline.next_cursor.as_deref(), Some("MTIzNA"));
+        let req = timeline.request(timeline.next_cursor.clone());
+        assert!(req.uri().query().unwrap().contains("cursor=MTIzNA"));
+
+        let events = vec![message("3", "1", "2"), message("2", "2", "1")];
+        let loaded = timeline.load_page(page(events, Some("NTY3OA")));
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(timeline.total_loaded, 2);
+        assert_eq!(timeline.next_cursor.as_deref(), Some("NTY3OA"));
+        assert!(timeline.has_more());
+
+        let loaded = timeline.load_page(page(vec![message("1", "1", "3")], None));
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(timeline.total_loaded, 3);
+        assert!(!timeline.has_more());
+
+        timeline.reset();
+        assert_eq!(timeline.total_loaded, 0);
+        assert!(timeline.has_more());
+    }
+
+    #[test]
+    fn quick_reply_kinds() {
+        let draft = DraftMessage::new("hi", 1u64)
+            .quick_reply_option("a", "meta-a", None)
+            .quick_reply_text_input("Your name", TextInputKeyboard::Default, "name");
+        assert_eq!(
+            draft.quick_reply.unwrap().to_json(),
+            serde_json::json!({
+                "type": "text_input",
+                "text_input": {"keyboard": "default", "label": "Your name", "metadata": "name"},
+            })
+        );
+
+        let event = |message_data: serde_json::Value| -> DirectMessage {
+            let json = serde_json::json!({
+                "event": {
+                    "type": "message_create",
+                    "id": "1234",
+                    "created_timestamp": "1600000000000",
+                    "message_create": {
+                        "target": {"recipient_id": "2"},
+                        "sender_id": "1",
+                        "message_data": message_data,
+                    },
+                },
+            });
+            DirectMessage::try_from(serde_json::from_value::<raw::SingleEvent>(json).unwrap())
+                .unwrap()
+        };
+        let entities = serde_json::json!({
+            "hashtags": [], "symbols": [], "urls": [], "user_mentions": []
+        });
+
+        let dm = event(serde_json::json!({
+            "text": "Where are you?",
+            "entities": entities,
+            "quick_reply": {"type": "location", "location": {"metadata": "where"}},
+        }));
+        match dm.quick_reply {
+            Some(QuickReplyRequest::Location { metadata }) => {
+                assert_eq!(metadata.as_deref(), Some("where"))
+            }
+            other => panic!("unexpected quick reply: {:?}", other),
+        }
+
+        let dm = event(serde_json::json!({
+            "text": "",
+            "entities": entities,
+            "quick_reply_response": {
+                "type": "location",
+                "metadata": "where",
+                "location": {
+                    "type": "shared_coordinate",
+                    "shared_coordinate": {
+                        "coordinates": {"type": "Point", "coordinates": [-122.4, 37.8]},
+                    },
+                },
+            },
+        }));
+        assert_eq!(
+            dm.quick_reply_response,
+            Some(QuickReplyResponse::Location {
+                metadata: Some("where".into()),
+                location: SharedLocation::Coordinates {
+                    latitude: 37.8,
+                    longitude: -122.4,
+                },
+            })
+        );
+
+        let dm = event(serde_json::json!({
+            "text": "42",
+            "entities": entities,
+            "quick_reply_response": {"type": "text_input", "metadata": "age"},
+        }));
+        assert_eq!(
+            dm.quick_reply_response,
+            Some(QuickReplyResponse::TextInput {
+                metadata: Some("age".into())
+            })
+        );
     }
 }
@@ -7,12 +7,16 @@ use crate::common::*;
 use std::collections::HashMap;
 
 use chrono;
-use serde::Deserialize;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
 
 use crate::entities::MediaEntity;
 use crate::tweet::TweetSource;
 
-use super::{Cta, DMEntities, DirectMessage, QuickReply};
+use super::{
+    Cta, DMEntities, DirectMessage, QuickReply, QuickReplyRequest, QuickReplyResponse,
+    SharedLocation, TextInputKeyboard,
+};
 
 // n.b. all of the types in this module are re-exported in `raw::types::direct` - these docs are
 // public!
@@ -48,10 +52,10 @@ pub struct RawDirectMessage {
     pub attachment: Option<MediaEntity>,
     /// A list of "call to action" buttons, if present.
     pub ctas: Option<Vec<Cta>>,
-    /// A list of "quick reply" options, if present.
-    pub quick_replies: Option<Vec<QuickReply>>,
-    /// The `metadata` associated with the Quick Reply chosen by the sender, if present.
-    pub quick_reply_response: Option<String>,
+    /// The "quick reply" request sent with the DM, if present.
+    pub quick_reply: Option<QuickReplyRequest>,
+    /// The sender's answer to a Quick Reply, if present.
+    pub quick_reply_response: Option<QuickReplyResponse>,
     /// The ID of the user who sent the DM.
     pub sender_id: u64,
     /// The string ID associated with the app used to send the DM, if sent by the authenticated
@@ -59,6 +63,11 @@ pub struct RawDirectMessage {
     pub source_app_id: Option<String>,
     /// The ID of the user who received the DM.
     pub recipient_id: u64,
+    /// Any fields in the DM's `message_data` that aren't loaded into the other fields.
+    ///
+    /// This is only available with the `raw-fields` feature enabled.
+    #[cfg(feature = "raw-fields")]
+    pub extra: serde_json::Map<String, serde_json::Value>,
     translated: bool,
 }
 
@@ -114,8 +123,10 @@ impl RawDirectMessage {
             sender_id: self.sender_id,
             source_app,
             recipient_id: self.recipient_id,
-            quick_replies: self.quick_replies,
+            quick_reply: self.quick_reply,
             quick_reply_response: self.quick_reply_response,
+            #[cfg(feature = "raw-fields")]
+            extra: self.extra,
         }
     }
 
@@ -131,7 +142,9 @@ impl From<DMEvent> for RawDirectMessage {
         use chrono::TimeZone;
         RawDirectMessage {
             id: ev.id,
-            created_at: chrono::Utc.timestamp_millis(ev.created_timestamp),
+            created_at: chrono::Utc
+                .timestamp_millis_opt(ev.created_timestamp)
+                .unwrap(),
             text: ev.message_create.message_data.text,
             entities: ev.message_create.message_data.entities,
             attachment: ev.message_create.message_data.attachment.map(|a| a.media),
@@ -139,16 +152,18 @@ impl From<DMEvent> for RawDirectMessage {
             sender_id: ev.message_create.sender_id,
             source_app_id: ev.message_create.source_app_id,
             recipient_id: ev.message_create.target.recipient_id,
-            quick_replies: ev
+            quick_reply: ev
                 .message_create
                 .message_data
                 .quick_reply
-                .map(|q| q.options),
+                .map(QuickReplyRequest::from),
             quick_reply_response: ev
                 .message_create
                 .message_data
                 .quick_reply_response
-                .map(|q| q.metadata),
+                .map(QuickReplyResponse::from),
+            #[cfg(feature = "raw-fields")]
+            extra: ev.message_create.message_data.extra,
             translated: false,
         }
     }
@@ -181,11 +196,9 @@ pub struct EventCursor {
 /// Wrapper enum to represent a `DMEvent` in the Account Activity API.
 ///
 /// As direct messages are part of the Account Activity API, they are presented as an event type in
-/// a broader event envelope. This enum mainly encapsulates the requirement that direct messages
-/// are returned as the `message_create` event type with the proper data structure.
-#[derive(Deserialize)]
-#[serde(tag = "type")]
-#[serde(rename_all = "snake_case")]
+/// a broader event envelope. Direct messages are returned as the `message_create` event type with
+/// the proper data structure; any other event type is kept as-is in the `Other` variant.
+#[allow(clippy::large_enum_variant)]
 pub enum EventType {
     /// A `message_create` event, representing a direct message.
     ///
@@ -193,13 +206,33 @@ pub enum EventType {
     /// deserializing. It should be combined with the `apps` map in a `SingleEvent` or
     /// `EventCursor` when converting into a `DirectMessage`.
     MessageCreate(RawDirectMessage),
+    /// Any other type of event, as the JSON object Twitter sent for it.
+    Other(serde_json::Value),
+}
+
+impl<'de> Deserialize<'de> for EventType {
+    fn deserialize<D>(deser: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = serde_json::Value::deserialize(deser)?;
+        if value.get("type").and_then(|t| t.as_str()) == Some("message_create") {
+            let dm = RawDirectMessage::deserialize(value).map_err(D::Error::custom)?;
+            Ok(EventType::MessageCreate(dm))
+        } else {
+            Ok(EventType::Other(value))
+        }
+    }
 }
 
 impl EventType {
-    /// Returns the inner `RawDirectMessage` structure from the `message_create` event.
-    pub fn as_raw_dm(self) -> RawDirectMessage {
-        let EventType::MessageCreate(dm) = self;
-        dm
+    /// Returns the inner `RawDirectMessage` structure from the `message_create` event, or `None`
+    /// if this is another type of event.
+    pub fn as_raw_dm(self) -> Option<RawDirectMessage> {
+        match self {
+            EventType::MessageCreate(dm) => Some(dm),
+            EventType::Other(_) => None,
+        }
     }
 }
 
@@ -242,12 +275,16 @@ struct MessageData {
     attachment: Option<MessageAttachment>,
     /// Information about URL, hashtag, or user-mention entities used in the message.
     entities: DMEntities,
-    /// Information about Quick Reply options, if present.
+    /// Information about a Quick Reply request, if present.
     quick_reply: Option<RawQuickReply>,
-    /// Information about a selected Quick Reply option, if the sender selected one.
-    quick_reply_response: Option<QuickReplyResponse>,
+    /// Information about the sender's answer to a Quick Reply, if they answered one.
+    quick_reply_response: Option<RawQuickReplyResponse>,
     /// The message text.
     text: String,
+    /// Any fields that aren't loaded into the other fields.
+    #[cfg(feature = "raw-fields")]
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Represents attached media information from within a `DMEvent`.
@@ -262,18 +299,124 @@ struct MessageAttachment {
     media: MediaEntity,
 }
 
-/// Represents a list of Quick Reply options from within a `DMEvent`.
+/// Represents a Quick Reply request from within a `DMEvent`.
 #[derive(Deserialize)]
-struct RawQuickReply {
-    /// The list of Quick Reply options sent with this message.
-    options: Vec<QuickReply>,
+#[serde(tag = "type")]
+#[serde(rename_all = "snake_case")]
+enum RawQuickReply {
+    /// A list of Quick Reply options sent with this message.
+    Options { options: Vec<QuickReply> },
+    /// A request to share a location.
+    Location {
+        #[serde(default)]
+        location: RawQuickReplyInput,
+    },
+    /// A request to type a response.
+    TextInput {
+        #[serde(default)]
+        text_input: RawQuickReplyInput,
+    },
 }
 
-/// Represents the `metadata` from a selected Quick Reply from within a `DMEvent`.
+/// The settings of a Location or Text Input Quick Reply from within a `DMEvent`.
+#[derive(Default, Deserialize)]
+struct RawQuickReplyInput {
+    /// The placeholder label of a Text Input Quick Reply.
+    label: Option<String>,
+    /// The keyboard of a Text Input Quick Reply.
+    keyboard: Option<TextInputKeyboard>,
+    /// The metadata of the Quick Reply.
+    metadata: Option<String>,
+}
+
+impl From<RawQuickReply> for QuickReplyRequest {
+    fn from(raw: RawQuickReply) -> QuickReplyRequest {
+        match raw {
+            RawQuickReply::Options { options } => QuickReplyRequest::Options(options),
+            RawQuickReply::Location { location } => QuickReplyRequest::Location {
+                metadata: location.metadata,
+            },
+            RawQuickReply::TextInput { text_input } => QuickReplyRequest::TextInput {
+                label: text_input.label,
+                keyboard: text_input.keyboard.unwrap_or(TextInputKeyboard::Default),
+                metadata: text_input.metadata,
+            },
+        }
+    }
+}
+
+/// Represents the answer to a Quick Reply from within a `DMEvent`.
 #[derive(Deserialize)]
-struct QuickReplyResponse {
+#[serde(tag = "type")]
+#[serde(rename_all = "snake_case")]
+enum RawQuickReplyResponse {
     /// The `metadata` field for the Quick Reply option the sender selected.
-    metadata: String,
+    Options { metadata: String },
+    /// The location the sender shared.
+    Location {
+        metadata: Option<String>,
+        location: RawSharedLocation,
+    },
+    /// The sender typed a response, which is the text of the message.
+    TextInput { metadata: Option<String> },
+}
+
+/// Represents a location shared in response to a Quick Reply from within a `DMEvent`.
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+#[serde(rename_all = "snake_case")]
+enum RawSharedLocation {
+    /// A point on the map, as a GeoJSON point.
+    SharedCoordinate { shared_coordinate: RawSharedPoint },
+    /// A place, of which only the ID is loaded.
+    SharedPlace { shared_place: RawSharedPlace },
+}
+
+#[derive(Deserialize)]
+struct RawSharedPoint {
+    coordinates: RawPoint,
+}
+
+#[derive(Deserialize)]
+struct RawPoint {
+    /// GeoJSON coordinates, as a (longitude, latitude) pair.
+    coordinates: (f64, f64),
+}
+
+#[derive(Deserialize)]
+struct RawSharedPlace {
+    place: RawPlaceId,
+}
+
+#[derive(Deserialize)]
+struct RawPlaceId {
+    id: String,
+}
+
+impl From<RawQuickReplyResponse> for QuickReplyResponse {
+    fn from(raw: RawQuickReplyResponse) -> QuickReplyResponse {
+        match raw {
+            RawQuickReplyResponse::Options { metadata } => QuickReplyResponse::Options { metadata },
+            RawQuickReplyResponse::Location { metadata, location } => {
+                let location = match location {
+                    RawSharedLocation::SharedCoordinate { shared_coordinate } => {
+                        let (longitude, latitude) = shared_coordinate.coordinates.coordinates;
+                        SharedLocation::Coordinates {
+                            latitude,
+                            longitude,
+                        }
+                    }
+                    RawSharedLocation::SharedPlace { shared_place } => SharedLocation::Place {
+                        place_id: shared_place.place.id,
+                    },
+                };
+                QuickReplyResponse::Location { metadata, location }
+            }
+            RawQuickReplyResponse::TextInput { metadata } => {
+                QuickReplyResponse::TextInput { metadata }
+            }
+        }
+    }
 }
 
 /// Represents the message target from within a `DMEvent`.
@@ -0,0 +1,867 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Process-wide rate budgeting for the requests egg-mode makes.
+//!
+//! Twitter gives every endpoint its own rate window, separately for each user token (and for the
+//! app as a whole, with a Bearer token). A service that makes calls for many users at once, from
+//! many tasks, has a hard time keeping track of all those windows by reading the `Response`s one by
+//! one. A [`Dispatcher`] does it for the whole process: once installed with [`set_dispatcher`],
+//! every request egg-mode sends goes through it, and it
+//!
+//! - remembers the rate-limit headers Twitter returns, for each endpoint and token;
+//! - holds back requests whose window is used up until it resets, instead of sending them only to
+//!   be refused;
+//! - counts how many requests were sent, held back, or refused, in [`DispatchMetrics`], and
+//!   reports how much of each endpoint's budget is left, in [`EndpointBudget`]s.
+//!
+//! Requests are told apart by their endpoint, which is their URL without the query string and with
+//! any numeric IDs in the path replaced by `:id`, and tokens by the user token
+//! (or consumer key, or Bearer token) in the request's `Authorization` header. The tokens
+//! themselves aren't kept, only a hash of them. Streaming connections aren't budgeted, as Twitter
+//! limits those by connection rather than by request.
+//!
+//! [`Dispatcher`]: struct.Dispatcher.html
+//! [`set_dispatcher`]: fn.set_dispatcher.html
+//! [`DispatchMetrics`]: struct.DispatchMetrics.html
+//! [`EndpointBudget`]: struct.EndpointBudget.html
+//!
+//! # Token pools
+//!
+//! A crawler that only reads public data can spread its calls over several Bearer tokens, to get
+//! the rate budget of all of them. A [`TokenPool`] installed with [`set_token_pool`] does this:
+//! whenever a request is signed with one of the pool's tokens, it's signed with whichever token of
+//! the pool has the most calls left for that endpoint instead. It learns each token's windows from
+//! the rate-limit headers of the responses, the same way a `Dispatcher` does. The two can be used
+//! together, in which case the dispatcher sees the token the request was actually signed with.
+//!
+//! Only read-only calls made with Bearer tokens are rotated. A Bearer token doesn't act for any
+//! user, so a `GET` request returns the same data whichever one signs it. Access tokens are never
+//! pooled: nearly every call made with one depends on its user, down to the `favorited` and
+//! `retweeted` flags of a tweet, or whether a protected account's tweets can be seen at all.
+//! Writes, and requests signed with tokens outside the pool, are always signed with the token they
+//! were given.
+//!
+//! [`TokenPool`]: struct.TokenPool.html
+//! [`set_token_pool`]: fn.set_token_pool.html
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use egg_mode::dispatch::{self, Dispatcher};
+//!
+//! // wait at most a minute for a rate window to reset, and keep two calls of every window spare
+//! let dispatcher = Dispatcher::new()
+//!     .with_max_wait(Duration::from_secs(60))
+//!     .with_reserve(2);
+//! dispatch::set_dispatcher(dispatcher.clone());
+//!
+//! // ...make calls as usual, from any task...
+//!
+//! let metrics = dispatcher.metrics();
+//! println!("{} requests sent, {} held back", metrics.sent, metrics.queued);
+//! ```
+
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+use std::time::Duration;
+
+use hyper::{Body, Method, Request};
+use lazy_static::lazy_static;
+
+use crate::auth::Token;
+use crate::common::RateLimit;
+use crate::error::{Error, Result};
+
+lazy_static! {
+    static ref DISPATCHER: RwLock<Option<Dispatcher>> = RwLock::new(None);
+    static ref TOKEN_POOL: RwLock<Option<TokenPool>> = RwLock::new(None);
+}
+
+/// Routes every request egg-mode makes in this process through the given `Dispatcher`, replacing
+/// any dispatcher already set.
+///
+/// Requests that were already started when this is called aren't affected.
+pub fn set_dispatcher(dispatcher: Dispatcher) {
+    *DISPATCHER.write().unwrap_or_else(PoisonError::into_inner) = Some(dispatcher);
+}
+
+/// Stops routing requests through the dispatcher set with `set_dispatcher`, if any.
+pub fn clear_dispatcher() {
+    *DISPATCHER.write().unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+/// Returns the dispatcher set with `set_dispatcher`, if any.
+pub(crate) fn current() -> Option<Dispatcher> {
+    DISPATCHER
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+/// Signs every read-only request made with one of the given pool's Bearer tokens with the pool's
+/// best token for that endpoint instead, replacing any pool already set.
+pub fn set_token_pool(pool: TokenPool) {
+    *TOKEN_POOL.write().unwrap_or_else(PoisonError::into_inner) = Some(pool);
+}
+
+/// Stops rotating tokens through the pool set with `set_token_pool`, if any.
+pub fn clear_token_pool() {
+    *TOKEN_POOL.write().unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+/// Returns the token pool set with `set_token_pool`, if any.
+pub(crate) fn current_token_pool() -> Option<TokenPool> {
+    TOKEN_POOL
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+/// Tracks the rate windows of every endpoint and token, and holds back requests that don't fit in
+/// them.
+///
+/// A `Dispatcher` does nothing until it's installed with `set_dispatcher`. Clones of a
+/// `Dispatcher` share their windows and metrics, so keep a clone to read the metrics from after
+/// installing it. See the [module documentation](index.html) for details.
+#[derive(Debug, Clone)]
+pub struct Dispatcher {
+    max_wait: Option<Duration>,
+    reserve: i32,
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    windows: HashMap<WindowKey, Window>,
+    metrics: DispatchMetrics,
+}
+
+/// Identifies the rate window of an endpoint, for a single token.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct WindowKey {
+    endpoint: String,
+    token: u64,
+}
+
+/// What the dispatcher knows about a single rate window.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Window {
+    limit: i32,
+    remaining: i32,
+    reset: i64,
+    in_flight: i32,
+}
+
+impl Window {
+    /// Returns how many seconds to wait before another request fits in this window, or `None` if
+    /// one can be sent now.
+    ///
+    /// Requests that were sent but haven't returned yet count against the window, as do the given
+    /// number of `reserve` calls. Once the window's reset time has passed, it's assumed to be
+    /// full again, so only the requests still in flight count against its limit. If those use up
+    /// the new window, the next reset isn't known until they return, so this waits a second before
+    /// checking again.
+    fn wait_secs(&self, reserve: i32, now: i64) -> Option<i64> {
+        if self.reset > now {
+            if self.remaining - self.in_flight > reserve {
+                None
+            } else {
+                Some(self.reset - now)
+            }
+        } else if self.limit < 0 || self.limit - self.in_flight > reserve {
+            None
+        } else {
+            Some(1)
+        }
+    }
+}
+
+/// A request that was let through by a `Dispatcher`, to be reported back when it's finished.
+pub(crate) struct Permit {
+    key: WindowKey,
+}
+
+/// A request that was signed with a token of a `TokenPool`, to be reported back when it's finished.
+pub(crate) struct PoolWindow {
+    key: WindowKey,
+}
+
+/// Counts of what a `Dispatcher` has done since it was created.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DispatchMetrics {
+    /// The number of requests that were sent.
+    pub sent: u64,
+    /// The number of requests that had to wait for a rate window to reset before being sent.
+    pub queued: u64,
+    /// The number of requests that weren't sent, because their rate window wouldn't reset within
+    /// the dispatcher's `max_wait`. These requests returned `Error::RateLimit`.
+    pub rejected: u64,
+    /// The number of requests that Twitter refused for going over a rate limit anyway, for
+    /// example because the window was also used by another process.
+    pub rate_limited: u64,
+    /// The number of requests that have been sent but haven't returned yet.
+    pub in_flight: u64,
+    /// The total time requests spent waiting for rate windows to reset.
+    pub waited: Duration,
+}
+
+/// The remaining budget of an endpoint, across every token that has called it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EndpointBudget {
+    /// The URL of the endpoint, without the scheme or query string, and with any numeric IDs in
+    /// the path replaced by `:id`.
+    pub endpoint: String,
+    /// The number of tokens whose rate window for this endpoint is known.
+    pub tokens: usize,
+    /// The number of calls left in the current windows of those tokens, put together.
+    pub remaining: i64,
+    /// The number of calls of this endpoint that have been sent but haven't returned yet.
+    pub in_flight: i64,
+    /// The UTC Unix timestamp of the soonest window reset, or `None` if every window has already
+    /// reset.
+    pub next_reset: Option<i64>,
+}
+
+impl Default for Dispatcher {
+    fn default() -> Dispatcher {
+        Dispatcher::new()
+    }
+}
+
+impl Dispatcher {
+    /// Creates a new `Dispatcher`, which waits as long as needed for rate windows to reset and
+    /// keeps no calls in reserve.
+    pub fn new() -> Dispatcher {
+        Dispatcher {
+            max_wait: None,
+            reserve: 0,
+            state: Arc::new(Mutex::new(State::default())),
+        }
+    }
+
+    /// Sets the longest time a request will wait for its rate window to reset.
+    ///
+    /// If a request would have to wait longer, it isn't sent, and returns `Error::RateLimit` with
+    /// the time the window resets instead.
+    pub fn with_max_wait(self, max_wait: Duration) -> Dispatcher {
+        Dispatcher {
+            max_wait: Some(max_wait),
+            ..self
+        }
+    }
+
+    /// Sets the number of calls of every rate window to leave unused.
+    ///
+    /// This leaves room for calls made outside of this process with the same tokens, like from
+    /// another server or the user's own Twitter client.
+    pub fn with_reserve(self, reserve: u32) -> Dispatcher {
+        Dispatcher {
+            reserve: reserve as i32,
+            ..self
+        }
+    }
+
+    /// Returns the counts of what this dispatcher has done since it was created.
+    pub fn metrics(&self) -> DispatchMetrics {
+        self.lock().metrics.clone()
+    }
+
+    /// Returns the remaining budget of every endpoint this dispatcher has seen a rate window for,
+    /// sorted by endpoint.
+    pub fn endpoints(&self) -> Vec<EndpointBudget> {
+        budgets(&self.lock().windows, chrono::Utc::now().timestamp())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Waits until the given request fits in its rate window, and reserves a place for it.
+    pub(crate) async fn acquire(&self, request: &Request<Body>) -> Result<Permit> {
+        let key = window_key(request);
+        let mut queued = false;
+
+        loop {
+            let wait = {
+                let mut state = self.lock();
+                let now = chrono::Utc::now().timestamp();
+                let wait = state
+                    .windows
+                    .get(&key)
+                    .and_then(|window| window.wait_secs(self.reserve, now));
+
+                match wait {
+                    None => {
+                        if let Some(window) = state.windows.get_mut(&key) {
+                            window.in_flight += 1;
+                        }
+                        state.metrics.sent += 1;
+                        state.metrics.in_flight += 1;
+                        return Ok(Permit { key });
+                    }
+                    Some(secs) => {
+                        let wait = Duration::from_secs(secs as u64);
+                        if matches!(self.max_wait, Some(max) if wait > max) {
+                            state.metrics.rejected += 1;
+                            return Err(Error::RateLimit((now + secs) as i32));
+                        }
+                        if !queued {
+                            state.metrics.queued += 1;
+                            queued = true;
+                        }
+                        state.metrics.waited += wait;
+                        wait
+                    }
+                }
+            };
+
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Records the result of a request that was let through by `acquire`, along with the
+    /// rate-limit headers of a successful response.
+    pub(crate) fn release<T>(
+        &self,
+        permit: Permit,
+        result: &Result<T>,
+        success_limit: Option<RateLimit>,
+    ) {
+        let rate_limit = result_rate_limit(result, success_limit);
+
+        let mut state = self.lock();
+        state.metrics.in_flight = state.metrics.in_flight.saturating_sub(1);
+        if let Err(Error::RateLimit(_)) = result {
+            state.metrics.rate_limited += 1;
+        }
+        update_window(
+            &mut state.windows,
+            permit.key,
+            rate_limit,
+            chrono::Utc::now().timestamp(),
+        );
+    }
+}
+
+/// A set of Bearer tokens that requests are spread over, by how much of their rate budget is left.
+///
+/// A `TokenPool` does nothing until it's installed with `set_token_pool`. Clones of a `TokenPool`
+/// share their windows, so keep a clone to read the remaining budgets from after installing it. See
+/// the [module documentation](index.html#token-pools) for details.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use egg_mode::dispatch::{self, TokenPool};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let tokens: Vec<egg_mode::Token> = unimplemented!();
+/// let pool = TokenPool::new(tokens.iter().cloned());
+/// dispatch::set_token_pool(pool.clone());
+///
+/// // any token of the pool can be given; the call is made with whichever has the most calls left
+/// let user = egg_mode::user::show("rustlang", &tokens[0]).await.unwrap();
+///
+/// for budget in pool.endpoints() {
+///     println!("{}: {} calls left", budget.endpoint, budget.remaining);
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct TokenPool {
+    tokens: Arc<Vec<(u64, Token)>>,
+    state: Arc<Mutex<PoolState>>,
+}
+
+#[derive(Debug, Default)]
+struct PoolState {
+    windows: HashMap<WindowKey, Window>,
+    next: usize,
+}
+
+impl TokenPool {
+    /// Creates a new `TokenPool` with the given Bearer tokens. Tokens that are given more than once
+    /// are only added once.
+    ///
+    /// Access tokens are left out, since the results of calls made with them depend on which user
+    /// they belong to. Requests made with them are always signed with the token they were given.
+    pub fn new(tokens: impl IntoIterator<Item = Token>) -> TokenPool {
+        let mut pooled: Vec<(u64, Token)> = Vec::new();
+        for token in tokens {
+            if let Token::Access { .. } = token {
+                continue;
+            }
+            let id = token_id(&token);
+            if !pooled.iter().any(|(other, _)| *other == id) {
+                pooled.push((id, token));
+            }
+        }
+
+        TokenPool {
+            tokens: Arc::new(pooled),
+            state: Arc::new(Mutex::new(PoolState::default())),
+        }
+    }
+
+    /// Returns the number of tokens in this pool.
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// Returns whether this pool has no tokens.
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Returns whether the given token is in this pool.
+    pub fn contains(&self, token: &Token) -> bool {
+        let id = token_id(token);
+        self.tokens.iter().any(|(other, _)| *other == id)
+    }
+
+    /// Returns the remaining budget of every endpoint the pool's tokens have seen a rate window for,
+    /// sorted by endpoint.
+    pub fn endpoints(&self) -> Vec<EndpointBudget> {
+        budgets(&self.lock().windows, chrono::Utc::now().timestamp())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, PoolState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Picks the token to sign a request to the given URL with, if the given token is in this pool
+    /// and the request is read-only.
+    ///
+    /// The token with the most calls left for the endpoint is picked. Tokens that haven't called
+    /// the endpoint yet are picked first, and ties go to the next token in turn. The picked token's
+    /// window is counted down right away, so that requests made at the same time are spread out;
+    /// the response's headers will correct it afterward.
+    pub(crate) fn select(&self, token: &Token, method: &Method, url: &str) -> Option<Token> {
+        if !self.contains(token) {
+            return None;
+        }
+
+        let endpoint = match url.parse::<hyper::Uri>() {
+            Ok(uri) if can_rotate(method) => endpoint_key(&uri),
+            _ => return None,
+        };
+        let mut state = self.lock();
+        let index = pick_token(
+            &self.tokens,
+            &endpoint,
+            &mut state,
+            chrono::Utc::now().timestamp(),
+        );
+        Some(self.tokens[index].1.clone())
+    }
+
+    /// Returns the rate window the given request will be recorded in, if it was signed with one of
+    /// this pool's tokens.
+    pub(crate) fn window(&self, request: &Request<Body>) -> Option<PoolWindow> {
+        let key = window_key(request);
+        if self.tokens.iter().any(|(id, _)| *id == key.token) {
+            Some(PoolWindow { key })
+        } else {
+            None
+        }
+    }
+
+    /// Records the result of a request in the window returned by `window`.
+    pub(crate) fn record<T>(
+        &self,
+        window: PoolWindow,
+        result: &Result<T>,
+        success_limit: Option<RateLimit>,
+    ) {
+        let rate_limit = result_rate_limit(result, success_limit);
+        update_window(
+            &mut self.lock().windows,
+            window.key,
+            rate_limit,
+            chrono::Utc::now().timestamp(),
+        );
+    }
+}
+
+/// Returns whether a request with the given method can be signed with any token of a `TokenPool`.
+fn can_rotate(method: &Method) -> bool {
+    *method == Method::GET
+}
+
+/// Picks the token of the given pool with the most calls left for the given endpoint, and counts
+/// down its window.
+fn pick_token(tokens: &[(u64, Token)], endpoint: &str, state: &mut PoolState, now: i64) -> usize {
+    let available = |window: Option<&Window>| match window {
+        None => i64::MAX,
+        Some(window) if window.reset <= now && window.limit >= 0 => i64::from(window.limit),
+        Some(window) if window.reset <= now => i64::MAX,
+        Some(window) => i64::from(window.remaining),
+    };
+
+    let mut index = 0;
+    let mut most = i64::MIN;
+    for offset in 0..tokens.len() {
+        let candidate = (state.next + offset) % tokens.len();
+        let key = WindowKey {
+            endpoint: endpoint.to_string(),
+            token: tokens[candidate].0,
+        };
+        let left = available(state.windows.get(&key));
+        if left > most {
+            index = candidate;
+            most = left;
+        }
+    }
+
+    state.next = index + 1;
+    let key = WindowKey {
+        endpoint: endpoint.to_string(),
+        token: tokens[index].0,
+    };
+    if let Some(window) = state.windows.get_mut(&key) {
+        if window.reset > now {
+            window.remaining = (window.remaining - 1).max(0);
+        }
+    }
+    index
+}
+
+/// Returns the rate-limit information that came back with the given result of a request.
+fn result_rate_limit<T>(result: &Result<T>, success_limit: Option<RateLimit>) -> Option<RateLimit> {
+    match result {
+        Ok(_) => success_limit,
+        Err(Error::RateLimit(reset)) => Some(RateLimit {
+            remaining: 0,
+            reset: *reset,
+            ..RateLimit::unknown()
+        }),
+        Err(e) => e.rate_limit(),
+    }
+}
+
+/// How long after its reset a window with no requests in flight is forgotten, in seconds. This is
+/// the length of Twitter's rate windows, so a forgotten window would have reset again anyway.
+const WINDOW_EXPIRY: i64 = 15 * 60;
+
+/// Finishes a request in the given window, updating it with the rate-limit information that came
+/// back, if any.
+///
+/// Windows that reset long enough ago with nothing in flight are forgotten along the way, so the
+/// windows of endpoints that are no longer called don't pile up.
+fn update_window(
+    windows: &mut HashMap<WindowKey, Window>,
+    key: WindowKey,
+    rate_limit: Option<RateLimit>,
+    now: i64,
+) {
+    windows.retain(|_, window| window.in_flight > 0 || window.reset + WINDOW_EXPIRY > now);
+
+    let in_flight = match windows.get_mut(&key) {
+        Some(window) => {
+            window.in_flight = (window.in_flight - 1).max(0);
+            window.in_flight
+        }
+        None => 0,
+    };
+
+    if let Some(rate_limit) = rate_limit {
+        if rate_limit.remaining >= 0 && rate_limit.reset >= 0 {
+            let limit = windows.get(&key).map_or(-1, |window| window.limit);
+            windows.insert(
+                key,
+                Window {
+                    limit: if rate_limit.limit >= 0 {
+                        rate_limit.limit
+                    } else {
+                        limit
+                    },
+                    remaining: rate_limit.remaining,
+                    reset: i64::from(rate_limit.reset),
+                    in_flight,
+                },
+            );
+        }
+    }
+}
+
+/// Puts the given windows together by endpoint.
+fn budgets(windows: &HashMap<WindowKey, Window>, now: i64) -> Vec<EndpointBudget> {
+    let mut budgets: HashMap<&str, EndpointBudget> = HashMap::new();
+
+    for (key, window) in windows {
+        let budget = budgets
+            .entry(&key.endpoint)
+            .or_insert_with(|| EndpointBudget {
+                endpoint: key.endpoint.clone(),
+                tokens: 0,
+                remaining: 0,
+                in_flight: 0,
+                next_reset: None,
+            });
+        budget.tokens += 1;
+        budget.in_flight += i64::from(window.in_flight);
+        if window.reset > now {
+            budget.remaining += i64::from(window.remaining);
+            budget.next_reset = Some(
+                budget
+                    .next_reset
+                    .map_or(window.reset, |r| r.min(window.reset)),
+            );
+        } else if window.limit >= 0 {
+            budget.remaining += i64::from(window.limit);
+        }
+    }
+
+    let mut budgets = budgets.values().cloned().collect::<Vec<_>>();
+    budgets.sort_by(|a, b| a.endpoint.cmp(&b.endpoint));
+    budgets
+}
+
+/// Returns the rate window the given request belongs to.
+fn window_key(request: &Request<Body>) -> WindowKey {
+    let endpoint = endpoint_key(request.uri());
+
+    let auth = request
+        .headers()
+        .get(hyper::header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .unwrap_or("");
+    let identity = oauth_param(auth, "oauth_token")
+        .or_else(|| oauth_param(auth, "oauth_consumer_key"))
+        .unwrap_or(auth);
+
+    WindowKey {
+        endpoint,
+        token: hash_identity(identity),
+    }
+}
+
+/// Returns the endpoint the given URL belongs to, without the scheme or query string.
+///
+/// Twitter shares one rate window between every ID an endpoint is called with, so path segments
+/// that are numeric IDs, like the `20` in `statuses/destroy/20.json`, are replaced by `:id`. The
+/// first segment is the API version, and is kept as it is.
+fn endpoint_key(uri: &hyper::Uri) -> String {
+    let path = uri
+        .path()
+        .split('/')
+        .enumerate()
+        .map(|(idx, segment)| {
+            let stem = segment.strip_suffix(".json").unwrap_or(segment);
+            if idx > 1 && !stem.is_empty() && stem.bytes().all(|b| b.is_ascii_digit()) {
+                Cow::Owned(segment.replacen(stem, ":id", 1))
+            } else {
+                Cow::Borrowed(segment)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+
+    format!("{}{}", uri.authority().map_or("", |a| a.as_str()), path)
+}
+
+/// Returns the identity `window_key` would read from a request signed with the given token.
+fn token_id(token: &Token) -> u64 {
+    match token {
+        Token::Access { access, .. } => hash_identity(&access.key),
+        Token::Bearer(bearer) => hash_identity(&format!("Bearer {}", bearer)),
+    }
+}
+
+fn hash_identity(identity: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    identity.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Reads the given parameter out of an OAuth `Authorization` header.
+fn oauth_param<'a>(header: &'a str, name: &str) -> Option<&'a str> {
+    let header = header.strip_prefix("OAuth ")?;
+    header.split(',').find_map(|param| {
+        let (key, value) = param.trim().split_at(param.trim().find('=')?);
+        if key == name {
+            Some(value[1..].trim_matches('"'))
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(endpoint: &str, token: u64) -> WindowKey {
+        WindowKey {
+            endpoint: endpoint.into(),
+            token,
+        }
+    }
+
+    #[test]
+    fn track_windows() {
+        let limit = |remaining, reset| {
+            Some(RateLimit {
+                limit: 15,
+                remaining,
+                reset,
+            })
+        };
+        let mut windows = HashMap::new();
+
+        update_window(&mut windows, key("show", 1), limit(1, 1000), 900);
+        update_window(&mut windows, key("show", 2), limit(10, 1200), 900);
+        update_window(&mut windows, key("lookup", 1), None, 900);
+        assert!(!windows.contains_key(&key("lookup", 1)));
+
+        let window = windows[&key("show", 1)];
+        assert_eq!(window.wait_secs(0, 900), None);
+        assert_eq!(window.wait_secs(1, 900), Some(100));
+        assert_eq!(window.wait_secs(1, 1000), None);
+
+        let busy = Window {
+            in_flight: 1,
+            ..window
+        };
+        assert_eq!(busy.wait_secs(0, 900), Some(100));
+
+        // requests still in flight after a reset count against the fresh window
+        let overrun = Window {
+            in_flight: 15,
+            ..window
+        };
+        assert_eq!(overrun.wait_secs(0, 1000), Some(1));
+        assert_eq!(overrun.wait_secs(0, 900), Some(100));
+        let unknown = Window {
+            limit: -1,
+            ..overrun
+        };
+        assert_eq!(unknown.wait_secs(0, 1000), None);
+
+        let summary = budgets(&windows, 900);
+        assert_eq!(summary.len(), 1);
+        assert_eq!(summary[0].tokens, 2);
+        assert_eq!(summary[0].remaining, 11);
+        assert_eq!(summary[0].next_reset, Some(1000));
+
+        // once a window resets, its whole limit is available again
+        let summary = budgets(&windows, 1100);
+        assert_eq!(summary[0].remaining, 25);
+        assert_eq!(summary[0].next_reset, Some(1200));
+
+        // windows that reset long ago are forgotten, unless something is still in flight
+        windows.get_mut(&key("show", 2)).unwrap().in_flight = 1;
+        update_window(&mut windows, key("lookup", 1), limit(5, 3000), 2500);
+        assert!(!windows.contains_key(&key("show", 1)));
+        assert!(windows.contains_key(&key("show", 2)));
+        assert!(windows.contains_key(&key("lookup", 1)));
+    }
+
+    #[test]
+    fn identify_tokens() {
+        let request = |auth: &str| {
+            Request::get("https://api.twitter.com/1.1/statuses/show.json?id=20")
+                .header(hyper::header::AUTHORIZATION, auth)
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let user = window_key(&request(
+            r#"OAuth oauth_consumer_key="app", oauth_nonce="1", oauth_token="user-a""#,
+        ));
+        let same_user = window_key(&request(
+            r#"OAuth oauth_consumer_key="app", oauth_nonce="2", oauth_token="user-a""#,
+        ));
+        let other_user = window_key(&request(
+            r#"OAuth oauth_consumer_key="app", oauth_nonce="1", oauth_token="user-b""#,
+        ));
+        let bearer = window_key(&request("Bearer abc"));
+
+        assert_eq!(user.endpoint, "api.twitter.com/1.1/statuses/show.json");
+
+        let template = |url: &str| endpoint_key(&url.parse().unwrap());
+        assert_eq!(
+            template("https://api.twitter.com/1.1/statuses/destroy/1234.json"),
+            "api.twitter.com/1.1/statuses/destroy/:id.json"
+        );
+        assert_eq!(
+            template("https://api.twitter.com/2/users/783214/tweets?max_results=5"),
+            "api.twitter.com/2/users/:id/tweets"
+        );
+        assert_eq!(user, same_user);
+        assert_ne!(user, other_user);
+        assert_ne!(user, bearer);
+        assert_eq!(bearer.token, token_id(&Token::Bearer("abc".into())));
+    }
+
+    #[test]
+    fn rotate_only_reads() {
+        assert!(can_rotate(&Method::GET));
+        assert!(!can_rotate(&Method::POST));
+
+        let access = Token::Access {
+            consumer: crate::KeyPair::new("app", "app-secret"),
+            access: crate::KeyPair::new("user", "user-secret"),
+        };
+        let pool = TokenPool::new(vec![
+            Token::Bearer("a".into()),
+            access.clone(),
+            Token::Bearer("b".into()),
+        ]);
+        assert_eq!(pool.len(), 2);
+        assert!(!pool.contains(&access));
+
+        let token = Token::Bearer("a".into());
+        let show = "https://api.twitter.com/1.1/statuses/show.json";
+        assert!(pool
+            .select(
+                &token,
+                &Method::POST,
+                "https://api.twitter.com/1.1/statuses/update.json"
+            )
+            .is_none());
+        assert!(pool.select(&token, &Method::GET, show).is_some());
+
+        // calls made with access tokens depend on their user, so they're never rotated
+        assert!(pool.select(&access, &Method::GET, show).is_none());
+    }
+
+    #[test]
+    fn rotate_tokens() {
+        let tokens = vec![
+            (1, Token::Bearer("a".into())),
+            (2, Token::Bearer("b".into())),
+            (3, Token::Bearer("c".into())),
+        ];
+        let mut state = PoolState::default();
+        let window = |remaining, reset| Window {
+            limit: 15,
+            remaining,
+            reset,
+            in_flight: 0,
+        };
+
+        // tokens that haven't been used yet are taken in turn
+        assert_eq!(pick_token(&tokens, "show", &mut state, 900), 0);
+        assert_eq!(pick_token(&tokens, "show", &mut state, 900), 1);
+
+        state.windows.insert(key("show", 1), window(2, 950));
+        state.windows.insert(key("show", 2), window(5, 1000));
+        state.windows.insert(key("show", 3), window(4, 1000));
+        assert_eq!(pick_token(&tokens, "show", &mut state, 900), 1);
+        assert_eq!(state.windows[&key("show", 2)].remaining, 4);
+        // ties go to the next token in turn
+        assert_eq!(pick_token(&tokens, "show", &mut state, 900), 2);
+        assert_eq!(pick_token(&tokens, "show", &mut state, 900), 1);
+
+        // once a window resets, its whole limit is available again
+        assert_eq!(pick_token(&tokens, "show", &mut state, 960), 0);
+    }
+}
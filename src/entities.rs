@@ -28,6 +28,21 @@
 //! let slice = &text[entity.range.0..entity.range.1];
 //! ```
 //!
+//! Twitter itself counts these offsets in Unicode codepoints rather than bytes; egg-mode converts
+//! them when it loads the entity. If you need to compare an entity against indices from another
+//! source that uses Twitter's convention, each entity type has a `codepoint_range` method that
+//! converts its range back, and the [`codepoint_range`] function does the same for any byte range:
+//!
+//! [`codepoint_range`]: fn.codepoint_range.html
+//!
+//! ```rust
+//! use egg_mode::entities::HashtagEntity;
+//!
+//! let text = "café #dinner";
+//! let entity = HashtagEntity { range: (6, 13), text: "dinner".to_string() };
+//! assert_eq!(entity.codepoint_range(text), (5, 12));
+//! ```
+//!
 //! ### Shortened, Display, and Expanded URLs
 //!
 //! URL and Media entities contain references to a URL within their parent text. However, due to
@@ -42,10 +57,44 @@
 //! - `display_url`: This is a truncated version of `expanded_url`, meant to be displayed inline
 //!   with the parent text. This is useful to show users where the link resolves to, without
 //!   potentially filling up a lot of space with the fullly expanded URL.
+//!
+//! ### Repaired Ranges
+//!
+//! Some payloads, especially ones from third-party archives, contain entity ranges that no longer
+//! match their text. When a tweet is loaded, egg-mode checks each range against the text, moves or
+//! shrinks the ones that don't line up so that slicing with them can't panic, and records what it
+//! changed as [`EntityRepair`]s in `Tweet::entity_repairs`.
+//!
+//! [`EntityRepair`]: struct.EntityRepair.html
+//!
+//! ### Adjusting Ranges After Editing Text
+//!
+//! Entity ranges only line up with the text exactly as Twitter delivered it. If you alter the text
+//! before rendering it - escaping it for HTML, replacing characters, inserting markup - every
+//! range after the first edit will be off. To keep entities aligned, perform the edit through an
+//! [`OffsetMap`], which records how each change moved the text around and can translate the old
+//! ranges into the new text:
+//!
+//! [`OffsetMap`]: struct.OffsetMap.html
+//!
+//! ```rust
+//! use egg_mode::entities::{HashtagEntity, OffsetMap};
+//!
+//! let text = "fish & chips #dinner";
+//! let entity = HashtagEntity { range: (13, 20), text: "dinner".to_string() };
+//!
+//! let (escaped, map) = OffsetMap::escape_html(text);
+//! let range = map.map_range(entity.range);
+//! assert_eq!(&escaped[range.0..range.1], "#dinner");
+//! ```
+use std::fmt;
+
 use mime;
 use serde::{Deserialize, Deserializer, Serialize};
 
-use crate::common::serde_via_string;
+use crate::common::{get, raw_request, serde_via_string};
+use crate::user::TwitterUser;
+use crate::{auth, error};
 
 ///Represents a hashtag or symbol extracted from another piece of text.
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -108,6 +157,96 @@ pub struct MediaEntity {
     pub video_info: Option<VideoInfo>,
     ///Media alt text, if present.
     pub ext_alt_text: Option<String>,
+    ///Extra information about the media, if present.
+    ///
+    ///Twitter gives this for videos published through its media tools, like promoted ("Amplify")
+    ///videos, and for media that was originally posted by another account.
+    #[serde(default)]
+    pub additional_media_info: Option<AdditionalMediaInfo>,
+}
+
+impl MediaEntity {
+    ///Returns the URL for the given size of this image, using HTTPS as the protocol.
+    ///
+    ///For videos and GIFs, this returns a URL for the given size of the thumbnail image.
+    pub fn photo_url_with_size(&self, size: PhotoSize) -> String {
+        format!("{}:{}", self.media_url_https, size)
+    }
+
+    ///For videos and GIFs, returns the video variant with the highest bitrate, optionally limited
+    ///to the given maximum bitrate.
+    ///
+    ///Only variants with a `video/*` content type are considered, which leaves out streaming
+    ///playlists like HLS. If `max_bitrate` is given and no variant fits under it, the variant with
+    ///the lowest bitrate is returned instead. Returns `None` for photos.
+    pub fn best_video(&self, max_bitrate: Option<i32>) -> Option<&VideoVariant> {
+        self.video_info.as_ref()?.best_variant(max_bitrate)
+    }
+
+    ///For videos and GIFs, returns the aspect ratio of the video, like `(16, 9)`. Returns `None`
+    ///for photos.
+    pub fn aspect_ratio(&self) -> Option<(i32, i32)> {
+        self.video_info.as_ref().map(|info| info.aspect_ratio)
+    }
+
+    ///For videos, returns the duration of the video. Returns `None` for photos and GIFs.
+    pub fn duration(&self) -> Option<std::time::Duration> {
+        self.video_info.as_ref()?.duration()
+    }
+
+    ///Downloads the media file for this entity.
+    ///
+    ///For photos, this loads the original-size image. For videos and GIFs, this loads the video
+    ///variant with the highest bitrate, as returned by `best_video(None)`. To download a different
+    ///size or variant, pick out its URL with `photo_url_with_size` or `best_video` and load it
+    ///yourself.
+    ///
+    ///The request is signed with the given token, so media attached to protected tweets can be
+    ///loaded as long as the token can view the tweet.
+    pub async fn download(&self, token: &auth::Token) -> error::Result<Vec<u8>> {
+        let url = match self.media_type {
+            MediaType::Photo => self.photo_url_with_size(PhotoSize::Orig),
+            MediaType::Video | MediaType::Gif => self
+                .best_video(None)
+                .ok_or(error::Error::MissingValue("video_info"))?
+                .url
+                .clone(),
+        };
+
+        let (_, body) = raw_request(get(&url, token, None)).await?;
+        Ok(body)
+    }
+}
+
+///Represents the size variants that can be requested for an image.
+///
+///These correspond to the suffixes Twitter allows to be appended to `media_url` and
+///`media_url_https` to load a differently-sized version of the image. All but `Orig` are
+///described by the `MediaSizes` of the entity.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PhotoSize {
+    ///A thumbnail, cropped to 150x150 pixels.
+    Thumb,
+    ///An image resized to fit within 680x680 pixels.
+    Small,
+    ///An image resized to fit within 1200x1200 pixels.
+    Medium,
+    ///An image resized to fit within 2048x2048 pixels.
+    Large,
+    ///The image as it was originally uploaded.
+    Orig,
+}
+
+impl fmt::Display for PhotoSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            PhotoSize::Thumb => write!(f, "thumb"),
+            PhotoSize::Small => write!(f, "small"),
+            PhotoSize::Medium => write!(f, "medium"),
+            PhotoSize::Large => write!(f, "large"),
+            PhotoSize::Orig => write!(f, "orig"),
+        }
+    }
 }
 
 ///Represents the types of media that can be attached to a tweet.
@@ -137,6 +276,46 @@ pub struct MediaSizes {
     pub large: MediaSize,
 }
 
+impl MediaSizes {
+    ///Computes the size variants Twitter generates for an image with the given original
+    ///dimensions.
+    ///
+    ///Version 2 of the Twitter API only reports the original width and height of a media file.
+    ///This reproduces the variants that version 1.1 reports alongside it: a 150x150 cropped
+    ///thumbnail, and small, medium, and large versions scaled down (but never up) to fit within
+    ///680, 1200, and 2048 pixels respectively.
+    pub fn from_dimensions(width: i32, height: i32) -> MediaSizes {
+        let fit = |limit: i32| {
+            let longest = width.max(height);
+            if longest <= limit || longest <= 0 {
+                MediaSize {
+                    w: width,
+                    h: height,
+                    resize: ResizeMode::Fit,
+                }
+            } else {
+                let scale = |side: i32| (side as i64 * limit as i64 / longest as i64) as i32;
+                MediaSize {
+                    w: scale(width),
+                    h: scale(height),
+                    resize: ResizeMode::Fit,
+                }
+            }
+        };
+
+        MediaSizes {
+            thumb: MediaSize {
+                w: 150,
+                h: 150,
+                resize: ResizeMode::Crop,
+            },
+            small: fit(680),
+            medium: fit(1200),
+            large: fit(2048),
+        }
+    }
+}
+
 ///Represents how an image has been resized for a given size variant.
 #[derive(Debug, Copy, Clone, Deserialize, Serialize, PartialEq)]
 pub enum ResizeMode {
@@ -172,6 +351,59 @@ pub struct VideoInfo {
     pub variants: Vec<VideoVariant>,
 }
 
+impl VideoInfo {
+    ///Returns the duration of the video, if Twitter gave one.
+    ///
+    ///This is not given for animated GIFs.
+    pub fn duration(&self) -> Option<std::time::Duration> {
+        let millis = self.duration_millis?;
+        Some(std::time::Duration::from_millis(millis.max(0) as u64))
+    }
+
+    ///Returns the video variant with the highest bitrate, optionally limited to the given maximum
+    ///bitrate.
+    ///
+    ///Only variants with a `video/*` content type are considered, which leaves out streaming
+    ///playlists like HLS. If `max_bitrate` is given and no variant fits under it, the variant with
+    ///the lowest bitrate is returned instead.
+    pub fn best_variant(&self, max_bitrate: Option<i32>) -> Option<&VideoVariant> {
+        let videos = || {
+            self.variants
+                .iter()
+                .filter(|v| v.content_type.type_() == mime::VIDEO)
+        };
+        let bitrate = |v: &&VideoVariant| v.bitrate.unwrap_or(0);
+
+        videos()
+            .filter(|v| match max_bitrate {
+                Some(max) => bitrate(v) <= max,
+                None => true,
+            })
+            .max_by_key(bitrate)
+            .or_else(|| videos().min_by_key(bitrate))
+    }
+}
+
+///Represents extra information about a media entity.
+///
+///Twitter gives this for videos published through its media tools, like promoted ("Amplify")
+///videos, and for media that was originally posted by another account. Every field is optional,
+///as Twitter only includes the ones that apply to the media.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct AdditionalMediaInfo {
+    ///The title given to the video by its publisher.
+    pub title: Option<String>,
+    ///The description given to the video by its publisher.
+    pub description: Option<String>,
+    ///Whether the video can be embedded on other sites.
+    pub embeddable: Option<bool>,
+    ///Whether the video can be monetized, for example with pre-roll ads.
+    pub monetizable: Option<bool>,
+    ///The account that originally posted the media, if it was posted by a different account than
+    ///the one that posted this tweet.
+    pub source_user: Option<Box<TwitterUser>>,
+}
+
 ///Represents information about a specific encoding of a video.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct VideoVariant {
@@ -220,6 +452,364 @@ pub struct MentionEntity {
     pub screen_name: String,
 }
 
+///Converts a byte range in the given text into the equivalent range of Unicode codepoints, the
+///convention Twitter uses for the `indices` of entities in its API responses.
+///
+///Offsets that fall inside a multi-byte character are rounded up to the next codepoint, and offsets
+///past the end of the text are clamped to its length in codepoints.
+pub fn codepoint_range((start, end): (usize, usize), text: &str) -> (usize, usize) {
+    let to_codepoint = |byte: usize| text.char_indices().take_while(|&(i, _)| i < byte).count();
+    (to_codepoint(start), to_codepoint(end))
+}
+
+macro_rules! impl_codepoint_range {
+    ($($entity:ty),+) => { $(
+        impl $entity {
+            ///Returns the `range` of this entity in Unicode codepoints instead of bytes, matching
+            ///the `indices` Twitter would return for it. `text` must be the text this entity was
+            ///extracted from.
+            pub fn codepoint_range(&self, text: &str) -> (usize, usize) {
+                codepoint_range(self.range, text)
+            }
+        }
+    )+ };
+}
+
+impl_codepoint_range!(HashtagEntity, MediaEntity, UrlEntity, MentionEntity);
+
+///The kinds of entity whose range can be repaired when a tweet is loaded.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum EntityKind {
+    ///A hashtag in `entities.hashtags`.
+    Hashtag,
+    ///A stock symbol in `entities.symbols`.
+    Symbol,
+    ///A link in `entities.urls`.
+    Url,
+    ///A user mention in `entities.user_mentions`.
+    Mention,
+    ///A media entity in `entities.media`.
+    Media,
+    ///A media entity in `extended_entities.media`.
+    ExtendedMedia,
+    ///The tweet's `display_text_range`.
+    DisplayTextRange,
+}
+
+///How an entity's range was repaired.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum RepairMethod {
+    ///The entity's text was found elsewhere in the tweet, and the range was moved there. If the
+    ///text appears more than once, the occurrence closest to the original range is used.
+    Relocated,
+    ///The range pointed outside the text or into the middle of a character, and the entity's text
+    ///couldn't be found, so the range was shrunk to fit the text. The range may now be empty.
+    Clamped,
+}
+
+///A record of an entity whose range didn't match its tweet's text, and how it was fixed.
+///
+///Tweets loaded from Twitter itself should never need repairs, but payloads from other sources,
+///like third-party archives, sometimes have entity ranges that no longer line up with the text.
+///Slicing the text with such a range could select the wrong text or panic, so when a tweet is
+///loaded egg-mode checks every range against the text, repairs the ones that don't match, and lists
+///them in `Tweet::entity_repairs`.
+///
+///A range that fits in the text but whose text doesn't match the entity is only changed if the
+///entity's text can be found elsewhere in the tweet; otherwise it's left as it was, since slicing
+///with it is still safe.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct EntityRepair {
+    ///The kind of entity that was repaired.
+    pub kind: EntityKind,
+    ///The position of the entity in its list, e.g. the index into `entities.hashtags`.
+    pub index: usize,
+    ///The range that was loaded for the entity, in codepoints, as given in the payload.
+    pub original: (usize, usize),
+    ///The repaired range of the entity, in bytes.
+    pub range: (usize, usize),
+    ///How the range was repaired.
+    pub method: RepairMethod,
+}
+
+///Converts the given range from codepoints into bytes, and checks that it selects the given text.
+///If it doesn't, the range is repaired and an `EntityRepair` is added to `repairs`.
+///
+///`expected` is the text the entity refers to, without any of the given `prefixes`, which are
+///compared case-insensitively. If `expected` is `None`, the range is only checked to fit the text.
+pub(crate) fn load_range(
+    kind: EntityKind,
+    index: usize,
+    range: &mut (usize, usize),
+    text: &str,
+    expected: Option<&str>,
+    prefixes: &[char],
+    repairs: &mut Vec<EntityRepair>,
+) {
+    let original = *range;
+    crate::common::codepoints_to_bytes(range, text);
+
+    let fits = range.0 <= range.1
+        && range.1 <= text.len()
+        && text.is_char_boundary(range.0)
+        && text.is_char_boundary(range.1);
+    let method = match expected {
+        Some(expected) if fits && selects(text, *range, expected, prefixes) => return,
+        Some(expected) => match relocate(text, range.0, expected, prefixes) {
+            Some(found) => {
+                *range = found;
+                RepairMethod::Relocated
+            }
+            None if fits => return,
+            None => RepairMethod::Clamped,
+        },
+        None if fits => return,
+        None => RepairMethod::Clamped,
+    };
+
+    if method == RepairMethod::Clamped {
+        let floor = |mut i: usize| {
+            i = i.min(text.len());
+            while !text.is_char_boundary(i) {
+                i -= 1;
+            }
+            i
+        };
+        let start = floor(range.0);
+        *range = (start, floor(range.1).max(start));
+    }
+
+    repairs.push(EntityRepair {
+        kind,
+        index,
+        original,
+        range: *range,
+        method,
+    });
+}
+
+///Returns whether the given range of `text` holds `expected`, after one of the given prefixes.
+fn selects(text: &str, (start, end): (usize, usize), expected: &str, prefixes: &[char]) -> bool {
+    let slice = &text[start..end];
+    if prefixes.is_empty() {
+        return slice == expected;
+    }
+
+    let mut chars = slice.chars();
+    match chars.next() {
+        Some(first) if prefixes.contains(&first) => {
+            chars.as_str().to_lowercase() == expected.to_lowercase()
+        }
+        _ => false,
+    }
+}
+
+///Finds the occurrence of `expected` in `text`, after one of the given prefixes, that's closest to
+///the given byte offset.
+fn relocate(text: &str, near: usize, expected: &str, prefixes: &[char]) -> Option<(usize, usize)> {
+    text.char_indices()
+        .filter_map(|(start, ch)| {
+            let end = if prefixes.is_empty() {
+                start + expected.len()
+            } else if prefixes.contains(&ch) {
+                start + ch.len_utf8() + expected.len()
+            } else {
+                return None;
+            };
+            if end <= text.len()
+                && text.is_char_boundary(end)
+                && selects(text, (start, end), expected, prefixes)
+            {
+                Some((start, end))
+            } else {
+                None
+            }
+        })
+        .min_by_key(|&(start, _)| (start as isize - near as isize).abs())
+}
+
+///A record of how byte offsets moved when a piece of text was rewritten.
+///
+///An `OffsetMap` is built up from a series of edits, each of which replaces one span of the
+///original text with a new span of some length. Once the edits have been recorded, `map_offset`
+///and `map_range` can translate positions in the original text into positions in the rewritten
+///text, so entity ranges (and fields like `Tweet::display_text_range`) can be carried over to the
+///new text.
+///
+///For the common case of escaping or unescaping HTML, `escape_html` and `unescape_html` perform
+///the rewrite and build the map at the same time. For simple character-by-character replacements,
+///`map_chars` does the same with a closure of your choosing. For anything else (for example,
+///Unicode normalization with an external crate), perform the rewrite yourself and call `record`
+///for each span you change.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OffsetMap {
+    edits: Vec<OffsetEdit>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct OffsetEdit {
+    original: (usize, usize),
+    new: (usize, usize),
+}
+
+impl OffsetMap {
+    ///Creates an empty `OffsetMap`, which maps every offset to itself.
+    pub fn new() -> OffsetMap {
+        OffsetMap::default()
+    }
+
+    ///Records that the given byte span of the original text was replaced with `new_len` bytes of
+    ///new text.
+    ///
+    ///Edits must be recorded in the order they appear in the original text, and may not overlap.
+    ///An empty `original` span records an insertion.
+    ///
+    ///# Panics
+    ///
+    ///This function will panic if `original` is backwards, or if it starts before the end of the
+    ///previously-recorded edit.
+    pub fn record(&mut self, original: (usize, usize), new_len: usize) {
+        assert!(
+            original.0 <= original.1,
+            "backwards range given to OffsetMap::record"
+        );
+        if let Some(last) = self.edits.last() {
+            assert!(
+                original.0 >= last.original.1,
+                "OffsetMap edits must be recorded in order"
+            );
+        }
+
+        let start = self.map_offset(original.0);
+        self.edits.push(OffsetEdit {
+            original,
+            new: (start, start + new_len),
+        });
+    }
+
+    ///Returns whether this map has no recorded edits.
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    ///Translates a byte offset in the original text into the equivalent offset in the rewritten
+    ///text.
+    ///
+    ///An offset that falls inside a replaced span is moved to the start of its replacement. An
+    ///offset at the end of a replaced span (or at the position of an insertion) is moved to the end
+    ///of the new text.
+    pub fn map_offset(&self, offset: usize) -> usize {
+        self.map_offset_inner(offset, false)
+    }
+
+    ///Translates a byte range in the original text into the equivalent range in the rewritten
+    ///text.
+    ///
+    ///If either end of the range falls inside a replaced span, the range is widened to cover the
+    ///entire replacement, so the result always contains the rewritten version of the original
+    ///range.
+    pub fn map_range(&self, range: (usize, usize)) -> (usize, usize) {
+        (
+            self.map_offset_inner(range.0, false),
+            self.map_offset_inner(range.1, true),
+        )
+    }
+
+    fn map_offset_inner(&self, offset: usize, round_up: bool) -> usize {
+        let mut mapped = offset;
+
+        for edit in &self.edits {
+            if offset >= edit.original.1 {
+                mapped = offset - edit.original.1 + edit.new.1;
+            } else if offset > edit.original.0 {
+                return if round_up { edit.new.1 } else { edit.new.0 };
+            } else {
+                break;
+            }
+        }
+
+        mapped
+    }
+
+    ///Rewrites the given text by passing each character through the given closure, returning the
+    ///new text and a map of how its offsets moved.
+    ///
+    ///If the closure returns `None`, the character is kept as-is. Otherwise, the character is
+    ///replaced with the returned string, which may be empty to remove the character.
+    pub fn map_chars<F, S>(text: &str, mut replace: F) -> (String, OffsetMap)
+    where
+        F: FnMut(char) -> Option<S>,
+        S: AsRef<str>,
+    {
+        let mut output = String::with_capacity(text.len());
+        let mut map = OffsetMap::new();
+
+        for (idx, ch) in text.char_indices() {
+            match replace(ch) {
+                Some(rep) => {
+                    let rep = rep.as_ref();
+                    output.push_str(rep);
+                    map.record((idx, idx + ch.len_utf8()), rep.len());
+                }
+                None => output.push(ch),
+            }
+        }
+
+        (output, map)
+    }
+
+    ///Escapes the characters `&`, `<`, and `>` in the given text, returning the escaped text and a
+    ///map of how its offsets moved.
+    ///
+    ///Note that the text of tweets returned by Twitter already has these characters escaped. This
+    ///function is meant for text you've already unescaped, or text that came from somewhere else.
+    pub fn escape_html(text: &str) -> (String, OffsetMap) {
+        OffsetMap::map_chars(text, |ch| match ch {
+            '&' => Some("&amp;"),
+            '<' => Some("&lt;"),
+            '>' => Some("&gt;"),
+            _ => None,
+        })
+    }
+
+    ///Unescapes the HTML entities `&amp;`, `&lt;`, and `&gt;` in the given text, returning the
+    ///unescaped text and a map of how its offsets moved.
+    ///
+    ///These are the only entities Twitter escapes in tweet text, so this can be used to get the
+    ///literal text of a tweet without breaking its entity ranges.
+    pub fn unescape_html(text: &str) -> (String, OffsetMap) {
+        const ENTITIES: &[(&str, &str)] = &[("&amp;", "&"), ("&lt;", "<"), ("&gt;", ">")];
+
+        let mut output = String::with_capacity(text.len());
+        let mut map = OffsetMap::new();
+        let mut rest = text;
+        let mut idx = 0;
+
+        while let Some(amp) = rest.find('&') {
+            output.push_str(&rest[..amp]);
+            idx += amp;
+            rest = &rest[amp..];
+
+            match ENTITIES.iter().find(|(ent, _)| rest.starts_with(ent)) {
+                Some((ent, ch)) => {
+                    output.push_str(ch);
+                    map.record((idx, idx + ent.len()), ch.len());
+                    idx += ent.len();
+                    rest = &rest[ent.len()..];
+                }
+                None => {
+                    output.push('&');
+                    idx += 1;
+                    rest = &rest[1..];
+                }
+            }
+        }
+        output.push_str(rest);
+
+        (output, map)
+    }
+}
+
 fn nullable_id<'de, D>(deserializer: D) -> Result<u64, D::Error>
 where
     D: Deserializer<'de>,
@@ -235,3 +825,117 @@ where
     let opt = Option::deserialize(deserializer)?;
     Ok(opt.unwrap_or_default())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{codepoint_range, MediaEntity, OffsetMap, VideoInfo};
+    use crate::common::codepoints_to_bytes;
+    use crate::common::tests::load_file;
+
+    #[test]
+    fn codepoint_range_roundtrip() {
+        let text = "🎉 ünïcode #tags";
+        let mut range = (10, 15);
+        codepoints_to_bytes(&mut range, text);
+        assert_eq!(&text[range.0..range.1], "#tags");
+        assert_eq!(codepoint_range(range, text), (10, 15));
+
+        assert_eq!(codepoint_range((0, 100), text), (0, 15));
+        // offsets inside the emoji round up to the next codepoint
+        assert_eq!(codepoint_range((1, 4), text), (1, 1));
+    }
+
+    #[test]
+    fn best_video_variant() {
+        let info: VideoInfo = serde_json::from_str(
+            r#"{
+                "aspect_ratio": [16, 9],
+                "duration_millis": 10000,
+                "variants": [
+                    { "content_type": "application/x-mpegURL", "url": "https://video.twimg.com/pl.m3u8" },
+                    { "bitrate": 832000, "content_type": "video/mp4", "url": "https://video.twimg.com/832.mp4" },
+                    { "bitrate": 2176000, "content_type": "video/mp4", "url": "https://video.twimg.com/2176.mp4" },
+                    { "bitrate": 256000, "content_type": "video/mp4", "url": "https://video.twimg.com/256.mp4" }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            info.best_variant(None).unwrap().url,
+            "https://video.twimg.com/2176.mp4"
+        );
+        assert_eq!(
+            info.best_variant(Some(1_000_000)).unwrap().url,
+            "https://video.twimg.com/832.mp4"
+        );
+        assert_eq!(
+            info.best_variant(Some(1000)).unwrap().url,
+            "https://video.twimg.com/256.mp4"
+        );
+    }
+
+    #[test]
+    fn additional_media_info() {
+        let sample = load_file("sample_payloads/amplify_video.json");
+        let media: MediaEntity = serde_json::from_str(&sample).unwrap();
+
+        assert_eq!(media.aspect_ratio(), Some((16, 9)));
+        assert_eq!(
+            media.duration(),
+            Some(std::time::Duration::from_millis(30_033))
+        );
+
+        let info = media.additional_media_info.unwrap();
+        assert_eq!(info.title.as_deref(), Some("Launch day"));
+        assert_eq!(info.monetizable, Some(true));
+        assert_eq!(info.embeddable, None);
+        assert_eq!(info.source_user.unwrap().screen_name, "TwitterDev");
+    }
+
+    #[test]
+    fn offset_map_escape() {
+        let text = "a&b <c> d";
+        let (escaped, map) = OffsetMap::escape_html(text);
+        assert_eq!(escaped, "a&amp;b &lt;c&gt; d");
+
+        // "b" stays a single character after the shift
+        assert_eq!(map.map_range((2, 3)), (6, 7));
+        // "<c>" covers the whole escaped span
+        assert_eq!(map.map_range((4, 7)), (8, 17));
+        assert_eq!(&escaped[8..17], "&lt;c&gt;");
+        // an offset in the middle of an edit snaps to the start of the replacement
+        assert_eq!(map.map_offset(1), 1);
+        assert_eq!(map.map_offset(text.len()), escaped.len());
+    }
+
+    #[test]
+    fn offset_map_unescape_roundtrip() {
+        let text = "x &amp; y &lt;3 &bogus; z";
+        let (unescaped, map) = OffsetMap::unescape_html(text);
+        assert_eq!(unescaped, "x & y <3 &bogus; z");
+
+        let range = map.map_range((10, 15));
+        assert_eq!(&unescaped[range.0..range.1], "<3");
+        let range = map.map_range((16, 23));
+        assert_eq!(&unescaped[range.0..range.1], "&bogus;");
+
+        let (escaped, _) = OffsetMap::escape_html("&");
+        assert_eq!(OffsetMap::unescape_html(&escaped).0, "&");
+    }
+
+    #[test]
+    fn offset_map_manual() {
+        let mut map = OffsetMap::new();
+        assert!(map.is_empty());
+        assert_eq!(map.map_range((3, 5)), (3, 5));
+
+        // insert two bytes at 0, delete bytes 2..4
+        map.record((0, 0), 2);
+        map.record((2, 4), 0);
+        assert_eq!(map.map_offset(0), 2);
+        assert_eq!(map.map_offset(1), 3);
+        assert_eq!(map.map_offset(3), 4);
+        assert_eq!(map.map_range((1, 6)), (3, 6));
+    }
+}
@@ -24,7 +24,7 @@ use serde_json;
 use std::{self, fmt};
 use tokio;
 
-use crate::common::Headers;
+use crate::common::{error_rate_limit, Headers, RateLimit};
 
 /// Convenient alias to a Result containing a local Error type
 pub type Result<T> = std::result::Result<T, Error>;
@@ -40,6 +40,13 @@ pub struct TwitterErrors {
     pub errors: Vec<TwitterErrorCode>,
 }
 
+impl TwitterErrors {
+    ///Returns whether any of these errors is of the given kind.
+    pub fn has_kind(&self, kind: TwitterErrorKind) -> bool {
+        self.errors.iter().any(|e| e.kind() == kind)
+    }
+}
+
 impl fmt::Display for TwitterErrors {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let mut first = true;
@@ -58,7 +65,7 @@ impl fmt::Display for TwitterErrors {
 }
 
 ///Represents a specific error returned from a Twitter API call.
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TwitterErrorCode {
     ///The error message returned by Twitter.
     pub message: String,
@@ -69,9 +76,172 @@ pub struct TwitterErrorCode {
     pub code: i32,
 }
 
+impl TwitterErrorCode {
+    ///Returns what kind of error this is, for the error codes egg-mode recognizes.
+    pub fn kind(&self) -> TwitterErrorKind {
+        TwitterErrorKind::from_code(self.code)
+    }
+}
+
 impl fmt::Display for TwitterErrorCode {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "#{}: {}", self.code, self.message)
+        write!(f, "#{}: {}", self.code, self.message)?;
+        if let Some(suggestion) = self.kind().suggestion() {
+            write!(f, " ({})", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+///An error Twitter returned alongside the data of a successful response, describing a part of the
+///request that couldn't be completed.
+///
+///These are collected into `Response::partial_errors`. Version 1.1 endpoints describe these errors
+///with a numeric code, while version 2 endpoints describe them as a [`Problem`][].
+///
+///[`Problem`]: struct.Problem.html
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum PartialError {
+    ///An error with a numeric error code, from a version 1.1 endpoint.
+    Code(TwitterErrorCode),
+    ///A problem reported by a version 2 endpoint.
+    Problem(Problem),
+}
+
+///Formats the given errors for `Error::NoData`.
+fn list_errors(errors: &[PartialError]) -> String {
+    errors
+        .iter()
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>()
+        .join(",\n")
+}
+
+impl PartialError {
+    ///Returns the numeric error code of this error, if it has one.
+    pub fn code(&self) -> Option<i32> {
+        match self {
+            PartialError::Code(err) => Some(err.code),
+            PartialError::Problem(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for PartialError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PartialError::Code(err) => write!(f, "{}", err),
+            PartialError::Problem(problem) => write!(f, "{}", problem),
+        }
+    }
+}
+
+///A problem with part of a request, as reported by a version 2 endpoint.
+///
+///Twitter describes these in the [problem details][] format: `problem_type` is a URL naming the
+///kind of problem, like `https://api.twitter.com/2/problems/resource-not-found`, and the other
+///fields describe which part of the request it applies to.
+///
+///[problem details]: https://developer.twitter.com/en/support/twitter-api/error-troubleshooting
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Problem {
+    ///A short summary of the kind of problem, like "Not Found Error".
+    pub title: String,
+    ///A URL that identifies the kind of problem.
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    ///A description of this particular problem.
+    #[serde(default)]
+    pub detail: Option<String>,
+    ///The value in the request that caused the problem, like the ID of a missing tweet.
+    #[serde(default)]
+    pub value: Option<String>,
+    ///The request parameter that `value` was given in.
+    #[serde(default)]
+    pub parameter: Option<String>,
+    ///The kind of resource the problem applies to, like `tweet` or `user`.
+    #[serde(default)]
+    pub resource_type: Option<String>,
+    ///The ID of the resource the problem applies to.
+    #[serde(default)]
+    pub resource_id: Option<String>,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.detail {
+            Some(detail) => write!(f, "{}: {}", self.title, detail),
+            None => write!(f, "{}", self.title),
+        }
+    }
+}
+
+///The kinds of error code returned by Twitter that egg-mode gives special treatment.
+///
+///These are returned by `TwitterErrorCode::kind`, so callers can match on common errors without
+///keeping track of Twitter's numeric codes. Each recognized kind also has a suggestion for how to
+///fix it, which is included when the error is displayed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TwitterErrorKind {
+    ///Twitter couldn't authenticate the request (code 32). This usually means the consumer key
+    ///and secret are wrong, or the request was signed incorrectly.
+    BadAuthentication,
+    ///The access token is invalid or has expired (code 89), usually because the user revoked the
+    ///app's access.
+    InvalidToken,
+    ///The tweet is a duplicate of one the user posted recently (code 187).
+    DuplicateStatus,
+    ///The user's account is temporarily locked (code 326), and the user needs to log in to
+    ///twitter.com to unlock it.
+    AccountLocked,
+    ///Any other error code. The enclosed value is the numeric code.
+    Other(i32),
+}
+
+impl TwitterErrorKind {
+    ///Returns the kind of error for the given numeric code.
+    pub fn from_code(code: i32) -> TwitterErrorKind {
+        match code {
+            32 => TwitterErrorKind::BadAuthentication,
+            89 => TwitterErrorKind::InvalidToken,
+            187 => TwitterErrorKind::DuplicateStatus,
+            326 => TwitterErrorKind::AccountLocked,
+            code => TwitterErrorKind::Other(code),
+        }
+    }
+
+    ///Returns the numeric code for this kind of error.
+    pub fn code(self) -> i32 {
+        match self {
+            TwitterErrorKind::BadAuthentication => 32,
+            TwitterErrorKind::InvalidToken => 89,
+            TwitterErrorKind::DuplicateStatus => 187,
+            TwitterErrorKind::AccountLocked => 326,
+            TwitterErrorKind::Other(code) => code,
+        }
+    }
+
+    ///Returns a short description of how to fix this kind of error, if egg-mode recognizes it.
+    ///
+    ///The text of these suggestions is meant for people, and may change between releases. Match
+    ///on the `TwitterErrorKind` itself to handle errors in code.
+    pub fn suggestion(self) -> Option<&'static str> {
+        match self {
+            TwitterErrorKind::BadAuthentication => Some(
+                "check that the consumer key and secret are correct, and that the system clock is accurate",
+            ),
+            TwitterErrorKind::InvalidToken => {
+                Some("the access token was revoked or expired; have the user sign in again")
+            }
+            TwitterErrorKind::DuplicateStatus => {
+                Some("the user already posted this text recently; change the text before retrying")
+            }
+            TwitterErrorKind::AccountLocked => Some(
+                "the account is temporarily locked; the user needs to log in to twitter.com to unlock it",
+            ),
+            TwitterErrorKind::Other(_) => None,
+        }
     }
 }
 
@@ -87,6 +257,155 @@ pub struct MediaError {
     pub message: String,
 }
 
+/// Represents a safety check that failed before egg-mode performed an action on the user's behalf.
+///
+/// This is returned as part of [`Error::PreconditionFailed`][] by functions that verify their
+/// target before acting on it, like `tweet::delete_checked`.
+///
+/// [`Error::PreconditionFailed`]: enum.Error.html#variant.PreconditionFailed
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum PreconditionError {
+    ///The tweet was posted by someone other than the authenticated user.
+    #[error("Tweet {tweet_id} was posted by user {author_id}, not the authenticated user")]
+    NotOwner {
+        ///The ID of the tweet that was checked.
+        tweet_id: u64,
+        ///The ID of the user who posted the tweet.
+        author_id: u64,
+    },
+    ///The tweet was posted more recently than the given bound.
+    #[error("Tweet {tweet_id} was posted at {created_at}, which is too recent")]
+    TooNew {
+        ///The ID of the tweet that was checked.
+        tweet_id: u64,
+        ///When the tweet was posted.
+        created_at: chrono::DateTime<chrono::Utc>,
+    },
+    ///The tweet was posted earlier than the given bound.
+    #[error("Tweet {tweet_id} was posted at {created_at}, which is too old")]
+    TooOld {
+        ///The ID of the tweet that was checked.
+        tweet_id: u64,
+        ///When the tweet was posted.
+        created_at: chrono::DateTime<chrono::Utc>,
+    },
+}
+
+/// Represents a problem with media that was found before uploading it.
+///
+/// This is returned as part of [`Error::InvalidMedia`][] when the `media_validation` feature is
+/// enabled.
+///
+/// [`Error::InvalidMedia`]: enum.Error.html#variant.InvalidMedia
+#[cfg(feature = "media_validation")]
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum MediaValidationError {
+    ///The media data was empty.
+    #[error("Media data is empty")]
+    Empty,
+    ///The media data doesn't start with the signature of its declared type.
+    #[error("Media data does not look like {declared}")]
+    TypeMismatch {
+        ///The media type that the data was declared as.
+        declared: mime::Mime,
+    },
+    ///The media data is larger than Twitter allows for its category.
+    #[error("Media is {size} bytes, larger than the {limit}-byte limit for {category}")]
+    TooLarge {
+        ///The size of the media data, in bytes.
+        size: usize,
+        ///The largest size Twitter allows for this category, in bytes.
+        limit: usize,
+        ///The category the media was uploaded as.
+        category: crate::media::MediaCategory,
+    },
+}
+
+/// Represents a problem with the frames of an animation, found while encoding it into a GIF.
+///
+/// This is returned by `media::encode_gif`, and as part of [`Error::InvalidAnimation`][] by
+/// `media::upload_animation`. It's only available with the `image` feature enabled.
+///
+/// [`Error::InvalidAnimation`]: enum.Error.html#variant.InvalidAnimation
+#[cfg(feature = "image")]
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum AnimationError {
+    ///No frames were given.
+    #[error("Animation has no frames")]
+    NoFrames,
+    ///The frames are empty, or larger than the 1280x1080 pixels Twitter allows.
+    #[error("Frame is {width}x{height}, outside the 1x1 to 1280x1080 range Twitter allows")]
+    BadDimensions {
+        ///The width of the frame.
+        width: u32,
+        ///The height of the frame.
+        height: u32,
+    },
+    ///The frames aren't all the same size.
+    #[error("Frame is {}x{}, but the first frame is {}x{}", actual.0, actual.1, expected.0, expected.1)]
+    SizeMismatch {
+        ///The width and height of the first frame.
+        expected: (u32, u32),
+        ///The width and height of the frame that differs from it.
+        actual: (u32, u32),
+    },
+    ///There are more than the 350 frames Twitter allows. The enclosed value is the number of
+    ///frames read before stopping.
+    #[error("Animation has {} frames, more than the 350 Twitter allows", _0)]
+    TooManyFrames(usize),
+    ///The frames have more than the 300 million pixels Twitter allows across a whole animation.
+    ///The enclosed value is the number of pixels read before stopping.
+    #[error(
+        "Animation has {} pixels, more than the 300 million Twitter allows",
+        _0
+    )]
+    TooManyPixels(u64),
+    ///The encoded GIF is larger than the 15MB Twitter allows. The enclosed value is its size in
+    ///bytes.
+    #[error("Encoded GIF is {} bytes, larger than the 15MB Twitter allows", _0)]
+    FileTooLarge(usize),
+    ///A frame couldn't be read from the given `Frames`. The enclosed value is the error from the
+    ///`image` crate.
+    #[error("Couldn't read frame: {}", _0)]
+    Decode(String),
+    ///The `image` crate's GIF encoder failed. The enclosed value is its error.
+    #[error("Couldn't encode GIF: {}", _0)]
+    Encode(String),
+}
+
+/// An error from posting a `tweet::ThreadDraft`, along with the tweets of the thread that were
+/// posted before it.
+///
+/// Tweets that were already posted aren't deleted when a later one fails, so these can be used to
+/// retry the rest of the thread, or to clean up after it.
+#[derive(Debug, thiserror::Error)]
+#[error("Error posting tweet {} of a thread: {}", posted.len() + 1, error)]
+pub struct ThreadError {
+    ///The tweets that were posted before the error, in order.
+    pub posted: Vec<crate::tweet::Tweet>,
+    ///The error from posting the next tweet.
+    #[source]
+    pub error: Error,
+}
+
+/// Represents a problem with the filters of a `StreamBuilder`, found before connecting.
+///
+/// This is returned as part of [`Error::InvalidStream`][] by `StreamBuilder::start`. It's only
+/// available with the `stream` feature enabled.
+///
+/// [`Error::InvalidStream`]: enum.Error.html#variant.InvalidStream
+#[cfg(feature = "stream")]
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum BuilderError {
+    ///None of `follow`, `track`, or `locations` were given, so Twitter would refuse the stream.
+    #[error("Stream has no follow, track, or locations filters")]
+    NoPredicates,
+    ///A bounding box given to `locations` has coordinates out of range, or its southwest corner
+    ///isn't south and west of its northeast corner. The enclosed value is the bounding box.
+    #[error("Invalid bounding box: {}", _0)]
+    InvalidBoundingBox(crate::place::BoundingBox),
+}
+
 /// A set of errors that can occur when interacting with Twitter.
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -111,9 +430,18 @@ pub enum Error {
     #[error("Future has already completed")]
     FutureAlreadyCompleted,
     ///The response from Twitter returned an error structure instead of the expected response. The
-    ///enclosed value was the response from Twitter.
+    ///enclosed values are the headers and errors of the response from Twitter. The rate-limit
+    ///information from the headers can be read with `Error::rate_limit`.
     #[error("Errors returned by Twitter: {_1}")]
     TwitterError(Headers, TwitterErrors),
+    ///Twitter answered a version 2 request with a successful status, but with only an `errors`
+    ///array and no `data`, meaning none of the request could be completed. The enclosed values are
+    ///the headers of the response and its errors, which are usually `PartialError::Problem`s.
+    ///
+    ///When some of the request did succeed, these errors are returned in `Response::partial_errors`
+    ///instead.
+    #[error("No data returned by Twitter: {}", list_errors(_1))]
+    NoData(Headers, Box<[PartialError]>),
     ///The response returned from Twitter contained an error indicating that the rate limit for
     ///that method has been reached. The enclosed value is the Unix timestamp in UTC when the next
     ///rate-limit window will open.
@@ -123,23 +451,56 @@ pub enum Error {
     ///post-processing. The enclosed value contains the error message from Twitter.
     #[error("Error processing media: {}", _0)]
     MediaError(#[from] MediaError),
-    ///The response from Twitter gave a response code that indicated an error. The enclosed value
-    ///was the response code.
+    ///The response from Twitter gave a response code that indicated an error. The enclosed values
+    ///are the response code and the rate-limit information from the response headers, if Twitter
+    ///included it.
     ///
     ///This is only returned if Twitter did not also return an [error code][TwitterErrors] in the
     ///response body. That check is performed before examining the status code.
     ///
     ///[TwitterErrors]: struct.TwitterErrors.html
     #[error("Error status received: {}", _0)]
-    BadStatus(hyper::StatusCode),
+    BadStatus(hyper::StatusCode, Option<RateLimit>),
     ///The web request experienced an error. The enclosed error was returned from hyper.
+    ///
+    ///Network errors that egg-mode can recognize are returned as `Dns`, `TlsError`,
+    ///`ConnectionReset`, or `Timeout` instead.
     #[error("Network error: {}", _0)]
-    NetError(#[from] hyper::Error),
-    ///The `native_tls` implementation returned an error. The enclosed error was returned from
+    NetError(#[source] hyper::Error),
+    ///The host name of the request could not be resolved. The enclosed error was returned from
+    ///hyper.
+    ///
+    ///This can be a temporary network problem, but can also mean that the machine is offline or
+    ///its DNS settings are wrong.
+    #[error("DNS lookup failed: {}", _0)]
+    Dns(#[source] hyper::Error),
+    ///The connection to Twitter was closed before the response was complete. The enclosed error
+    ///was returned from hyper.
+    ///
+    ///This is usually a temporary network problem, so the request can be retried.
+    #[error("Connection reset: {}", _0)]
+    ConnectionReset(#[source] hyper::Error),
+    ///The connection to Twitter timed out. The enclosed error was returned from hyper.
+    ///
+    ///This is usually a temporary network problem, so the request can be retried.
+    #[error("Connection timed out: {}", _0)]
+    Timeout(#[source] hyper::Error),
+    ///A secure connection to Twitter could not be set up. The enclosed error was returned from
     ///`native_tls`.
+    ///
+    ///This usually means the TLS configuration is wrong, for example if the system's root
+    ///certificates are missing, rather than a temporary network problem.
     #[cfg(feature = "native_tls")]
     #[error("TLS error: {}", _0)]
     TlsError(#[from] native_tls::Error),
+    ///A secure connection to Twitter could not be set up. The enclosed error was returned from
+    ///`rustls`.
+    ///
+    ///This usually means the TLS configuration is wrong, for example if the system's root
+    ///certificates are missing, rather than a temporary network problem.
+    #[cfg(any(feature = "rustls", feature = "rustls_webpki"))]
+    #[error("TLS error: {}", _0)]
+    TlsError(#[from] rustls::TLSError),
     ///An error was experienced while processing the response stream. The enclosed error was
     ///returned from libstd.
     #[error("IO error: {}", _0)]
@@ -170,4 +531,300 @@ pub enum Error {
     ///panic if it receives malformed headers or the like.
     #[error("Error converting headers: {}", _0)]
     HeaderConvertError(#[from] std::num::ParseIntError),
+    ///A safety check requested before acting on the user's behalf did not pass, so the action was
+    ///not performed. The enclosed value describes which check failed.
+    #[error("Precondition failed: {}", _0)]
+    PreconditionFailed(#[from] PreconditionError),
+    ///`DraftTweet::send_once` did not send a draft, because a draft with the same content was
+    ///sent recently. The enclosed value is the draft's content hash.
+    #[error("Draft with content hash {:016x} was already sent", _0)]
+    DuplicateDraft(u64),
+    ///Media given to an upload function failed validation, so it was not uploaded. The enclosed
+    ///value describes the problem.
+    ///
+    ///This is only returned when the `media_validation` feature is enabled.
+    #[cfg(feature = "media_validation")]
+    #[error("Invalid media: {}", _0)]
+    InvalidMedia(#[from] MediaValidationError),
+    ///Frames given to `media::upload_animation` couldn't be encoded into a GIF that Twitter
+    ///accepts, so nothing was uploaded. The enclosed value describes the problem.
+    ///
+    ///This is only returned when the `image` feature is enabled.
+    #[cfg(feature = "image")]
+    #[error("Invalid animation: {}", _0)]
+    InvalidAnimation(#[from] AnimationError),
+    ///An image given to `media::upload_media_auto` couldn't be decoded or shrunk to fit
+    ///Twitter's limits, so nothing was uploaded. The enclosed value is the error from the `image`
+    ///crate.
+    ///
+    ///This is only returned when the `image` feature is enabled.
+    #[cfg(feature = "image")]
+    #[error("Error processing image: {}", _0)]
+    ImageError(#[from] image::ImageError),
+    ///A language code given to `StreamBuilder::language` is not one that Twitter supports, so the
+    ///stream was not started. The enclosed value is the unsupported code.
+    #[error("Unsupported language code: {}", _0)]
+    UnsupportedLanguage(String),
+    ///The filters given to a `StreamBuilder` can't start a stream, so it wasn't connected. The
+    ///enclosed value describes the problem.
+    ///
+    ///This is only returned when the `stream` feature is enabled.
+    #[cfg(feature = "stream")]
+    #[error("Invalid stream filters: {}", _0)]
+    InvalidStream(#[from] BuilderError),
+    ///Twitter has retired the version 1.1 streaming endpoint the stream connected to, or no longer
+    ///allows the app to use it. The enclosed value names the egg-mode function for the version 2
+    ///endpoint that replaced it.
+    ///
+    ///This is returned by `TwitterStream` instead of `BadStatus` when Twitter rejects the
+    ///connection with `410 Gone`, or with `403 Forbidden` and error code 453.
+    #[error("Streaming endpoint has been retired; use {alternative} instead")]
+    EndpointRetired {
+        ///The endpoint that replaced the retired one.
+        alternative: &'static str,
+    },
+    ///The response body was larger than the limit set with `ClientConfig::max_response_size`,
+    ///so it wasn't read. The enclosed value is the limit, in bytes.
+    #[error("Response body is larger than the {}-byte limit", _0)]
+    ResponseTooLarge(usize),
+    ///`DraftTweet::place_from_coordinates` did not find any place at the given coordinate. The
+    ///enclosed values are the latitude and longitude that were searched.
+    #[error("No place found at {}, {}", _0, _1)]
+    NoPlaceFound(f64, f64),
+}
+
+impl Error {
+    /// Returns the rate-limit information that accompanied a failed call, if any.
+    ///
+    /// Twitter includes the rate-limit headers on most failed calls, like when a tweet or user
+    /// couldn't be found, and failed calls still count against the rate limit. This returns them
+    /// for `TwitterError` and `BadStatus` errors, so retries can be scheduled the same way as for
+    /// a successful call. Errors that didn't come from a response, or whose response didn't
+    /// include the headers, return `None`.
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        match self {
+            Error::TwitterError(headers, _) | Error::NoData(headers, _) => {
+                error_rate_limit(headers)
+            }
+            Error::BadStatus(_, rate_limit) => *rate_limit,
+            _ => None,
+        }
+    }
+
+    /// Returns the kinds of the errors Twitter returned, for `TwitterError` errors.
+    ///
+    /// Other errors return an empty list.
+    pub fn twitter_kinds(&self) -> Vec<TwitterErrorKind> {
+        match self {
+            Error::TwitterError(_, errors) => errors.errors.iter().map(|e| e.kind()).collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Returns a suggestion for how to fix this error, if it's an error from Twitter that egg-mode
+    /// recognizes.
+    ///
+    /// If Twitter returned several errors, this is the suggestion for the first one that has one.
+    /// The suggestion is also included when the error is displayed.
+    pub fn suggestion(&self) -> Option<&'static str> {
+        self.twitter_kinds()
+            .into_iter()
+            .filter_map(TwitterErrorKind::suggestion)
+            .next()
+    }
+}
+
+/// The error egg-mode's DNS resolver wraps lookup failures in, so `From<hyper::Error>` can tell
+/// them apart from other connection errors.
+#[derive(Debug, thiserror::Error)]
+#[error(transparent)]
+pub(crate) struct DnsError(pub(crate) std::io::Error);
+
+/// The error type of the TLS implementation egg-mode was built with.
+#[cfg(feature = "native_tls")]
+type TlsCause = native_tls::Error;
+#[cfg(any(feature = "rustls", feature = "rustls_webpki"))]
+type TlsCause = rustls::TLSError;
+
+/// The kinds of network error that `From<hyper::Error>` sorts hyper's errors into.
+enum NetErrorKind {
+    Dns,
+    ConnectionReset,
+    Timeout,
+}
+
+impl From<hyper::Error> for Error {
+    fn from(err: hyper::Error) -> Error {
+        use std::error::Error as StdError;
+
+        if err.source().and_then(find_cause::<TlsCause>).is_some() {
+            let tls = err.into_cause().and_then(take_cause::<TlsCause>);
+            return Error::TlsError(tls.expect("TLS error was just found in the same cause"));
+        }
+
+        match net_error_kind(&err) {
+            Some(NetErrorKind::Dns) => Error::Dns(err),
+            Some(NetErrorKind::ConnectionReset) => Error::ConnectionReset(err),
+            Some(NetErrorKind::Timeout) => Error::Timeout(err),
+            None => Error::NetError(err),
+        }
+    }
+}
+
+/// Looks for an error of type `T` in the given error, or in the `io::Error`s it wraps.
+///
+/// TLS implementations hand their errors to hyper either directly or wrapped in `io::Error`s,
+/// whose `source` skips over the error they wrap, so this unwraps them with `get_ref` instead.
+fn find_cause<'a, T: std::error::Error + 'static>(
+    err: &'a (dyn std::error::Error + 'static),
+) -> Option<&'a T> {
+    if let Some(err) = err.downcast_ref::<T>() {
+        return Some(err);
+    }
+
+    find_cause(err.downcast_ref::<std::io::Error>()?.get_ref()?)
+}
+
+/// Takes ownership of the error that `find_cause` would find in the given error.
+fn take_cause<T: std::error::Error + 'static>(
+    err: Box<dyn std::error::Error + Send + Sync>,
+) -> Option<T> {
+    match err.downcast::<T>() {
+        Ok(err) => Some(*err),
+        Err(err) => take_cause(err.downcast::<std::io::Error>().ok()?.into_inner()?),
+    }
+}
+
+/// Looks through the causes of the given error to find what kind of network problem it was, if
+/// it was one egg-mode recognizes.
+fn net_error_kind(err: &hyper::Error) -> Option<NetErrorKind> {
+    use std::error::Error as StdError;
+    use std::io::ErrorKind;
+
+    if err.is_timeout() {
+        return Some(NetErrorKind::Timeout);
+    }
+
+    let mut source = err.source();
+    while let Some(cause) = source {
+        if cause.is::<DnsError>() {
+            return Some(NetErrorKind::Dns);
+        }
+
+        if let Some(io) = cause.downcast_ref::<std::io::Error>() {
+            match io.kind() {
+                ErrorKind::TimedOut => return Some(NetErrorKind::Timeout),
+                ErrorKind::ConnectionReset
+                | ErrorKind::ConnectionAborted
+                | ErrorKind::BrokenPipe => return Some(NetErrorKind::ConnectionReset),
+                _ => (),
+            }
+        }
+
+        source = cause.source();
+    }
+
+    if err.is_incomplete_message() {
+        Some(NetErrorKind::ConnectionReset)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DnsError, Error, TwitterErrorKind, TwitterErrors};
+    use futures::{executor::block_on, stream};
+    use hyper::Body;
+    use std::io;
+
+    fn body_error<E>(err: E) -> Error
+    where
+        E: Into<Box<dyn std::error::Error + Send + Sync>> + Send + 'static,
+    {
+        let chunks: Vec<Result<Vec<u8>, E>> = vec![Err(err)];
+        let body = Body::wrap_stream(stream::iter(chunks));
+        block_on(hyper::body::to_bytes(body)).unwrap_err().into()
+    }
+
+    fn io_error(kind: io::ErrorKind, msg: &'static str) -> Error {
+        body_error(io::Error::new(kind, msg))
+    }
+
+    #[test]
+    fn classify_net_errors() {
+        assert!(matches!(
+            io_error(io::ErrorKind::ConnectionReset, "reset by peer"),
+            Error::ConnectionReset(_)
+        ));
+        assert!(matches!(
+            io_error(io::ErrorKind::TimedOut, "timed out"),
+            Error::Timeout(_)
+        ));
+        assert!(matches!(
+            body_error(DnsError(io::Error::other("lookup failed"))),
+            Error::Dns(_)
+        ));
+        assert!(matches!(
+            io_error(io::ErrorKind::Other, "dns error"),
+            Error::NetError(_)
+        ));
+        assert!(matches!(
+            io_error(io::ErrorKind::InvalidData, "invalid certificate"),
+            Error::NetError(_)
+        ));
+    }
+
+    #[cfg(feature = "native_tls")]
+    #[test]
+    fn classify_tls_errors() {
+        let err = native_tls::Certificate::from_der(b"not a certificate")
+            .err()
+            .unwrap();
+        assert!(matches!(body_error(err), Error::TlsError(_)));
+    }
+
+    #[cfg(any(feature = "rustls", feature = "rustls_webpki"))]
+    #[test]
+    fn classify_tls_errors() {
+        // this is how hyper-rustls hands over a failed handshake
+        let err = rustls::TLSError::General("bad certificate".into());
+        let err = io::Error::new(io::ErrorKind::InvalidData, err);
+        let err = io::Error::other(err);
+        assert!(matches!(body_error(err), Error::TlsError(_)));
+    }
+
+    #[test]
+    fn twitter_error_kinds() {
+        use std::error::Error as StdError;
+
+        let errors: TwitterErrors = serde_json::from_str(
+            r#"{"errors":[{"code":187,"message":"Status is a duplicate."},{"code":1,"message":"?"}]}"#,
+        )
+        .unwrap();
+        assert!(errors.has_kind(TwitterErrorKind::DuplicateStatus));
+        assert!(!errors.has_kind(TwitterErrorKind::InvalidToken));
+        assert_eq!(errors.errors[1].kind(), TwitterErrorKind::Other(1));
+        assert_eq!(TwitterErrorKind::from_code(326).code(), 326);
+        assert!(errors
+            .to_string()
+            .starts_with("#187: Status is a duplicate. (the user already posted"));
+
+        let err = Error::TwitterError(Default::default(), errors);
+        assert_eq!(
+            err.twitter_kinds(),
+            vec![
+                TwitterErrorKind::DuplicateStatus,
+                TwitterErrorKind::Other(1)
+            ]
+        );
+        assert_eq!(
+            err.suggestion(),
+            TwitterErrorKind::DuplicateStatus.suggestion()
+        );
+
+        let err = io_error(io::ErrorKind::Other, "something else");
+        assert!(err.source().is_some());
+        assert_eq!(err.suggestion(), None);
+    }
 }
@@ -0,0 +1,541 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Typed events describing activity on a user's account.
+//!
+//! Twitter used to deliver events like likes, follows, and blocks over "user streams". Those
+//! streams are gone, but the same events are now delivered by the [Account Activity API][aaa],
+//! which sends them to a webhook as JSON payloads. The types in this module can be deserialized
+//! from those payloads, and are meant to be shared by any code that handles account activity,
+//! whether it's received by a webhook or emulated by polling other endpoints.
+//!
+//! [aaa]: https://developer.twitter.com/en/docs/twitter-api/enterprise/account-activity-api/overview
+//!
+//! The simplest way to handle a webhook payload is to deserialize it into an [`ActivityPayload`]
+//! and walk through its events:
+//!
+//! [`ActivityPayload`]: struct.ActivityPayload.html
+//!
+//! ```rust
+//! use egg_mode::events::{ActivityPayload, Event};
+//!
+//! let body = r#"{
+//!     "for_user_id": "3001969357",
+//!     "direct_message_indicate_typing_events": [{
+//!         "created_timestamp": "1518127183443",
+//!         "sender_id": "3284025577",
+//!         "target": { "recipient_id": "3001969357" }
+//!     }]
+//! }"#;
+//!
+//! let payload: ActivityPayload = serde_json::from_str(body).unwrap();
+//! for event in payload.into_events() {
+//!     if let Event::Typing(typing) = event {
+//!         println!("user {} is typing...", typing.sender_id);
+//!     }
+//! }
+//! ```
+//!
+//! ## Replaying missed events
+//!
+//! If your webhook was down for a while, [`replay`] asks Twitter to deliver the events from a
+//! given window of time again, to every subscription of a webhook. Twitter runs this as a job in
+//! the background, sending the events to the webhook as regular payloads, and reports when the job
+//! is done with one more payload containing a [`ReplayJobStatus`]:
+//!
+//! [`replay`]: fn.replay.html
+//! [`ReplayJobStatus`]: struct.ReplayJobStatus.html
+//!
+//! ```rust
+//! use egg_mode::events::{ReplayJobState, ReplayStatusPayload};
+//!
+//! let body = r#"{
+//!     "replay_job_status": {
+//!         "webhook_id": "1234567890",
+//!         "job_id": "1095098195724558337",
+//!         "job_state": "Complete",
+//!         "job_state_description": "All data has been delivered and processed"
+//!     }
+//! }"#;
+//!
+//! if let Ok(payload) = serde_json::from_str::<ReplayStatusPayload>(body) {
+//!     let status = payload.replay_job_status;
+//!     assert_eq!(status.job_state, ReplayJobState::Complete);
+//! }
+//! ```
+//!
+//! Twitter doesn't offer a way to ask for the status of a replay job; it's only reported to the
+//! webhook. The replay API is part of the enterprise Account Activity API, and uses an app-only
+//! (Bearer) token.
+
+use chrono::{self, TimeZone};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+
+use crate::common::*;
+use crate::tweet::Tweet;
+use crate::user::TwitterUser;
+use crate::{auth, error, links};
+
+/// A single batch of events delivered by the Account Activity API.
+///
+/// Each kind of event is delivered in its own list, which will be empty if the payload didn't
+/// contain any events of that kind. Payloads can also contain kinds of events that aren't covered
+/// here, like new tweets or Direct Messages; those are ignored when deserializing.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ActivityPayload {
+    /// The ID of the user whose account these events happened on.
+    #[serde(with = "serde_via_string")]
+    pub for_user_id: u64,
+    /// Tweets that were liked, either by the user or by someone else liking the user's tweets.
+    #[serde(default)]
+    pub favorite_events: Vec<FavoriteEvent>,
+    /// Follows and unfollows, either by the user or of the user.
+    #[serde(default)]
+    pub follow_events: Vec<FollowEvent>,
+    /// Blocks and unblocks performed by the user.
+    #[serde(default)]
+    pub block_events: Vec<BlockEvent>,
+    /// Mutes and unmutes performed by the user.
+    #[serde(default)]
+    pub mute_events: Vec<MuteEvent>,
+    /// Notices that someone is typing a Direct Message to the user.
+    #[serde(default)]
+    pub direct_message_indicate_typing_events: Vec<DirectMessageIndicateTyping>,
+}
+
+impl ActivityPayload {
+    /// Collects all the events in this payload into a single list.
+    ///
+    /// Events are grouped by kind, in the order of the fields on `ActivityPayload`.
+    pub fn into_events(self) -> Vec<Event> {
+        let mut events = Vec::with_capacity(
+            self.favorite_events.len()
+                + self.follow_events.len()
+                + self.block_events.len()
+                + self.mute_events.len()
+                + self.direct_message_indicate_typing_events.len(),
+        );
+
+        events.extend(self.favorite_events.into_iter().map(Event::Favorite));
+        events.extend(self.follow_events.into_iter().map(Event::Follow));
+        events.extend(self.block_events.into_iter().map(Event::Block));
+        events.extend(self.mute_events.into_iter().map(Event::Mute));
+        events.extend(
+            self.direct_message_indicate_typing_events
+                .into_iter()
+                .map(Event::Typing),
+        );
+
+        events
+    }
+}
+
+/// Any one of the events in this module.
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum Event {
+    /// A tweet was liked.
+    Favorite(FavoriteEvent),
+    /// A user was followed or unfollowed.
+    Follow(FollowEvent),
+    /// A user was blocked or unblocked.
+    Block(BlockEvent),
+    /// A user was muted or unmuted.
+    Mute(MuteEvent),
+    /// Someone is typing a Direct Message.
+    Typing(DirectMessageIndicateTyping),
+}
+
+/// A tweet was liked.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FavoriteEvent {
+    /// An opaque ID for this event.
+    pub id: String,
+    /// When the tweet was liked.
+    #[serde(with = "serde_datetime")]
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    /// The tweet that was liked.
+    pub favorited_status: Tweet,
+    /// The user who liked the tweet.
+    pub user: TwitterUser,
+}
+
+/// Whether a `FollowEvent` was a follow or an unfollow.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FollowAction {
+    /// The `source` user followed the `target` user.
+    Follow,
+    /// The `source` user unfollowed the `target` user.
+    Unfollow,
+}
+
+/// A user was followed or unfollowed.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FollowEvent {
+    /// Whether this was a follow or an unfollow.
+    #[serde(rename = "type")]
+    pub action: FollowAction,
+    /// When the follow or unfollow happened.
+    #[serde(rename = "created_timestamp", deserialize_with = "deserialize_millis")]
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    /// The user who followed or unfollowed.
+    pub source: ActivityUser,
+    /// The user who was followed or unfollowed.
+    pub target: ActivityUser,
+}
+
+/// Whether a `BlockEvent` was a block or an unblock.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlockAction {
+    /// The `source` user blocked the `target` user.
+    Block,
+    /// The `source` user unblocked the `target` user.
+    Unblock,
+}
+
+/// A user was blocked or unblocked.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BlockEvent {
+    /// Whether this was a block or an unblock.
+    #[serde(rename = "type")]
+    pub action: BlockAction,
+    /// When the block or unblock happened.
+    #[serde(rename = "created_timestamp", deserialize_with = "deserialize_millis")]
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    /// The user who blocked or unblocked.
+    pub source: ActivityUser,
+    /// The user who was blocked or unblocked.
+    pub target: ActivityUser,
+}
+
+/// Whether a `MuteEvent` was a mute or an unmute.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MuteAction {
+    /// The `source` user muted the `target` user.
+    Mute,
+    /// The `source` user unmuted the `target` user.
+    Unmute,
+}
+
+/// A user was muted or unmuted.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MuteEvent {
+    /// Whether this was a mute or an unmute.
+    #[serde(rename = "type")]
+    pub action: MuteAction,
+    /// When the mute or unmute happened.
+    #[serde(rename = "created_timestamp", deserialize_with = "deserialize_millis")]
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    /// The user who muted or unmuted.
+    pub source: ActivityUser,
+    /// The user who was muted or unmuted.
+    pub target: ActivityUser,
+}
+
+/// The user that took part in a follow, block, or mute event.
+///
+/// The Account Activity API describes these users with a smaller object than the `TwitterUser`
+/// returned by the rest of the API: their ID is given as a string, their creation time as a
+/// millisecond timestamp, and fields like `entities` and the profile colors are left out.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ActivityUser {
+    /// The user's numeric ID.
+    #[serde(with = "serde_via_string")]
+    pub id: u64,
+    /// The user's display name.
+    pub name: String,
+    /// The user's screen name, without the leading @.
+    pub screen_name: String,
+    /// The location the user entered in their profile, if any.
+    #[serde(default)]
+    pub location: Option<String>,
+    /// The website the user linked in their profile, if any.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// The user's profile description, if any.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Whether the user's tweets are protected.
+    #[serde(default)]
+    pub protected: bool,
+    /// Whether the user is verified.
+    #[serde(default)]
+    pub verified: bool,
+    /// The number of users following this user.
+    #[serde(default)]
+    pub followers_count: i32,
+    /// The number of users this user follows.
+    #[serde(default)]
+    pub friends_count: i32,
+    /// The number of public lists this user is a member of.
+    #[serde(default)]
+    pub listed_count: i32,
+    /// The number of tweets this user has liked.
+    #[serde(default)]
+    pub favourites_count: i32,
+    /// The number of tweets, including retweets, this user has posted.
+    #[serde(default)]
+    pub statuses_count: i32,
+    /// When the user's account was created.
+    #[serde(rename = "created_timestamp", deserialize_with = "deserialize_millis")]
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    /// The URL of the user's profile image, over HTTPS.
+    #[serde(default)]
+    pub profile_image_url_https: Option<String>,
+    /// Whether the user hasn't uploaded a profile image.
+    #[serde(default)]
+    pub default_profile_image: bool,
+}
+
+/// Someone is typing a Direct Message.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(from = "RawTypingEvent")]
+pub struct DirectMessageIndicateTyping {
+    /// When the typing notice was sent.
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    /// The ID of the user who is typing.
+    pub sender_id: u64,
+    /// The ID of the user who will receive the message.
+    pub recipient_id: u64,
+}
+
+#[derive(Deserialize)]
+struct RawTypingEvent {
+    #[serde(deserialize_with = "deserialize_millis")]
+    created_timestamp: chrono::DateTime<chrono::Utc>,
+    #[serde(with = "serde_via_string")]
+    sender_id: u64,
+    target: RawTypingTarget,
+}
+
+#[derive(Deserialize)]
+struct RawTypingTarget {
+    #[serde(with = "serde_via_string")]
+    recipient_id: u64,
+}
+
+impl From<RawTypingEvent> for DirectMessageIndicateTyping {
+    fn from(raw: RawTypingEvent) -> DirectMessageIndicateTyping {
+        DirectMessageIndicateTyping {
+            created_at: raw.created_timestamp,
+            sender_id: raw.sender_id,
+            recipient_id: raw.target.recipient_id,
+        }
+    }
+}
+
+/// A replay job started by `replay`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReplayJob {
+    /// The ID of the job, which is included in the `ReplayJobStatus` sent when it's done.
+    #[serde(with = "serde_via_string")]
+    pub job_id: u64,
+    /// When the job was created.
+    #[serde(with = "serde_datetime")]
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// The payload sent to a webhook when a replay job is done.
+///
+/// This is sent on its own, instead of as part of an `ActivityPayload`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReplayStatusPayload {
+    /// The status of the job.
+    pub replay_job_status: ReplayJobStatus,
+}
+
+/// The result of a replay job started by `replay`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReplayJobStatus {
+    /// The ID of the webhook the events were delivered to.
+    #[serde(with = "serde_via_string")]
+    pub webhook_id: u64,
+    /// The ID of the job, as returned by `replay`.
+    #[serde(with = "serde_via_string")]
+    pub job_id: u64,
+    /// Whether every event was delivered.
+    pub job_state: ReplayJobState,
+    /// Twitter's description of the result.
+    pub job_state_description: String,
+}
+
+/// Whether a replay job delivered every event.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+pub enum ReplayJobState {
+    /// Every event in the window was delivered to the webhook.
+    Complete,
+    /// Some events couldn't be delivered, for example because the webhook failed its checks during
+    /// the replay. The job can be started again for the same window.
+    Incomplete,
+    /// Twitter reported a state egg-mode doesn't know about.
+    #[serde(other)]
+    Unknown,
+}
+
+/// Asks Twitter to deliver the events from the given window of time to the given webhook again.
+///
+/// The events are replayed for every subscription of the webhook, and are sent to it as regular
+/// payloads in the background. Once every event has been sent, Twitter sends a
+/// `ReplayStatusPayload` to the webhook with the ID of the returned `ReplayJob`.
+///
+/// The times are rounded down to the minute. Twitter only keeps events for the last five days, and
+/// only allows one replay job per webhook to run at a time; other requests are rejected with a
+/// `TwitterError`.
+///
+/// ```rust,no_run
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let token: egg_mode::Token = unimplemented!();
+/// use chrono::{Duration, Utc};
+///
+/// let to = Utc::now();
+/// let from = to - Duration::hours(2);
+/// let job = egg_mode::events::replay(1234567890, from, to, &token).await.unwrap();
+/// println!("started replay job {}", job.job_id);
+/// # }
+/// ```
+pub async fn replay(
+    webhook_id: u64,
+    from: chrono::DateTime<chrono::Utc>,
+    to: chrono::DateTime<chrono::Utc>,
+    token: &auth::Token,
+) -> error::Result<Response<ReplayJob>> {
+    let url = format!(
+        "{}/{}/subscriptions/all.json",
+        links::account_activity::REPLAY_STEM,
+        webhook_id
+    );
+    let params = ParamList::new()
+        .add_param("from_date", replay_date(from))
+        .add_param("to_date", replay_date(to));
+
+    let req = post(&url, token, Some(&params));
+    request_with_json_response(req).await
+}
+
+/// Formats a time in the `yyyymmddhhmm` form used by the replay API.
+fn replay_date(time: chrono::DateTime<chrono::Utc>) -> String {
+    time.format("%Y%m%d%H%M").to_string()
+}
+
+/// Deserializes a timestamp given as a string of milliseconds since the Unix epoch.
+fn deserialize_millis<'de, D>(ser: D) -> Result<chrono::DateTime<chrono::Utc>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let millis: i64 = serde_via_string::deserialize(ser)?;
+    chrono::Utc
+        .timestamp_millis_opt(millis)
+        .single()
+        .ok_or_else(|| D::Error::custom("timestamp out of range"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        replay_date, ActivityPayload, BlockAction, Event, FollowAction, MuteAction, ReplayJob,
+        ReplayJobState, ReplayStatusPayload,
+    };
+    use crate::common::tests::load_file;
+
+    #[test]
+    fn parse_activity_payload() {
+        let tweet: serde_json::Value =
+            serde_json::from_str(&load_file("sample_payloads/sample-extended-onepic.json"))
+                .unwrap();
+        let users: Vec<serde_json::Value> =
+            serde_json::from_str(&load_file("sample_payloads/user_array.json")).unwrap();
+
+        let payload = serde_json::json!({
+            "for_user_id": "783214",
+            "favorite_events": [{
+                "id": "a7ba59eab0bfcba386f7acedac279542",
+                "created_at": "Mon Mar 26 16:33:26 +0000 2018",
+                "timestamp_ms": 1522082006140u64,
+                "favorited_status": tweet,
+                "user": users[0],
+            }],
+            "direct_message_indicate_typing_events": [{
+                "created_timestamp": "1518127183443",
+                "sender_id": "3284025577",
+                "target": { "recipient_id": "3001969357" },
+            }],
+        });
+
+        let payload: ActivityPayload = serde_json::from_value(payload).unwrap();
+        assert_eq!(payload.for_user_id, 783214);
+        assert!(payload.follow_events.is_empty());
+        assert!(payload.mute_events.is_empty());
+
+        let events = payload.into_events();
+        assert_eq!(events.len(), 2);
+        match &events[0] {
+            Event::Favorite(fav) => assert_eq!(fav.favorited_status.id, 782349500404862976),
+            other => panic!("expected a favorite event, got {:?}", other),
+        }
+        match &events[1] {
+            Event::Typing(typing) => {
+                assert_eq!(typing.sender_id, 3284025577);
+                assert_eq!(typing.recipient_id, 3001969357);
+            }
+            other => panic!("expected a typing event, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_user_events() {
+        let payload: ActivityPayload =
+            serde_json::from_str(&load_file("sample_payloads/aaa-user-events.json")).unwrap();
+        assert_eq!(payload.for_user_id, 2244994945);
+
+        let follow = &payload.follow_events[0];
+        assert_eq!(follow.action, FollowAction::Follow);
+        assert_eq!(follow.created_at.timestamp_millis(), 1517588749178);
+        assert_eq!(follow.source.id, 3001969357);
+        assert_eq!(follow.source.screen_name, "jimmyprobetw");
+        assert_eq!(follow.target.id, 2244994945);
+        assert_eq!(follow.target.followers_count, 431784);
+        assert!(follow.target.verified);
+        assert_eq!(follow.target.created_at.timestamp_millis(), 1376424880000);
+
+        let block = &payload.block_events[0];
+        assert_eq!(block.action, BlockAction::Block);
+        assert_eq!(block.source.id, 2244994945);
+        assert_eq!(block.target.location, None);
+
+        let mute = &payload.mute_events[0];
+        assert_eq!(mute.action, MuteAction::Unmute);
+        assert_eq!(mute.target.id, 3001969357);
+
+        assert_eq!(payload.into_events().len(), 3);
+    }
+
+    #[test]
+    fn parse_replay() {
+        let job: ReplayJob = serde_json::from_str(
+            r#"{"job_id": "1095098195724558337", "created_at": "2019-02-11T22:24:05.000Z"}"#,
+        )
+        .unwrap();
+        assert_eq!(job.job_id, 1095098195724558337);
+        assert_eq!(replay_date(job.created_at), "201902112224");
+
+        let payload: ReplayStatusPayload = serde_json::from_str(
+            r#"{"replay_job_status": {
+                "webhook_id": "1234567890",
+                "job_id": "1095098195724558337",
+                "job_state": "Incomplete",
+                "job_state_description": "Job failed to deliver all events, please try again"
+            }}"#,
+        )
+        .unwrap();
+        let status = payload.replay_job_status;
+        assert_eq!(status.webhook_id, 1234567890);
+        assert_eq!(status.job_id, job.job_id);
+        assert_eq!(status.job_state, ReplayJobState::Incomplete);
+    }
+}
@@ -65,7 +65,8 @@
 //! # Crate features
 //!
 //! While all of egg-mode's features are available by default, it allows you to configure how it
-//! connects to Twitter and how it uses HTTPS. The crate's Cargo features are the following:
+//! connects to Twitter and how it uses HTTPS, and to leave out parts of the API your app doesn't
+//! use. The crate's Cargo features are the following:
 //!
 //! * `native_tls`: On by default. With this feature on, egg-mode uses `native-tls` to access your
 //!   operating system's native TLS functionality to access Twitter.
@@ -77,9 +78,38 @@
 //!   certificates to verify the connection, instead of using your operating system's root
 //!   certificates.
 //!
-//! Keep in mind that these features are mutually exclusive - if you enable more than one, a
+//! * `stream`: On by default. Enables the `stream` module, for the streaming API.
+//! * `media`: On by default. Enables the upload functions in the `media` module. Without it, the
+//!   module only contains the types used to attach uploaded media to tweets and messages.
+//! * `dm`: On by default. Enables the `direct` module, for Direct Messages.
+//! * `lists`: On by default. Enables the functions in the `list` module. Without it, the module
+//!   only contains the types for lists, like `ListID`.
+//! * `places`: On by default. Enables the functions in the `place` module and
+//!   `DraftTweet::place_from_coordinates`. Without it, the module only contains the types for
+//!   places, like the `Place` attached to tweets.
+//!
+//! * `raw-fields`: Off by default. With this feature on, `Tweet`, `TwitterUser`, `DirectMessage`,
+//!   `List`, and `TrendLocation` gain an `extra` field that holds any fields Twitter returned that
+//!   egg-mode doesn't otherwise load.
+//! * `strict-fields`: Off by default. With this feature on, `raw::set_unknown_field_handler` can
+//!   report the fields in Twitter's responses that egg-mode doesn't load, to notice new fields as
+//!   they appear.
+//! * `media_validation`: Off by default, and turns on `media`. With this feature on, the functions
+//!   in the `media` module check that uploaded data matches its declared type and fits Twitter's
+//!   size limits before sending it.
+//! * `zeroize_secrets`: Off by default. With this feature on, egg-mode depends on the `zeroize`
+//!   crate: `KeyPair` and `Token` implement `zeroize::Zeroize`, and the copies of secrets made
+//!   while signing requests are overwritten with zeroes once they're no longer needed.
+//! * `auth-debug`: Off by default. With this feature on, the `auth::debug` module lets you inspect
+//!   the OAuth signature base string of each request, with secrets masked.
+//! * `image`: Off by default, and turns on `media`. With this feature on, egg-mode depends on the
+//!   `image` crate, and `media::upload_animation` encodes an `image::Frames` sequence into an
+//!   animated GIF and uploads it. `media::upload_media_auto` also shrinks photos that are too
+//!   large for Twitter before uploading them.
+//!
+//! Keep in mind that the TLS features are mutually exclusive - if you enable more than one, a
 //! compile error will result. If you need to use `rustls` or `rustls_webpki`, remember to set
-//! `default-features = false` in your Cargo.toml.
+//! `default-features = false` in your Cargo.toml, and turn back on the API features you use.
 //!
 //! # Types and Functions
 //!
@@ -140,6 +170,8 @@
 //!
 //! * `place`: Here are actions that look up physical locations that can be attached to tweets, as
 //!   well at the `Place` struct that appears on tweets with locations attached.
+//! * `notifications`: Emulates the notification feed that user streams used to provide, by
+//!   polling mentions, retweets, likes, and followers and reporting what changed.
 //! * `service`: These are some miscellaneous methods that show information about the Twitter
 //!   service as a whole, like loading the maximum length of t.co URLs or loading the current Terms
 //!   of Service or Privacy Policy.
@@ -153,9 +185,20 @@
 //!   through certain collections of results from Twitter.
 //! * `entities`: Whenever some text can be returned that may contain links, hashtags, media, or
 //!   user mentions, its metadata is parsed into something that lives in this module.
+//! * `events`: Typed events for activity on a user's account, like likes, follows, and blocks, as
+//!   delivered by the Account Activity API, and a way to have Twitter replay missed events.
+//! * `snowflake`: Twitter's numeric IDs contain the time they were created. This module has
+//!   functions to read that time back out of an ID.
+//! * `stats`: Records users' follower, friend, and tweet counts over time, to report how they
+//!   changed.
+//! * `dispatch`: Routes every request egg-mode makes through a `Dispatcher`, which keeps track of
+//!   rate windows across the whole process and holds back requests until their window resets. It
+//!   also has a `TokenPool`, which spreads requests over several Bearer tokens.
 //! * `error`: Any interaction with Twitter may result in an error condition, be it from finding a
 //!   tweet or user that doesn't exist or the network connection being unavailable. All the error
 //!   types are aggregated into an enum in this module.
+//! * `v2`: The few endpoints egg-mode supports from version 2 of the Twitter API return their data
+//!   in a different shape than the rest of the API. The types for that data live in this module.
 
 #![warn(missing_docs)]
 #![warn(unused_extern_crates)]
@@ -166,20 +209,28 @@ mod common;
 pub mod account;
 pub mod auth;
 pub mod cursor;
+#[cfg(feature = "dm")]
 pub mod direct;
+pub mod dispatch;
 pub mod entities;
 pub mod error;
+pub mod events;
 mod links;
 pub mod list;
 pub mod media;
+pub mod notifications;
 pub mod place;
 pub mod raw;
 pub mod search;
 pub mod service;
+pub mod snowflake;
+pub mod stats;
+#[cfg(feature = "stream")]
 pub mod stream;
 pub mod trend;
 pub mod tweet;
 pub mod user;
+pub mod v2;
 
 pub use crate::auth::{KeyPair, Token};
-pub use crate::common::{RateLimit, Response, ResponseIter};
+pub use crate::common::{ClientConfig, PayloadOptions, RateLimit, Response, ResponseIter};
@@ -59,13 +59,18 @@ pub mod statuses {
     pub const UNLIKE: &str = "https://api.twitter.com/1.1/favorites/destroy.json";
     pub const UPDATE: &str = "https://api.twitter.com/1.1/statuses/update.json";
     pub const DELETE_STEM: &str = "https://api.twitter.com/1.1/statuses/destroy";
+    pub const USERS_V2_STEM: &str = "https://api.twitter.com/2/users";
+    pub const COUNTS_RECENT_V2: &str = "https://api.twitter.com/2/tweets/counts/recent";
+    pub const COUNTS_ALL_V2: &str = "https://api.twitter.com/2/tweets/counts/all";
 }
 
+#[cfg(feature = "media")]
 pub mod media {
     pub const UPLOAD: &str = "https://upload.twitter.com/1.1/media/upload.json";
     pub const METADATA: &str = "https://upload.twitter.com/1.1/media/metadata/create.json";
 }
 
+#[cfg(feature = "lists")]
 pub mod lists {
     pub const STATUSES: &str = "https://api.twitter.com/1.1/lists/statuses.json";
     pub const MEMBERS: &str = "https://api.twitter.com/1.1/lists/members.json";
@@ -94,14 +99,21 @@ pub mod account {
     pub const UPDATE_PROFILE_BNNER: &str =
         "https://api.twitter.com/1.1/account/update_profile_banner.json";
     pub const UPDATE_PROFILE: &str = "https://api.twitter.com/1.1/account/update_profile.json";
+    pub const REMOVE_PROFILE_BANNER: &str =
+        "https://api.twitter.com/1.1/account/remove_profile_banner.json";
+    pub const DEFAULT_PROFILE_IMAGE: &str =
+        "https://abs.twimg.com/sticky/default_profile_images/default_profile.png";
+    pub const SETTINGS: &str = "https://api.twitter.com/1.1/account/settings.json";
 }
 
+#[cfg(feature = "places")]
 pub mod place {
     pub const SHOW_STEM: &str = "https://api.twitter.com/1.1/geo/id";
     pub const REVERSE_GEOCODE: &str = "https://api.twitter.com/1.1/geo/reverse_geocode.json";
     pub const SEARCH: &str = "https://api.twitter.com/1.1/geo/search.json";
 }
 
+#[cfg(feature = "dm")]
 pub mod direct {
     pub const SHOW: &str = "https://api.twitter.com/1.1/direct_messages/events/show.json";
     pub const LIST: &str = "https://api.twitter.com/1.1/direct_messages/events/list.json";
@@ -116,16 +128,26 @@ pub mod service {
     pub const TERMS: &str = "https://api.twitter.com/1.1/help/tos.json";
     pub const PRIVACY: &str = "https://api.twitter.com/1.1/help/privacy.json";
     pub const CONFIG: &str = "https://api.twitter.com/1.1/help/configuration.json";
+    pub const LANGUAGES: &str = "https://api.twitter.com/1.1/help/languages.json";
     pub const RATE_LIMIT_STATUS: &str =
         "https://api.twitter.com/1.1/application/rate_limit_status.json";
 }
 
+#[cfg(feature = "stream")]
 pub mod stream {
     pub const SAMPLE: &str = "https://stream.twitter.com/1.1/statuses/sample.json";
     pub const FILTER: &str = "https://stream.twitter.com/1.1/statuses/filter.json";
+    pub const FILTER_V2: &str = "https://api.twitter.com/2/tweets/search/stream";
+    pub const SAMPLE_V2: &str = "https://api.twitter.com/2/tweets/sample/stream";
+    pub const SAMPLE10_V2: &str = "https://api.twitter.com/2/tweets/sample10/stream";
 }
 
 pub mod trend {
     pub const CLOSEST: &str = "https://api.twitter.com/1.1/trends/closest.json";
     pub const AVAILABLE: &str = "https://api.twitter.com/1.1/trends/available.json";
+    pub const PLACE: &str = "https://api.twitter.com/1.1/trends/place.json";
+}
+
+pub mod account_activity {
+    pub const REPLAY_STEM: &str = "https://api.twitter.com/1.1/account_activity/replay/webhooks";
 }
@@ -2,13 +2,21 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::collections::HashMap;
 use std::convert::TryFrom;
 
+use futures::future;
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+use hyper::StatusCode;
+
 use super::*;
 
 use crate::common::RateLimit;
 use crate::cursor::{CursorIter, ListCursor, UserCursor};
-use crate::error::{Error::TwitterError, Result};
+use crate::error::{
+    Error::{self, BadStatus, TwitterError},
+    Result,
+};
 use crate::user::{TwitterUser, UserID};
 use crate::{auth, links, tweet};
 
@@ -32,7 +40,7 @@ pub fn memberships<T: Into<UserID>>(user: T, token: &auth::Token) -> CursorIter<
 ///
 ///If the user has more than 100 lists total like this, you'll need to call `ownerships` and
 ///`subscriptions` separately to be able to properly load everything.
-pub async fn list<'id, T: Into<UserID>>(
+pub async fn list<T: Into<UserID>>(
     user: T,
     owned_first: bool,
     token: &auth::Token,
@@ -73,6 +81,43 @@ pub async fn show(list: ListID, token: &auth::Token) -> Result<Response<List>> {
     request_with_json_response(req).await
 }
 
+///Look up information for a single list, along with its first `preview_count` members.
+///
+///The list and the first page of its members are loaded at the same time, so this takes about as
+///long as a single call to `show`. Twitter returns at most 5000 members in one page, so larger
+///values of `preview_count` are capped there. The returned `ListPreview` can be kept to avoid
+///loading the list again, and its `more_members` method loads the rest of the members when they're
+///needed.
+///
+///If `preview_count` is zero, the members aren't loaded, and the preview's `next_cursor` refers to
+///the first page of members.
+///
+///The `Response` returned by this function carries the rate-limit information from the call to
+///`show`.
+pub async fn show_with_members(
+    list: ListID,
+    preview_count: u32,
+    token: &auth::Token,
+) -> Result<Response<ListPreview>> {
+    if preview_count == 0 {
+        let info = show(list, token).await?;
+        return Ok(Response::map(info, |list| ListPreview {
+            list,
+            members: vec![],
+            next_cursor: -1,
+        }));
+    }
+
+    let page = members(list.clone(), token).with_page_size(preview_count.min(5000) as i32);
+    let (info, page) = future::try_join(show(list, token), page.call()).await?;
+
+    Ok(Response::map(info, |list| ListPreview {
+        list,
+        members: page.response.users,
+        next_cursor: page.response.next_cursor,
+    }))
+}
+
 ///Look up the users that have been added to the given list.
 ///
 ///This function returns a `Stream` over the users returned by Twitter. This method defaults to
@@ -93,8 +138,100 @@ pub fn subscribers(list: ListID, token: &auth::Token) -> CursorIter<UserCursor>
     CursorIter::new(links::lists::SUBSCRIBERS, token, Some(params), Some(20))
 }
 
+///Look up the users that are members of the given list, along with how each of them is connected
+///to the authenticated user.
+///
+///Members are loaded 100 at a time, and each page is followed by a call to
+///`user::relation_lookup` for its members, so this takes two calls per 100 members. The
+///relationships are those between each member and the *authenticated user*, since that's what
+///Twitter reports, so this is meant for auditing lists the authenticated user owns.
+///
+///The `Response` for each member carries the rate-limit information from the call to `members`
+///that loaded it.
+pub fn members_with_roles(
+    list: ListID,
+    token: &auth::Token,
+) -> impl Stream<Item = Result<Response<ListMember>>> {
+    with_roles(members(list, token).with_page_size(100), token.clone())
+}
+
+///Look up the users that have subscribed to the given list, along with how each of them is
+///connected to the authenticated user.
+///
+///This loads subscribers in the same way as `members_with_roles`; see its documentation for
+///details.
+pub fn subscribers_with_roles(
+    list: ListID,
+    token: &auth::Token,
+) -> impl Stream<Item = Result<Response<ListMember>>> {
+    with_roles(subscribers(list, token).with_page_size(100), token.clone())
+}
+
+///Loads the pages of the given cursor, joining each page with its users' relations to the
+///authenticated user.
+fn with_roles(
+    cursor: CursorIter<UserCursor>,
+    token: auth::Token,
+) -> impl Stream<Item = Result<Response<ListMember>>> {
+    stream::try_unfold((cursor, token), |(mut cursor, token)| async move {
+        if cursor.next_cursor == 0 {
+            return Ok::<_, Error>(None);
+        }
+
+        let page = cursor.call().await?;
+        cursor.previous_cursor = page.previous_cursor;
+        cursor.next_cursor = page.next_cursor;
+
+        let rate_limit_status = page.rate_limit_status;
+        let users = page.response.users;
+        let relations = if users.is_empty() {
+            Vec::new()
+        } else {
+            let ids = users.iter().map(|user| user.id).collect::<Vec<_>>();
+            user::relation_lookup(ids, &token).await?.response
+        };
+
+        let members = join_roles(users, relations)
+            .into_iter()
+            .map(|member| Response::new(rate_limit_status, member))
+            .collect::<Vec<_>>();
+
+        Ok(Some((members, (cursor, token))))
+    })
+    .map_ok(|page| stream::iter(page).map(Ok))
+    .try_flatten()
+}
+
+///Pairs each of the given users with their relation to the authenticated user. Users missing from
+///`relations` are given no connections.
+pub(super) fn join_roles(
+    users: Vec<TwitterUser>,
+    relations: Vec<user::RelationLookup>,
+) -> Vec<ListMember> {
+    let relations = relations
+        .into_iter()
+        .map(|relation| (relation.id, relation.connections))
+        .collect::<HashMap<_, _>>();
+
+    users
+        .into_iter()
+        .map(|user| {
+            let connections = relations.get(&user.id).map(Vec::as_slice).unwrap_or(&[]);
+            ListMember {
+                follows_owner: connections
+                    .iter()
+                    .any(|c| matches!(c, user::Connection::FollowedBy)),
+                followed_by_owner: connections
+                    .iter()
+                    .any(|c| matches!(c, user::Connection::Following)),
+                user,
+            }
+        })
+        .collect()
+}
+
 ///Check whether the given user is subscribed to the given list.
-pub async fn is_subscribed<'id, T: Into<UserID>>(
+pub async fn is_subscribed<T: Into<UserID>>(
     user: T,
     list: ListID,
     token: &auth::Token,
@@ -125,7 +262,7 @@ pub async fn is_subscribed<'id, T: Into<UserID>>(
 }
 
 ///Check whether the given user has been added to the given list.
-pub async fn is_member<'id, T: Into<UserID>>(
+pub async fn is_member<T: Into<UserID>>(
     user: T,
     list: ListID,
     token: &auth::Token,
@@ -159,7 +296,12 @@ pub async fn is_member<'id, T: Into<UserID>>(
 ///The interface for loading statuses from a list is exactly the same as loading from a personal
 ///timeline. see the [`Timeline`] docs for details.
 ///
+///Retweets in a list's timeline are placed by when they were retweeted, so paging through it while
+///new retweets arrive can return the same tweet more than once. Use [`Timeline::dedup_by_id`] to
+///have each tweet returned only once.
+///
 ///[`Timeline`]: ../tweet/struct.Timeline.html
+///[`Timeline::dedup_by_id`]: ../tweet/struct.Timeline.html#method.dedup_by_id
 pub fn statuses(list: ListID, with_rts: bool, token: &auth::Token) -> tweet::Timeline {
     let params = ParamList::new()
         .add_list_param(list)
@@ -173,7 +315,7 @@ pub fn statuses(list: ListID, with_rts: bool, token: &auth::Token) -> tweet::Tim
 ///Note that lists cannot have more than 5000 members.
 ///
 ///Upon success, the future returned by this function yields the freshly-modified list.
-pub async fn add_member<'id, T: Into<UserID>>(
+pub async fn add_member<T: Into<UserID>>(
     list: ListID,
     user: T,
     token: &auth::Token,
@@ -200,7 +342,7 @@ pub async fn add_member<'id, T: Into<UserID>>(
 ///When using this method, take care not to add and remove many members in rapid succession; there
 ///are no guarantees that the result of a `add_member_list` or `remove_member_list` will be
 ///immediately available for a corresponding removal or addition, respectively.
-pub async fn add_member_list<'id, T, I>(
+pub async fn add_member_list<T, I>(
     members: I,
     list: ListID,
     token: &auth::Token,
@@ -235,7 +377,7 @@ where
 }
 
 ///Removes the given user from the given list.
-pub async fn remove_member<'id, T: Into<UserID>>(
+pub async fn remove_member<T: Into<UserID>>(
     list: ListID,
     user: T,
     token: &auth::Token,
@@ -348,6 +490,158 @@ pub async fn unsubscribe(list: ListID, token: &auth::Token) -> Result<Response<L
     request_with_json_response(req).await
 }
 
+///Checks whether each list the given user is subscribed to still exists and can be loaded.
+///
+///This is usually called with the authenticated user, to check the lists they follow. The user is
+///always sent to Twitter, rather than left for Twitter to take from the token, so the audit covers
+///the same account however the request is signed.
+///
+///This loads every list from `subscriptions`, then looks each one up again with `show`. Lists that
+///have been deleted or can't be loaded are reported in the returned `SubscriptionAudit` rather than
+///stopping the audit; only errors unrelated to a specific list, like hitting the rate limit or
+///losing the network connection, end the audit early. Since `show` is called once per list, keep
+///its rate limit in mind if the user is subscribed to many lists.
+///
+///Since the subscriptions are loaded at the time of the audit, lists that are renamed are only
+///detected here if the rename happens while the audit is running. To detect renames of lists you
+///loaded earlier, pass them to `audit_lists` instead.
+///
+///The `Response` returned by this function carries the rate-limit information from the last call
+///to `show` that succeeded.
+pub async fn subscriptions_audit<T: Into<UserID>>(
+    user: T,
+    token: &auth::Token,
+) -> Result<Response<SubscriptionAudit>> {
+    let subscriptions = subscriptions(user, token)
+        .with_page_size(1000)
+        .map_ok(|list| list.response)
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    audit_lists(subscriptions, token).await
+}
+
+///Checks whether each of the given lists still exists, can be loaded, and has kept its name.
+///
+///This is useful for checking lists that were saved by an earlier run of your app. Each list is
+///looked up by ID; lists whose name or slug has changed are reported as `Renamed`, and lists that
+///no longer exist or return an error are reported without stopping the audit. Only errors
+///unrelated to a specific list, like hitting the rate limit or losing the network connection, end
+///the audit early.
+///
+///The `Response` returned by this function carries the rate-limit information from the last call
+///to `show` that succeeded.
+pub async fn audit_lists<I: IntoIterator<Item = List>>(
+    lists: I,
+    token: &auth::Token,
+) -> Result<Response<SubscriptionAudit>> {
+    let mut audit = SubscriptionAudit::default();
+    let mut rate_limit_status = None;
+
+    for list in lists {
+        let current = show(ListID::from_id(list.id), token).await.map(|current| {
+            rate_limit_status = Some(current.rate_limit_status);
+            current.response
+        });
+        let status = subscription_status(&list, current)?;
+        audit.lists.push((list, status));
+    }
+
+    Ok(Response::new(
+        rate_limit_status.unwrap_or(RateLimit::unknown()),
+        audit,
+    ))
+}
+
+///Compares a list with the result of looking it up again, returning the error instead if it isn't
+///about the list itself.
+fn subscription_status(list: &List, current: Result<List>) -> Result<SubscriptionStatus> {
+    match current {
+        Ok(current) => {
+            if current.name != list.name || current.slug != list.slug {
+                Ok(SubscriptionStatus::Renamed(current))
+            } else {
+                Ok(SubscriptionStatus::Active(current))
+            }
+        }
+        Err(TwitterError(_, ref terrs)) if terrs.errors.iter().any(|e| e.code == 34) => {
+            Ok(SubscriptionStatus::Missing)
+        }
+        Err(BadStatus(StatusCode::NOT_FOUND, _)) => Ok(SubscriptionStatus::Missing),
+        Err(err @ TwitterError(..)) | Err(err @ BadStatus(..)) => {
+            Ok(SubscriptionStatus::Inaccessible(err))
+        }
+        Err(err) => Err(err),
+    }
+}
+
+///Loads the given list and all of its members into a `ListSnapshot`.
+///
+///The snapshot can be serialized to back up the list, and given to `import_members` to recreate
+///it later or on another account. Members are loaded 5000 at a time, the largest page Twitter
+///allows, so even a full list only takes two calls to `members` in addition to the call to
+///`show`.
+///
+///The `Response` returned by this function carries the rate-limit information from the last call
+///to `members`.
+pub async fn export_members(list: ListID, token: &auth::Token) -> Result<Response<ListSnapshot>> {
+    let info = show(list.clone(), token).await?;
+    let mut rate_limit_status = info.rate_limit_status;
+    let info = info.response;
+
+    let mut members = Vec::with_capacity(info.member_count as usize);
+    let mut iter = self::members(list, token).with_page_size(5000);
+    while let Some(user) = iter.try_next().await? {
+        rate_limit_status = user.rate_limit_status;
+        members.push(SnapshotMember {
+            id: user.response.id,
+            screen_name: user.response.screen_name,
+        });
+    }
+
+    Ok(Response::new(
+        rate_limit_status,
+        ListSnapshot {
+            name: info.name,
+            slug: info.slug,
+            description: info.description,
+            public: info.public,
+            members,
+        },
+    ))
+}
+
+///Creates a new list owned by the authenticated user from the given `ListSnapshot`, and adds its
+///members.
+///
+///The list is created with the snapshot's name, description, and visibility, then the members are
+///added by ID in batches of 100, the most Twitter allows in one call to `add_member_list`. Accounts
+///that have since been deleted or suspended are skipped by Twitter without an error. Note that
+///Twitter limits lists to 5000 members.
+///
+///If adding a batch of members fails, the error is returned and the new list is left in place with
+///the members added so far. On success, the future returned by this function yields the new list,
+///as returned by the last call that modified it.
+pub async fn import_members(
+    snapshot: &ListSnapshot,
+    token: &auth::Token,
+) -> Result<Response<List>> {
+    let mut list = create(
+        snapshot.name.clone(),
+        snapshot.public,
+        Some(snapshot.description.clone()),
+        token,
+    )
+    .await?;
+
+    for chunk in snapshot.members.chunks(100) {
+        let id = ListID::from_id(list.id);
+        list = add_member_list(chunk.iter().map(|member| member.id), id, token).await?;
+    }
+
+    Ok(list)
+}
+
 ///Begins updating a list's metadata.
 ///
 ///This method is exposed using a builder struct. See the [`ListUpdate`] docs for details.
@@ -361,3 +655,48 @@ pub fn update(list: ListID) -> ListUpdate {
         desc: None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::tests::load_file;
+    use crate::common::Headers;
+    use crate::error::{Error, TwitterErrors};
+
+    #[test]
+    fn audit_statuses() {
+        let list: List =
+            serde_json::from_str(&load_file("sample_payloads/sample-list.json")).unwrap();
+
+        let status = subscription_status(&list, Ok(list.clone())).unwrap();
+        assert!(matches!(status, SubscriptionStatus::Active(_)));
+
+        let mut renamed = list.clone();
+        renamed.name = "Spatial Computing 2".to_string();
+        renamed.slug = "spatial-computing-2".to_string();
+        match subscription_status(&list, Ok(renamed)).unwrap() {
+            SubscriptionStatus::Renamed(current) => assert_eq!(current.slug, "spatial-computing-2"),
+            other => panic!("expected Renamed, got {:?}", other),
+        }
+
+        let errors: TwitterErrors = serde_json::from_str(
+            r#"{"errors":[{"code":34,"message":"Sorry, that page does not exist."}]}"#,
+        )
+        .unwrap();
+        let not_found = TwitterError(Headers::new(), errors);
+        let status = subscription_status(&list, Err(not_found)).unwrap();
+        assert!(matches!(status, SubscriptionStatus::Missing));
+
+        let not_found = BadStatus(StatusCode::NOT_FOUND, None);
+        let status = subscription_status(&list, Err(not_found)).unwrap();
+        assert!(matches!(status, SubscriptionStatus::Missing));
+
+        let forbidden = BadStatus(StatusCode::FORBIDDEN, None);
+        let status = subscription_status(&list, Err(forbidden)).unwrap();
+        assert!(matches!(status, SubscriptionStatus::Inaccessible(_)));
+
+        // errors that aren't about the list end the audit
+        let err = subscription_status(&list, Err(Error::RateLimit(1600000000))).unwrap_err();
+        assert!(matches!(err, Error::RateLimit(_)));
+    }
+}
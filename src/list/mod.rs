@@ -41,6 +41,14 @@
 //! - `ListUpdate`: When updating a list's metadata, all the fields that can be updated are
 //!   optional, so the `update` function returns this builder struct so you don't have to provide
 //!   all the parameters if you don't need to.
+//! - `SubscriptionAudit`/`SubscriptionStatus`: The report returned by `subscriptions_audit` and
+//!   `audit_lists`, describing which lists are still available.
+//! - `ListMember`: A member or subscriber of a list along with their relationship to the
+//!   authenticated user, as returned by `members_with_roles` and `subscribers_with_roles`.
+//! - `ListPreview`: A list's metadata along with its first few members, as returned by
+//!   `show_with_members`.
+//! - `ListSnapshot`/`SnapshotMember`: A portable copy of a list and its members, created by
+//!   `export_members` and recreated by `import_members`.
 //!
 //! ## Functions
 //!
@@ -62,18 +70,29 @@
 //! - `ownerships`/`subscriptions`/`list`: Note that `list` will only return the most recent 100
 //!   lists in the `ownerships`/`subscriptions` sets.
 //! - `memberships`
-//! - `members`/`is_member`
-//! - `subscribers`/`is_subscriber`
-//! - `show`
+//! - `members`/`is_member`/`members_with_roles`
+//! - `subscribers`/`is_subscriber`/`subscribers_with_roles`
+//! - `show`/`show_with_members`
 //! - `statuses`
+//! - `subscriptions_audit`/`audit_lists`: Checks whether lists still exist and whether they've
+//!   been renamed, without stopping at the first missing list.
+//!
+//! ### Backup and migration
+//!
+//! - `export_members`/`import_members`: Saves a list and its members as a `ListSnapshot`, which
+//!   can be serialized and later used to recreate the list, possibly on another account.
 
 use chrono;
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer, Serialize};
 
 use crate::common::*;
-use crate::{auth, links, user};
+use crate::user;
+#[cfg(feature = "lists")]
+use crate::{auth, links};
 
+#[cfg(feature = "lists")]
 mod fun;
+#[cfg(feature = "lists")]
 pub use self::fun::*;
 
 /// Convenience enum to refer to a list via its owner and name or via numeric ID.
@@ -159,9 +178,151 @@ pub struct List {
     ///The full name of the list, preceded by `/`, that can be preceded with `https://twitter.com`
     ///to create a link to the list.
     pub uri: String,
+    ///Whether the list is public. Private lists can only be seen by their creator.
+    #[serde(rename = "mode", deserialize_with = "deserialize_list_mode")]
+    pub public: bool,
     ///UTC timestamp of when the list was created.
     #[serde(with = "serde_datetime")]
     pub created_at: chrono::DateTime<chrono::Utc>,
+    ///Any fields returned by Twitter that egg-mode doesn't load into the other fields of this
+    ///struct.
+    ///
+    ///This is only available with the `raw-fields` feature enabled. It can be used to access data
+    ///that Twitter has added to its responses before egg-mode supports it.
+    #[cfg(feature = "raw-fields")]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+fn deserialize_list_mode<'de, D>(ser: D) -> Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let mode = String::deserialize(ser)?;
+    Ok(mode == "public")
+}
+
+/// A portable copy of a list's metadata and members, as created by `export_members`.
+///
+/// This can be serialized with serde to save a backup of a list, and given to `import_members` to
+/// recreate the list, either on the same account or a different one.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ListSnapshot {
+    /// The name of the list.
+    pub name: String,
+    /// The "slug" of the list at the time it was exported. A recreated list will get a new slug
+    /// based on its name.
+    pub slug: String,
+    /// The description of the list.
+    pub description: String,
+    /// Whether the list was public.
+    pub public: bool,
+    /// The members of the list.
+    pub members: Vec<SnapshotMember>,
+}
+
+/// A single member of a list, as saved in a `ListSnapshot`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SnapshotMember {
+    /// The numeric ID of the account. This is used to add the account when importing, since it
+    /// doesn't change when the account is renamed.
+    pub id: u64,
+    /// The screen name of the account at the time the list was exported.
+    pub screen_name: String,
+}
+
+/// A member or subscriber of a list, along with how they're connected to the authenticated user.
+///
+/// This is returned by `members_with_roles` and `subscribers_with_roles`. The connections come
+/// from `user::relation_lookup`, which reports relationships with the authenticated user, so they
+/// describe the list's owner when the authenticated user owns the list.
+#[derive(Debug, Clone)]
+pub struct ListMember {
+    /// The member or subscriber.
+    pub user: user::TwitterUser,
+    /// Whether this user follows the authenticated user.
+    pub follows_owner: bool,
+    /// Whether the authenticated user follows this user.
+    pub followed_by_owner: bool,
+}
+
+/// A list's metadata along with the first page of its members, as returned by `show_with_members`.
+///
+/// This holds everything needed to display a list, so it can be kept around instead of loading the
+/// list again. When more members are needed, `more_members` continues from where the preview left
+/// off.
+#[cfg(feature = "lists")]
+#[derive(Debug, Clone)]
+pub struct ListPreview {
+    /// The list's metadata.
+    pub list: List,
+    /// The first members of the list, in the order Twitter returned them.
+    pub members: Vec<user::TwitterUser>,
+    /// The cursor ID of the page of members after the ones in `members`, or `0` if the preview
+    /// already contains every member of the list.
+    pub next_cursor: i64,
+}
+
+#[cfg(feature = "lists")]
+impl ListPreview {
+    /// Returns whether the list has more members than the ones in the preview.
+    pub fn has_more_members(&self) -> bool {
+        self.next_cursor != 0
+    }
+
+    /// Returns a `CursorIter` that loads the rest of the list's members, starting after the ones
+    /// already in the preview. Members are loaded 5000 at a time, the largest page Twitter allows,
+    /// regardless of how many were in the preview.
+    pub fn more_members(
+        &self,
+        token: &auth::Token,
+    ) -> crate::cursor::CursorIter<crate::cursor::UserCursor> {
+        members(ListID::from_id(self.list.id), token)
+            .with_page_size(5000)
+            .resume_from(self.next_cursor)
+    }
+}
+
+/// The current state of a list checked by `subscriptions_audit` or `audit_lists`.
+#[derive(Debug)]
+pub enum SubscriptionStatus {
+    /// The list still exists under the same name. The enclosed value is the list's current
+    /// metadata.
+    Active(List),
+    /// The list still exists, but its name or slug has changed. The enclosed value is the list's
+    /// current metadata.
+    Renamed(List),
+    /// The list no longer exists.
+    Missing,
+    /// Twitter returned an error when loading the list, for example because it was made private.
+    /// The enclosed value is the error returned.
+    Inaccessible(crate::error::Error),
+}
+
+/// A report on a set of lists, as returned by `subscriptions_audit` and `audit_lists`.
+#[derive(Debug, Default)]
+pub struct SubscriptionAudit {
+    /// Each list that was checked, as it was known before the audit, paired with its current
+    /// status.
+    pub lists: Vec<(List, SubscriptionStatus)>,
+}
+
+impl SubscriptionAudit {
+    /// Returns the lists that no longer exist or could not be loaded.
+    pub fn dead(&self) -> impl Iterator<Item = &List> {
+        self.lists.iter().filter_map(|(list, status)| match status {
+            SubscriptionStatus::Missing | SubscriptionStatus::Inaccessible(_) => Some(list),
+            _ => None,
+        })
+    }
+
+    /// Returns the lists that have been renamed, as pairs of the list before and after the rename.
+    pub fn renamed(&self) -> impl Iterator<Item = (&List, &List)> {
+        self.lists.iter().filter_map(|(list, status)| match status {
+            SubscriptionStatus::Renamed(current) => Some((list, current)),
+            _ => None,
+        })
+    }
 }
 
 /// Represents a pending update to a list's metadata.
@@ -184,6 +345,7 @@ pub struct List {
 /// let list = update.name("Official Support").send(&token).await.unwrap();
 /// # }
 /// ```
+#[cfg(feature = "lists")]
 pub struct ListUpdate {
     list: ListID,
     name: Option<String>,
@@ -191,6 +353,7 @@ pub struct ListUpdate {
     desc: Option<String>,
 }
 
+#[cfg(feature = "lists")]
 impl ListUpdate {
     ///Updates the name of the list.
     pub fn name(self, name: impl Into<String>) -> ListUpdate {
@@ -234,7 +397,7 @@ impl ListUpdate {
 
 #[cfg(test)]
 mod tests {
-    use super::List;
+    use super::{List, ListSnapshot, SnapshotMember};
     use crate::common::tests::load_file;
 
     #[test]
@@ -242,6 +405,73 @@ mod tests {
         let content = load_file("sample_payloads/sample-list.json");
         let list = ::serde_json::from_str::<List>(&content).unwrap();
         assert_eq!(list.full_name, "@Scobleizer/all-people-in-spatial-2");
-        assert_eq!(list.user.screen_name, "Scobleizer")
+        assert_eq!(list.user.screen_name, "Scobleizer");
+        assert!(list.public);
+    }
+
+    #[cfg(feature = "lists")]
+    #[test]
+    fn member_roles() {
+        use super::fun::join_roles;
+
+        let sample = load_file("sample_payloads/user_array.json");
+        let users: Vec<crate::user::TwitterUser> = serde_json::from_str(&sample).unwrap();
+        let users = users.into_iter().take(3).collect::<Vec<_>>();
+        let ids = users.iter().map(|user| user.id).collect::<Vec<_>>();
+
+        let relations = serde_json::from_value(serde_json::json!([
+            { "name": "a", "screen_name": "a", "id": ids[0], "id_str": "", "connections": ["following", "followed_by"] },
+            { "name": "b", "screen_name": "b", "id": ids[1], "id_str": "", "connections": ["none"] },
+        ]))
+        .unwrap();
+
+        let members = join_roles(users, relations);
+        assert_eq!(members.len(), 3);
+        assert!(members[0].follows_owner && members[0].followed_by_owner);
+        assert!(!members[1].follows_owner && !members[1].followed_by_owner);
+        assert_eq!(members[2].user.id, ids[2]);
+        assert!(!members[2].follows_owner && !members[2].followed_by_owner);
+    }
+
+    #[test]
+    fn snapshot_roundtrip() {
+        let snapshot = ListSnapshot {
+            name: "rust people".to_string(),
+            slug: "rust-people".to_string(),
+            description: "people who write rust".to_string(),
+            public: false,
+            members: vec![SnapshotMember {
+                id: 165262228,
+                screen_name: "rustlang".to_string(),
+            }],
+        };
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let parsed: ListSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, snapshot);
+    }
+
+    #[cfg(feature = "lists")]
+    #[test]
+    fn preview_more_members() {
+        let content = load_file("sample_payloads/sample-list.json");
+        let list = ::serde_json::from_str::<List>(&content).unwrap();
+        let sample = load_file("sample_payloads/user_array.json");
+        let users: Vec<crate::user::TwitterUser> = serde_json::from_str(&sample).unwrap();
+
+        let mut preview = super::ListPreview {
+            list,
+            members: users.into_iter().take(3).collect(),
+            next_cursor: 1510022917713813206,
+        };
+        assert!(preview.has_more_members());
+
+        let token = crate::Token::Bearer("token".to_string());
+        let more = preview.more_members(&token);
+        assert_eq!(more.checkpoint(), 1510022917713813206);
+        assert_eq!(more.page_size, Some(5000));
+
+        preview.next_cursor = 0;
+        assert!(!preview.has_more_members());
     }
 }
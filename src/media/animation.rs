@@ -0,0 +1,190 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Encoding of animated GIFs from `image::Frames`, with the `image` crate's GIF encoder.
+
+use std::future::Future;
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::Frames;
+
+use crate::error::{self, AnimationError};
+use crate::{auth, media};
+
+/// The largest GIF Twitter accepts, in pixels.
+const MAX_WIDTH: u32 = 1280;
+const MAX_HEIGHT: u32 = 1080;
+/// The most frames Twitter accepts in a GIF.
+const MAX_FRAMES: usize = 350;
+/// The most pixels Twitter accepts across all the frames of a GIF.
+const MAX_TOTAL_PIXELS: u64 = 300_000_000;
+/// The largest GIF file Twitter accepts, in bytes.
+const MAX_FILE_SIZE: usize = 15 * 1024 * 1024;
+
+/// Encodes the given frames into an animated GIF that loops forever.
+///
+/// The frames can come from decoding another animation, with `AnimationDecoder::into_frames`, or
+/// be drawn by hand and collected with `Frames::new`. They're encoded with the `image` crate's
+/// `GifEncoder`, which reduces each frame to its own palette of 256 colors.
+///
+/// This checks the animation against Twitter's limits for GIFs: every frame must be the same
+/// size, at most 1280x1080, there can be at most 350 frames and 300 million pixels across all of
+/// them, and the encoded file must be 15MB or smaller. Twitter doesn't take animations in other
+/// formats, so this doesn't encode MP4 video; encode videos with a tool like `ffmpeg` and upload
+/// them with `upload_media`.
+///
+/// This is only available with the `image` feature enabled.
+pub fn encode_gif(frames: Frames<'_>) -> Result<Vec<u8>, AnimationError> {
+    let mut out = Vec::new();
+    {
+        let mut encoder = GifEncoder::new(&mut out);
+        encoder
+            .set_repeat(Repeat::Infinite)
+            .map_err(|e| AnimationError::Encode(e.to_string()))?;
+
+        let mut size = None;
+        let mut count = 0;
+        for frame in frames {
+            let frame = frame.map_err(|e| AnimationError::Decode(e.to_string()))?;
+            let (width, height) = frame.buffer().dimensions();
+            match size {
+                None if width == 0 || height == 0 || width > MAX_WIDTH || height > MAX_HEIGHT => {
+                    return Err(AnimationError::BadDimensions { width, height });
+                }
+                None => size = Some((width, height)),
+                Some(expected) if expected != (width, height) => {
+                    return Err(AnimationError::SizeMismatch {
+                        expected,
+                        actual: (width, height),
+                    });
+                }
+                Some(_) => (),
+            }
+
+            count += 1;
+            if count > MAX_FRAMES {
+                return Err(AnimationError::TooManyFrames(count));
+            }
+            let total_pixels = u64::from(width) * u64::from(height) * count as u64;
+            if total_pixels > MAX_TOTAL_PIXELS {
+                return Err(AnimationError::TooManyPixels(total_pixels));
+            }
+
+            encoder
+                .encode_frame(frame)
+                .map_err(|e| AnimationError::Encode(e.to_string()))?;
+        }
+
+        if count == 0 {
+            return Err(AnimationError::NoFrames);
+        }
+    }
+
+    if out.len() > MAX_FILE_SIZE {
+        return Err(AnimationError::FileTooLarge(out.len()));
+    }
+
+    Ok(out)
+}
+
+/// Encodes the given frames into an animated GIF with `encode_gif`, and uploads it.
+///
+/// The animation is uploaded as a GIF, so Twitter will show it as an animated GIF in tweets. As
+/// with other uploads, check the status of the returned media with `get_status` before attaching
+/// it, since Twitter needs time to process GIFs. The frames are encoded before this returns, so
+/// the returned future doesn't hold on to them.
+///
+/// ```rust,no_run
+/// # use egg_mode::Token;
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let token: Token = unimplemented!();
+/// use image::{Delay, Frame, Frames, Rgba, RgbaImage};
+/// use egg_mode::media::upload_animation;
+///
+/// // a square that fades from black to red
+/// let frames = (0..=255u8).step_by(15).map(|red| {
+///     let image = RgbaImage::from_pixel(64, 64, Rgba([red, 0, 0, 255]));
+///     Ok(Frame::from_parts(image, 0, 0, Delay::from_numer_denom_ms(50, 1)))
+/// });
+///
+/// let handle = upload_animation(Frames::new(Box::new(frames)), &token).await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// This is only available with the `image` feature enabled.
+pub fn upload_animation<'a>(
+    frames: Frames<'_>,
+    token: &'a auth::Token,
+) -> impl Future<Output = error::Result<media::MediaHandle>> + 'a {
+    let data = encode_gif(frames);
+    async move {
+        let data = data?;
+        media::upload(&data, &media::media_types::image_gif())
+            .send(token)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{AnimationDecoder, Delay, Frame, ImageResult, Rgba, RgbaImage};
+
+    fn frames(frames: Vec<Frame>) -> Frames<'static> {
+        Frames::new(Box::new(frames.into_iter().map(ImageResult::Ok)))
+    }
+
+    fn frame(width: u32, height: u32, color: [u8; 4], millis: u32) -> Frame {
+        let image = RgbaImage::from_pixel(width, height, Rgba(color));
+        Frame::from_parts(image, 0, 0, Delay::from_numer_denom_ms(millis, 1))
+    }
+
+    #[test]
+    fn encode_frames() {
+        let gif = encode_gif(frames(vec![
+            frame(4, 2, [255, 0, 0, 255], 100),
+            frame(4, 2, [0, 0, 255, 255], 40),
+        ]))
+        .unwrap();
+        assert!(gif.starts_with(b"GIF89a\x04\x00\x02\x00"));
+        assert!(gif.ends_with(&[0x3b]));
+
+        let decoded = image::codecs::gif::GifDecoder::new(std::io::Cursor::new(gif))
+            .unwrap()
+            .into_frames()
+            .collect_frames()
+            .unwrap();
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded[0].delay().numer_denom_ms(), (100, 1));
+        assert_eq!(decoded[1].buffer().get_pixel(0, 0), &Rgba([0, 0, 255, 255]));
+    }
+
+    #[test]
+    fn reject_bad_frames() {
+        assert_eq!(encode_gif(frames(vec![])), Err(AnimationError::NoFrames));
+        assert_eq!(
+            encode_gif(frames(vec![frame(1281, 1, [0; 4], 10)])),
+            Err(AnimationError::BadDimensions {
+                width: 1281,
+                height: 1
+            })
+        );
+        assert_eq!(
+            encode_gif(frames(vec![
+                frame(1, 1, [0; 4], 10),
+                frame(2, 1, [0; 4], 10)
+            ])),
+            Err(AnimationError::SizeMismatch {
+                expected: (1, 1),
+                actual: (2, 1)
+            })
+        );
+        assert_eq!(
+            encode_gif(frames(vec![frame(1, 1, [0; 4], 10); 351])),
+            Err(AnimationError::TooManyFrames(351))
+        );
+    }
+}
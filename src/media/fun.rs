@@ -0,0 +1,590 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::time::Duration;
+
+use base64;
+use futures::stream::{self, Stream};
+use hyper::{Body, Request};
+use serde::Deserialize;
+
+use crate::common::*;
+use crate::{auth, error, links};
+
+use super::*;
+
+/// Begins an upload of the given media, with options not available through `upload_media`.
+///
+/// See the [`UploadBuilder`] docs for the available options.
+///
+/// [`UploadBuilder`]: struct.UploadBuilder.html
+pub fn upload<'a>(data: &'a [u8], media_type: &mime::Mime) -> UploadBuilder<'a> {
+    UploadBuilder {
+        data,
+        media_type: media_type.clone(),
+        category: None,
+        additional_owners: vec![],
+        dm: None,
+    }
+}
+
+/// Represents a media upload before it is sent, with all its options.
+///
+/// This is returned by [`upload`]. `upload_media` and `upload_media_for_dm` are shortcuts for the
+/// common cases of this builder.
+///
+/// [`upload`]: fn.upload.html
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # use egg_mode::Token;
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let token: Token = unimplemented!();
+/// use egg_mode::media::{self, media_types, MediaCategory};
+///
+/// let gif = b"some gif bytes"; //pretend we loaded a GIF file into this
+/// let handle = media::upload(gif, &media_types::image_gif())
+///     // post the GIF as a still image instead of an animation
+///     .media_category(MediaCategory::Image)
+///     // let another account attach this media to their own tweets
+///     .additional_owners(vec![783214])
+///     .send(&token)
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+#[must_use = "UploadBuilder is a builder; call `send` to upload the media"]
+pub struct UploadBuilder<'a> {
+    data: &'a [u8],
+    media_type: mime::Mime,
+    category: Option<MediaCategory>,
+    additional_owners: Vec<u64>,
+    dm: Option<bool>,
+}
+
+impl<'a> UploadBuilder<'a> {
+    /// Sets the category Twitter uses to process the media, instead of picking one based on its
+    /// MIME type.
+    pub fn media_category(self, category: MediaCategory) -> Self {
+        UploadBuilder {
+            category: Some(category),
+            ..self
+        }
+    }
+
+    /// Allows the given users to use the uploaded media in their own tweets, in addition to the
+    /// authenticated user. Twitter accepts up to 100 additional owners.
+    pub fn additional_owners(self, owners: Vec<u64>) -> Self {
+        UploadBuilder {
+            additional_owners: owners,
+            ..self
+        }
+    }
+
+    /// Prepares the media to be attached to a Direct Message instead of a tweet.
+    ///
+    /// If `shared` is `true`, the media can be attached to multiple messages, but anyone with its
+    /// URL can view it. See [`upload_media_for_dm`] for the caveats of setting `shared`.
+    ///
+    /// [`upload_media_for_dm`]: fn.upload_media_for_dm.html
+    pub fn for_dm(self, shared: bool) -> Self {
+        UploadBuilder {
+            dm: Some(shared),
+            ..self
+        }
+    }
+
+    /// Uploads the media.
+    ///
+    /// The upload proceeds in 1MB chunks until completed. After completion, be sure to check the
+    /// status of the uploaded media with [`get_status`].
+    ///
+    /// [`get_status`]: fn.get_status.html
+    pub async fn send(self, token: &auth::Token) -> error::Result<MediaHandle> {
+        let req = self.init_request(token)?;
+        let media = request_with_json_response::<RawMedia>(req).await?.response;
+
+        finish_upload(media, self.data, token).await
+    }
+
+    /// Uploads the media, reporting its progress as a `Stream`.
+    ///
+    /// Unlike `send`, this also waits for Twitter to finish processing the media, checking its
+    /// status as often as Twitter asks, so the last item of the stream is a `MediaHandle` that is
+    /// ready to be attached to a tweet. This is meant for videos and GIFs, which are uploaded in
+    /// many chunks and need time to process, so that progress can be shown while they upload.
+    ///
+    /// The stream ends after `UploadProgress::Done`, or after the first error. If Twitter fails to
+    /// process the media, its reason is returned as `Error::MediaError`.
+    ///
+    /// ```rust,no_run
+    /// # use egg_mode::Token;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let token: Token = unimplemented!();
+    /// use egg_mode::media::{self, media_types, UploadProgress};
+    /// use futures::TryStreamExt;
+    ///
+    /// let video = b"some video bytes"; //pretend we loaded a video file into this
+    /// let mut progress = media::upload(video, &media_types::video_mp4()).send_with_progress(&token);
+    ///
+    /// while let Some(step) = progress.try_next().await? {
+    ///     match step {
+    ///         UploadProgress::ChunkSent { n, total } => println!("uploaded {}/{}", n, total),
+    ///         UploadProgress::Processing { percent } => println!("processing: {}%", percent),
+    ///         UploadProgress::Done(handle) => println!("ready: {:?}", handle.id),
+    ///         _ => (),
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn send_with_progress(
+        self,
+        token: &auth::Token,
+    ) -> impl Stream<Item = error::Result<UploadProgress>> + Unpin + Send + 'a {
+        let token = token.clone();
+        let steps = stream::unfold(UploadState::Init(self), move |state| {
+            let token = token.clone();
+            async move {
+                match upload_step(state, &token).await {
+                    Ok(Some((progress, next))) => Some((Ok(progress), next)),
+                    Ok(None) => None,
+                    Err(e) => Some((Err(e), UploadState::Finished)),
+                }
+            }
+        });
+
+        Box::pin(steps)
+    }
+
+    /// Builds the `INIT` request that starts the upload.
+    fn init_request(&self, token: &auth::Token) -> error::Result<Request<Body>> {
+        let params = self.init_params()?;
+        Ok(post(links::media::UPLOAD, token, Some(&params)))
+    }
+
+    /// Builds the parameters of the `INIT` request.
+    fn init_params(&self) -> error::Result<ParamList> {
+        let category = self
+            .category
+            .unwrap_or_else(|| MediaCategory::from(&self.media_type));
+
+        #[cfg(feature = "media_validation")]
+        validate(self.data, &self.media_type, category)?;
+
+        let category = match self.dm {
+            Some(_) => category.dm_category().to_string(),
+            None => category.to_string(),
+        };
+        let owners = self
+            .additional_owners
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let params = ParamList::new()
+            .add_param("command", "INIT")
+            .add_param("total_bytes", self.data.len().to_string())
+            .add_param("media_type", self.media_type.to_string())
+            .add_param("media_category", category)
+            .add_opt_param("shared", self.dm.map(|shared| shared.to_string()))
+            .add_opt_param(
+                "additional_owners",
+                Some(owners).filter(|owners| !owners.is_empty()),
+            );
+        Ok(params)
+    }
+}
+
+/// The steps of an upload started with `UploadBuilder::send_with_progress`.
+#[derive(Debug, Clone)]
+pub enum UploadProgress {
+    /// Twitter accepted the upload, and the media is about to be sent.
+    Init,
+    /// A chunk of the media was sent. `n` counts the chunks sent so far, starting at 1, out of
+    /// `total`.
+    ChunkSent {
+        /// The number of chunks sent so far.
+        n: usize,
+        /// The number of chunks the media was split into.
+        total: usize,
+    },
+    /// Every chunk was sent, and the upload is about to be finalized.
+    Finalizing,
+    /// Twitter is processing the media. This is returned each time its status is checked.
+    Processing {
+        /// How much of the processing is done, from 0 to 100. This is 0 if Twitter hasn't
+        /// started processing the media yet.
+        percent: u8,
+    },
+    /// The media is ready to be attached to a tweet.
+    Done(MediaHandle),
+}
+
+/// The size of the chunks media is uploaded in.
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Where an upload started with `send_with_progress` is up to.
+enum UploadState<'a> {
+    Init(UploadBuilder<'a>),
+    Append {
+        id: String,
+        data: &'a [u8],
+        index: usize,
+    },
+    Finalize(String),
+    Process {
+        id: String,
+        wait: u64,
+    },
+    Finished,
+}
+
+/// Performs the next step of an upload, returning the progress it made and the step after it.
+async fn upload_step<'a>(
+    state: UploadState<'a>,
+    token: &auth::Token,
+) -> error::Result<Option<(UploadProgress, UploadState<'a>)>> {
+    match state {
+        UploadState::Init(builder) => {
+            let req = builder.init_request(token)?;
+            let media = request_with_json_response::<RawMedia>(req).await?.response;
+            let next = UploadState::Append {
+                id: media.id,
+                data: builder.data,
+                index: 0,
+            };
+            Ok(Some((UploadProgress::Init, next)))
+        }
+        UploadState::Append { id, data, index } => {
+            let total = data.chunks(CHUNK_SIZE).len();
+            if index >= total {
+                return Ok(Some((
+                    UploadProgress::Finalizing,
+                    UploadState::Finalize(id),
+                )));
+            }
+
+            let start = index * CHUNK_SIZE;
+            let chunk = &data[start..data.len().min(start + CHUNK_SIZE)];
+            raw_request(append_request(&id, chunk, index, token)).await?;
+
+            let progress = UploadProgress::ChunkSent {
+                n: index + 1,
+                total,
+            };
+            let next = UploadState::Append {
+                id,
+                data,
+                index: index + 1,
+            };
+            Ok(Some((progress, next)))
+        }
+        UploadState::Finalize(id) => {
+            let (media, percent) = load_media(finalize_request(&id, token)).await?;
+            processing_step(media, percent)
+        }
+        UploadState::Process { id, wait } => {
+            tokio::time::sleep(Duration::from_secs(wait)).await;
+            let params = ParamList::new()
+                .add_param("command", "STATUS")
+                .add_param("media_id", id);
+            let req = get(links::media::UPLOAD, token, Some(&params));
+            let (media, percent) = load_media(req).await?;
+            processing_step(media, percent)
+        }
+        UploadState::Finished => Ok(None),
+    }
+}
+
+/// Turns the media returned by `FINALIZE` or `STATUS` into the next step of an upload.
+fn processing_step<'a>(
+    media: RawMedia,
+    percent: u8,
+) -> error::Result<Option<(UploadProgress, UploadState<'a>)>> {
+    let wait = match &media.progress {
+        None | Some(ProgressInfo::Success) => {
+            return Ok(Some((
+                UploadProgress::Done(media.into()),
+                UploadState::Finished,
+            )))
+        }
+        Some(ProgressInfo::Failed(err)) => return Err(err.clone().into()),
+        Some(ProgressInfo::Pending(wait)) => *wait,
+        Some(ProgressInfo::InProgress(wait)) => *wait,
+    };
+
+    let next = UploadState::Process { id: media.id, wait };
+    Ok(Some((UploadProgress::Processing { percent }, next)))
+}
+
+/// Loads the given `FINALIZE` or `STATUS` request, returning the media along with how much of
+/// its processing is done.
+async fn load_media(req: Request<Body>) -> error::Result<(RawMedia, u8)> {
+    let json = request_with_json_response::<serde_json::Value>(req)
+        .await?
+        .response;
+    let percent = json
+        .pointer("/processing_info/progress_percent")
+        .and_then(|percent| percent.as_f64())
+        .map_or(0, |percent| (percent as u8).min(100));
+    Ok((RawMedia::deserialize(json)?, percent))
+}
+
+/// Builds the `APPEND` request that sends the chunk at the given index.
+fn append_request(id: &str, chunk: &[u8], index: usize, token: &auth::Token) -> Request<Body> {
+    let params = ParamList::new()
+        .add_param("command", "APPEND")
+        .add_param("media_id", id.to_string())
+        .add_param("media_data", base64::encode(chunk))
+        .add_param("segment_index", index.to_string());
+    post(links::media::UPLOAD, token, Some(&params))
+}
+
+/// Builds the `FINALIZE` request that completes the upload.
+fn finalize_request(id: &str, token: &auth::Token) -> Request<Body> {
+    let params = ParamList::new()
+        .add_param("command", "FINALIZE")
+        .add_param("media_id", id.to_string());
+    post(links::media::UPLOAD, token, Some(&params))
+}
+
+/// Upload media to the server.
+///
+/// The upload proceeds in 1MB chunks until completed. After completion,
+/// be sure to check the status of the uploaded media with [`get_status`].
+/// Twitter often needs time to post-process media before it can be attached
+/// to a tweet.
+pub async fn upload_media(
+    data: &[u8],
+    media_type: &mime::Mime,
+    token: &auth::Token,
+) -> error::Result<MediaHandle> {
+    upload(data, media_type).send(token).await
+}
+
+/// Upload media to the server, for use in a Direct Message.
+///
+/// This function works the same as [`upload_media`], but uses a separate set of `media_category`
+/// values to allow the resulting media to be attached to a Direct Message.
+///
+/// Because of the private nature of DMs, a separate flag is used to allow for media to be attached
+/// to multiple messages. If the `shared` argument is set to `true`, then the resulting `MediaId`
+/// can be used in multiple messages, but the resulting URL for the upload can be accessed by
+/// anyone with the URL, rather then being private to the message. Twitter states that you must
+/// provide the user with clear notice that the media can be viewed by anyone with the URL, and get
+/// their express permission to set `shared`. Also note that even if you set `shared` to `true`,
+/// the resulting media can only be attached to messages from the same user. The default (and
+/// recommended) value for `shared` is `false`.
+///
+/// The upload proceeds in 1MB chunks until completed. After completion, be sure to check the
+/// status of the uploaded media with [`get_status`]. Twitter often needs time to post-process
+/// media before it can be attached to a message.
+pub async fn upload_media_for_dm(
+    data: &[u8],
+    media_type: &mime::Mime,
+    shared: bool,
+    token: &auth::Token,
+) -> error::Result<MediaHandle> {
+    upload(data, media_type).for_dm(shared).send(token).await
+}
+
+async fn finish_upload(
+    media: RawMedia,
+    data: &[u8],
+    token: &auth::Token,
+) -> error::Result<MediaHandle> {
+    // divide into 1MB chunks
+    for (ix, chunk) in data.chunks(CHUNK_SIZE).enumerate() {
+        // This request has no response (upon success)
+        raw_request(append_request(&media.id, chunk, ix, token)).await?;
+    }
+
+    let req = finalize_request(&media.id, token);
+    Ok(request_with_json_response::<RawMedia>(req)
+        .await?
+        .response
+        .into())
+}
+
+/// Check the status of uploaded media
+pub async fn get_status(media_id: MediaId, token: &auth::Token) -> error::Result<MediaHandle> {
+    let params = ParamList::new()
+        .add_param("command", "STATUS")
+        .add_param("media_id", media_id.0);
+    let req = get(links::media::UPLOAD, token, Some(&params));
+    Ok(request_with_json_response::<RawMedia>(req)
+        .await?
+        .response
+        .into())
+}
+
+/// Set metadata for a media upload. At the moment the only attribute that may
+/// be set is `alt_text`.
+pub async fn set_metadata(
+    media_id: &MediaId,
+    alt_text: &str,
+    token: &auth::Token,
+) -> error::Result<()> {
+    let payload = serde_json::json!({
+        "media_id": media_id.0,
+        "alt_text": {
+            "text": alt_text
+        }
+    });
+    let req = post_json(links::media::METADATA, token, payload);
+    raw_request(req).await?;
+    Ok(())
+}
+
+/// Creates a signed URL for the given media file, which can be loaded without the token.
+///
+/// Media attached to Direct Messages, or posted by protected accounts, can only be loaded with a
+/// request signed by a token that's allowed to see it. `MediaEntity::download` can do that within
+/// this process, but a URL handed to a browser's `<img>` tag or to another process can't carry an
+/// `Authorization` header. This function signs a GET request for the given URL and puts the OAuth
+/// parameters in its query string instead, so the returned URL can be loaded by anything that can
+/// make a plain GET request.
+///
+/// The returned URL includes the public halves of the consumer and access keys and the request
+/// signature, but not the secrets, so it can't be used to make any other request. It's
+/// short-lived: Twitter only accepts a signature for a few minutes after it's made, and may refuse
+/// to accept the same signature twice, so create a new URL every time one is handed out.
+///
+/// Returns `None` if the given token is a Bearer token, as those can't sign a request without
+/// putting the token itself in the URL, or if the given URL can't be parsed.
+///
+/// ```rust,no_run
+/// # use egg_mode::Token;
+/// # let token: Token = unimplemented!();
+/// # let message: egg_mode::direct::DirectMessage = unimplemented!();
+/// if let Some(media) = &message.attachment {
+///     let url = egg_mode::media::signed_url(&media.media_url_https, &token).unwrap();
+///     println!("<img src=\"{}\">", url);
+/// }
+/// ```
+pub fn signed_url(url: &str, token: &auth::Token) -> Option<String> {
+    let (consumer, access) = match token {
+        auth::Token::Access { consumer, access } => (consumer, access),
+        auth::Token::Bearer(_) => return None,
+    };
+
+    let mut url = url::Url::parse(url).ok()?;
+    let params = url
+        .query_pairs()
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .fold(ParamList::new(), |params, (k, v)| params.add_param(k, v));
+    url.set_query(None);
+    url.set_fragment(None);
+
+    Some(auth::raw::signed_get_url(
+        url.as_str(),
+        &params,
+        consumer,
+        access,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn sign_media_url() {
+        use super::signed_url;
+        use crate::auth::{KeyPair, Token};
+
+        let token = Token::Access {
+            consumer: KeyPair::new("consumer-key", "consumer-secret"),
+            access: KeyPair::new("access-key", "access-secret"),
+        };
+        let url = signed_url(
+            "https://ton.twitter.com/1.1/ton/data/dm/1/2/abc.jpg?size=small#frag",
+            &token,
+        )
+        .unwrap();
+        let url = url::Url::parse(&url).unwrap();
+
+        assert_eq!(url.path(), "/1.1/ton/data/dm/1/2/abc.jpg");
+        assert_eq!(url.fragment(), None);
+        let params: std::collections::HashMap<_, _> = url.query_pairs().into_owned().collect();
+        assert_eq!(params["size"], "small");
+        assert_eq!(params["oauth_consumer_key"], "consumer-key");
+        assert_eq!(params["oauth_token"], "access-key");
+        assert!(params.contains_key("oauth_signature"));
+        assert!(!url.as_str().contains("secret"));
+
+        assert!(signed_url("https://ton.twitter.com/a.jpg", &Token::Bearer("b".into())).is_none());
+        assert!(signed_url("not a url", &token).is_none());
+    }
+
+    #[test]
+    fn upload_init_params() {
+        use super::upload;
+        use crate::media::MediaCategory;
+
+        let mut data = b"\x89PNG\r\n\x1a\n".to_vec();
+        data.resize(16, 0);
+        let params = upload(&data, &mime::IMAGE_PNG).init_params().unwrap();
+        assert_eq!(params.get("command").unwrap(), "INIT");
+        assert_eq!(params.get("total_bytes").unwrap(), "16");
+        assert_eq!(params.get("media_type").unwrap(), "image/png");
+        assert_eq!(params.get("media_category").unwrap(), "tweet_image");
+        assert!(!params.contains_key("shared"));
+        assert!(!params.contains_key("additional_owners"));
+
+        let params = upload(&data, &mime::IMAGE_PNG)
+            .media_category(MediaCategory::Gif)
+            .additional_owners(vec![783214, 6253282])
+            .for_dm(true)
+            .init_params()
+            .unwrap();
+        assert_eq!(params.get("media_category").unwrap(), "dm_gif");
+        assert_eq!(params.get("shared").unwrap(), "true");
+        assert_eq!(params.get("additional_owners").unwrap(), "783214,6253282");
+    }
+
+    use super::RawMedia;
+    use crate::common::tests::load_file;
+
+    fn load_media(path: &str) -> RawMedia {
+        let content = load_file(path);
+        ::serde_json::from_str::<RawMedia>(&content).unwrap()
+    }
+
+    #[test]
+    fn upload_progress_steps() {
+        use super::{processing_step, UploadProgress, UploadState};
+        use crate::error::Error;
+
+        let media = load_media("sample_payloads/media_in_progress.json");
+        match processing_step(media, 8) {
+            Ok(Some((
+                UploadProgress::Processing { percent: 8 },
+                UploadState::Process { id, wait },
+            ))) => {
+                assert_eq!(id, "13");
+                assert_eq!(wait, 10);
+            }
+            _ => panic!("expected processing to continue"),
+        }
+
+        let media = load_media("sample_payloads/media.json");
+        match processing_step(media, 0) {
+            Ok(Some((UploadProgress::Done(handle), UploadState::Finished))) => {
+                assert_eq!(handle.id.0, "710511363345354753")
+            }
+            _ => panic!("expected the upload to be done"),
+        }
+
+        let media = load_media("sample_payloads/media_fail.json");
+        match processing_step(media, 0) {
+            Err(Error::MediaError(err)) => assert_eq!(err.name, "InvalidMedia"),
+            _ => panic!("expected a media error"),
+        }
+    }
+}
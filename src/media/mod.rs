@@ -9,6 +9,8 @@
 //!
 //! ```rust,no_run
 //! # use egg_mode::Token;
+//! # #[cfg(not(feature = "media"))] fn main() {}
+//! # #[cfg(feature = "media")]
 //! # #[tokio::main]
 //! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
 //! # let token: Token = unimplemented!();
@@ -22,18 +24,62 @@
 //! let tweet = draft.send(&token).await?;
 //! # }
 //! ```
+//!
+//! For more control over the upload, such as overriding the media category Twitter uses to process
+//! the file or allowing another account to use the uploaded media, use the [`UploadBuilder`]
+//! returned by [`upload`].
+//!
+//! [`UploadBuilder`]: struct.UploadBuilder.html
+//! [`upload`]: fn.upload.html
+//!
+//! To show the progress of a large upload, like a video, [`UploadBuilder::send_with_progress`]
+//! returns a `Stream` of [`UploadProgress`] updates, and waits for Twitter to process the media.
+//!
+//! [`UploadBuilder::send_with_progress`]: struct.UploadBuilder.html#method.send_with_progress
+//! [`UploadProgress`]: enum.UploadProgress.html
+//!
+//! With the `image` feature enabled, animations can be uploaded straight from their frames with
+//! [`upload_animation`], which encodes an `image::Frames` sequence from the [`image`] crate into an
+//! animated GIF within Twitter's limits.
+//!
+//! [`image`]: https://docs.rs/image
+//!
+//! The `image` feature also adds [`upload_media_auto`], which shrinks photos that are larger than
+//! Twitter allows before uploading them, instead of having Twitter reject them.
+//!
+//! [`upload_media_auto`]: fn.upload_media_auto.html
+//!
+//! [`upload_animation`]: fn.upload_animation.html
+//!
+//! To show media attached to a Direct Message or a protected tweet outside of your process, like
+//! in a web page, [`signed_url`] creates a short-lived URL that can be loaded without the token.
+//!
+//! [`signed_url`]: fn.signed_url.html
 
 use std::time::{Duration, Instant};
 
-use base64;
 use serde::de::Error;
 use serde::{Deserialize, Deserializer};
 
-use crate::common::*;
-use crate::{auth, error, links};
+use crate::error;
 
 use mime;
 
+#[cfg(feature = "image")]
+mod animation;
+#[cfg(feature = "media")]
+mod fun;
+#[cfg(feature = "image")]
+mod resize;
+
+#[cfg(feature = "media")]
+pub use self::fun::*;
+
+#[cfg(feature = "image")]
+pub use self::animation::{encode_gif, upload_animation};
+#[cfg(feature = "image")]
+pub use self::resize::upload_media_auto;
+
 /// A collection of convenience functions that return media types accepted by Twitter.
 ///
 /// These are convenience types that can be handed to [`upload_media`] to set the right
@@ -43,6 +89,9 @@ use mime;
 /// Note that using `image_gif` and `video_mp4` will automatically set the upload's
 /// `media_category` to `tweet_gif` and `tweet_video` respectively, allowing
 /// larger file sizes and extra processing time.
+///
+/// If you don't know the type of a file ahead of time, `from_bytes` reads it from the file's
+/// contents, and `from_extension` guesses it from the file's name.
 pub mod media_types {
     use mime::{self, Mime};
 
@@ -70,6 +119,64 @@ pub mod media_types {
     pub fn video_mp4() -> Mime {
         "video/mp4".parse().unwrap()
     }
+
+    /// Returns the media type of the given data, as read from the file signature at its start.
+    ///
+    /// This recognizes the types in this module: PNG, JPG, GIF, and WEBP images, and MP4 videos.
+    /// Returns `None` for anything else.
+    ///
+    /// ```rust
+    /// use egg_mode::media::media_types;
+    ///
+    /// let image = b"\x89PNG\r\n\x1a\n rest of the image";
+    /// assert_eq!(media_types::from_bytes(image), Some(media_types::image_png()));
+    /// assert_eq!(media_types::from_bytes(b"plain text"), None);
+    /// ```
+    pub fn from_bytes(data: &[u8]) -> Option<Mime> {
+        if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(image_png())
+        } else if data.starts_with(&[0xff, 0xd8, 0xff]) {
+            Some(image_jpg())
+        } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+            Some(image_gif())
+        } else if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+            Some(image_webp())
+        } else if data.len() >= 8 && &data[4..8] == b"ftyp" {
+            Some(video_mp4())
+        } else {
+            None
+        }
+    }
+
+    /// Returns the media type that files with the given extension usually have.
+    ///
+    /// The extension can be given with or without a leading dot, or as part of a file name, and is
+    /// matched without regard to case. This recognizes the same types as `from_bytes`, and returns
+    /// `None` for anything else. Since the extension of a file doesn't have to match its contents,
+    /// prefer `from_bytes` when the data is at hand.
+    ///
+    /// ```rust
+    /// use egg_mode::media::media_types;
+    ///
+    /// assert_eq!(media_types::from_extension("jpeg"), Some(media_types::image_jpg()));
+    /// assert_eq!(media_types::from_extension("cat.PNG"), Some(media_types::image_png()));
+    /// assert_eq!(media_types::from_extension("notes.txt"), None);
+    /// ```
+    pub fn from_extension(extension: &str) -> Option<Mime> {
+        let extension = match extension.rfind('.') {
+            Some(dot) => &extension[dot + 1..],
+            None => extension,
+        };
+
+        match extension.to_ascii_lowercase().as_str() {
+            "png" => Some(image_png()),
+            "jpg" | "jpeg" | "jpe" => Some(image_jpg()),
+            "gif" => Some(image_gif()),
+            "webp" => Some(image_webp()),
+            "mp4" | "m4v" => Some(video_mp4()),
+            _ => None,
+        }
+    }
 }
 
 /// Upload progress info.
@@ -100,6 +207,7 @@ enum RawProgressInfoTag {
 #[derive(Debug, Deserialize)]
 struct RawProgressInfo {
     state: RawProgressInfoTag,
+    #[allow(dead_code)]
     progress_percent: Option<f64>,
     check_after_secs: Option<u64>,
     error: Option<error::MediaError>,
@@ -182,8 +290,13 @@ impl MediaHandle {
 
 /// Represents the kind of media that Twitter will accept.
 /// `.to_string()` will return a string suitable for use in API calls
+///
+/// By default, the category is picked based on the MIME type of the upload. To set it explicitly,
+/// use [`UploadBuilder::media_category`].
+///
+/// [`UploadBuilder::media_category`]: struct.UploadBuilder.html#method.media_category
 #[derive(Debug, Copy, Clone, PartialEq, Eq, derive_more::Display)]
-enum MediaCategory {
+pub enum MediaCategory {
     /// Static image. Four can be attached to a single tweet.
     #[display(fmt = "tweet_image")]
     Image,
@@ -209,6 +322,7 @@ impl From<&mime::Mime> for MediaCategory {
 }
 
 impl MediaCategory {
+    #[cfg(feature = "media")]
     fn dm_category(&self) -> &'static str {
         match self {
             MediaCategory::Image => "dm_image",
@@ -216,123 +330,62 @@ impl MediaCategory {
             MediaCategory::Video => "dm_video",
         }
     }
-}
 
-/// Upload media to the server.
-///
-/// The upload proceeds in 1MB chunks until completed. After completion,
-/// be sure to check the status of the uploaded media with [`get_status`].
-/// Twitter often needs time to post-process media before it can be attached
-/// to a tweet.
-pub async fn upload_media(
-    data: &[u8],
-    media_type: &mime::Mime,
-    token: &auth::Token,
-) -> error::Result<MediaHandle> {
-    let media_category = MediaCategory::from(media_type);
-    let params = ParamList::new()
-        .add_param("command", "INIT")
-        .add_param("total_bytes", data.len().to_string())
-        .add_param("media_type", media_type.to_string())
-        .add_param("media_category", media_category.to_string());
-    let req = post(links::media::UPLOAD, token, Some(&params));
-
-    let media = request_with_json_response::<RawMedia>(req).await?.response;
-
-    finish_upload(media, data, token).await
+    /// The largest file Twitter accepts for this category, in bytes.
+    #[cfg(feature = "media_validation")]
+    fn size_limit(self) -> usize {
+        match self {
+            MediaCategory::Image => 5 * 1024 * 1024,
+            MediaCategory::Gif => 15 * 1024 * 1024,
+            MediaCategory::Video => 512 * 1024 * 1024,
+        }
+    }
 }
 
-/// Upload media to the server, for use in a Direct Message.
-///
-/// This function works the same as [`upload_media`], but uses a separate set of `media_category`
-/// values to allow the resulting media to be attached to a Direct Message.
+/// Checks the given media before it is uploaded.
 ///
-/// Because of the private nature of DMs, a separate flag is used to allow for media to be attached
-/// to multiple messages. If the `shared` argument is set to `true`, then the resulting `MediaId`
-/// can be used in multiple messages, but the resulting URL for the upload can be accessed by
-/// anyone with the URL, rather then being private to the message. Twitter states that you must
-/// provide the user with clear notice that the media can be viewed by anyone with the URL, and get
-/// their express permission to set `shared`. Also note that even if you set `shared` to `true`,
-/// the resulting media can only be attached to messages from the same user. The default (and
-/// recommended) value for `shared` is `false`.
+/// This checks that the data isn't empty, that it starts with the file signature of the given
+/// media type, and that it fits within Twitter's size limit for the given category: 5MB for images,
+/// 15MB for GIFs, and 512MB for videos. Media types without a known signature are only checked for
+/// size.
 ///
-/// The upload proceeds in 1MB chunks until completed. After completion, be sure to check the
-/// status of the uploaded media with [`get_status`]. Twitter often needs time to post-process
-/// media before it can be attached to a message.
-pub async fn upload_media_for_dm(
+/// The upload functions in this module call this automatically when the `media_validation` feature
+/// is enabled, so Twitter's less descriptive errors for these problems can be avoided.
+#[cfg(feature = "media_validation")]
+pub fn validate(
     data: &[u8],
     media_type: &mime::Mime,
-    shared: bool,
-    token: &auth::Token,
-) -> error::Result<MediaHandle> {
-    let media_category = MediaCategory::from(media_type);
-    let params = ParamList::new()
-        .add_param("command", "INIT")
-        .add_param("total_bytes", data.len().to_string())
-        .add_param("media_type", media_type.to_string())
-        .add_param("media_category", media_category.dm_category())
-        .add_param("shared", shared.to_string());
-    let req = post(links::media::UPLOAD, token, Some(&params));
-
-    let media = request_with_json_response::<RawMedia>(req).await?.response;
-
-    finish_upload(media, data, token).await
-}
-
-async fn finish_upload(
-    media: RawMedia,
-    data: &[u8],
-    token: &auth::Token,
-) -> error::Result<MediaHandle> {
-    // divide into 1MB chunks
-    for (ix, chunk) in data.chunks(1024 * 1024).enumerate() {
-        let params = ParamList::new()
-            .add_param("command", "APPEND")
-            .add_param("media_id", media.id.clone())
-            .add_param("media_data", base64::encode(chunk))
-            .add_param("segment_index", ix.to_string());
-        let req = post(links::media::UPLOAD, token, Some(&params));
-        // This request has no response (upon success)
-        raw_request(req).await?;
+    category: MediaCategory,
+) -> Result<(), error::MediaValidationError> {
+    if data.is_empty() {
+        return Err(error::MediaValidationError::Empty);
     }
 
-    let params = ParamList::new()
-        .add_param("command", "FINALIZE")
-        .add_param("media_id", media.id.clone());
-    let req = post(links::media::UPLOAD, token, Some(&params));
-    Ok(request_with_json_response::<RawMedia>(req)
-        .await?
-        .response
-        .into())
-}
+    let known_types = [
+        media_types::image_png(),
+        media_types::image_jpg(),
+        media_types::image_gif(),
+        media_types::image_webp(),
+        media_types::video_mp4(),
+    ];
+    let matches_type = !known_types.contains(media_type)
+        || media_types::from_bytes(data).as_ref() == Some(media_type);
+
+    if !matches_type {
+        return Err(error::MediaValidationError::TypeMismatch {
+            declared: media_type.clone(),
+        });
+    }
 
-/// Check the status of uploaded media
-pub async fn get_status(media_id: MediaId, token: &auth::Token) -> error::Result<MediaHandle> {
-    let params = ParamList::new()
-        .add_param("command", "STATUS")
-        .add_param("media_id", media_id.0);
-    let req = get(links::media::UPLOAD, token, Some(&params));
-    Ok(request_with_json_response::<RawMedia>(req)
-        .await?
-        .response
-        .into())
-}
+    let limit = category.size_limit();
+    if data.len() > limit {
+        return Err(error::MediaValidationError::TooLarge {
+            size: data.len(),
+            limit,
+            category,
+        });
+    }
 
-/// Set metadata for a media upload. At the moment the only attribute that may
-/// be set is `alt_text`.
-pub async fn set_metadata(
-    media_id: &MediaId,
-    alt_text: &str,
-    token: &auth::Token,
-) -> error::Result<()> {
-    let payload = serde_json::json!({
-        "media_id": media_id.0,
-        "alt_text": {
-            "text": alt_text
-        }
-    });
-    let req = post_json(links::media::METADATA, token, payload);
-    raw_request(req).await?;
     Ok(())
 }
 
@@ -341,6 +394,66 @@ mod tests {
     use super::RawMedia;
     use crate::common::tests::load_file;
 
+    #[cfg(feature = "media_validation")]
+    #[test]
+    fn validate_media() {
+        use super::{media_types, validate, MediaCategory};
+        use crate::error::MediaValidationError;
+
+        let png = b"\x89PNG\r\n\x1a\n rest of the image";
+        assert_eq!(
+            validate(png, &media_types::image_png(), MediaCategory::Image),
+            Ok(())
+        );
+        assert_eq!(
+            validate(png, &media_types::image_jpg(), MediaCategory::Image),
+            Err(MediaValidationError::TypeMismatch {
+                declared: media_types::image_jpg()
+            })
+        );
+        assert_eq!(
+            validate(b"", &media_types::image_png(), MediaCategory::Image),
+            Err(MediaValidationError::Empty)
+        );
+
+        let mut gif = b"GIF89a".to_vec();
+        gif.resize(6 * 1024 * 1024, 0);
+        assert_eq!(
+            validate(&gif, &media_types::image_gif(), MediaCategory::Gif),
+            Ok(())
+        );
+        match validate(&gif, &media_types::image_gif(), MediaCategory::Image) {
+            Err(MediaValidationError::TooLarge { limit, .. }) => assert_eq!(limit, 5 * 1024 * 1024),
+            other => panic!("Unexpected validation result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn sniff_media_types() {
+        use super::media_types::{self, from_bytes, from_extension};
+
+        assert_eq!(from_bytes(b"GIF87a..."), Some(media_types::image_gif()));
+        assert_eq!(
+            from_bytes(&[0xff, 0xd8, 0xff, 0xe0]),
+            Some(media_types::image_jpg())
+        );
+        assert_eq!(
+            from_bytes(b"RIFF\0\0\0\0WEBPVP8 "),
+            Some(media_types::image_webp())
+        );
+        assert_eq!(
+            from_bytes(b"\0\0\0\x18ftypmp42"),
+            Some(media_types::video_mp4())
+        );
+        assert_eq!(from_bytes(b"RIFF\0\0\0\0WAVE"), None);
+        assert_eq!(from_bytes(b""), None);
+
+        assert_eq!(from_extension(".webp"), Some(media_types::image_webp()));
+        assert_eq!(from_extension("clip.MP4"), Some(media_types::video_mp4()));
+        assert_eq!(from_extension("gif.bmp"), None);
+        assert_eq!(from_extension(""), None);
+    }
+
     fn load_media(path: &str) -> RawMedia {
         let content = load_file(path);
         ::serde_json::from_str::<RawMedia>(&content).unwrap()
@@ -364,7 +477,7 @@ mod tests {
 
         match media.progress {
             Some(super::ProgressInfo::Pending(5)) => (),
-            other => assert!(false, "Unexpected value of progress={:?}", other),
+            other => panic!("Unexpected value of progress={:?}", other),
         }
     }
 
@@ -378,7 +491,7 @@ mod tests {
 
         match media.progress {
             Some(super::ProgressInfo::InProgress(10)) => (),
-            other => assert!(false, "Unexpected value of progress={:?}", other),
+            other => panic!("Unexpected value of progress={:?}", other),
         }
     }
 
@@ -399,7 +512,7 @@ mod tests {
                     message: "Unsupported video format".to_string(),
                 }
             ),
-            other => assert!(false, "Unexpected value of progress={:?}", other),
+            other => panic!("Unexpected value of progress={:?}", other),
         }
     }
 }
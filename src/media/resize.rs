@@ -0,0 +1,176 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Shrinking of still images that are too large for Twitter, for `upload_media_auto`.
+
+use std::borrow::Cow;
+use std::io::Cursor;
+
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageDecoder, ImageReader};
+
+use crate::{auth, error, media};
+
+/// The largest still image Twitter accepts, in bytes.
+const MAX_IMAGE_SIZE: usize = 5 * 1024 * 1024;
+
+/// The JPEG quality that shrunk photos are encoded with.
+const JPEG_QUALITY: u8 = 85;
+
+/// The most times an image is scaled down before giving up.
+const MAX_ATTEMPTS: usize = 8;
+
+/// Upload media to the server, shrinking still images that are larger than Twitter allows.
+///
+/// PNG, JPG, and WEBP images larger than Twitter's 5MB limit are decoded, turned upright according
+/// to their EXIF orientation, and encoded again, scaled down as much as needed to fit while keeping
+/// their aspect ratio. Images with transparency are encoded as PNGs, and all others as JPGs. Since
+/// the EXIF data isn't kept, the orientation is applied to the pixels themselves, so the image
+/// still shows the right way up.
+///
+/// Images that already fit, and other media like GIFs and videos, are uploaded unchanged, the same
+/// way as `upload_media`.
+///
+/// ```rust,no_run
+/// # use egg_mode::Token;
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let token: Token = unimplemented!();
+/// use egg_mode::media::{media_types, upload_media_auto};
+///
+/// let photo = std::fs::read("camera-roll/IMG_0001.jpg")?;
+/// let handle = upload_media_auto(&photo, &media_types::image_jpg(), &token).await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// If the image can't be decoded, this returns `Error::ImageError` without uploading anything.
+///
+/// This is only available with the `image` feature enabled.
+pub async fn upload_media_auto(
+    data: &[u8],
+    media_type: &mime::Mime,
+    token: &auth::Token,
+) -> error::Result<media::MediaHandle> {
+    let (data, media_type) = fit_image(data, media_type, MAX_IMAGE_SIZE)?;
+    media::upload(&data, &media_type).send(token).await
+}
+
+/// Returns the given image re-encoded to fit in `limit` bytes, along with its new media type, or
+/// the given data unchanged if it already fits or isn't a still image.
+fn fit_image<'a>(
+    data: &'a [u8],
+    media_type: &mime::Mime,
+    limit: usize,
+) -> error::Result<(Cow<'a, [u8]>, mime::Mime)> {
+    let still = [
+        media::media_types::image_png(),
+        media::media_types::image_jpg(),
+        media::media_types::image_webp(),
+    ];
+    if data.len() <= limit || !still.contains(media_type) {
+        return Ok((Cow::Borrowed(data), media_type.clone()));
+    }
+
+    let mut decoder = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()?
+        .into_decoder()?;
+    let orientation = decoder.orientation()?;
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+
+    let transparent = image.color().has_alpha();
+    let mut scaled = image.clone();
+    for _ in 0..MAX_ATTEMPTS {
+        let encoded = encode(&scaled, transparent)?;
+        if encoded.len() <= limit {
+            let media_type = if transparent {
+                media::media_types::image_png()
+            } else {
+                media::media_types::image_jpg()
+            };
+            return Ok((Cow::Owned(encoded), media_type));
+        }
+
+        // file size grows with the number of pixels, so scale both sides by the square root of how
+        // far over the limit the image is, with some room to spare
+        let scale = (limit as f64 / encoded.len() as f64).sqrt() * 0.9;
+        let width = ((f64::from(scaled.width()) * scale) as u32).max(1);
+        let height = ((f64::from(scaled.height()) * scale) as u32).max(1);
+        scaled = image.resize(width, height, FilterType::Lanczos3);
+    }
+
+    Err(error::Error::ImageError(image::ImageError::Limits(
+        image::error::LimitError::from_kind(image::error::LimitErrorKind::DimensionError),
+    )))
+}
+
+/// Encodes the given image as a PNG if it has transparency, or as a JPG otherwise.
+fn encode(image: &DynamicImage, transparent: bool) -> image::ImageResult<Vec<u8>> {
+    let mut out = Vec::new();
+    if transparent {
+        DynamicImage::ImageRgba8(image.to_rgba8()).write_with_encoder(PngEncoder::new(&mut out))?;
+    } else {
+        DynamicImage::ImageRgb8(image.to_rgb8())
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut out, JPEG_QUALITY))?;
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageEncoder, Rgb, RgbImage};
+
+    /// An EXIF chunk that only holds an orientation of 6, which means the image is to be rotated
+    /// 90 degrees clockwise to show it upright.
+    const ROTATE_90: &[u8] = &[
+        0x49, 0x49, 0x2a, 0x00, 0x08, 0x00, 0x00, 0x00, 0x01, 0x00, 0x12, 0x01, 0x03, 0x00, 0x01,
+        0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    /// Makes a noisy photo, which doesn't compress well.
+    fn noise(width: u32, height: u32) -> RgbImage {
+        let mut state = 12345u32;
+        RgbImage::from_fn(width, height, |_, _| {
+            state = state.wrapping_mul(1103515245).wrapping_add(12345);
+            let [r, g, b, _] = state.to_le_bytes();
+            Rgb([r, g, b])
+        })
+    }
+
+    #[test]
+    fn shrink_images() {
+        let mut jpg = Vec::new();
+        let mut encoder = JpegEncoder::new_with_quality(&mut jpg, 100);
+        encoder.set_exif_metadata(ROTATE_90.to_vec()).unwrap();
+        let photo = noise(300, 200);
+        encoder
+            .write_image(&photo, 300, 200, image::ExtendedColorType::Rgb8)
+            .unwrap();
+
+        let jpg_type = media::media_types::image_jpg();
+        let (fit, media_type) = fit_image(&jpg, &jpg_type, jpg.len()).unwrap();
+        assert!(matches!(fit, Cow::Borrowed(_)));
+        assert_eq!(media_type, jpg_type);
+
+        let limit = jpg.len() / 4;
+        let (fit, media_type) = fit_image(&jpg, &jpg_type, limit).unwrap();
+        assert!(fit.len() <= limit);
+        assert_eq!(media_type, jpg_type);
+        let shrunk = image::load_from_memory(&fit).unwrap();
+        // the orientation was applied, so the image is now taller than it is wide, with the same
+        // aspect ratio as before
+        assert!(shrunk.height() > shrunk.width());
+        let ratio = f64::from(shrunk.height()) / f64::from(shrunk.width());
+        assert!((ratio - 1.5).abs() < 0.05);
+
+        // other media is never touched
+        let gif_type = media::media_types::image_gif();
+        let (fit, _) = fit_image(&jpg, &gif_type, limit).unwrap();
+        assert_eq!(fit.len(), jpg.len());
+    }
+}
@@ -0,0 +1,501 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A notification feed for the authenticated user, emulated by polling.
+//!
+//! Twitter's user streams used to deliver mentions, retweets, likes, and follows as they happened.
+//! Without them, clients have to poll the REST API and work out for themselves what changed since
+//! the last poll. The [`NotificationFeed`] in this module does that work: it polls each source on
+//! its own schedule, compares the results to what it saw last time, and hands back one stream of
+//! typed [`Notification`]s.
+//!
+//! [`NotificationFeed`]: struct.NotificationFeed.html
+//! [`Notification`]: enum.Notification.html
+//!
+//! The sources that can be polled are:
+//!
+//! - `Source::Mentions`: new tweets in `tweet::mentions_timeline`. Polled every minute by default.
+//!   If more than 200 mentions arrived since the last poll, older pages are loaded until reaching
+//!   it, each counting as a call.
+//! - `Source::Retweets`: increases in the retweet counts of the tweets in `tweet::retweets_of_me`.
+//!   Polled every five minutes by default.
+//! - `Source::Likes`: increases in the like counts of the user's own recent tweets, loaded with
+//!   `tweet::user_timeline`. Not polled by default.
+//! - `Source::Followers`: accounts that started or stopped following the user, found by comparing
+//!   `user::followers_ids`. Not polled by default, since loading every follower ID can take many
+//!   calls for popular accounts.
+//!
+//! The first poll of each source only records a baseline, so that existing followers and old
+//! retweets aren't reported as new. The state each source compares against is kept in
+//! [`NotificationCursors`], which can be saved and given to a new feed to pick up where an
+//! earlier one left off.
+//!
+//! [`NotificationCursors`]: struct.NotificationCursors.html
+//!
+//! ```rust,no_run
+//! # use egg_mode::Token;
+//! # #[tokio::main]
+//! # async fn main() {
+//! # let token: Token = unimplemented!();
+//! use egg_mode::notifications::{self, Notification, Source};
+//! use std::time::Duration;
+//!
+//! let mut feed = notifications::feed(&token)
+//!     .poll_every(Source::Followers, Some(Duration::from_secs(15 * 60)));
+//!
+//! while let Some(notification) = feed.next().await {
+//!     match notification {
+//!         Ok(Notification::Mention(tweet)) => println!("mentioned: {}", tweet.text),
+//!         Ok(Notification::NewFollower(id)) => println!("new follower: {}", id),
+//!         Ok(other) => println!("{:?}", other),
+//!         Err(e) => println!("error polling notifications: {}", e),
+//!     }
+//! }
+//! # }
+//! ```
+//!
+//! ## Why this doesn't use the `events` types
+//!
+//! The [`events`] module describes the same kinds of activity, as delivered by the Account
+//! Activity API, and is what a webhook should use. Those types describe a single event in full: a
+//! `FavoriteEvent` carries the user who liked the tweet and when, and a `FollowEvent` carries both
+//! users' profiles. Polling can't see any of that. The REST API only says that a tweet's like or
+//! retweet count went up, not who caused it or when, and `followers_ids` only gives the IDs of new
+//! followers. Building `events` types from a poll would mean making up the fields that polling
+//! can't see. Instead, a `Notification` only holds what the poll actually found, like the number of
+//! new likes since the last poll. Mentions have no `events` type at all, since the Account
+//! Activity API delivers them as plain tweets.
+//!
+//! [`events`]: ../events/index.html
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Duration;
+
+use futures::stream::{self, Stream, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+
+use crate::tweet::{self, Tweet};
+use crate::{auth, error, user};
+
+/// Creates a new `NotificationFeed` for the authenticated user.
+///
+/// The feed polls mentions and retweets with their default schedules; use
+/// `NotificationFeed::poll_every` to change which sources are polled and how often.
+pub fn feed(token: &auth::Token) -> NotificationFeed {
+    let mut intervals = HashMap::new();
+    intervals.insert(Source::Mentions, Duration::from_secs(60));
+    intervals.insert(Source::Retweets, Duration::from_secs(5 * 60));
+
+    NotificationFeed {
+        token: token.clone(),
+        user_id: None,
+        intervals,
+        next_poll: HashMap::new(),
+        cursors: NotificationCursors::default(),
+        queue: VecDeque::new(),
+    }
+}
+
+/// A source of notifications polled by a `NotificationFeed`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Source {
+    /// Tweets mentioning the user.
+    Mentions,
+    /// Retweets of the user's tweets.
+    Retweets,
+    /// Likes of the user's tweets.
+    Likes,
+    /// Accounts following or unfollowing the user.
+    Followers,
+}
+
+/// A single event in a `NotificationFeed`.
+#[derive(Debug, Clone)]
+pub enum Notification {
+    /// Someone mentioned the user in the enclosed tweet.
+    Mention(Tweet),
+    /// One of the user's tweets was retweeted.
+    Retweeted {
+        /// The user's tweet, with its current retweet count.
+        tweet: Tweet,
+        /// How many retweets it gained since the last poll.
+        new_retweets: i32,
+    },
+    /// One of the user's tweets was liked.
+    Liked {
+        /// The user's tweet, with its current like count.
+        tweet: Tweet,
+        /// How many likes it gained since the last poll.
+        new_likes: i32,
+    },
+    /// The account with the enclosed ID started following the user.
+    NewFollower(u64),
+    /// The account with the enclosed ID stopped following the user, or was deleted or suspended.
+    Unfollowed(u64),
+}
+
+/// The state a `NotificationFeed` compares each poll against.
+///
+/// Each field is `None` until its source has been polled once. Save this with `serde` and give it
+/// to `NotificationFeed::with_cursors` to resume a feed without losing track of what was already
+/// reported.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct NotificationCursors {
+    /// The ID of the newest mention seen so far, or `Some(0)` if mentions have been polled but
+    /// none were found yet.
+    pub mentions_since: Option<u64>,
+    /// The last retweet count seen for each tweet in `retweets_of_me`. Tweets that drop out of a
+    /// poll are kept, so they aren't reported again if they come back.
+    pub retweet_counts: Option<HashMap<u64, i32>>,
+    /// The last like count seen for each of the user's tweets in their timeline. Tweets that drop
+    /// out of a poll are kept, so they aren't reported again if they come back.
+    pub like_counts: Option<HashMap<u64, i32>>,
+    /// The IDs of the user's followers in the last poll.
+    pub followers: Option<HashSet<u64>>,
+}
+
+/// A polled feed of notifications for the authenticated user.
+///
+/// See the [module documentation](index.html) for details.
+#[must_use = "NotificationFeed does nothing unless `next` is called or it's turned into a stream"]
+pub struct NotificationFeed {
+    token: auth::Token,
+    user_id: Option<u64>,
+    intervals: HashMap<Source, Duration>,
+    next_poll: HashMap<Source, Instant>,
+    cursors: NotificationCursors,
+    queue: VecDeque<Notification>,
+}
+
+impl NotificationFeed {
+    /// Sets how often to poll the given source. `None` stops polling it.
+    ///
+    /// Keep each endpoint's rate limit in mind: `mentions_timeline` and `retweets_of_me` allow 75
+    /// calls per 15 minutes, `user_timeline` 900, and `followers_ids` 15, with each call loading
+    /// up to 5000 followers.
+    pub fn poll_every(mut self, source: Source, interval: Option<Duration>) -> Self {
+        match interval {
+            Some(interval) => self.intervals.insert(source, interval),
+            None => self.intervals.remove(&source),
+        };
+        self
+    }
+
+    /// Sets the ID of the authenticated user.
+    ///
+    /// This is needed to poll `Source::Likes` and `Source::Followers`. If it isn't set, the feed
+    /// calls `auth::verify_tokens` to load it before polling either source.
+    pub fn user_id(self, user_id: u64) -> Self {
+        NotificationFeed {
+            user_id: Some(user_id),
+            ..self
+        }
+    }
+
+    /// Resumes the feed from the given cursors, saved from an earlier feed.
+    pub fn with_cursors(self, cursors: NotificationCursors) -> Self {
+        NotificationFeed { cursors, ..self }
+    }
+
+    /// Returns the state this feed compares new polls against.
+    pub fn cursors(&self) -> &NotificationCursors {
+        &self.cursors
+    }
+
+    /// Waits for the next notification, polling sources as they come due.
+    ///
+    /// Returns `None` if no sources are being polled. If a poll fails, its error is returned and
+    /// that source is polled again after its usual interval, or once the rate limit resets if the
+    /// error was `Error::RateLimit`; the feed can keep being used afterward.
+    pub async fn next(&mut self) -> Option<error::Result<Notification>> {
+        loop {
+            if let Some(notification) = self.queue.pop_front() {
+                return Some(Ok(notification));
+            }
+
+            let now = Instant::now();
+            let (source, due) = self
+                .intervals
+                .keys()
+                .map(|source| (*source, self.next_poll.get(source).copied().unwrap_or(now)))
+                .min_by_key(|(_, due)| *due)?;
+
+            tokio::time::sleep_until(due).await;
+
+            let result = self.poll(source).await;
+            let next_poll = match result {
+                Err(error::Error::RateLimit(reset)) => {
+                    let wait = i64::from(reset) - chrono::Utc::now().timestamp();
+                    Instant::now() + Duration::from_secs(wait.max(0) as u64 + 1)
+                }
+                _ => Instant::now() + self.intervals[&source],
+            };
+            self.next_poll.insert(source, next_poll);
+
+            if let Err(e) = result {
+                return Some(Err(e));
+            }
+        }
+    }
+
+    /// Converts this feed into a `Stream` of notifications.
+    ///
+    /// Like `next`, errors from a poll don't end the stream.
+    pub fn into_stream(self) -> impl Stream<Item = error::Result<Notification>> {
+        stream::unfold(self, |mut feed| async move {
+            let notification = feed.next().await?;
+            Some((notification, feed))
+        })
+    }
+
+    async fn poll(&mut self, source: Source) -> error::Result<()> {
+        match source {
+            Source::Mentions => {
+                let timeline = tweet::mentions_timeline(&self.token).with_page_size(200);
+                let since_id = self.cursors.mentions_since;
+                let after = since_id.filter(|&id| id > 0);
+
+                // page back until reaching the last poll, so busy stretches don't lose mentions;
+                // the baseline only needs the newest one
+                let mut tweets = Vec::new();
+                let mut max_id = None;
+                loop {
+                    let page = timeline.call(after, max_id).await?.response;
+                    max_id = older_page(&page, after);
+                    tweets.extend(page);
+                    if since_id.is_none() || max_id.is_none() {
+                        break;
+                    }
+                }
+
+                let (newest, mentions) = diff_mentions(since_id, tweets);
+                self.cursors.mentions_since = Some(newest);
+                self.queue
+                    .extend(mentions.into_iter().map(Notification::Mention));
+            }
+            Source::Retweets => {
+                let timeline = tweet::retweets_of_me(&self.token).with_page_size(100);
+                let tweets = timeline.call(None, None).await?.response;
+
+                let (counts, changes) =
+                    diff_counts(self.cursors.retweet_counts.as_ref(), tweets, |t| {
+                        t.retweet_count
+                    });
+                self.cursors.retweet_counts = Some(counts);
+                self.queue
+                    .extend(changes.into_iter().map(|(tweet, new_retweets)| {
+                        Notification::Retweeted {
+                            tweet,
+                            new_retweets,
+                        }
+                    }));
+            }
+            Source::Likes => {
+                let user_id = self.load_user_id().await?;
+                let timeline =
+                    tweet::user_timeline(user_id, true, false, &self.token).with_page_size(200);
+                let tweets = timeline.call(None, None).await?.response;
+
+                let (counts, changes) =
+                    diff_counts(self.cursors.like_counts.as_ref(), tweets, |t| {
+                        t.favorite_count
+                    });
+                self.cursors.like_counts = Some(counts);
+                self.queue.extend(
+                    changes
+                        .into_iter()
+                        .map(|(tweet, new_likes)| Notification::Liked { tweet, new_likes }),
+                );
+            }
+            Source::Followers => {
+                let user_id = self.load_user_id().await?;
+                let followers = user::followers_ids(user_id, &self.token)
+                    .with_page_size(5000)
+                    .map_ok(|id| id.response)
+                    .try_collect::<HashSet<u64>>()
+                    .await?;
+
+                if let Some(previous) = &self.cursors.followers {
+                    self.queue.extend(
+                        followers
+                            .difference(previous)
+                            .map(|id| Notification::NewFollower(*id)),
+                    );
+                    self.queue.extend(
+                        previous
+                            .difference(&followers)
+                            .map(|id| Notification::Unfollowed(*id)),
+                    );
+                }
+                self.cursors.followers = Some(followers);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn load_user_id(&mut self) -> error::Result<u64> {
+        if let Some(id) = self.user_id {
+            return Ok(id);
+        }
+
+        let id = auth::verify_tokens(&self.token).await?.id;
+        self.user_id = Some(id);
+        Ok(id)
+    }
+}
+
+/// Returns the ID of the newest mention seen so far and the mentions to report, oldest first,
+/// given the cursor from the last poll and the mentions loaded since then.
+///
+/// If there's no cursor yet, this only sets the baseline. If there are no mentions at all, the
+/// returned ID is zero, so that the next poll isn't also treated as the baseline.
+fn diff_mentions(since_id: Option<u64>, tweets: Vec<Tweet>) -> (u64, Vec<Tweet>) {
+    let newest = tweets
+        .iter()
+        .map(|t| t.id)
+        .chain(since_id)
+        .max()
+        .unwrap_or(0);
+    let mentions = match since_id {
+        Some(_) => {
+            let mut seen = HashSet::new();
+            tweets
+                .into_iter()
+                .rev()
+                .filter(|t| seen.insert(t.id))
+                .collect()
+        }
+        None => Vec::new(),
+    };
+
+    (newest, mentions)
+}
+
+/// Returns the `max_id` to load the page of mentions after the given one with, or `None` if the
+/// page is empty or already reached `since_id`.
+fn older_page(page: &[Tweet], since_id: Option<u64>) -> Option<u64> {
+    let max_id = page.iter().map(|t| t.id).min()? - 1;
+    match since_id {
+        Some(since) if max_id <= since => None,
+        _ => Some(max_id),
+    }
+}
+
+/// Compares the counts in the given tweets to the previous counts, returning the updated counts and
+/// the tweets whose count went up, along with how much it went up by.
+///
+/// If there are no previous counts, this only returns the new counts. Otherwise the new counts are
+/// merged into the previous ones, so a tweet that drops out of one poll and comes back in a later
+/// one is compared against the last count seen for it. Tweets that were never seen before are
+/// compared against zero.
+fn diff_counts(
+    previous: Option<&HashMap<u64, i32>>,
+    tweets: Vec<Tweet>,
+    count: impl Fn(&Tweet) -> i32,
+) -> (HashMap<u64, i32>, Vec<(Tweet, i32)>) {
+    let mut counts = previous.cloned().unwrap_or_default();
+    counts.extend(tweets.iter().map(|t| (t.id, count(t))));
+    let changes = match previous {
+        Some(previous) => tweets
+            .into_iter()
+            .filter_map(|t| {
+                let change = count(&t) - previous.get(&t.id).copied().unwrap_or(0);
+                if change > 0 {
+                    Some((t, change))
+                } else {
+                    None
+                }
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+
+    (counts, changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::tests::load_file;
+
+    #[test]
+    fn mention_baseline() {
+        let sample = load_file("sample_payloads/tweet_array.json");
+        let tweets: Vec<Tweet> = serde_json::from_str(&sample).unwrap();
+        let newest = tweets.iter().map(|t| t.id).max().unwrap();
+
+        // a first poll with mentions only records the newest one
+        let (since, mentions) = diff_mentions(None, tweets.clone());
+        assert_eq!(since, newest);
+        assert!(mentions.is_empty());
+
+        // a first poll without mentions still records that the baseline was taken...
+        let (since, mentions) = diff_mentions(None, Vec::new());
+        assert_eq!(since, 0);
+        assert!(mentions.is_empty());
+
+        // ...so the mentions that show up afterward are reported, oldest first
+        let (since, mentions) = diff_mentions(Some(since), tweets.clone());
+        assert_eq!(since, newest);
+        assert_eq!(mentions.len(), tweets.len());
+        assert_eq!(mentions.first().unwrap().id, tweets.last().unwrap().id);
+
+        let (since, mentions) = diff_mentions(Some(newest), Vec::new());
+        assert_eq!(since, newest);
+        assert!(mentions.is_empty());
+    }
+
+    #[test]
+    fn page_back_mentions() {
+        let sample = load_file("sample_payloads/tweet_array.json");
+        let tweets: Vec<Tweet> = serde_json::from_str(&sample).unwrap();
+        let oldest = tweets.iter().map(|t| t.id).min().unwrap();
+
+        assert_eq!(older_page(&tweets, None), Some(oldest - 1));
+        assert_eq!(older_page(&tweets, Some(oldest - 10)), Some(oldest - 1));
+        // the page reaches the last poll, so there's nothing older to load
+        assert_eq!(older_page(&tweets, Some(oldest - 1)), None);
+        assert_eq!(older_page(&[], Some(20)), None);
+    }
+
+    #[test]
+    fn count_changes() {
+        let sample = load_file("sample_payloads/tweet_array.json");
+        let tweets: Vec<Tweet> = serde_json::from_str(&sample).unwrap();
+        let retweets = |t: &Tweet| t.retweet_count;
+
+        let (baseline, changes) = diff_counts(None, tweets.clone(), retweets);
+        assert_eq!(baseline.len(), tweets.len());
+        assert!(changes.is_empty());
+
+        let mut previous = baseline.clone();
+        let bumped = tweets.iter().find(|t| t.retweet_count > 0).unwrap();
+        *previous.get_mut(&bumped.id).unwrap() -= 1;
+        previous.remove(&tweets.last().unwrap().id);
+
+        let (counts, changes) = diff_counts(Some(&previous), tweets.clone(), retweets);
+        assert_eq!(counts, baseline);
+        assert!(changes
+            .iter()
+            .any(|(t, change)| t.id == bumped.id && *change == 1));
+        for (tweet, change) in &changes {
+            assert!(*change > 0);
+            assert!(tweet.id == bumped.id || !previous.contains_key(&tweet.id));
+        }
+
+        // a tweet that drops out of a poll keeps its count...
+        let bumped_id = bumped.id;
+        let (returning, rest): (Vec<_>, Vec<_>) =
+            tweets.into_iter().partition(|t| t.id == bumped_id);
+        let (counts, changes) = diff_counts(Some(&baseline), rest, retweets);
+        assert_eq!(counts, baseline);
+        assert!(changes.is_empty());
+
+        // ...so it isn't reported again when it comes back
+        let (_, changes) = diff_counts(Some(&counts), returning, retweets);
+        assert!(changes.is_empty());
+    }
+}
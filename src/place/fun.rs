@@ -4,6 +4,7 @@
 
 use crate::common::*;
 use crate::error::{Error, Result};
+use crate::trend::{self, TrendLocation, Trends};
 use crate::{auth, links};
 
 use super::PlaceQuery;
@@ -149,3 +150,163 @@ pub async fn search_url(url: &str, token: &auth::Token) -> Result<Response<Searc
     let req = get(links::place::REVERSE_GEOCODE, token, Some(&params));
     request_with_json_response(req).await
 }
+
+///Loads the trending topics for the given place.
+///
+///Twitter only has trending topics for some cities and countries, identified by "where on earth
+///IDs" (WOEIDs) instead of place IDs, so this looks through `trend::available` for the location
+///that best matches the place:
+///
+///1. A city with trends whose name and country match the place, or one of the places in its
+///   `contained_within`.
+///2. The country of the place, if it has trends.
+///3. The location with trends closest to the center of the place's bounding box, as found by
+///   `trend::closest`.
+///
+///If none of these work, the worldwide trends are loaded. The `locations` of the returned `Trends`
+///show which location was used.
+///
+///The `Response` returned by this function carries the rate-limit information from the call to
+///`trend::place`.
+///
+///```rust,no_run
+///# use egg_mode::Token;
+///# #[tokio::main]
+///# async fn main() {
+///# let token: Token = unimplemented!();
+///let place = egg_mode::place::show("18810aa5b43e76c7", &token).await.unwrap();
+///let trends = egg_mode::place::trends(&place, &token).await.unwrap();
+///
+///println!("trending in {}:", trends.locations[0].name);
+///for trend in &trends.trends {
+///    println!("{}", trend.name);
+///}
+///# }
+///```
+pub async fn trends(place: &Place, token: &auth::Token) -> Result<Response<Trends>> {
+    let available = trend::available(token).await?;
+    let woeid = match trend_woeid(place, &available) {
+        Some(woeid) => woeid,
+        None => match bounding_box_center(&place.bounding_box) {
+            Some((lat, long)) => trend::closest(lat as f32, long as f32, token)
+                .await?
+                .first()
+                .map_or(trend::woeid::WORLDWIDE, |location| location.woeid),
+            None => trend::woeid::WORLDWIDE,
+        },
+    };
+
+    trend::place(woeid, false, token).await
+}
+
+///The `PlaceType` code Twitter uses for cities in trend locations.
+const TREND_TOWN: u32 = 7;
+///The `PlaceType` code Twitter uses for countries in trend locations.
+const TREND_COUNTRY: u32 = 12;
+
+///Finds the WOEID in the given trend locations that best matches the given place, by name and
+///country code.
+fn trend_woeid(place: &Place, locations: &[TrendLocation]) -> Option<u32> {
+    let same_country = |location: &&TrendLocation| matches!(location.country_code, Some(ref code) if code.eq_ignore_ascii_case(&place.country_code));
+
+    if place.place_type != PlaceType::Country {
+        let names = std::iter::once(place)
+            .chain(place.contained_within.iter().flatten())
+            .filter(|place| place.place_type.rank() <= PlaceType::City.rank())
+            .map(|place| place.name.to_lowercase())
+            .collect::<Vec<_>>();
+        let town = locations
+            .iter()
+            .filter(|location| location.place_type.code == TREND_TOWN)
+            .filter(same_country)
+            .find(|location| names.contains(&location.name.to_lowercase()));
+        if let Some(town) = town {
+            return Some(town.woeid);
+        }
+    }
+
+    locations
+        .iter()
+        .filter(|location| location.place_type.code == TREND_COUNTRY)
+        .find(same_country)
+        .map(|location| location.woeid)
+}
+
+///Returns the (latitude, longitude) center of the given bounding box, whose points are
+///(longitude, latitude) pairs.
+fn bounding_box_center(bounding_box: &[(f64, f64)]) -> Option<(f64, f64)> {
+    if bounding_box.is_empty() {
+        return None;
+    }
+
+    let count = bounding_box.len() as f64;
+    let long = bounding_box.iter().map(|point| point.0).sum::<f64>() / count;
+    let lat = bounding_box.iter().map(|point| point.1).sum::<f64>() / count;
+    Some((lat, long))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn location(name: &str, country_code: Option<&str>, code: u32, woeid: u32) -> TrendLocation {
+        serde_json::from_value(serde_json::json!({
+            "country": "",
+            "countryCode": country_code,
+            "name": name,
+            "parentid": 1,
+            "placeType": { "code": code, "name": "" },
+            "url": "",
+            "woeid": woeid,
+        }))
+        .unwrap()
+    }
+
+    fn place(name: &str, place_type: &str, country_code: &str) -> Place {
+        serde_json::from_value(serde_json::json!({
+            "id": "",
+            "attributes": {},
+            "bounding_box": {
+                "type": "Polygon",
+                "coordinates": [[[-97.0, 32.0], [-96.0, 32.0], [-96.0, 33.0], [-97.0, 33.0]]],
+            },
+            "country": "",
+            "country_code": country_code,
+            "full_name": name,
+            "name": name,
+            "place_type": place_type,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn match_trend_location() {
+        let locations = vec![
+            location("Worldwide", None, 19, 1),
+            location("United States", Some("US"), TREND_COUNTRY, 23424977),
+            location("Dallas-Ft. Worth", Some("US"), TREND_TOWN, 2388929),
+            location("London", Some("GB"), TREND_TOWN, 44418),
+            location("Canada", Some("CA"), TREND_COUNTRY, 23424775),
+        ];
+
+        let london = place("London", "city", "GB");
+        assert_eq!(trend_woeid(&london, &locations), Some(44418));
+
+        let mut neighborhood = place("Soho", "neighborhood", "GB");
+        neighborhood.contained_within = Some(vec![london]);
+        assert_eq!(trend_woeid(&neighborhood, &locations), Some(44418));
+
+        let dallas = place("Dallas", "city", "US");
+        assert_eq!(trend_woeid(&dallas, &locations), Some(23424977));
+
+        let london_ontario = place("London", "city", "CA");
+        assert_eq!(trend_woeid(&london_ontario, &locations), Some(23424775));
+
+        let paris = place("Paris", "city", "FR");
+        assert_eq!(trend_woeid(&paris, &locations), None);
+        assert_eq!(
+            bounding_box_center(&paris.bounding_box),
+            Some((32.5, -96.5))
+        );
+    }
+}
@@ -25,6 +25,9 @@
 //! Along with the list of place results, Twitter also returns the full search URL. egg-mode
 //! returns this URL as part of the result struct, allowing you to perform the same search using
 //! the `reverse_geocode_url` or `search_url` functions.
+//!
+//! To see what's trending around a place, call `trends`, which finds the closest location that
+//! Twitter has trending topics for and loads them from the `trend` module.
 
 use std::collections::HashMap;
 use std::fmt;
@@ -33,11 +36,15 @@ use serde::de::Error;
 use serde::{Deserialize, Deserializer, Serialize};
 use serde_json;
 
+#[cfg(feature = "places")]
 use crate::common::*;
+#[cfg(feature = "places")]
 use crate::{auth, error, links};
 
+#[cfg(feature = "places")]
 mod fun;
 
+#[cfg(feature = "places")]
 pub use self::fun::*;
 
 // https://developer.twitter.com/en/docs/tweets/data-dictionary/overview/geo-objects#place
@@ -68,8 +75,109 @@ pub struct Place {
     pub contained_within: Option<Vec<Place>>,
 }
 
+impl Place {
+    ///Returns the smallest `BoundingBox` that contains this place's `bounding_box`, or `None` if
+    ///the place doesn't have one.
+    ///
+    ///This can be given to `StreamBuilder::locations` to stream the tweets posted around this
+    ///place.
+    pub fn bounds(&self) -> Option<BoundingBox> {
+        BoundingBox::around(&self.bounding_box)
+    }
+}
+
+///Represents a rectangle of (longitude, latitude) coordinates, from its southwest corner to its
+///northeast corner.
+///
+///This is used to filter streams with `StreamBuilder::locations`, and can be made from the
+///`bounding_box` of a `Place` with `Place::bounds`.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct BoundingBox {
+    southwest: (f64, f64),
+    northeast: (f64, f64),
+}
+
+impl fmt::Display for BoundingBox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{},{},{},{}",
+            self.southwest.0, self.southwest.1, self.northeast.0, self.northeast.1
+        )
+    }
+}
+
+impl BoundingBox {
+    ///Creates a new `BoundingBox` from the (longitude, latitude) pairs of its southwest and
+    ///northeast corners.
+    ///
+    ///The corners aren't checked here; `is_valid` tells whether they make a box Twitter accepts,
+    ///and `StreamBuilder::start` returns an error for boxes that don't.
+    pub fn new(southwest: (f64, f64), northeast: (f64, f64)) -> BoundingBox {
+        BoundingBox {
+            southwest,
+            northeast,
+        }
+    }
+
+    ///Returns the smallest `BoundingBox` that contains all of the given (longitude, latitude)
+    ///points, like the polygon in `Place::bounding_box`. Returns `None` if no points are given.
+    pub fn around(points: &[(f64, f64)]) -> Option<BoundingBox> {
+        let (&first, rest) = points.split_first()?;
+        Some(
+            rest.iter()
+                .fold(BoundingBox::new(first, first), |bbox, &(long, lat)| {
+                    BoundingBox {
+                        southwest: (bbox.southwest.0.min(long), bbox.southwest.1.min(lat)),
+                        northeast: (bbox.northeast.0.max(long), bbox.northeast.1.max(lat)),
+                    }
+                }),
+        )
+    }
+
+    ///Returns the southwest corner of this box, as a (longitude, latitude) pair.
+    pub fn southwest(&self) -> (f64, f64) {
+        self.southwest
+    }
+
+    ///Returns the northeast corner of this box, as a (longitude, latitude) pair.
+    pub fn northeast(&self) -> (f64, f64) {
+        self.northeast
+    }
+
+    ///Returns whether this is a box Twitter accepts: both corners are valid coordinates, and the
+    ///southwest corner is south and west of the northeast one.
+    pub fn is_valid(&self) -> bool {
+        let in_range = |(long, lat): (f64, f64)| {
+            (-180.0..=180.0).contains(&long) && (-90.0..=90.0).contains(&lat)
+        };
+
+        in_range(self.southwest)
+            && in_range(self.northeast)
+            && self.southwest.0 <= self.northeast.0
+            && self.southwest.1 <= self.northeast.1
+    }
+
+    ///Returns whether the given point, as a (longitude, latitude) pair, falls within this
+    ///bounding box.
+    pub fn contains(&self, long: f64, lat: f64) -> bool {
+        self.southwest.0 <= long
+            && long <= self.northeast.0
+            && self.southwest.1 <= lat
+            && lat <= self.northeast.1
+    }
+
+    ///Returns whether this box and the given one overlap, including if they only share an edge.
+    pub fn intersects(&self, other: &BoundingBox) -> bool {
+        self.southwest.0 <= other.northeast.0
+            && other.southwest.0 <= self.northeast.0
+            && self.southwest.1 <= other.northeast.1
+            && other.southwest.1 <= self.northeast.1
+    }
+}
+
 ///Represents the type of region represented by a given place.
-#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum PlaceType {
     ///A coordinate with no area.
     #[serde(rename = "poi")]
@@ -88,6 +196,20 @@ pub enum PlaceType {
     Country,
 }
 
+#[cfg(feature = "places")]
+impl PlaceType {
+    ///Orders place types from the smallest area to the largest.
+    pub(crate) fn rank(self) -> u8 {
+        match self {
+            PlaceType::PointOfInterest => 0,
+            PlaceType::Neighborhood => 1,
+            PlaceType::City => 2,
+            PlaceType::Admin => 3,
+            PlaceType::Country => 4,
+        }
+    }
+}
+
 ///Represents the accuracy of a GPS measurement, when being given to a location search.
 #[derive(Debug, Copy, Clone)]
 pub enum Accuracy {
@@ -134,6 +256,7 @@ impl<'de> Deserialize<'de> for SearchResult {
 ///To complete your search setup and send the query to Twitter, hand your tokens to `call`. The
 ///list of results from Twitter will be returned, as well as a URL to perform the same search via
 ///`reverse_geocode_url`.
+#[cfg(feature = "places")]
 pub struct GeocodeBuilder {
     coordinate: (f64, f64),
     accuracy: Option<Accuracy>,
@@ -141,6 +264,7 @@ pub struct GeocodeBuilder {
     max_results: Option<u32>,
 }
 
+#[cfg(feature = "places")]
 impl GeocodeBuilder {
     ///Begins building a reverse-geocode query with the given coordinate.
     fn new(latitude: f64, longitude: f64) -> Self {
@@ -209,6 +333,7 @@ impl GeocodeBuilder {
     }
 }
 
+#[cfg(feature = "places")]
 enum PlaceQuery {
     LatLon(f64, f64),
     Query(CowStr),
@@ -223,6 +348,7 @@ enum PlaceQuery {
 ///To complete your search setup and send the query to Twitter, hand your tokens to `call`. The
 ///list of results from Twitter will be returned, as well as a URL to perform the same search via
 ///`search_url`.
+#[cfg(feature = "places")]
 pub struct SearchBuilder {
     query: PlaceQuery,
     accuracy: Option<Accuracy>,
@@ -232,6 +358,7 @@ pub struct SearchBuilder {
     attributes: Option<HashMap<String, String>>,
 }
 
+#[cfg(feature = "places")]
 impl SearchBuilder {
     ///Begins building a location search with the given query.
     fn new(query: PlaceQuery) -> Self {
@@ -402,6 +529,22 @@ mod tests {
         bounding_box: Vec<(f64, f64)>,
     }
 
+    #[test]
+    fn bounds_around_points() {
+        let points = [(-74.0, 40.5), (-73.5, 40.0), (-73.0, 41.0)];
+        let bbox = super::BoundingBox::around(&points).unwrap();
+        assert_eq!(bbox.southwest(), (-74.0, 40.0));
+        assert_eq!(bbox.northeast(), (-73.0, 41.0));
+        assert!(bbox.is_valid());
+        assert!(super::BoundingBox::around(&[]).is_none());
+
+        let london = super::BoundingBox::new((-0.5, 51.3), (0.3, 51.7));
+        assert!(!bbox.intersects(&london));
+        assert!(bbox.intersects(&super::BoundingBox::new((-73.0, 39.0), (-72.0, 40.0))));
+        assert!(!super::BoundingBox::new((-73.0, 40.0), (-74.0, 41.0)).is_valid());
+        assert!(!super::BoundingBox::new((-190.0, 40.0), (-74.0, 41.0)).is_valid());
+    }
+
     #[test]
     fn parse_and_serialize_polygon_bounding_box() {
         let content = load_file("sample_payloads/bounding_box-polygon.json");
@@ -23,7 +23,7 @@
 //! [`Token`]: ../auth/enum.Token.html
 //! [`ParamList`]: struct.ParamList.html
 //!
-//! There are three basic request functions, based on how the endpoint expects to be called:
+//! There are four basic request functions, based on how the endpoint expects to be called:
 //!
 //! * `request_get` assembles a GET request, with the given parameters appended to the URL as a
 //!   query string. All GET endpoints that egg-mode currently wraps use this function to encode and
@@ -35,8 +35,13 @@
 //!   takes arbitrary data and formats it in the POST body as JSON. The provided data is *not* used
 //!   as part of the OAuth signature. At time of writing (between releases 0.14 and 0.15) the only
 //!   egg-mode endpoint that uses this function is [`media::set_metadata`].
+//! * `request_multipart` assembles a POST request with a `multipart/form-data` body, made of
+//!   [`FormPart`]s that can hold text or files with their content type. As with
+//!   `request_post_json`, the body is *not* used as part of the OAuth signature. This can be used
+//!   with the non-chunked form of `POST media/upload`, among others.
 //!
 //! [`media::set_metadata`]: ../media/fn.set_metadata.html
+//! [`FormPart`]: struct.FormPart.html
 //!
 //! Once you have a `Request`, you can hand it to the `response_*` functions in this module to
 //! process it. Which one you select depends on how much processing you want egg-mode to do with
@@ -56,6 +61,14 @@
 //! [`response_raw_bytes`]: fn.response_raw_bytes.html
 //! [`response_json`]: fn.response_json.html
 //!
+//! For endpoints that keep the connection open and send data as it becomes available, like
+//! enterprise streaming APIs that egg-mode doesn't wrap, [`stream`] returns the response body as a
+//! `Stream` of raw chunks, leaving it to you to split and parse the messages.
+//! If the endpoint sends the same messages as the standard streaming API, `response_as_stream`
+//! can parse them into a `TwitterStream` instead.
+//!
+//! [`stream`]: fn.stream.html
+//!
 //! In addition, there are `request_as_*` and `response_as_*` functions available to format a
 //! request using one of the wrappers used in egg-mode. If the endpoint you're using is one that
 //! currently uses one of these wrapper types or returns and accepts data the same way as one of
@@ -70,21 +83,25 @@
 //!
 //! [`auth`]: auth/index.html
 
+#[cfg(feature = "stream")]
 use hyper::{Body, Request};
 
 use crate::auth::Token;
 use crate::cursor;
+#[cfg(feature = "stream")]
 use crate::stream::TwitterStream;
 
 use crate::tweet::Timeline as TweetTimeline;
 
 pub use crate::common::Headers;
-pub use crate::common::ParamList;
+pub use crate::common::{ParamEntry, ParamList};
 
 pub use crate::auth::raw::delete as request_delete;
 pub use crate::auth::raw::get as request_get;
+pub use crate::auth::raw::multipart as request_multipart;
 pub use crate::auth::raw::post as request_post;
 pub use crate::auth::raw::post_json as request_post_json;
+pub use crate::auth::raw::FormPart;
 
 /// Assemble a GET request and convert it to a `Timeline` of tweets.
 ///
@@ -151,9 +168,15 @@ pub fn request_as_cursor_iter<T: cursor::Cursor + serde::de::DeserializeOwned>(
 
 pub use crate::common::get_response as response_future;
 pub use crate::common::raw_request as response_raw_bytes;
+pub use crate::common::raw_stream as stream;
 pub use crate::common::request_with_empty_response as response_empty;
 pub use crate::common::request_with_json_response as response_json;
 
+pub use crate::common::endpoint_family;
+
+#[cfg(feature = "strict-fields")]
+pub use crate::common::{clear_unknown_field_handler, set_unknown_field_handler, UnknownField};
+
 /// Converts the given request into a `TwitterStream`.
 ///
 /// This function can be used for endpoints that open a persistent stream, like `GET
@@ -163,6 +186,7 @@ pub use crate::common::request_with_json_response as response_json;
 ///
 /// Since the `TwitterStream` type doesn't need to provide additional parameters to the request, it
 /// can take a signed, completed request as its constructor.
+#[cfg(feature = "stream")]
 pub fn response_as_stream(req: Request<Body>) -> TwitterStream {
     TwitterStream::new(req)
 }
@@ -233,5 +257,6 @@ pub mod auth {
 /// available here that represent the data sent "across the wire", which can be converted into
 /// regular egg-mode types. See the individual module docs for details.
 pub mod types {
+    #[cfg(feature = "dm")]
     pub mod direct;
 }
@@ -14,12 +14,12 @@
 //! converted from JSON returned from Twitter. The types `SingleEvent` and `EventCursor` match the
 //! data sent for the `show` and `list` endpoints, respectively, and can thus be deserialized
 //! directly from those responses. These types can then be used with implementations of the `From`
-//! trait to be converted into a single `DirectMessage` or a `Vec<DirectMessage>`, respectively.
+//! trait to be converted into a `DirectMessageEvent` or a `Vec<DirectMessageEvent>`, or with
+//! `TryFrom` and `From` into a single `DirectMessage` or a `Vec<DirectMessage>`, respectively.
 //!
 //! The `RawDirectMessage` type represents a minimally-processed version of the `message_create`
 //! event data sent by Twitter. It's contained within the `EventType` enum, which abstracts the
-//! fact that the event data structure allows for other types of events. As egg-mode only loads
-//! direct messages using these types, it only serves to ensure that the proper data is received by
-//! Twitter.
+//! fact that the event data structure allows for other types of events. Events of other types are
+//! kept as raw JSON in `EventType::Other`.
 
 pub use crate::direct::raw::*;
@@ -43,14 +43,54 @@
 //!
 //! [search-doc]: https://developer.twitter.com/en/docs/tweets/search/api-reference/get-search-tweets
 //! [search-place]: https://developer.twitter.com/en/docs/tweets/search/guides/tweets-by-place
+//!
+//! ## Watching for new tweets
+//!
+//! To keep up with new tweets matching a search, call `poller` instead of `call`. The returned
+//! `Poller` runs the search repeatedly, keeping track of the newest tweet it has seen and waiting
+//! between polls according to the rate limit, and can be turned into a `Stream` of new tweets.
+//!
+//! ## Searching for users too
+//!
+//! To show matching accounts alongside matching tweets, call `universal`, which runs this search
+//! and `user::search` at the same time and returns the first page of each as `UniversalResults`.
+//!
+//! ## Counting tweets
+//!
+//! If you only need to know how many tweets match a query, rather than the tweets themselves,
+//! `counts` loads the number of matching tweets per minute, hour, or day from version 2 of the
+//! Twitter API. This requires a Bearer token from `auth::bearer_token`.
+//!
+//! ```rust,no_run
+//! # use egg_mode::Token;
+//! # #[tokio::main]
+//! # async fn main() {
+//! # let token: Token = unimplemented!();
+//! use egg_mode::search::{self, Granularity};
+//!
+//! let counts = search::counts("rustlang", Granularity::Day)
+//!     .call(&token)
+//!     .await
+//!     .unwrap();
+//!
+//! for bucket in &counts.buckets {
+//!     println!("{}: {} tweets", bucket.start.date_naive(), bucket.tweet_count);
+//! }
+//! # }
+//! ```
 
 use std::fmt;
+use std::time::Duration;
 
+use futures::future;
+use futures::stream::{self, Stream, StreamExt};
 use serde::{Deserialize, Deserializer};
 
+use chrono;
+
 use crate::common::*;
-use crate::tweet::Tweet;
-use crate::{auth, error, links};
+use crate::tweet::{Tweet, TweetFilter};
+use crate::{auth, error, links, user};
 
 ///Begin setting up a tweet search with the given query.
 pub fn search<S: Into<CowStr>>(query: S) -> SearchBuilder {
@@ -63,6 +103,7 @@ pub fn search<S: Into<CowStr>>(query: S) -> SearchBuilder {
         geocode: None,
         since_id: None,
         max_id: None,
+        filter: None,
     }
 }
 
@@ -108,6 +149,7 @@ pub struct SearchBuilder {
     geocode: Option<(f32, f32, Distance)>,
     since_id: Option<u64>,
     max_id: Option<u64>,
+    filter: Option<TweetFilter>,
 }
 
 impl SearchBuilder {
@@ -174,8 +216,55 @@ impl SearchBuilder {
         }
     }
 
+    ///Only return tweets that pass the given predicate, on this page of results and the ones
+    ///loaded from it with `older` and `newer`.
+    ///
+    ///The filter is applied after each page is loaded, and `older` and `newer` page from the full
+    ///set of results Twitter returned, so filtering doesn't cause tweets to be skipped or loaded
+    ///twice. This means a page can come back with fewer tweets than requested, or none at all.
+    ///
+    ///To only search for tweets in a certain language, prefer `lang`, which has Twitter do the
+    ///filtering. Calling this more than once requires tweets to pass every filter.
+    pub fn filter_tweets<F>(self, filter: F) -> Self
+    where
+        F: Fn(&Tweet) -> bool + Send + Sync + 'static,
+    {
+        let filter = TweetFilter::new(filter);
+        SearchBuilder {
+            filter: Some(match self.filter {
+                Some(existing) => existing.and(filter),
+                None => filter,
+            }),
+            ..self
+        }
+    }
+
     ///Finalize the search terms and return the first page of responses.
     pub async fn call(self, token: &auth::Token) -> Result<Response<SearchResult>, error::Error> {
+        let (params, filter) = self.into_params();
+        SearchResult::load(params, filter, token).await
+    }
+
+    ///Turn this search into a `Poller`, which repeatedly runs it and returns only the tweets
+    ///that are new since the last poll.
+    ///
+    ///If `since_tweet` was set, the first poll only returns tweets posted after it. Any
+    ///`max_tweet` is ignored. See the `Poller` documentation for details.
+    pub fn poller(self) -> Poller {
+        let since_id = self.since_id;
+        let (mut params, filter) = self.into_params();
+        params.remove("since_id");
+        params.remove("max_id");
+        Poller {
+            params,
+            filter,
+            since_id,
+            interval: Duration::from_secs(30),
+        }
+    }
+
+    ///Assembles the parameters to send to Twitter for this search.
+    fn into_params(self) -> (ParamList, Option<TweetFilter>) {
         let params = ParamList::new()
             .extended_tweets()
             .add_param("q", self.query)
@@ -197,11 +286,7 @@ impl SearchBuilder {
                 }),
             );
 
-        let req = get(links::statuses::SEARCH, token, Some(&params));
-        let mut resp = request_with_json_response::<SearchResult>(req).await?;
-
-        resp.response.params = Some(params);
-        Ok(resp)
+        (params, self.filter)
     }
 }
 
@@ -224,22 +309,81 @@ struct RawSearchMetaData {
     since_id: u64,
 }
 
+impl From<RawSearchMetaData> for SearchMetadata {
+    fn from(raw: RawSearchMetaData) -> SearchMetadata {
+        SearchMetadata {
+            completed_in: raw.completed_in,
+            count: raw.count,
+            max_id: raw.max_id,
+            since_id: raw.since_id,
+            query: raw.query,
+            next_results: raw.next_results.as_deref().map(parse_search_params),
+            refresh_url: raw.refresh_url.as_deref().map(parse_search_params),
+        }
+    }
+}
+
+///Parses the query string Twitter gives in `next_results` and `refresh_url` into its parameters.
+fn parse_search_params(query: &str) -> ParamList {
+    let query = query.strip_prefix('?').unwrap_or(query);
+    url::form_urlencoded::parse(query.as_bytes())
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect()
+}
+
 impl<'de> Deserialize<'de> for SearchResult {
     fn deserialize<D>(deser: D) -> Result<SearchResult, D::Error>
     where
         D: Deserializer<'de>,
     {
         let raw = RawSearch::deserialize(deser)?;
+        let ids = || raw.statuses.iter().map(|t| t.id);
+        let (page_min_id, page_max_id) = (ids().min(), ids().max());
+        let metadata = SearchMetadata::from(raw.search_metadata);
         Ok(SearchResult {
+            page_min_id,
+            page_max_id,
             statuses: raw.statuses,
-            query: raw.search_metadata.query,
-            max_id: raw.search_metadata.max_id,
-            since_id: raw.search_metadata.since_id,
+            query: metadata.query.clone(),
+            max_id: metadata.max_id,
+            since_id: metadata.since_id,
+            more: metadata.next_results.is_some(),
+            metadata,
             params: None,
+            filter: None,
         })
     }
 }
 
+///Information Twitter returns about a page of search results.
+///
+///The parameters in `next_results` and `refresh_url` are the ones Twitter suggests for loading the
+///next page of older results and checking for newer ones, respectively. `SearchResult::older` and
+///`newer` already handle this, but to drive the pagination yourself, these can be given to
+///`raw::request_get` along with the search URL, `https://api.twitter.com/1.1/search/tweets.json`.
+///Note that they don't include `tweet_mode=extended`, so add it with `ParamList::extended_tweets`
+///to receive full tweet text.
+#[derive(Debug, Clone)]
+pub struct SearchMetadata {
+    ///How long Twitter took to run the search, in seconds.
+    pub completed_in: f64,
+    ///The number of tweets that were asked for, per page.
+    pub count: u64,
+    ///The largest tweet ID that could be returned in this page, or the ID given to
+    ///`SearchBuilder::max_tweet`.
+    pub max_id: u64,
+    ///The tweet ID given to `SearchBuilder::since_tweet`, or zero if it wasn't set.
+    pub since_id: u64,
+    ///The query that was searched for, URL-encoded.
+    pub query: String,
+    ///The parameters to load the next page of older results with, or `None` if there are no more
+    ///results.
+    pub next_results: Option<ParamList>,
+    ///The parameters to search for results newer than this page with, or `None` if this page had
+    ///no results.
+    pub refresh_url: Option<ParamList>,
+}
+
 ///Represents a page of search results, along with metadata to request the next or previous page.
 #[derive(Debug)]
 pub struct SearchResult {
@@ -252,10 +396,37 @@ pub struct SearchResult {
     pub max_id: u64,
     ///First tweet id in this page of results. This id can be used in `SearchBuilder::since_tweet`
     pub since_id: u64,
+    ///The rest of the information Twitter returned about this page, including the parameters it
+    ///suggests for loading the next page.
+    pub metadata: SearchMetadata,
     params: Option<ParamList>,
+    filter: Option<TweetFilter>,
+    ///The smallest tweet ID in this page before it was filtered.
+    page_min_id: Option<u64>,
+    ///The largest tweet ID in this page before it was filtered.
+    page_max_id: Option<u64>,
+    ///Whether Twitter has more results to load with `older`.
+    more: bool,
 }
 
 impl SearchResult {
+    ///Loads a page of search results with the given parameters, applying the given filter.
+    async fn load(
+        params: ParamList,
+        filter: Option<TweetFilter>,
+        token: &auth::Token,
+    ) -> Result<Response<SearchResult>, error::Error> {
+        let req = get(links::statuses::SEARCH, token, Some(&params));
+        let mut resp = request_with_json_response::<SearchResult>(req).await?;
+
+        if let Some(filter) = &filter {
+            filter.apply(&mut resp.response.statuses);
+        }
+        resp.response.params = Some(params);
+        resp.response.filter = filter;
+        Ok(resp)
+    }
+
     ///Load the next page of search results for the same query.
     pub async fn older(&self, token: &auth::Token) -> Result<Response<SearchResult>, error::Error> {
         let mut params = self
@@ -267,17 +438,13 @@ impl SearchResult {
 
         params.remove("since_id");
 
-        if let Some(min_id) = self.statuses.iter().map(|t| t.id).min() {
+        if let Some(min_id) = self.page_min_id {
             params.add_param_ref("max_id", (min_id - 1).to_string());
         } else {
             params.remove("max_id");
         }
 
-        let req = get(links::statuses::SEARCH, token, Some(&params));
-        let mut resp = request_with_json_response::<SearchResult>(req).await?;
-
-        resp.response.params = Some(params);
-        Ok(resp)
+        SearchResult::load(params, self.filter.clone(), token).await
     }
 
     ///Load the previous page of search results for the same query.
@@ -290,16 +457,517 @@ impl SearchResult {
             .extended_tweets();
 
         params.remove("max_id");
-        if let Some(max_id) = self.statuses.iter().map(|t| t.id).max() {
+        if let Some(max_id) = self.page_max_id {
             params.add_param_ref("since_id", max_id.to_string());
         } else {
             params.remove("since_id");
         }
 
-        let req = get(links::statuses::SEARCH, token, Some(&params));
-        let mut resp = request_with_json_response::<SearchResult>(req).await?;
+        SearchResult::load(params, self.filter.clone(), token).await
+    }
+}
 
-        resp.response.params = Some(params);
-        Ok(resp)
+///Repeatedly runs a search, returning only the tweets that are new since the last poll.
+///
+///A `Poller` is created with `SearchBuilder::poller`, and remembers the newest tweet ID it has
+///seen so each poll only asks Twitter for newer tweets. Turning it into a `Stream` with `stream`
+///gives each new tweet once, oldest first, waiting between polls:
+///
+///```rust,no_run
+///# use egg_mode::Token;
+///# #[tokio::main]
+///# async fn main() {
+///# let token: Token = unimplemented!();
+///use egg_mode::search;
+///use futures::TryStreamExt;
+///
+///let mut tweets = search::search("#rustlang").count(100).poller().stream(&token);
+///
+///while let Some(tweet) = tweets.try_next().await.unwrap() {
+///    println!("<@{}> {}", tweet.user.as_ref().unwrap().screen_name, tweet.text);
+///}
+///# }
+///```
+///
+///The first poll returns the most recent page of results, or, if `since_tweet` was set on the
+///search, every result posted after that tweet. After that, if more tweets were posted between
+///polls than fit on one page, the `Poller` loads as many pages as it needs to catch up, so tweets
+///aren't skipped.
+///
+///Between polls, the `Poller` waits for the interval set with `with_interval` (30 seconds by
+///default). If the rate limit for searches is running low, it waits longer, spreading the
+///remaining calls over the rest of the rate-limit window, and if the rate limit runs out, it
+///waits for the window to reset.
+///
+///To pick up where a `Poller` left off after a restart, save the ID of the last tweet it
+///returned, and give it to `SearchBuilder::since_tweet` when recreating it. Since tweets are
+///returned oldest first, every tweet before that one has already been returned.
+///
+///If a poll fails, the error is returned from the stream, and the same tweets are asked for again
+///on the next poll. The stream never ends on its own.
+#[must_use = "Poller is lazy and won't do anything unless turned into a stream"]
+pub struct Poller {
+    params: ParamList,
+    filter: Option<TweetFilter>,
+    since_id: Option<u64>,
+    interval: Duration,
+}
+
+impl Poller {
+    ///Sets the minimum time to wait between polls.
+    pub fn with_interval(self, interval: Duration) -> Self {
+        Poller { interval, ..self }
+    }
+
+    ///Returns the ID of the newest tweet this `Poller` has seen, if any.
+    ///
+    ///The next poll will only return tweets posted after this one.
+    pub fn since_id(&self) -> Option<u64> {
+        self.since_id
+    }
+
+    ///Loads the tweets that are new since the last poll, oldest first, along with the rate-limit
+    ///information from the last call.
+    ///
+    ///This makes one call to Twitter, or several if it needs to catch up. If the rate limit runs
+    ///out while catching up, this waits for it to reset. If a call fails, nothing is returned
+    ///and `since_id` is left alone, so the next poll asks for the same tweets.
+    pub async fn poll(
+        &mut self,
+        token: &auth::Token,
+    ) -> Result<Response<Vec<Tweet>>, error::Error> {
+        let mut params = self.params.clone();
+        if let Some(since_id) = self.since_id {
+            params.add_param_ref("since_id", since_id.to_string());
+        }
+
+        let mut tweets = Vec::new();
+        let mut newest = self.since_id;
+        let rate_limit_status = loop {
+            let mut page =
+                match SearchResult::load(params.clone(), self.filter.clone(), token).await {
+                    Ok(page) => page,
+                    Err(error::Error::RateLimit(reset)) => {
+                        sleep_until_reset(reset).await;
+                        continue;
+                    }
+                    Err(e) => return Err(e),
+                };
+
+            newest = newest.max(page.page_max_id);
+            tweets.append(&mut page.response.statuses);
+
+            //only catch up when there's a previous poll to catch up to
+            match page.page_min_id {
+                Some(min_id) if page.more && self.since_id.is_some() => {
+                    params.add_param_ref("max_id", (min_id - 1).to_string());
+                    if page.rate_limit_status.remaining == 0 {
+                        sleep_until_reset(page.rate_limit_status.reset).await;
+                    }
+                }
+                _ => break page.rate_limit_status,
+            }
+        };
+
+        tweets.sort_by_key(|tweet| tweet.id);
+        tweets.dedup_by_key(|tweet| tweet.id);
+        self.since_id = newest;
+
+        Ok(Response::new(rate_limit_status, tweets))
+    }
+
+    ///Turns this `Poller` into a `Stream` that polls for new tweets forever, returning each one
+    ///as it's found.
+    pub fn stream(
+        self,
+        token: &auth::Token,
+    ) -> impl Stream<Item = Result<Response<Tweet>, error::Error>> + Unpin + Send {
+        let token = token.clone();
+        let stream = stream::unfold((self, None), move |(mut poller, wait)| {
+            let token = token.clone();
+            async move {
+                if let Some(wait) = wait {
+                    tokio::time::sleep(wait).await;
+                }
+
+                match poller.poll(&token).await {
+                    Ok(tweets) => {
+                        let wait = poller.delay(&tweets.rate_limit_status);
+                        let tweets = tweets.into_iter().map(Ok).collect::<Vec<_>>();
+                        Some((tweets, (poller, Some(wait))))
+                    }
+                    Err(e) => {
+                        let wait = poller.interval;
+                        Some((vec![Err(e)], (poller, Some(wait))))
+                    }
+                }
+            }
+        })
+        .map(stream::iter)
+        .flatten();
+        Box::pin(stream)
+    }
+
+    ///Works out how long to wait before the next poll, given the rate-limit information from the
+    ///last one.
+    fn delay(&self, rate_limit: &RateLimit) -> Duration {
+        if rate_limit.reset < 0 || rate_limit.remaining < 0 {
+            return self.interval;
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        let until_reset = (i64::from(rate_limit.reset) - now).max(0) as u64 + 1;
+        if rate_limit.remaining == 0 {
+            Duration::from_secs(until_reset)
+        } else {
+            let spread = Duration::from_secs(until_reset / rate_limit.remaining as u64);
+            spread.max(self.interval)
+        }
+    }
+}
+
+///Waits until the given rate-limit reset time has passed.
+async fn sleep_until_reset(reset: i32) {
+    let now = chrono::Utc::now().timestamp();
+    let secs = (i64::from(reset) - now).max(0) as u64 + 1;
+    tokio::time::sleep(Duration::from_secs(secs)).await;
+}
+
+///Searches for tweets and users matching the given query at the same time.
+///
+///This loads the first page of `search` for tweets and the first page of `user::search` for
+///accounts, which is what clients usually show as "top results" before the user picks one kind.
+///The tweet search uses Twitter's defaults, which mix popular and recent tweets; the user search
+///loads 20 users, the most Twitter returns in one page. To load more of either kind, call `older`
+///on the tweet results, or start a separate `user::search`.
+///
+///If either search fails, the error is returned and the other one's results are dropped.
+///
+///```rust,no_run
+///# use egg_mode::Token;
+///# #[tokio::main]
+///# async fn main() {
+///# let token: Token = unimplemented!();
+///let results = egg_mode::search::universal("rustlang", &token).await.unwrap();
+///
+///for user in &results.users.response {
+///    println!("@{}", user.screen_name);
+///}
+///for tweet in &results.tweets.statuses {
+///    println!("{}", tweet.text);
+///}
+///# }
+///```
+pub async fn universal<S: Into<CowStr>>(
+    query: S,
+    token: &auth::Token,
+) -> Result<UniversalResults, error::Error> {
+    let (tweets, users) = universal_searches(query.into(), token);
+    let (tweets, users) = future::try_join(tweets.call(token), users.call()).await?;
+
+    Ok(UniversalResults { tweets, users })
+}
+
+///Sets up the tweet and user searches run by `universal`.
+fn universal_searches(query: CowStr, token: &auth::Token) -> (SearchBuilder, user::UserSearch) {
+    let users = user::search(query.clone(), token).with_page_size(20);
+    (search(query), users)
+}
+
+///The results of searching for both tweets and users, as returned by `universal`.
+///
+///Each kind of result comes from a separate call to Twitter with its own rate limit, so they're
+///kept in separate `Response`s.
+#[derive(Debug)]
+pub struct UniversalResults {
+    ///The first page of tweets matching the query.
+    pub tweets: Response<SearchResult>,
+    ///The first page of users matching the query.
+    pub users: Response<Vec<user::TwitterUser>>,
+}
+
+///Begin setting up a request for the number of tweets matching the given query over time.
+///
+///The query uses the syntax of version 2 of the Twitter API, which differs slightly from the
+///syntax used by `search`. See [Twitter's documentation][query-doc] for details.
+///
+///[query-doc]: https://developer.twitter.com/en/docs/twitter-api/tweets/counts/integrate/build-a-query
+pub fn counts<S: Into<CowStr>>(query: S, granularity: Granularity) -> CountsBuilder {
+    CountsBuilder {
+        query: query.into(),
+        granularity,
+        start_time: None,
+        end_time: None,
+        since_id: None,
+        until_id: None,
+        next_token: None,
+        full_archive: false,
+    }
+}
+
+///Represents the length of time covered by each bucket of tweet counts.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Granularity {
+    ///Count tweets per minute.
+    Minute,
+    ///Count tweets per hour.
+    Hour,
+    ///Count tweets per day.
+    Day,
+}
+
+///Display impl that turns the variants into strings that can be used as search parameters.
+impl fmt::Display for Granularity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Granularity::Minute => write!(f, "minute"),
+            Granularity::Hour => write!(f, "hour"),
+            Granularity::Day => write!(f, "day"),
+        }
+    }
+}
+
+///Represents a request for tweet counts before being sent.
+#[derive(Debug, Clone)]
+#[must_use = "CountsBuilder is lazy and won't do anything unless `call`ed"]
+pub struct CountsBuilder {
+    query: CowStr,
+    granularity: Granularity,
+    start_time: Option<chrono::DateTime<chrono::Utc>>,
+    end_time: Option<chrono::DateTime<chrono::Utc>>,
+    since_id: Option<u64>,
+    until_id: Option<u64>,
+    next_token: Option<String>,
+    full_archive: bool,
+}
+
+impl CountsBuilder {
+    ///Only counts tweets posted at or after the given time. By default, Twitter counts tweets
+    ///from the last 7 days, or the last 30 days when counting the full archive.
+    pub fn start_time(self, start_time: chrono::DateTime<chrono::Utc>) -> Self {
+        CountsBuilder {
+            start_time: Some(start_time),
+            ..self
+        }
+    }
+
+    ///Only counts tweets posted before the given time.
+    pub fn end_time(self, end_time: chrono::DateTime<chrono::Utc>) -> Self {
+        CountsBuilder {
+            end_time: Some(end_time),
+            ..self
+        }
+    }
+
+    ///Only counts tweets with higher IDs than (i.e. that were posted after) the given tweet ID.
+    pub fn since_tweet(self, since_id: u64) -> Self {
+        CountsBuilder {
+            since_id: Some(since_id),
+            ..self
+        }
+    }
+
+    ///Only counts tweets with lower IDs than (i.e. that were posted before) the given tweet ID.
+    pub fn until_tweet(self, until_id: u64) -> Self {
+        CountsBuilder {
+            until_id: Some(until_id),
+            ..self
+        }
+    }
+
+    ///Counts tweets from the full archive of public tweets, instead of the last 7 days.
+    ///
+    ///This requires Academic Research access to the Twitter API.
+    pub fn full_archive(self) -> Self {
+        CountsBuilder {
+            full_archive: true,
+            ..self
+        }
+    }
+
+    ///Continues counting from the `next_token` of an earlier `TweetCounts`.
+    ///
+    ///When counting the full archive, Twitter returns at most 31 days of buckets at a time; the
+    ///`next_token` of the result can be used to load the rest.
+    pub fn next_token(self, next_token: impl Into<String>) -> Self {
+        CountsBuilder {
+            next_token: Some(next_token.into()),
+            ..self
+        }
+    }
+
+    ///Finalize the request and load the tweet counts.
+    pub async fn call(self, token: &auth::Token) -> Result<Response<TweetCounts>, error::Error> {
+        let format_time = |time: chrono::DateTime<chrono::Utc>| {
+            time.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+        };
+        let params = ParamList::new()
+            .add_param("query", self.query)
+            .add_param("granularity", self.granularity.to_string())
+            .add_opt_param("start_time", self.start_time.map(format_time))
+            .add_opt_param("end_time", self.end_time.map(format_time))
+            .add_opt_param("since_id", self.since_id.map_string())
+            .add_opt_param("until_id", self.until_id.map_string())
+            .add_opt_param("next_token", self.next_token);
+
+        let link = if self.full_archive {
+            links::statuses::COUNTS_ALL_V2
+        } else {
+            links::statuses::COUNTS_RECENT_V2
+        };
+
+        let req = get(link, token, Some(&params));
+        request_with_json_response(req).await
+    }
+}
+
+///The number of tweets matching a query over time, as returned by `counts`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(from = "RawTweetCounts")]
+pub struct TweetCounts {
+    ///The counts for each period of time, from earliest to latest.
+    pub buckets: Vec<CountBucket>,
+    ///The total number of tweets counted across all the buckets.
+    pub total_tweet_count: u64,
+    ///A token that can be given to `CountsBuilder::next_token` to load more buckets, if there are
+    ///any.
+    pub next_token: Option<String>,
+}
+
+///The number of tweets matching a query within a single period of time.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CountBucket {
+    ///The beginning of this period of time.
+    pub start: chrono::DateTime<chrono::Utc>,
+    ///The end of this period of time.
+    pub end: chrono::DateTime<chrono::Utc>,
+    ///The number of matching tweets posted during this period of time.
+    pub tweet_count: u64,
+}
+
+#[derive(Deserialize)]
+struct RawTweetCounts {
+    #[serde(default)]
+    data: Vec<CountBucket>,
+    meta: RawCountsMeta,
+}
+
+#[derive(Deserialize)]
+struct RawCountsMeta {
+    total_tweet_count: u64,
+    next_token: Option<String>,
+}
+
+impl From<RawTweetCounts> for TweetCounts {
+    fn from(raw: RawTweetCounts) -> TweetCounts {
+        TweetCounts {
+            buckets: raw.data,
+            total_tweet_count: raw.meta.total_tweet_count,
+            next_token: raw.meta.next_token,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{search, universal_searches, SearchResult, TweetCounts};
+    use crate::{RateLimit, Token};
+
+    #[test]
+    fn parse_metadata() {
+        let content = r#"{
+            "statuses": [],
+            "search_metadata": {
+                "completed_in": 0.035,
+                "max_id": 1250000000000000000,
+                "max_id_str": "1250000000000000000",
+                "next_results": "?max_id=1249999999999999999&q=%23rustlang+-filter%3Aretweets&count=15&include_entities=1",
+                "query": "%23rustlang+-filter%3Aretweets",
+                "count": 15,
+                "since_id": 0,
+                "since_id_str": "0"
+            }
+        }"#;
+        let result = ::serde_json::from_str::<SearchResult>(content).unwrap();
+        let metadata = &result.metadata;
+
+        assert_eq!(metadata.completed_in, 0.035);
+        assert_eq!(metadata.count, 15);
+        assert_eq!(result.max_id, 1250000000000000000);
+        assert!(metadata.refresh_url.is_none());
+
+        let next = metadata.next_results.as_ref().unwrap();
+        assert_eq!(next.get("max_id").unwrap(), "1249999999999999999");
+        assert_eq!(next.get("q").unwrap(), "#rustlang -filter:retweets");
+        assert_eq!(next.len(), 4);
+    }
+
+    #[test]
+    fn parse_counts() {
+        let content = r#"{
+            "data": [
+                {"end": "2021-05-26T00:00:00.000Z", "start": "2021-05-25T00:00:00.000Z", "tweet_count": 1510},
+                {"end": "2021-05-27T00:00:00.000Z", "start": "2021-05-26T00:00:00.000Z", "tweet_count": 1734}
+            ],
+            "meta": {"total_tweet_count": 3244}
+        }"#;
+        let counts = ::serde_json::from_str::<TweetCounts>(content).unwrap();
+
+        assert_eq!(counts.buckets.len(), 2);
+        assert_eq!(counts.buckets[1].tweet_count, 1734);
+        assert_eq!(counts.total_tweet_count, 3244);
+        assert!(counts.next_token.is_none());
+        assert_eq!(
+            counts.buckets[0].end - counts.buckets[0].start,
+            chrono::Duration::days(1)
+        );
+    }
+
+    #[test]
+    fn poller_delay() {
+        let poller = search("rustlang")
+            .since_tweet(1234)
+            .max_tweet(5678)
+            .poller()
+            .with_interval(Duration::from_secs(10));
+        assert_eq!(poller.since_id(), Some(1234));
+        assert!(poller.params.get("since_id").is_none());
+        assert!(poller.params.get("max_id").is_none());
+
+        let reset = (chrono::Utc::now().timestamp() + 599) as i32;
+        let limit = |remaining| RateLimit {
+            limit: 180,
+            remaining,
+            reset,
+        };
+
+        let plenty = poller.delay(&limit(150));
+        assert_eq!(plenty, Duration::from_secs(10));
+        let low = poller.delay(&limit(20));
+        assert!(low >= Duration::from_secs(29) && low <= Duration::from_secs(30));
+        let out = poller.delay(&limit(0));
+        assert!(out >= Duration::from_secs(599) && out <= Duration::from_secs(600));
+        let missing = poller.delay(&RateLimit::unknown());
+        assert_eq!(missing, Duration::from_secs(10));
+    }
+
+    #[test]
+    fn universal_params() {
+        let token = Token::Bearer("token".to_string());
+        let (tweets, users) = universal_searches("#rustlang".into(), &token);
+
+        let (params, filter) = tweets.into_params();
+        assert_eq!(params.get("q").unwrap(), "#rustlang");
+        assert_eq!(params.get("tweet_mode").unwrap(), "extended");
+        assert!(params.get("result_type").is_none());
+        assert!(params.get("count").is_none());
+        assert!(filter.is_none());
+
+        let state = users.resume_state();
+        assert_eq!(state.query, "#rustlang");
+        assert_eq!(state.page_num, 1);
+        assert_eq!(state.page_size, 20);
     }
 }
@@ -82,6 +82,29 @@ pub async fn config(token: &auth::Token) -> Result<Response<Configuration>> {
     request_with_json_response(req).await
 }
 
+///Returns a future that resolves to the list of languages Twitter supports, with their codes and
+///names.
+///
+///The codes are the ones Twitter uses for the `lang` of tweets and users, so this list can be used
+///to fill in a language picker for `SearchBuilder::lang` or `StreamBuilder::language`. Note that
+///the streaming API only supports filtering on some of these languages; see
+///`stream::SUPPORTED_LANGUAGES`.
+///
+///```rust,no_run
+///# use egg_mode::Token;
+///# #[tokio::main]
+///# async fn main() {
+///# let token: Token = unimplemented!();
+///for lang in egg_mode::service::languages(&token).await.unwrap().iter() {
+///    println!("{}: {} ({})", lang.code, lang.name, lang.local_name);
+///}
+///# }
+///```
+pub async fn languages(token: &auth::Token) -> Result<Response<Vec<Language>>> {
+    let req = get(links::service::LANGUAGES, token, None);
+    request_with_json_response(req).await
+}
+
 ///Return the current rate-limit status for all available methods from the authenticated user.
 ///
 ///The struct returned by this method is organized by what module in egg-mode a given method
@@ -144,6 +167,21 @@ pub struct Configuration {
     pub non_username_paths: Vec<String>,
 }
 
+///A language supported by Twitter, as returned by `languages`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Language {
+    ///The language code, like `en` or `zh-cn`, as used in the `lang` of tweets and users.
+    pub code: String,
+    ///The name of the language in English.
+    pub name: String,
+    ///The name of the language in the language itself.
+    pub local_name: String,
+    ///Whether the language is fully supported (`production`) or still being translated (`beta`),
+    ///if Twitter said so.
+    #[serde(default)]
+    pub status: Option<String>,
+}
+
 /// Represents the current rate-limit status of many Twitter API calls.
 ///
 /// This is organized by module, so for example, if you wanted to see your rate-limit status for
@@ -291,6 +329,7 @@ impl FromStr for Method {
             "/search/tweets" => Ok(Method::Search(SearchMethod::Search)),
 
             "/help/configuration" => Ok(Method::Service(ServiceMethod::Config)),
+            "/help/languages" => Ok(Method::Service(ServiceMethod::Languages)),
             "/help/privacy" => Ok(Method::Service(ServiceMethod::Privacy)),
             "/help/tos" => Ok(Method::Service(ServiceMethod::Terms)),
             "/account/verify_credentials" => Ok(Method::Service(ServiceMethod::VerifyTokens)),
@@ -379,6 +418,8 @@ pub enum ServiceMethod {
     Privacy,
     ///`service::config`
     Config,
+    ///`service::languages`
+    Languages,
     ///`service::rate_limit_status`
     RateLimitStatus,
     ///`verify_tokens`
@@ -482,4 +523,17 @@ mod tests {
         let sample = load_file("sample_payloads/rate_limit_sample.json");
         ::serde_json::from_str::<RateLimitStatus>(&sample).unwrap();
     }
+
+    #[test]
+    fn parse_languages() {
+        let sample = r#"[
+            {"code": "fr", "status": "production", "name": "French", "local_name": "français"},
+            {"code": "zh-cn", "name": "Simplified Chinese", "local_name": "简体中文"}
+        ]"#;
+        let langs = ::serde_json::from_str::<Vec<Language>>(sample).unwrap();
+        assert_eq!(langs[0].local_name, "français");
+        assert_eq!(langs[0].status.as_deref(), Some("production"));
+        assert_eq!(langs[1].code, "zh-cn");
+        assert_eq!(langs[1].status, None);
+    }
 }
@@ -0,0 +1,75 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Functions for reading information out of Twitter's numeric IDs.
+//!
+//! Since November 2010, the IDs Twitter gives to tweets (and, since 2013, to users, direct
+//! messages, lists, and media) are "[Snowflake]" IDs, which contain the time they were created in
+//! their upper bits. This means you can tell when a tweet was posted just from its ID, which is
+//! handy when all you have is an ID, or when you want to compare IDs and timestamps without loading
+//! the full object.
+//!
+//! [Snowflake]: https://developer.twitter.com/en/docs/twitter-ids
+//!
+//! ```rust
+//! use chrono::{TimeZone, Utc};
+//!
+//! let posted = egg_mode::snowflake::timestamp(1212092628029698048);
+//! assert_eq!(posted, Utc.with_ymd_and_hms(2019, 12, 31, 19, 26, 16).unwrap()
+//!     + chrono::Duration::milliseconds(771));
+//! ```
+
+use chrono::{self, TimeZone};
+
+/// The Unix timestamp, in milliseconds, that Snowflake timestamps are counted from.
+pub const TWITTER_EPOCH_MILLIS: i64 = 1288834974657;
+
+/// The first tweet ID that was generated as a Snowflake ID.
+///
+/// IDs lower than this were assigned sequentially and don't contain a timestamp.
+pub const FIRST_SNOWFLAKE_ID: u64 = 29700859247;
+
+/// Returns whether the given ID was generated as a Snowflake ID, and therefore contains a
+/// timestamp.
+pub fn is_snowflake(id: u64) -> bool {
+    id >= FIRST_SNOWFLAKE_ID
+}
+
+/// Returns the time the given Snowflake ID was generated, to the millisecond.
+///
+/// For tweets, this is the time the tweet was posted. Note that this function can't tell whether
+/// the given ID is a Snowflake ID; calling it on an older, sequential ID will return a meaningless
+/// timestamp from 2010. Use `is_snowflake` to check first if you may be handling old IDs.
+pub fn timestamp(id: u64) -> chrono::DateTime<chrono::Utc> {
+    let millis = (id >> 22) as i64 + TWITTER_EPOCH_MILLIS;
+    chrono::Utc.timestamp_millis_opt(millis).unwrap()
+}
+
+/// Returns the lowest Snowflake ID that could have been generated at the given time.
+///
+/// This can be used to turn a time into a `since_id` or `max_id` parameter for functions that
+/// page through tweets by ID.
+pub fn min_id_at(time: chrono::DateTime<chrono::Utc>) -> u64 {
+    let millis = time.timestamp_millis() - TWITTER_EPOCH_MILLIS;
+    if millis <= 0 {
+        0
+    } else {
+        (millis as u64) << 22
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip_timestamp() {
+        let id = 1212092628029698048;
+        let time = timestamp(id);
+        assert!(is_snowflake(id));
+        assert!(min_id_at(time) <= id);
+        assert_eq!(timestamp(min_id_at(time)), time);
+        assert!(!is_snowflake(20));
+    }
+}
@@ -0,0 +1,336 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Tracking how users' follower, friend, and tweet counts change over time.
+//!
+//! Twitter only reports a user's current counts, so following how they change means loading the
+//! user regularly and remembering what was loaded. A [`UserStatsTracker`] does this: each time it
+//! loads or is given a user, it saves a [`UserSnapshot`] of their counts to a [`SnapshotStore`],
+//! and it can compare the saved snapshots to find out how much the counts changed, and how fast.
+//!
+//! [`UserStatsTracker`]: struct.UserStatsTracker.html
+//! [`UserSnapshot`]: struct.UserSnapshot.html
+//! [`SnapshotStore`]: trait.SnapshotStore.html
+//!
+//! egg-mode provides [`MemorySnapshotStore`], which keeps snapshots in memory for as long as the
+//! process runs. To keep the history across restarts, implement `SnapshotStore` on top of your own
+//! storage; `UserSnapshot` implements `Serialize` and `Deserialize` to help with this.
+//!
+//! [`MemorySnapshotStore`]: struct.MemorySnapshotStore.html
+//!
+//! ```rust,no_run
+//! # use egg_mode::Token;
+//! # #[tokio::main]
+//! # async fn main() {
+//! # let token: Token = unimplemented!();
+//! use egg_mode::stats::{MemorySnapshotStore, UserStatsTracker};
+//!
+//! let tracker = UserStatsTracker::new(MemorySnapshotStore::new());
+//!
+//! // call this on a timer, like once an hour
+//! tracker.refresh(vec!["rustlang"], &token).await.unwrap();
+//!
+//! let since = chrono::Utc::now() - chrono::Duration::days(7);
+//! if let Some(change) = tracker.change_since(2347475684, since) {
+//!     println!(
+//!         "{} new followers this week ({:.1} per day)",
+//!         change.followers,
+//!         change.followers_per_day()
+//!     );
+//! }
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::auth;
+use crate::common::*;
+use crate::error::Result;
+use crate::user::{self, TwitterUser, UserID};
+
+/// A user's follower, friend, and tweet counts at a point in time.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserSnapshot {
+    /// The ID of the user.
+    pub user_id: u64,
+    /// When the counts were loaded.
+    pub taken_at: DateTime<Utc>,
+    /// The number of accounts following the user.
+    pub followers_count: i32,
+    /// The number of accounts the user follows.
+    pub friends_count: i32,
+    /// The number of tweets the user has posted, including retweets.
+    pub statuses_count: i32,
+}
+
+impl UserSnapshot {
+    /// Takes a snapshot of the given user's counts, as of now.
+    pub fn new(user: &TwitterUser) -> UserSnapshot {
+        UserSnapshot::at(user, Utc::now())
+    }
+
+    /// Takes a snapshot of the given user's counts, as of the given time.
+    pub fn at(user: &TwitterUser, taken_at: DateTime<Utc>) -> UserSnapshot {
+        UserSnapshot {
+            user_id: user.id,
+            taken_at,
+            followers_count: user.followers_count,
+            friends_count: user.friends_count,
+            statuses_count: user.statuses_count,
+        }
+    }
+}
+
+/// How much a user's counts changed between two snapshots.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct StatsDelta {
+    /// The ID of the user.
+    pub user_id: u64,
+    /// When the earlier snapshot was taken.
+    pub from: DateTime<Utc>,
+    /// When the later snapshot was taken.
+    pub to: DateTime<Utc>,
+    /// The change in the number of followers.
+    pub followers: i64,
+    /// The change in the number of accounts followed.
+    pub friends: i64,
+    /// The change in the number of tweets.
+    pub statuses: i64,
+}
+
+impl StatsDelta {
+    /// Computes how much the counts changed from `from` to `to`.
+    ///
+    /// Both snapshots should be of the same user; the returned delta has the `user_id` of `to`.
+    pub fn between(from: &UserSnapshot, to: &UserSnapshot) -> StatsDelta {
+        StatsDelta {
+            user_id: to.user_id,
+            from: from.taken_at,
+            to: to.taken_at,
+            followers: i64::from(to.followers_count) - i64::from(from.followers_count),
+            friends: i64::from(to.friends_count) - i64::from(from.friends_count),
+            statuses: i64::from(to.statuses_count) - i64::from(from.statuses_count),
+        }
+    }
+
+    /// The time between the two snapshots.
+    pub fn duration(&self) -> chrono::Duration {
+        self.to - self.from
+    }
+
+    /// The average change in followers per day between the two snapshots.
+    pub fn followers_per_day(&self) -> f64 {
+        self.per_day(self.followers)
+    }
+
+    /// The average change in accounts followed per day between the two snapshots.
+    pub fn friends_per_day(&self) -> f64 {
+        self.per_day(self.friends)
+    }
+
+    /// The average change in tweets per day between the two snapshots.
+    pub fn statuses_per_day(&self) -> f64 {
+        self.per_day(self.statuses)
+    }
+
+    /// Spreads the given change over the days between the snapshots. A change between snapshots
+    /// taken at the same time is reported as 0.
+    fn per_day(&self, change: i64) -> f64 {
+        let seconds = self.duration().num_seconds();
+        if seconds <= 0 {
+            return 0.0;
+        }
+        change as f64 * 86_400.0 / seconds as f64
+    }
+}
+
+/// Storage for the snapshots recorded by a [`UserStatsTracker`].
+///
+/// egg-mode provides [`MemorySnapshotStore`], which keeps snapshots in memory. To keep them across
+/// restarts or share them between processes, implement this trait on top of your own storage.
+///
+/// [`UserStatsTracker`]: struct.UserStatsTracker.html
+/// [`MemorySnapshotStore`]: struct.MemorySnapshotStore.html
+pub trait SnapshotStore {
+    /// Saves the given snapshot.
+    fn save(&self, snapshot: UserSnapshot);
+
+    /// Loads every saved snapshot of the given user, in any order.
+    fn load(&self, user_id: u64) -> Vec<UserSnapshot>;
+}
+
+/// A `SnapshotStore` that keeps snapshots in memory.
+#[derive(Debug, Default)]
+pub struct MemorySnapshotStore {
+    snapshots: Mutex<HashMap<u64, Vec<UserSnapshot>>>,
+}
+
+impl MemorySnapshotStore {
+    /// Creates a new, empty `MemorySnapshotStore`.
+    pub fn new() -> MemorySnapshotStore {
+        MemorySnapshotStore::default()
+    }
+}
+
+impl SnapshotStore for MemorySnapshotStore {
+    fn save(&self, snapshot: UserSnapshot) {
+        self.snapshots
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(snapshot.user_id)
+            .or_default()
+            .push(snapshot);
+    }
+
+    fn load(&self, user_id: u64) -> Vec<UserSnapshot> {
+        self.snapshots
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&user_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+/// Records users' counts over time, and reports how they changed.
+///
+/// See the [module documentation](index.html) for an example.
+#[derive(Debug)]
+pub struct UserStatsTracker<S> {
+    store: S,
+}
+
+impl<S: SnapshotStore> UserStatsTracker<S> {
+    /// Creates a new `UserStatsTracker` that saves its snapshots to the given store.
+    pub fn new(store: S) -> UserStatsTracker<S> {
+        UserStatsTracker { store }
+    }
+
+    /// Returns the store this tracker saves its snapshots to.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Saves a snapshot of the given user's counts, as of now, and returns it.
+    ///
+    /// This can be used with users loaded some other way, to avoid loading them again.
+    pub fn record(&self, user: &TwitterUser) -> UserSnapshot {
+        let snapshot = UserSnapshot::new(user);
+        self.store.save(snapshot);
+        snapshot
+    }
+
+    /// Loads the given users with `user::lookup`, and saves a snapshot of each of them.
+    ///
+    /// Users that couldn't be loaded, for example because they were suspended, are skipped. Like
+    /// `user::lookup`, this loads at most 100 users at once.
+    pub async fn refresh<T, I>(
+        &self,
+        accts: I,
+        token: &auth::Token,
+    ) -> Result<Response<Vec<UserSnapshot>>>
+    where
+        T: Into<UserID>,
+        I: IntoIterator<Item = T>,
+    {
+        let users = user::lookup(accts, token).await?;
+        Ok(Response::map(users, |users| {
+            users.iter().map(|user| self.record(user)).collect()
+        }))
+    }
+
+    /// Loads every snapshot of the given user, oldest first.
+    pub fn history(&self, user_id: u64) -> Vec<UserSnapshot> {
+        let mut history = self.store.load(user_id);
+        history.sort_by_key(|snapshot| snapshot.taken_at);
+        history
+    }
+
+    /// Returns how much the given user's counts changed between their last two snapshots.
+    ///
+    /// Returns `None` if fewer than two snapshots of the user were saved.
+    pub fn latest_change(&self, user_id: u64) -> Option<StatsDelta> {
+        let history = self.history(user_id);
+        match history.as_slice() {
+            [.., from, to] => Some(StatsDelta::between(from, to)),
+            _ => None,
+        }
+    }
+
+    /// Returns how much the given user's counts changed since the given time.
+    ///
+    /// The change is measured from the last snapshot taken at or before `since`, or from the first
+    /// snapshot if they were all taken after it, to the latest snapshot. Returns `None` if fewer
+    /// than two snapshots of the user were saved.
+    pub fn change_since(&self, user_id: u64, since: DateTime<Utc>) -> Option<StatsDelta> {
+        let history = self.history(user_id);
+        if history.len() < 2 {
+            return None;
+        }
+
+        let start = history
+            .iter()
+            .rposition(|snapshot| snapshot.taken_at <= since)
+            .unwrap_or(0)
+            .min(history.len() - 2);
+        Some(StatsDelta::between(
+            &history[start],
+            &history[history.len() - 1],
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MemorySnapshotStore, SnapshotStore, UserSnapshot, UserStatsTracker};
+    use chrono::{Duration, TimeZone, Utc};
+
+    fn snapshot(day: i64, followers: i32, statuses: i32) -> UserSnapshot {
+        UserSnapshot {
+            user_id: 783214,
+            taken_at: Utc.timestamp_opt(1_600_000_000, 0).unwrap() + Duration::days(day),
+            followers_count: followers,
+            friends_count: 100,
+            statuses_count: statuses,
+        }
+    }
+
+    #[test]
+    fn track_changes() {
+        let tracker = UserStatsTracker::new(MemorySnapshotStore::new());
+        assert!(tracker.latest_change(783214).is_none());
+
+        // saved out of order, to check that the history gets sorted
+        tracker.store().save(snapshot(4, 1300, 60));
+        tracker.store().save(snapshot(0, 1000, 40));
+        assert!(tracker.store().load(1).is_empty());
+
+        let change = tracker.latest_change(783214).unwrap();
+        assert_eq!(change.followers, 300);
+        assert_eq!(change.friends, 0);
+        assert_eq!(change.statuses, 20);
+        assert_eq!(change.duration(), Duration::days(4));
+        assert_eq!(change.followers_per_day(), 75.0);
+        assert_eq!(change.statuses_per_day(), 5.0);
+
+        tracker.store().save(snapshot(2, 1100, 50));
+        let history = tracker.history(783214);
+        assert_eq!(history.len(), 3);
+        assert!(history.windows(2).all(|w| w[0].taken_at <= w[1].taken_at));
+
+        let change = tracker.latest_change(783214).unwrap();
+        assert_eq!(change.followers, 200);
+
+        let since = snapshot(3, 0, 0).taken_at;
+        assert_eq!(tracker.change_since(783214, since).unwrap().followers, 200);
+        let before = snapshot(-1, 0, 0).taken_at;
+        assert_eq!(tracker.change_since(783214, before).unwrap().followers, 300);
+        let after = snapshot(10, 0, 0).taken_at;
+        assert_eq!(tracker.change_since(783214, after).unwrap().followers, 200);
+    }
+}
@@ -0,0 +1,212 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Arrival timestamps for stream messages, and tracking of how far behind a stream is running.
+
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{Context, Poll};
+
+use chrono::{DateTime, Duration, Utc};
+use futures::Stream;
+
+use super::{StreamMessage, TwitterStream};
+use crate::error;
+
+/// The number of recent tweets a `LagMonitor` keeps the lag of.
+const LAG_SAMPLES: usize = 100;
+
+/// A message from a stream, along with when it was received.
+///
+/// This is returned by the stream from `TwitterStream::timestamped`.
+#[derive(Debug)]
+pub struct Timestamped<T> {
+    /// When the message was read from the connection.
+    pub received_at: DateTime<Utc>,
+    /// The message itself.
+    pub message: T,
+}
+
+impl Timestamped<StreamMessage> {
+    /// Returns how long after the tweet was posted it was received, for tweet messages.
+    ///
+    /// Twitter gives tweets' creation times to the second, so this is only accurate to a second.
+    /// If the local clock is behind Twitter's, this can be negative.
+    pub fn lag(&self) -> Option<Duration> {
+        Some(self.received_at - self.message.created_at()?)
+    }
+}
+
+impl StreamMessage {
+    /// Returns when the tweet in this message was posted, for tweet messages.
+    ///
+    /// Returns `None` for other messages, and for version 2 tweets that were loaded without their
+    /// `created_at` field.
+    pub fn created_at(&self) -> Option<DateTime<Utc>> {
+        match self {
+            StreamMessage::Tweet(tweet) => Some(tweet.created_at),
+            StreamMessage::TweetV2 { tweet, .. } => tweet.created_at,
+            _ => None,
+        }
+    }
+}
+
+/// Tracks how far behind real time the tweets from a stream are arriving.
+///
+/// Every tweet a `TwitterStream` reads is compared against the time it was posted, and the lag of
+/// the last 100 tweets is kept. If the lag keeps growing, the consumer of the stream isn't keeping
+/// up with it, and Twitter will eventually disconnect it.
+///
+/// Clones of a `LagMonitor` share their measurements, so one can be taken from the stream with
+/// `TwitterStream::lag_monitor` and read from another task while the stream is being consumed.
+///
+/// Twitter gives tweets' creation times to the second, so the lag is only accurate to a second,
+/// and it depends on the local clock being in sync with Twitter's.
+///
+/// ```rust,no_run
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let token: egg_mode::Token = unimplemented!();
+/// use futures::TryStreamExt;
+///
+/// let stream = egg_mode::stream::sample(&token);
+/// let lag = stream.lag_monitor();
+///
+/// tokio::spawn(async move {
+///     loop {
+///         tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+///         if let Some(average) = lag.average() {
+///             println!("tweets are arriving {}s late", average.num_seconds());
+///         }
+///     }
+/// });
+///
+/// stream.try_for_each(|_| futures::future::ok(())).await.unwrap();
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct LagMonitor {
+    samples: Arc<Mutex<VecDeque<Duration>>>,
+}
+
+impl LagMonitor {
+    /// Creates a new `LagMonitor` with no measurements.
+    pub fn new() -> LagMonitor {
+        LagMonitor::default()
+    }
+
+    /// Records the lag of the given message, if it's a tweet.
+    pub fn record(&self, message: &StreamMessage, received_at: DateTime<Utc>) {
+        if let Some(created_at) = message.created_at() {
+            let mut samples = self.lock();
+            if samples.len() == LAG_SAMPLES {
+                samples.pop_front();
+            }
+            samples.push_back(received_at - created_at);
+        }
+    }
+
+    /// Returns the lag of the most recent tweet, if any tweets were received.
+    pub fn latest(&self) -> Option<Duration> {
+        self.lock().back().cloned()
+    }
+
+    /// Returns the average lag of the most recent tweets, if any tweets were received.
+    pub fn average(&self) -> Option<Duration> {
+        let samples = self.lock();
+        if samples.is_empty() {
+            return None;
+        }
+        let total: i64 = samples.iter().map(|lag| lag.num_milliseconds()).sum();
+        Some(Duration::milliseconds(total / samples.len() as i64))
+    }
+
+    /// Returns the largest lag of the most recent tweets, if any tweets were received.
+    pub fn max(&self) -> Option<Duration> {
+        self.lock().iter().max().cloned()
+    }
+
+    /// Returns the number of recent tweets the lag is measured over, up to 100.
+    pub fn samples(&self) -> usize {
+        self.lock().len()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<Duration>> {
+        self.samples.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A `Stream` of messages from a `TwitterStream`, along with when they were received.
+///
+/// This is returned by `TwitterStream::timestamped`.
+#[must_use = "Streams are lazy and do nothing unless polled"]
+pub struct TimestampedStream {
+    inner: TwitterStream,
+}
+
+impl TimestampedStream {
+    pub(crate) fn new(inner: TwitterStream) -> TimestampedStream {
+        TimestampedStream { inner }
+    }
+
+    /// Returns a `LagMonitor` that tracks how far behind this stream is running.
+    pub fn lag_monitor(&self) -> LagMonitor {
+        self.inner.lag_monitor()
+    }
+}
+
+impl Stream for TimestampedStream {
+    type Item = Result<Timestamped<StreamMessage>, error::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.inner).poll_next(cx).map(|msg| {
+            msg.map(|msg| {
+                msg.map(|message| Timestamped {
+                    received_at: self.inner.last_received,
+                    message,
+                })
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::tests::load_file;
+
+    #[test]
+    fn measure_lag() {
+        let tweet: StreamMessage =
+            serde_json::from_str(&load_file("sample_payloads/sample-stream.json")).unwrap();
+        let posted = tweet.created_at().unwrap();
+
+        let monitor = LagMonitor::new();
+        assert_eq!(monitor.average(), None);
+
+        monitor.record(&StreamMessage::Ping, posted);
+        assert_eq!(monitor.samples(), 0);
+
+        monitor.record(&tweet, posted + Duration::seconds(2));
+        monitor.record(&tweet, posted + Duration::seconds(4));
+        assert_eq!(monitor.latest(), Some(Duration::seconds(4)));
+        assert_eq!(monitor.average(), Some(Duration::seconds(3)));
+        assert_eq!(monitor.max(), Some(Duration::seconds(4)));
+
+        for _ in 0..LAG_SAMPLES {
+            monitor
+                .clone()
+                .record(&tweet, posted + Duration::seconds(1));
+        }
+        assert_eq!(monitor.samples(), LAG_SAMPLES);
+        assert_eq!(monitor.max(), Some(Duration::seconds(1)));
+
+        let stamped = Timestamped {
+            received_at: posted + Duration::seconds(5),
+            message: tweet,
+        };
+        assert_eq!(stamped.lag(), Some(Duration::seconds(5)));
+    }
+}
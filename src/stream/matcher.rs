@@ -0,0 +1,284 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Client-side matching of tweets against the filters of a `StreamBuilder`.
+
+use std::collections::HashSet;
+
+use super::{BoundingBox, StreamBuilder, StreamMessage};
+use crate::tweet::Tweet;
+
+/// The filters of a stream that matched a tweet, as returned by `StreamMatcher`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MatchedBy {
+    /// The followed user IDs that matched, because the tweet was posted by that user, replies to
+    /// them, or is a retweet of one of their tweets.
+    pub follow: Vec<u64>,
+    /// The tracked phrases that matched, as they were given to `StreamBuilder::track`.
+    pub track: Vec<String>,
+    /// The locations that matched, because the tweet's coordinates or place fall within them.
+    pub locations: Vec<BoundingBox>,
+}
+
+impl MatchedBy {
+    /// Returns whether none of the stream's filters matched.
+    ///
+    /// Since `StreamMatcher` only approximates Twitter's matching, this can happen for tweets
+    /// that Twitter delivered, for example if the match was in a part of the tweet egg-mode
+    /// doesn't check.
+    pub fn is_empty(&self) -> bool {
+        self.follow.is_empty() && self.track.is_empty() && self.locations.is_empty()
+    }
+}
+
+/// Works out which filters of a stream matched each of its tweets.
+///
+/// Twitter combines the `follow`, `track`, and `locations` filters of a stream, and doesn't say
+/// which of them caused a tweet to be delivered. A `StreamMatcher`, created with
+/// `StreamBuilder::matcher`, checks each tweet against the filters itself, following the rules in
+/// [Twitter's documentation][rules]:
+///
+/// * A followed user matches tweets they posted, replies to them, and retweets of their tweets.
+/// * A tracked phrase matches when every word in it appears in the tweet, ignoring case and
+///   punctuation. The tweet's text, the URLs of its links, its hashtags, and the screen names it
+///   mentions are checked, along with the retweeted or quoted tweet. Words that start with `#` or
+///   `@` only match hashtags or mentions.
+/// * A location matches tweets whose coordinates fall within it, or whose place overlaps it.
+///
+/// [rules]: https://developer.twitter.com/en/docs/twitter-api/v1/tweets/filter-realtime/guides/basic-stream-parameters
+///
+/// This is an approximation of Twitter's matching, so in rare cases a delivered tweet may not
+/// match any filter.
+///
+/// ```rust,no_run
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let token: egg_mode::Token = unimplemented!();
+/// use egg_mode::stream::filter;
+/// use futures::TryStreamExt;
+///
+/// let builder = filter().follow(&[783214]).track(&["rustlang"]);
+/// let matcher = builder.matcher();
+/// let mut stream = builder.start(&token).unwrap().map_ok(|msg| matcher.tag(msg));
+///
+/// while let Some((msg, matched)) = stream.try_next().await.unwrap() {
+///     if let Some(matched) = matched {
+///         println!("matched {:?} and {:?}", matched.follow, matched.track);
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct StreamMatcher {
+    follow: Vec<u64>,
+    track: Vec<(String, Vec<String>)>,
+    locations: Vec<BoundingBox>,
+}
+
+impl StreamMatcher {
+    pub(crate) fn new(builder: &StreamBuilder) -> StreamMatcher {
+        let mut seen = HashSet::new();
+        let track = builder
+            .track
+            .iter()
+            .map(|phrase| phrase.trim())
+            .filter(|phrase| seen.insert(phrase.to_lowercase()))
+            .map(|phrase| {
+                let terms = phrase
+                    .split_whitespace()
+                    .map(|term| term.to_lowercase())
+                    .collect();
+                (phrase.to_string(), terms)
+            })
+            .collect();
+
+        let mut follow = builder.follow.clone();
+        follow.sort_unstable();
+        follow.dedup();
+
+        StreamMatcher {
+            follow,
+            track,
+            locations: builder.locations.clone(),
+        }
+    }
+
+    /// Returns the filters that match the given tweet.
+    pub fn matches(&self, tweet: &Tweet) -> MatchedBy {
+        let follow = self
+            .follow
+            .iter()
+            .cloned()
+            .filter(|&id| matches_follow(tweet, id))
+            .collect();
+
+        let track = if self.track.is_empty() {
+            Vec::new()
+        } else {
+            let words = TweetWords::new(tweet);
+            self.track
+                .iter()
+                .filter(|(_, terms)| terms.iter().all(|term| words.contains(term)))
+                .map(|(phrase, _)| phrase.clone())
+                .collect()
+        };
+
+        let locations = self
+            .locations
+            .iter()
+            .cloned()
+            .filter(|bbox| matches_location(tweet, bbox))
+            .collect();
+
+        MatchedBy {
+            follow,
+            track,
+            locations,
+        }
+    }
+
+    /// Pairs the given message with the filters it matched, if it's a tweet.
+    ///
+    /// This is meant to be used with `map_ok` on a stream, to tag each tweet as it arrives.
+    pub fn tag(&self, msg: StreamMessage) -> (StreamMessage, Option<MatchedBy>) {
+        let matched = match msg {
+            StreamMessage::Tweet(ref tweet) => Some(self.matches(tweet)),
+            _ => None,
+        };
+        (msg, matched)
+    }
+}
+
+fn matches_follow(tweet: &Tweet, id: u64) -> bool {
+    let author = |tweet: &Tweet| tweet.user.as_ref().map(|user| user.id);
+
+    author(tweet) == Some(id)
+        || tweet.in_reply_to_user_id == Some(id)
+        || tweet.retweeted_status.as_deref().and_then(author) == Some(id)
+}
+
+fn matches_location(tweet: &Tweet, bbox: &BoundingBox) -> bool {
+    // Twitter gives tweet coordinates in GeoJSON order, which is (longitude, latitude)
+    if let Some((long, lat)) = tweet.coordinates {
+        return bbox.contains(long, lat);
+    }
+
+    match tweet.place.as_ref().and_then(|place| place.bounds()) {
+        Some(bounds) => bounds.intersects(bbox),
+        None => false,
+    }
+}
+
+/// The words, hashtags, and mentions of a tweet that tracked phrases are checked against.
+struct TweetWords {
+    words: HashSet<String>,
+    hashtags: HashSet<String>,
+    mentions: HashSet<String>,
+}
+
+impl TweetWords {
+    fn new(tweet: &Tweet) -> TweetWords {
+        let mut words = TweetWords {
+            words: HashSet::new(),
+            hashtags: HashSet::new(),
+            mentions: HashSet::new(),
+        };
+        words.add_tweet(tweet);
+        if let Some(ref rt) = tweet.retweeted_status {
+            words.add_tweet(rt);
+        }
+        if let Some(ref quote) = tweet.quoted_status {
+            words.add_tweet(quote);
+        }
+        words
+    }
+
+    fn add_tweet(&mut self, tweet: &Tweet) {
+        self.words.extend(split_words(&tweet.text));
+        for url in &tweet.entities.urls {
+            self.words.extend(split_words(&url.display_url));
+            if let Some(ref expanded) = url.expanded_url {
+                self.words.extend(split_words(expanded));
+            }
+        }
+        for hashtag in &tweet.entities.hashtags {
+            self.hashtags.insert(hashtag.text.to_lowercase());
+        }
+        for mention in &tweet.entities.user_mentions {
+            let name = mention.screen_name.to_lowercase();
+            self.words.insert(name.clone());
+            self.mentions.insert(name);
+        }
+    }
+
+    fn contains(&self, term: &str) -> bool {
+        if let Some(tag) = term.strip_prefix('#') {
+            self.hashtags.contains(tag)
+        } else if let Some(name) = term.strip_prefix('@') {
+            self.mentions.contains(name)
+        } else {
+            // terms with punctuation in them, like "example.com", match if all their parts do
+            let mut parts = split_words(term).peekable();
+            parts.peek().is_some() && parts.all(|part| self.words.contains(&part))
+        }
+    }
+}
+
+/// Splits the given text into lowercase words, at anything that isn't a letter, number, or
+/// underscore.
+fn split_words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::filter;
+    use super::*;
+    use crate::common::tests::load_file;
+
+    #[test]
+    fn tag_tweets() {
+        let sample = load_file("sample_payloads/sample-stream.json");
+        let mut json: serde_json::Value = serde_json::from_str(&sample).unwrap();
+        json["text"] = "Reading about Rust's new release at example.com/news #RustLang".into();
+        json["entities"]["hashtags"] =
+            serde_json::json!([{ "text": "RustLang", "indices": [53, 62] }]);
+        json["coordinates"] = serde_json::json!({ "type": "Point", "coordinates": [-73.5, 40.5] });
+        json["in_reply_to_user_id"] = 12345.into();
+        json["retweeted_status"] = serde_json::Value::Null;
+        json["quoted_status"] = serde_json::Value::Null;
+        let tweet: Tweet = serde_json::from_value(json).unwrap();
+
+        let new_york = BoundingBox::new((-74.0, 40.0), (-73.0, 41.0));
+        let london = BoundingBox::new((-0.5, 51.3), (0.3, 51.7));
+        let matcher = filter()
+            .follow(&[12345, 67890])
+            .track([
+                "rust release",
+                "RUST",
+                "rust python",
+                "#rustlang",
+                "@rustlang",
+                "example.com",
+                "rusts",
+            ])
+            .locations(&[new_york, london])
+            .matcher();
+
+        let (msg, matched) = matcher.tag(StreamMessage::Tweet(tweet));
+        assert!(matches!(msg, StreamMessage::Tweet(_)));
+        let matched = matched.unwrap();
+        assert_eq!(matched.follow, vec![12345]);
+        assert_eq!(
+            matched.track,
+            vec!["rust release", "RUST", "#rustlang", "example.com"]
+        );
+        assert_eq!(matched.locations, vec![new_york]);
+
+        let (_, matched) = matcher.tag(StreamMessage::Ping);
+        assert!(matched.is_none());
+    }
+}
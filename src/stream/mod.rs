@@ -20,7 +20,8 @@
 //! let stream = filter()
 //!     // find tweets mentioning any of the following:
 //!     .track(&["rustlang", "python", "java", "javascript"])
-//!     .start(&token);
+//!     .start(&token)
+//!     .unwrap();
 //!
 //! stream.try_for_each(|m| {
 //!     // Check the message type and print tweet to console
@@ -36,35 +37,59 @@
 //! account of random disconnects, networks resets and stalls. The key points are:
 //!
 //! * The Twitter API sends a Ping message every 30 seconds of message inactivity. So set a timeout
-//! such that after (say) 1 minute of inactivity, the client bounces the connection. This will protect
-//! against network stalls
+//!   such that after (say) 1 minute of inactivity, the client bounces the connection. This will
+//!   protect against network stalls
 //! * Twitter will rate-limit reconnect attempts. So attempt conenctions with a linear or exponential
-//! backoff strategy
+//!   backoff strategy
 //! * In the case of an unreliable connection (e.g. mobile network), fall back to the polling API
 //!
 //! The [official guide](https://developer.twitter.com/en/docs/tweets/filter-realtime/guides/connecting) has more information.
+//!
+//! ### Matched filters
+//! Twitter doesn't say which of a stream's filters matched each tweet it delivers. To find out,
+//! use a [`StreamMatcher`] from `StreamBuilder::matcher` to check each tweet against the filters.
+//!
+//! [`StreamMatcher`]: struct.StreamMatcher.html
+//!
+//! ### Lag
+//! If a consumer can't keep up with a stream, Twitter buffers the backlog for a while and then
+//! disconnects it. Every `TwitterStream` measures how long after posting each tweet arrives; use
+//! `TwitterStream::lag_monitor` to watch for a growing backlog, or `TwitterStream::timestamped` to
+//! get the arrival time of each message.
+use std::collections::HashSet;
 use std::future::Future;
 use std::pin::Pin;
 use std::str::FromStr;
 use std::task::{Context, Poll};
 use std::{self, io};
 
+use chrono::{DateTime, Utc};
 use futures::Stream;
 use hyper::client::ResponseFuture;
 use hyper::{Body, Request};
 use serde::de::Error;
-use serde::{Deserialize, Deserializer, Serialize};
+use serde::{Deserialize, Deserializer};
 use serde_json;
 
 use crate::auth::Token;
 use crate::common::*;
+use crate::error::BuilderError;
 use crate::tweet::Tweet;
-use crate::{error, links};
+use crate::{error, links, v2};
+
+mod lag;
+mod matcher;
+
+pub use self::lag::{LagMonitor, Timestamped, TimestampedStream};
+pub use self::matcher::{MatchedBy, StreamMatcher};
+pub use crate::place::BoundingBox;
+pub use crate::tweet::FilterLevel;
 
 // TODO rewrite this
 // https://developer.twitter.com/en/docs/tweets/filter-realtime/guides/streaming-message-types
 /// Represents the kinds of messages that can be sent over Twitter's Streaming API.
 #[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum StreamMessage {
     /// A blank line, sent periodically to keep the connection alive.
     Ping,
@@ -76,6 +101,15 @@ pub enum StreamMessage {
     /// Note that the `entities` inside the `user` field will be empty for tweets received via the
     /// Streaming API.
     Tweet(Tweet),
+    /// A new tweet, received from a version 2 stream like [`sample_v2`].
+    ///
+    /// [`sample_v2`]: fn.sample_v2.html
+    TweetV2 {
+        /// The tweet that was posted.
+        tweet: v2::Tweet,
+        /// Objects referenced by the tweet, such as its author.
+        includes: v2::Includes,
+    },
     /// Notice given when a user deletes a post.
     ///
     /// Clients are expected to comply with these notices by removing the status "from memory and
@@ -144,54 +178,149 @@ impl<'de> Deserialize<'de> for StreamMessage {
     where
         D: Deserializer<'de>,
     {
-        macro_rules! fetch {
-            ($input: ident, $key: expr) => {
-                $input
-                    .get($key)
-                    .and_then(|val| serde_json::from_value(val.clone()).ok())
-                    .ok_or_else(|| D::Error::custom("Failed"))
-            };
-        }
-
-        let input = serde_json::Value::deserialize(deser)?;
-        let msg = if let Some(del) = input.get("delete").and_then(|d| d.get("status")) {
-            StreamMessage::Delete {
-                status_id: fetch!(del, "id")?,
-                user_id: fetch!(del, "user_id")?,
-            }
-        } else if let Some(scrub) = input.get("scrub_geo") {
-            StreamMessage::ScrubGeo {
-                user_id: fetch!(scrub, "user_id")?,
-                up_to_status_id: fetch!(scrub, "up_to_status_id")?,
+        // The message is read from the input once into serde's buffer for untagged enums, and each
+        // kind of message is then tried against that buffer in order. That includes tweets, which
+        // are only loaded after every kind listed before them has failed on its missing key, but
+        // each attempt borrows from the buffer rather than copying it like a `serde_json::Value`.
+        let msg = match RawMessage::deserialize(deser)? {
+            RawMessage::Delete { delete } => StreamMessage::Delete {
+                status_id: delete.status.id,
+                user_id: delete.status.user_id,
+            },
+            RawMessage::ScrubGeo { scrub_geo } => StreamMessage::ScrubGeo {
+                user_id: scrub_geo.user_id,
+                up_to_status_id: scrub_geo.up_to_status_id,
+            },
+            RawMessage::StatusWithheld { status_withheld } => StreamMessage::StatusWithheld {
+                status_id: status_withheld.id,
+                user_id: status_withheld.user_id,
+                withheld_in_countries: status_withheld.withheld_in_countries,
+            },
+            RawMessage::UserWithheld { user_withheld } => StreamMessage::UserWithheld {
+                user_id: user_withheld.id,
+                withheld_in_countries: user_withheld.withheld_in_countries,
+            },
+            RawMessage::Disconnect { disconnect } => {
+                StreamMessage::Disconnect(disconnect.code, disconnect.reason)
             }
-        } else if let Some(tweet) = input.get("status_withheld") {
-            StreamMessage::StatusWithheld {
-                status_id: fetch!(tweet, "id")?,
-                user_id: fetch!(tweet, "user_id")?,
-                withheld_in_countries: fetch!(tweet, "withheld_in_countries")?,
-            }
-        } else if let Some(user) = input.get("user_withheld") {
-            StreamMessage::UserWithheld {
-                user_id: fetch!(user, "id")?,
-                withheld_in_countries: fetch!(user, "withheld_in_countries")?,
-            }
-        } else if let Some(err) = input.get("disconnect") {
-            StreamMessage::Disconnect(fetch!(err, "code")?, fetch!(err, "reason")?)
-        } else if let Some(friends) = input.get("friends") {
-            StreamMessage::FriendList(
-                serde_json::from_value(friends.clone())
-                    .map_err(|e| D::Error::custom(format!("{}", e)))?,
-            )
-        // TODO remove clone?
-        } else if let Ok(tweet) = serde_json::from_value::<Tweet>(input.clone()) {
-            StreamMessage::Tweet(tweet)
-        } else {
-            StreamMessage::Unknown(input.clone())
+            RawMessage::FriendList { friends } => StreamMessage::FriendList(friends),
+            RawMessage::TweetV2 { data, includes } => StreamMessage::TweetV2 {
+                tweet: data,
+                includes,
+            },
+            RawMessage::Tweet(tweet) => StreamMessage::Tweet(tweet),
+            RawMessage::Unknown(UnknownMessage(value)) => StreamMessage::Unknown(value),
         };
         Ok(msg)
     }
 }
 
+/// The shapes a `StreamMessage` can take, as sent by Twitter.
+#[derive(Deserialize)]
+#[serde(untagged)]
+#[allow(clippy::large_enum_variant)]
+enum RawMessage {
+    Delete {
+        delete: DeleteMessage,
+    },
+    ScrubGeo {
+        scrub_geo: ScrubGeoMessage,
+    },
+    StatusWithheld {
+        status_withheld: StatusWithheldMessage,
+    },
+    UserWithheld {
+        user_withheld: UserWithheldMessage,
+    },
+    Disconnect {
+        disconnect: DisconnectMessage,
+    },
+    FriendList {
+        friends: Vec<u64>,
+    },
+    TweetV2 {
+        data: v2::Tweet,
+        #[serde(default, deserialize_with = "includes_or_default")]
+        includes: v2::Includes,
+    },
+    Tweet(Tweet),
+    Unknown(UnknownMessage),
+}
+
+/// Loads the `includes` of a version 2 tweet, leaving them empty if they can't be loaded, so the
+/// tweet itself isn't lost.
+fn includes_or_default<'de, D>(deser: D) -> Result<v2::Includes, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(v2::Includes::deserialize(deser).unwrap_or_default())
+}
+
+/// A message that isn't any of the kinds egg-mode knows about.
+///
+/// Messages with the key of a known kind are rejected, so that a malformed message of that kind is
+/// reported as an error rather than passed along as `StreamMessage::Unknown`.
+struct UnknownMessage(serde_json::Value);
+
+impl<'de> Deserialize<'de> for UnknownMessage {
+    fn deserialize<D>(deser: D) -> Result<UnknownMessage, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        const KNOWN_KEYS: &[&str] = &[
+            "delete",
+            "scrub_geo",
+            "status_withheld",
+            "user_withheld",
+            "disconnect",
+            "friends",
+            "data",
+        ];
+
+        let value = serde_json::Value::deserialize(deser)?;
+        if let Some(key) = KNOWN_KEYS.iter().find(|key| value.get(**key).is_some()) {
+            return Err(D::Error::custom(format!("invalid {} message", key)));
+        }
+        Ok(UnknownMessage(value))
+    }
+}
+
+#[derive(Deserialize)]
+struct DeleteMessage {
+    status: DeletedStatus,
+}
+
+#[derive(Deserialize)]
+struct DeletedStatus {
+    id: u64,
+    user_id: u64,
+}
+
+#[derive(Deserialize)]
+struct ScrubGeoMessage {
+    user_id: u64,
+    up_to_status_id: u64,
+}
+
+#[derive(Deserialize)]
+struct StatusWithheldMessage {
+    id: u64,
+    user_id: u64,
+    withheld_in_countries: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct UserWithheldMessage {
+    id: u64,
+    withheld_in_countries: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct DisconnectMessage {
+    code: u64,
+    reason: String,
+}
+
 impl FromStr for StreamMessage {
     type Err = error::Error;
     fn from_str(input: &str) -> Result<Self, error::Error> {
@@ -211,23 +340,65 @@ pub struct TwitterStream {
     request: Option<Request<Body>>,
     response: Option<ResponseFuture>,
     body: Option<Body>,
+    /// If this stream connects to a version 1.1 endpoint, the endpoint that replaced it.
+    alternative: Option<&'static str>,
+    /// The body of an error response that is being checked for a retirement notice, along with
+    /// the status code and rate limit of the response.
+    error_body: Option<(hyper::StatusCode, Option<RateLimit>, ErrorBodyFuture)>,
+    lag: LagMonitor,
+    last_received: DateTime<Utc>,
+    /// The longest message the stream will buffer, taken from the client configuration when the
+    /// stream was created.
+    max_size: usize,
+    /// Whether the stream has given up on its connection, after which it only returns `None`.
+    finished: bool,
 }
 
+type ErrorBodyFuture = Pin<Box<dyn Future<Output = error::Result<Vec<u8>>> + Send>>;
+
 impl TwitterStream {
     pub(crate) fn new(request: Request<Body>) -> TwitterStream {
+        let alternative = retired_alternative(&request.uri().to_string());
         TwitterStream {
             buf: vec![],
             request: Some(request),
             response: None,
             body: None,
+            alternative,
+            error_body: None,
+            lag: LagMonitor::new(),
+            last_received: Utc::now(),
+            max_size: max_response_size(),
+            finished: false,
         }
     }
+
+    /// Returns a `LagMonitor` that tracks how far behind real time the tweets from this stream are
+    /// arriving.
+    ///
+    /// The monitor shares its measurements with the stream, so it can be read from elsewhere
+    /// while the stream is being consumed. See the [`LagMonitor`] docs for details.
+    ///
+    /// [`LagMonitor`]: struct.LagMonitor.html
+    pub fn lag_monitor(&self) -> LagMonitor {
+        self.lag.clone()
+    }
+
+    /// Converts this stream into one that returns each message along with the time it was
+    /// received.
+    pub fn timestamped(self) -> TimestampedStream {
+        TimestampedStream::new(self)
+    }
 }
 
 impl Stream for TwitterStream {
     type Item = Result<StreamMessage, error::Error>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        if self.finished {
+            return Poll::Ready(None);
+        }
+
         if let Some(req) = self.request.take() {
             self.response = Some(get_response(req));
         }
@@ -242,15 +413,47 @@ impl Stream for TwitterStream {
                 Poll::Ready(Ok(resp)) => {
                     let status = resp.status();
                     if !status.is_success() {
-                        //TODO: should i try to pull the response regardless?
-                        return Poll::Ready(Some(Err(error::Error::BadStatus(status))));
+                        let rate_limit = error_rate_limit(resp.headers());
+                        match (status, self.alternative) {
+                            (hyper::StatusCode::GONE, Some(alternative)) => {
+                                return Poll::Ready(Some(Err(error::Error::EndpointRetired {
+                                    alternative,
+                                })));
+                            }
+                            (hyper::StatusCode::FORBIDDEN, Some(_)) => {
+                                // read the body to see whether the endpoint was retired
+                                let body = Box::pin(read_body(resp.into_body(), self.max_size));
+                                self.error_body = Some((status, rate_limit, body));
+                            }
+                            _ => {
+                                return Poll::Ready(Some(Err(error::Error::BadStatus(
+                                    status, rate_limit,
+                                ))));
+                            }
+                        }
+                    } else {
+                        self.body = Some(resp.into_body());
                     }
-
-                    self.body = Some(resp.into_body());
                 }
             }
         }
 
+        if let Some((status, rate_limit, mut body)) = self.error_body.take() {
+            return match body.as_mut().poll(cx) {
+                Poll::Pending => {
+                    self.error_body = Some((status, rate_limit, body));
+                    Poll::Pending
+                }
+                Poll::Ready(Ok(body)) if is_retirement_notice(&body) => {
+                    let alternative = self.alternative.unwrap_or_default();
+                    Poll::Ready(Some(Err(error::Error::EndpointRetired { alternative })))
+                }
+                Poll::Ready(_) => {
+                    Poll::Ready(Some(Err(error::Error::BadStatus(status, rate_limit))))
+                }
+            };
+        }
+
         if let Some(mut body) = self.body.take() {
             loop {
                 match Pin::new(&mut body).poll_next(cx) {
@@ -282,7 +485,19 @@ impl Stream for TwitterStream {
                             };
 
                             self.buf.drain(..pos);
-                            return Poll::Ready(Some(Ok(resp?)));
+                            let msg = resp?;
+                            self.last_received = Utc::now();
+                            self.lag.record(&msg, self.last_received);
+                            return Poll::Ready(Some(Ok(msg)));
+                        }
+
+                        // a message this long means the stream has gone wrong; drop the connection
+                        // rather than keep buffering it, and end the stream after the error
+                        let limit = self.max_size;
+                        if self.buf.len() > limit {
+                            self.buf.clear();
+                            self.finished = true;
+                            return Poll::Ready(Some(Err(error::Error::ResponseTooLarge(limit))));
                         }
                     }
                 }
@@ -293,37 +508,30 @@ impl Stream for TwitterStream {
     }
 }
 
-/// Represents the amount of filtering that can be done to streams on Twitter's side.
-///
-/// According to Twitter's documentation, "When displaying a stream of Tweets to end users
-/// (dashboards or live feeds at a presentation or conference, for example) it is suggested that
-/// you set this value to medium."
-#[derive(Copy, Clone, Debug, Deserialize, Serialize)]
-pub enum FilterLevel {
-    /// No filtering.
-    #[serde(rename = "none")]
-    None,
-    /// A light amount of filtering.
-    #[serde(rename = "low")]
-    Low,
-    /// A medium amount of filtering.
-    #[serde(rename = "medium")]
-    Medium,
-}
-
-/// `Display` impl to turn `FilterLevel` variants into the form needed for stream parameters. This
-/// is basically "the variant name, in lowercase".
-// TODO Probably can remove this somehow
-impl ::std::fmt::Display for FilterLevel {
-    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
-        match *self {
-            FilterLevel::None => write!(f, "none"),
-            FilterLevel::Low => write!(f, "low"),
-            FilterLevel::Medium => write!(f, "medium"),
-        }
+/// Returns the function for the endpoint that replaced the given version 1.1 streaming endpoint,
+/// or `None` if the URL isn't one of those endpoints.
+fn retired_alternative(url: &str) -> Option<&'static str> {
+    if url.starts_with(links::stream::FILTER) {
+        Some("`stream::filter_v2`")
+    } else if url.starts_with(links::stream::SAMPLE) {
+        Some("`stream::sample_v2`")
+    } else {
+        None
     }
 }
 
+/// The error code Twitter returns to apps whose access level doesn't include the endpoint,
+/// which is what version 1.1 streaming endpoints return once they are retired for the app.
+const CLIENT_NOT_ENROLLED: i32 = 453;
+
+/// Returns whether the given `403 Forbidden` response body says that the endpoint isn't
+/// available to the app anymore.
+fn is_retirement_notice(body: &[u8]) -> bool {
+    serde_json::from_slice::<error::TwitterErrors>(body)
+        .map(|errors| errors.errors.iter().any(|e| e.code == CLIENT_NOT_ENROLLED))
+        .unwrap_or(false)
+}
+
 /// Represents a `TwitterStream` before it is started. Use the various methods to build
 /// up the filters on your stream.
 ///
@@ -332,8 +540,9 @@ impl ::std::fmt::Display for FilterLevel {
 /// all tweets that match (user id OR phrase), NOT (user id AND phrase).
 /// For more details see the [official docs](https://developer.twitter.com/en/docs/tweets/filter-realtime/guides/basic-stream-parameters)
 ///
-/// __Note__: The user __must__ specify at least one `track`, `follow` or `locations` filter or else
-/// the stream will __fail__ at point of connection.
+/// __Note__: At least one `track`, `follow` or `locations` filter must be given, or `start` will
+/// return `BuilderError::NoPredicates` instead of connecting.
+#[derive(Debug, Clone)]
 pub struct StreamBuilder {
     url: &'static str,
     follow: Vec<u64>,
@@ -390,7 +599,9 @@ impl StreamBuilder {
     /// corresponding to any of the languages listed on Twitter’s
     /// [advanced search](https://twitter.com/search-advancedpage) page.
     ///
-    /// __Note__ This library does __not__ validate the language codes.
+    /// The codes are checked against `SUPPORTED_LANGUAGES` when the stream is started, and `start`
+    /// returns `Error::UnsupportedLanguage` for any code that isn't in that list. Codes are
+    /// compared without regard to case.
     pub fn language<I: IntoIterator<Item = S>, S: AsRef<str>>(mut self, languages: I) -> Self {
         self.language
             .extend(languages.into_iter().map(|s| s.as_ref().to_string()));
@@ -429,11 +640,36 @@ impl StreamBuilder {
     }
 
     /// Finalizes the stream parameters and returns the resulting `TwitterStream`.
-    pub fn start(self, token: &Token) -> TwitterStream {
-        // Re connection failure, arguably this library should check that either 'track' or
-        // 'follow' exist and return an error if not. However, in such a case the request is not
-        // 'invalid' from POV of twitter api, rather it is invalid at the application level.
-        // So I think the current behaviour make sense.
+    ///
+    /// Repeated `track` phrases are only sent to Twitter once. The stream isn't connected, and an
+    /// error is returned instead, if:
+    ///
+    /// - none of `follow`, `track`, or `locations` were given, or one of the `locations` isn't a
+    ///   valid bounding box, in which case this returns `Error::InvalidStream` with the
+    ///   `BuilderError` describing the problem;
+    /// - any of the codes given to `language` aren't in `SUPPORTED_LANGUAGES`, in which case this
+    ///   returns `Error::UnsupportedLanguage`.
+    pub fn start(self, token: &Token) -> Result<TwitterStream, error::Error> {
+        let params = self.params()?;
+        let req = post(self.url, token, Some(&params));
+
+        Ok(TwitterStream::new(req))
+    }
+
+    fn params(&self) -> Result<ParamList, error::Error> {
+        if self.follow.is_empty() && self.track.is_empty() && self.locations.is_empty() {
+            return Err(BuilderError::NoPredicates.into());
+        }
+        if let Some(bbox) = self.locations.iter().find(|bbox| !bbox.is_valid()) {
+            return Err(BuilderError::InvalidBoundingBox(*bbox).into());
+        }
+        if let Some(lang) = self
+            .language
+            .iter()
+            .find(|lang| !is_supported_language(lang))
+        {
+            return Err(error::Error::UnsupportedLanguage(lang.clone()));
+        }
 
         let mut params =
             ParamList::new().add_opt_param("filter_level", self.filter_level.map_string());
@@ -449,12 +685,28 @@ impl StreamBuilder {
         }
 
         if !self.track.is_empty() {
-            let to_track = self.track.join(",");
+            // Twitter matches phrases without regard to case, so phrases that only differ in case
+            // are repeats too
+            let mut seen = HashSet::new();
+            let to_track = self
+                .track
+                .iter()
+                .map(|phrase| phrase.trim())
+                .filter(|phrase| seen.insert(phrase.to_lowercase()))
+                .collect::<Vec<_>>()
+                .join(",");
             params.add_param_ref("track", to_track);
         }
 
         if !self.language.is_empty() {
-            let langs = self.language.join(",");
+            let mut seen = HashSet::new();
+            let langs = self
+                .language
+                .iter()
+                .map(|lang| lang.to_lowercase())
+                .filter(|lang| seen.insert(lang.clone()))
+                .collect::<Vec<_>>()
+                .join(",");
             params.add_param_ref("language", langs);
         }
 
@@ -468,9 +720,244 @@ impl StreamBuilder {
             params.add_param_ref("locations", locs);
         }
 
-        let req = post(self.url, token, Some(&params));
+        Ok(params)
+    }
 
-        TwitterStream::new(req)
+    /// Returns a `StreamMatcher` that finds which of this stream's filters matched each tweet.
+    ///
+    /// See the [`StreamMatcher`] documentation for details.
+    ///
+    /// [`StreamMatcher`]: struct.StreamMatcher.html
+    pub fn matcher(&self) -> StreamMatcher {
+        StreamMatcher::new(self)
+    }
+
+    /// Finalizes the stream parameters and returns a `ManagedFilterStream`, whose filters can be
+    /// changed later without ending the stream.
+    ///
+    /// See the [`ManagedFilterStream`] documentation for details.
+    ///
+    /// Like `start`, this returns `Error::UnsupportedLanguage` if any of the given language codes
+    /// aren't supported.
+    ///
+    /// [`ManagedFilterStream`]: struct.ManagedFilterStream.html
+    pub fn start_managed(self, token: &Token) -> Result<ManagedFilterStream, error::Error> {
+        let current = self.start(token)?;
+        Ok(ManagedFilterStream {
+            token: token.clone(),
+            lag: current.lag_monitor(),
+            switch: Switchover::new(current),
+        })
+    }
+}
+
+/// The language codes accepted by `StreamBuilder::language`.
+///
+/// These are the languages Twitter detects in tweets, as listed by its `help/languages` endpoint,
+/// plus `und` for tweets whose language couldn't be determined.
+pub const SUPPORTED_LANGUAGES: &[&str] = &[
+    "am", "ar", "bg", "bn", "bo", "ca", "ckb", "cs", "cy", "da", "de", "dv", "el", "en", "en-gb",
+    "es", "et", "eu", "fa", "fi", "fil", "fr", "ga", "gu", "he", "hi", "hr", "ht", "hu", "hy",
+    "id", "in", "is", "it", "iu", "iw", "ja", "ka", "km", "kn", "ko", "lo", "lt", "lv", "ml", "mr",
+    "ms", "msa", "my", "ne", "nl", "no", "or", "pa", "pl", "ps", "pt", "ro", "ru", "sd", "si",
+    "sk", "sl", "sr", "sv", "ta", "te", "th", "tl", "tr", "ug", "uk", "und", "ur", "vi", "zh",
+    "zh-cn", "zh-tw",
+];
+
+fn is_supported_language(lang: &str) -> bool {
+    SUPPORTED_LANGUAGES
+        .iter()
+        .any(|supported| supported.eq_ignore_ascii_case(lang))
+}
+
+/// A filtered stream whose parameters can be replaced while it is running.
+///
+/// Twitter only applies filter parameters when a stream connection is opened, so changing the
+/// tracked phrases or followed users requires a new connection. `ManagedFilterStream` handles this
+/// switchover for you: calling [`update_filter`] opens a second connection with the new
+/// parameters, while messages keep arriving from the old connection. As soon as the new
+/// connection delivers its first message, the old connection is closed and the stream continues
+/// from the new one. Tweets that were already delivered by the old connection during the overlap
+/// are not repeated by the new one.
+///
+/// Twitter only allows one filtered stream connection per account at a time, so it may close the
+/// old connection as soon as the new one opens. Errors and disconnections from the old connection
+/// during the switchover are therefore not reported; an error opening the *new* connection is
+/// returned from the stream, and the old connection is kept if it's still open.
+///
+/// [`update_filter`]: #method.update_filter
+///
+/// ```rust,no_run
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let token: egg_mode::Token = unimplemented!();
+/// use egg_mode::stream::{filter, StreamMessage};
+/// use futures::TryStreamExt;
+///
+/// let mut stream = filter().track(&["rustlang"]).start_managed(&token).unwrap();
+///
+/// while let Some(msg) = stream.try_next().await.unwrap() {
+///     if let StreamMessage::Tweet(tweet) = msg {
+///         println!("{}", tweet.text);
+///         if tweet.text.contains("#rustconf") {
+///             stream.update_filter(filter().track(&["rustlang", "rustconf"])).unwrap();
+///         }
+///     }
+/// }
+/// # }
+/// ```
+#[must_use = "Streams are lazy and do nothing unless polled"]
+pub struct ManagedFilterStream {
+    token: Token,
+    switch: Switchover<TwitterStream>,
+    lag: LagMonitor,
+}
+
+impl ManagedFilterStream {
+    /// Replaces the filter parameters of this stream with the ones in the given `StreamBuilder`.
+    ///
+    /// The new connection is opened the next time the stream is polled. If a previous update is
+    /// still connecting, it is abandoned in favor of this one. If the new parameters are rejected
+    /// by `StreamBuilder::start`, the error is returned and the stream is left as it was.
+    pub fn update_filter(&mut self, filter: StreamBuilder) -> Result<(), error::Error> {
+        let mut pending = filter.start(&self.token)?;
+        pending.lag = self.lag.clone();
+        self.switch.start(pending);
+        Ok(())
+    }
+
+    /// Returns whether the stream is currently switching over to new filter parameters.
+    pub fn is_switching(&self) -> bool {
+        self.switch.pending.is_some()
+    }
+
+    /// Returns a `LagMonitor` that tracks how far behind real time the tweets from this stream are
+    /// arriving.
+    ///
+    /// The monitor is carried over when the filter is updated, so it covers every connection this
+    /// stream makes.
+    pub fn lag_monitor(&self) -> LagMonitor {
+        self.lag.clone()
+    }
+}
+
+impl Stream for ManagedFilterStream {
+    type Item = Result<StreamMessage, error::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.switch).poll_next(cx)
+    }
+}
+
+/// The switchover from one stream connection to another, for `ManagedFilterStream`.
+///
+/// While the new connection is opening, the IDs of the tweets from the old one are kept in
+/// `overlap`. Once the new connection delivers its first message, the old one is dropped, and the
+/// tweets in `overlap` are skipped if the new connection repeats them. Since tweet IDs grow over
+/// time, the new connection is caught up once it delivers a tweet newer than any in `overlap`, so
+/// the set is cleared then, and no longer checked.
+struct Switchover<S> {
+    current: Option<S>,
+    pending: Option<S>,
+    overlap: HashSet<u64>,
+}
+
+impl<S> Switchover<S> {
+    fn new(current: S) -> Switchover<S> {
+        Switchover {
+            current: Some(current),
+            pending: None,
+            overlap: HashSet::new(),
+        }
+    }
+
+    /// Starts switching over to the given connection, abandoning any that was still connecting.
+    fn start(&mut self, pending: S) {
+        self.pending = Some(pending);
+        self.overlap.clear();
+    }
+
+    /// Returns whether the given message from the new connection was already delivered by the old
+    /// one, and clears `overlap` once the new connection has caught up.
+    fn is_repeat(&mut self, msg: &StreamMessage) -> bool {
+        let id = match msg {
+            StreamMessage::Tweet(tweet) if !self.overlap.is_empty() => tweet.id,
+            _ => return false,
+        };
+
+        if self.overlap.remove(&id) {
+            true
+        } else {
+            if self.overlap.iter().all(|&seen| seen < id) {
+                self.overlap.clear();
+            }
+            false
+        }
+    }
+}
+
+impl<S> Stream for Switchover<S>
+where
+    S: Stream<Item = Result<StreamMessage, error::Error>> + Unpin,
+{
+    type Item = Result<StreamMessage, error::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        if let Some(pending) = this.pending.as_mut() {
+            match Pin::new(pending).poll_next(cx) {
+                Poll::Ready(Some(Ok(msg))) => {
+                    // the new connection is live, so switch over to it
+                    this.current = this.pending.take();
+                    if !this.is_repeat(&msg) {
+                        return Poll::Ready(Some(Ok(msg)));
+                    }
+                }
+                Poll::Ready(Some(Err(e))) => {
+                    this.pending = None;
+                    this.overlap.clear();
+                    return Poll::Ready(Some(Err(e)));
+                }
+                Poll::Ready(None) => {
+                    this.pending = None;
+                    this.overlap.clear();
+                }
+                Poll::Pending => {
+                    if let Some(current) = this.current.as_mut() {
+                        match Pin::new(current).poll_next(cx) {
+                            Poll::Ready(Some(Ok(msg))) => {
+                                if let StreamMessage::Tweet(ref tweet) = msg {
+                                    this.overlap.insert(tweet.id);
+                                }
+                                return Poll::Ready(Some(Ok(msg)));
+                            }
+                            Poll::Ready(Some(Err(_))) | Poll::Ready(None) => {
+                                this.current = None;
+                            }
+                            Poll::Pending => (),
+                        }
+                    }
+                    return Poll::Pending;
+                }
+            }
+        }
+
+        loop {
+            let current = match this.current.as_mut() {
+                Some(current) => current,
+                None => return Poll::Ready(None),
+            };
+
+            match Pin::new(current).poll_next(cx) {
+                Poll::Ready(Some(Ok(msg))) if this.is_repeat(&msg) => continue,
+                Poll::Ready(None) => {
+                    this.current = None;
+                    return Poll::Ready(None);
+                }
+                other => return other,
+            }
+        }
     }
 }
 
@@ -493,36 +980,150 @@ pub fn sample(token: &Token) -> TwitterStream {
     TwitterStream::new(req)
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
-/// Represents a bounding box of (longitude, latitude) pairs.
+/// Begins building a request to the version 2 sampled stream, which returns "roughly a 1% random
+/// sample of publicly available Tweets in real-time".
+///
+/// Unlike the v1.1 [`sample`] stream, this stream requires a Bearer token from
+/// [`auth::bearer_token`]. Tweets arrive as [`StreamMessage::TweetV2`].
+///
+/// [`sample`]: fn.sample.html
+/// [`auth::bearer_token`]: ../auth/fn.bearer_token.html
+/// [`StreamMessage::TweetV2`]: enum.StreamMessage.html#variant.TweetV2
+///
+/// ```rust,no_run
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let token: egg_mode::Token = unimplemented!();
+/// use futures::TryStreamExt;
+/// use egg_mode::stream::{sample_v2, StreamMessage};
 ///
-/// Guaranteed to be in-bounds.
-pub struct BoundingBox {
-    southwest: (f64, f64),
-    northeast: (f64, f64),
+/// let builder = sample_v2();
+/// let mut stream = builder.start(&token);
+///
+/// while let Some(msg) = stream.try_next().await.unwrap() {
+///     if let StreamMessage::TweetV2 { tweet, .. } = msg {
+///         println!("{}", tweet.text);
+///     }
+/// }
+///
+/// // after a disconnect, recover the tweets that were missed in the meantime
+/// let stream = builder.backfill_minutes(2).start(&token);
+/// # }
+/// ```
+pub fn sample_v2() -> SampleStreamBuilder {
+    SampleStreamBuilder {
+        partition: None,
+        backfill_minutes: None,
+    }
 }
 
-impl ::std::fmt::Display for BoundingBox {
-    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
-        write!(
-            f,
-            "{},{},{},{}",
-            self.southwest.0, self.southwest.1, self.northeast.0, self.northeast.1
-        )
+/// Begins building a request to the version 2 filtered stream, which returns the tweets that
+/// match the filter rules saved for the app.
+///
+/// Unlike the v1.1 [`filter`] stream, the filters aren't given when connecting. Twitter keeps
+/// them as rules that are managed with the `POST /2/tweets/search/stream/rules` endpoint, which
+/// can be called with [`raw::request_post_json`]. Like [`sample_v2`], this stream requires a
+/// Bearer token from [`auth::bearer_token`], and tweets arrive as [`StreamMessage::TweetV2`].
+///
+/// [`filter`]: fn.filter.html
+/// [`raw::request_post_json`]: ../raw/fn.request_post_json.html
+/// [`sample_v2`]: fn.sample_v2.html
+/// [`auth::bearer_token`]: ../auth/fn.bearer_token.html
+/// [`StreamMessage::TweetV2`]: enum.StreamMessage.html#variant.TweetV2
+pub fn filter_v2() -> FilterStreamBuilder {
+    FilterStreamBuilder {
+        backfill_minutes: None,
     }
 }
 
-impl BoundingBox {
-    /// New BoundingBox. Expects (logitude, latitude pairs) describing the southwest and
-    /// northeast points of the bounding box. Checks the values are in-bounds.
-    pub fn new(southwest: (f64, f64), northeast: (f64, f64)) -> BoundingBox {
-        // TODO integrate with `bounding_box` in `place` module.
-        // TODO check consitency
-        BoundingBox {
-            southwest,
-            northeast,
+/// Represents a connection to the version 2 filtered stream before it is started.
+///
+/// Like `SampleStreamBuilder`, `start` does not consume the builder, so the same settings can be
+/// used to reconnect after the stream drops.
+#[derive(Debug, Clone)]
+pub struct FilterStreamBuilder {
+    backfill_minutes: Option<u8>,
+}
+
+impl FilterStreamBuilder {
+    /// Asks Twitter to deliver tweets from up to the given number of minutes before connecting.
+    ///
+    /// This works the same way as [`SampleStreamBuilder::backfill_minutes`], and is also only
+    /// available to accounts with Academic Research access.
+    ///
+    /// __Note__: This library does __not__ validate the number of minutes.
+    ///
+    /// [`SampleStreamBuilder::backfill_minutes`]: struct.SampleStreamBuilder.html#method.backfill_minutes
+    pub fn backfill_minutes(self, minutes: u8) -> Self {
+        FilterStreamBuilder {
+            backfill_minutes: Some(minutes),
         }
     }
+
+    /// Finalizes the stream parameters and returns the resulting `TwitterStream`.
+    pub fn start(&self, token: &Token) -> TwitterStream {
+        let params = v2::add_fields(ParamList::new())
+            .add_opt_param("backfill_minutes", self.backfill_minutes.map_string());
+
+        let req = get(links::stream::FILTER_V2, token, Some(&params));
+        TwitterStream::new(req)
+    }
+}
+
+/// Represents a connection to the version 2 sampled stream before it is started.
+///
+/// Unlike `StreamBuilder`, `start` does not consume the builder, so the same settings can be used
+/// to reconnect after the stream drops.
+#[derive(Debug, Clone)]
+pub struct SampleStreamBuilder {
+    partition: Option<u8>,
+    backfill_minutes: Option<u8>,
+}
+
+impl SampleStreamBuilder {
+    /// Connects to one partition of the 10% sampled stream, instead of the 1% stream.
+    ///
+    /// The 10% stream is only available to accounts with Academic Research access, and is split
+    /// into 20 partitions, numbered 1 through 20, each of which needs a separate connection.
+    ///
+    /// __Note__: This library does __not__ validate the partition number.
+    pub fn partition(self, partition: u8) -> Self {
+        SampleStreamBuilder {
+            partition: Some(partition),
+            ..self
+        }
+    }
+
+    /// Asks Twitter to deliver tweets from up to the given number of minutes before connecting.
+    ///
+    /// This is meant for recovering tweets that were missed while reconnecting after a
+    /// disconnection. Twitter allows up to 5 minutes of backfill, and this is only available to
+    /// accounts with Academic Research access. Tweets delivered this way may include duplicates
+    /// of tweets that were received before the disconnection.
+    ///
+    /// __Note__: This library does __not__ validate the number of minutes.
+    pub fn backfill_minutes(self, minutes: u8) -> Self {
+        SampleStreamBuilder {
+            backfill_minutes: Some(minutes),
+            ..self
+        }
+    }
+
+    /// Finalizes the stream parameters and returns the resulting `TwitterStream`.
+    pub fn start(&self, token: &Token) -> TwitterStream {
+        let url = if self.partition.is_some() {
+            links::stream::SAMPLE10_V2
+        } else {
+            links::stream::SAMPLE_V2
+        };
+
+        let params = v2::add_fields(ParamList::new())
+            .add_opt_param("partition", self.partition.map_string())
+            .add_opt_param("backfill_minutes", self.backfill_minutes.map_string());
+
+        let req = get(url, token, Some(&params));
+        TwitterStream::new(req)
+    }
 }
 
 #[cfg(test)]
@@ -535,6 +1136,35 @@ mod tests {
         ::serde_json::from_str(&sample).unwrap()
     }
 
+    #[test]
+    fn detect_retired_endpoint() {
+        assert!(retired_alternative(links::stream::FILTER).is_some());
+        assert!(retired_alternative(links::stream::SAMPLE).is_some());
+        assert_eq!(retired_alternative(links::stream::SAMPLE_V2), None);
+
+        assert_eq!(retired_alternative(links::stream::FILTER_V2), None);
+
+        let client_not_enrolled = br#"{"errors":[{"message":"You currently have access to a subset of Twitter API v2 endpoints","code":453}]}"#;
+        assert!(is_retirement_notice(client_not_enrolled));
+        assert!(!is_retirement_notice(
+            b"This endpoint has been retired and is no longer available."
+        ));
+        assert!(!is_retirement_notice(
+            br#"{"errors":[{"message":"Invalid or expired token.","code":89}]}"#
+        ));
+        assert!(!is_retirement_notice(b""));
+    }
+
+    #[test]
+    fn filter_v2_request() {
+        let token = Token::Bearer("token".to_string());
+        let stream = filter_v2().backfill_minutes(2).start(&token);
+        let uri = stream.request.as_ref().unwrap().uri().to_string();
+        assert!(uri.starts_with(links::stream::FILTER_V2));
+        assert!(uri.contains("backfill_minutes=2"));
+        assert!(uri.contains("tweet.fields="));
+    }
+
     #[test]
     fn parse_tweet_stream() {
         let msg = load_stream("sample_payloads/sample-stream.json");
@@ -545,6 +1175,50 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_v2_tweet_stream() {
+        let msg = load_stream("sample_payloads/v2-sample-stream.json");
+        if let StreamMessage::TweetV2 { tweet, includes } = msg {
+            assert_eq!(tweet.id, 1407370711394660354);
+            assert_eq!(tweet.author_id, Some(2244994945));
+            assert_eq!(tweet.conversation_id, Some(1407370711394660354));
+            assert!(tweet.created_at.is_some());
+            let author = includes.user(tweet.author_id.unwrap()).unwrap();
+            assert_eq!(author.username, "TwitterDev");
+        } else {
+            panic!("Not a v2 tweet")
+        }
+    }
+
+    #[test]
+    fn filter_params() {
+        let builder = filter()
+            .track(["rustlang", "RustLang", " rustlang", "rustconf"])
+            .language(["en", "EN", "zh-TW"])
+            .filter_level(FilterLevel::Medium);
+        let params = builder.params().unwrap();
+
+        assert_eq!(params.get("track").unwrap(), "rustlang,rustconf");
+        assert_eq!(params.get("language").unwrap(), "en,zh-tw");
+        assert_eq!(params.get("filter_level").unwrap(), "medium");
+
+        match filter().language(["en"]).params() {
+            Err(error::Error::InvalidStream(BuilderError::NoPredicates)) => (),
+            other => panic!("expected NoPredicates, got {:?}", other.map(|_| ())),
+        }
+        let backwards = BoundingBox::new((-73.0, 41.0), (-74.0, 40.0));
+        match filter().locations(&[backwards]).params() {
+            Err(error::Error::InvalidStream(BuilderError::InvalidBoundingBox(bbox))) => {
+                assert_eq!(bbox, backwards)
+            }
+            other => panic!("expected InvalidBoundingBox, got {:?}", other.map(|_| ())),
+        }
+        match filter().track(["rustlang"]).language(["en", "xx"]).params() {
+            Err(error::Error::UnsupportedLanguage(lang)) => assert_eq!(lang, "xx"),
+            other => panic!("expected UnsupportedLanguage, got {:?}", other.map(|_| ())),
+        }
+    }
+
     #[test]
     fn parse_empty_stream() {
         let msg = StreamMessage::from_str("").unwrap();
@@ -554,4 +1228,163 @@ mod tests {
             panic!("Not a ping")
         }
     }
+
+    #[test]
+    fn parse_notices() {
+        let parse = |input: &str| StreamMessage::from_str(input).unwrap();
+
+        match parse(
+            r#"{"delete":{"status":{"id":1234,"id_str":"1234","user_id":3,"user_id_str":"3"}}}"#,
+        ) {
+            StreamMessage::Delete { status_id, user_id } => {
+                assert_eq!((status_id, user_id), (1234, 3))
+            }
+            other => panic!("expected Delete, got {:?}", other),
+        }
+        match parse(r#"{"scrub_geo":{"user_id":14090548,"up_to_status_id":23260136625}}"#) {
+            StreamMessage::ScrubGeo {
+                user_id,
+                up_to_status_id,
+            } => {
+                assert_eq!((user_id, up_to_status_id), (14090548, 23260136625))
+            }
+            other => panic!("expected ScrubGeo, got {:?}", other),
+        }
+        match parse(r#"{"disconnect":{"code":4,"stream_name":"x","reason":"too slow"}}"#) {
+            StreamMessage::Disconnect(code, reason) => {
+                assert_eq!((code, reason.as_str()), (4, "too slow"))
+            }
+            other => panic!("expected Disconnect, got {:?}", other),
+        }
+        match parse(r#"{"friends":[1,2,3]}"#) {
+            StreamMessage::FriendList(friends) => assert_eq!(friends, [1, 2, 3]),
+            other => panic!("expected FriendList, got {:?}", other),
+        }
+        match parse(r#"{"warning":{"code":"FALLING_BEHIND"}}"#) {
+            StreamMessage::Unknown(value) => assert_eq!(value["warning"]["code"], "FALLING_BEHIND"),
+            other => panic!("expected Unknown, got {:?}", other),
+        }
+        assert!(StreamMessage::from_str(r#"{"delete":{"status":{}}}"#).is_err());
+
+        // messages that were already parsed into a `Value` still deserialize
+        let value =
+            serde_json::json!({"user_withheld": {"id": 7, "withheld_in_countries": ["DE"]}});
+        match serde_json::from_value::<StreamMessage>(value).unwrap() {
+            StreamMessage::UserWithheld {
+                user_id,
+                withheld_in_countries,
+            } => {
+                assert_eq!(
+                    (user_id, withheld_in_countries),
+                    (7, vec!["DE".to_string()])
+                )
+            }
+            other => panic!("expected UserWithheld, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn managed_switchover() {
+        use futures::channel::mpsc;
+        use futures::task::noop_waker;
+
+        type Connection = mpsc::UnboundedReceiver<Result<StreamMessage, error::Error>>;
+
+        let sample = load_file("sample_payloads/sample-stream.json");
+        let base = match StreamMessage::from_str(&sample).unwrap() {
+            StreamMessage::Tweet(tweet) => tweet,
+            other => panic!("expected Tweet, got {:?}", other),
+        };
+        let tweet = |id: u64| {
+            let mut tweet = base.clone();
+            tweet.id = id;
+            Ok(StreamMessage::Tweet(tweet))
+        };
+
+        let poll = |switch: &mut Switchover<Connection>| {
+            let waker = noop_waker();
+            Pin::new(switch).poll_next(&mut Context::from_waker(&waker))
+        };
+        let next = |switch: &mut Switchover<Connection>| match poll(switch) {
+            Poll::Ready(Some(Ok(StreamMessage::Tweet(tweet)))) => Some(tweet.id),
+            Poll::Pending => None,
+            other => panic!("expected a tweet, got {:?}", other),
+        };
+
+        let (old_tx, old) = mpsc::unbounded();
+        let mut switch = Switchover::new(old);
+        old_tx.unbounded_send(tweet(1)).unwrap();
+        assert_eq!(next(&mut switch), Some(1));
+        assert!(switch.overlap.is_empty());
+
+        // while the new connection opens, tweets keep coming from the old one
+        let (new_tx, new) = mpsc::unbounded();
+        switch.start(new);
+        old_tx.unbounded_send(tweet(2)).unwrap();
+        old_tx.unbounded_send(tweet(3)).unwrap();
+        old_tx.unbounded_send(tweet(4)).unwrap();
+        assert_eq!(next(&mut switch), Some(2));
+        assert_eq!(next(&mut switch), Some(3));
+        assert_eq!(next(&mut switch), Some(4));
+        assert_eq!(next(&mut switch), None);
+
+        // the first message from the new connection switches over and drops the old one, and
+        // tweets the old one already delivered are skipped
+        new_tx.unbounded_send(tweet(3)).unwrap();
+        assert_eq!(next(&mut switch), None);
+        assert!(switch.pending.is_none());
+        assert!(old_tx.is_closed());
+        assert_eq!(switch.overlap.len(), 2);
+
+        new_tx.unbounded_send(tweet(4)).unwrap();
+        new_tx.unbounded_send(tweet(5)).unwrap();
+        assert_eq!(next(&mut switch), Some(5));
+        assert!(switch.overlap.is_empty());
+
+        // once the new connection delivers a newer tweet than the old one did, it's caught up, and
+        // the tweets it missed repeating aren't remembered anymore
+        let (newer_tx, newer) = mpsc::unbounded();
+        switch.start(newer);
+        new_tx.unbounded_send(tweet(6)).unwrap();
+        new_tx.unbounded_send(tweet(7)).unwrap();
+        assert_eq!(next(&mut switch), Some(6));
+        assert_eq!(next(&mut switch), Some(7));
+        newer_tx.unbounded_send(tweet(8)).unwrap();
+        assert_eq!(next(&mut switch), Some(8));
+        assert!(switch.overlap.is_empty());
+        newer_tx.unbounded_send(tweet(7)).unwrap();
+        assert_eq!(next(&mut switch), Some(7));
+
+        // when the new connection fails, the old one is kept
+        let (failed_tx, failed) = mpsc::unbounded();
+        switch.start(failed);
+        failed_tx
+            .unbounded_send(Err(error::Error::FutureAlreadyCompleted))
+            .unwrap();
+        assert!(matches!(poll(&mut switch), Poll::Ready(Some(Err(_)))));
+        assert!(switch.pending.is_none());
+        newer_tx.unbounded_send(tweet(9)).unwrap();
+        assert_eq!(next(&mut switch), Some(9));
+    }
+
+    #[test]
+    fn oversized_message_ends_stream() {
+        use futures::StreamExt;
+
+        let request = Request::get(links::stream::SAMPLE_V2)
+            .body(Body::empty())
+            .unwrap();
+        let mut stream = TwitterStream::new(request);
+        stream.request = None;
+        stream.body = Some(Body::from("a message with no line break"));
+        stream.max_size = 8;
+
+        let next = futures::executor::block_on(stream.next());
+        assert!(matches!(next, Some(Err(error::Error::ResponseTooLarge(8)))));
+        assert!(stream.buf.is_empty());
+
+        // the connection was dropped, so the stream ends instead of failing again
+        assert!(futures::executor::block_on(stream.next()).is_none());
+        assert!(futures::executor::block_on(stream.next()).is_none());
+    }
 }
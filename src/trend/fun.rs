@@ -1,6 +1,6 @@
 use crate::common::*;
-use crate::error::Result;
-use crate::trend::TrendLocation;
+use crate::error::{Error, Result};
+use crate::trend::{TrendLocation, Trends};
 use crate::{auth, links};
 
 ///Returns the locations that Twitter has trending topic information for, closest to a
@@ -24,3 +24,35 @@ pub async fn available(token: &auth::Token) -> Result<Response<Vec<TrendLocation
     let req = get(links::trend::AVAILABLE, token, None);
     request_with_json_response(req).await
 }
+
+///Returns the top 50 trending topics for the location with the given WOEID.
+///
+///Common WOEIDs are available in the `woeid` module; the locations with trending topics can be
+///loaded with `available` or `closest`. If `exclude_hashtags` is true, Twitter will leave
+///hashtags out of the returned trends.
+pub async fn place(
+    woeid: u32,
+    exclude_hashtags: bool,
+    token: &auth::Token,
+) -> Result<Response<Trends>> {
+    let params = ParamList::new()
+        .add_param("id", woeid.to_string())
+        .add_opt_param(
+            "exclude",
+            if exclude_hashtags {
+                Some("hashtags")
+            } else {
+                None
+            },
+        );
+
+    let req = get(links::trend::PLACE, token, Some(&params));
+
+    let resp: Response<Vec<Trends>> = request_with_json_response(req).await?;
+    Response::try_map(resp, |trends| {
+        trends
+            .into_iter()
+            .next()
+            .ok_or(Error::MissingValue("trends"))
+    })
+}
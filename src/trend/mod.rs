@@ -1,15 +1,25 @@
 //! Sturcts and functions for working with trending topic in Twitter.
 //!
-//! In this module, you are able to get locations with trending topics.
+//! In this module, you are able to get locations with trending topics, and the trending topics
+//! themselves. WOEIDs for common locations are available in the `woeid` module.
 //!
 //! ## Types
 //! - `TrendLocation`: the element of trending information returned by trend API
 //! - `PlaceType`: a member in `TrendLocation`, which includes the code and related name
 //!   to specify the kind of place
+//! - `Trends`: the trending topics for a location, returned by `place`
+//! - `Trend`: a single trending topic
+//!
+//! ## Functions
+//! - `available`/`closest`: load the locations with trending topics
+//! - `place`: load the trending topics for a location. To load them for a `Place` instead of a
+//!   WOEID, use `place::trends`.
+//! - `format_volume`: format a tweet count for display, like "1.2K"
 use serde::{Deserialize, Serialize};
 
 mod fun;
 mod raw;
+pub mod woeid;
 
 pub use self::fun::*;
 
@@ -46,6 +56,8 @@ impl From<raw::RawTrendLocation> for TrendLocation {
             place_type: raw.place_type,
             url: raw.url,
             woeid: raw.woeid,
+            #[cfg(feature = "raw-fields")]
+            extra: raw.extra,
         }
     }
 }
@@ -58,3 +70,133 @@ pub struct PlaceType {
     ///The name of the location type
     pub name: String,
 }
+
+///The trending topics for a location, as returned by `place`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Trends {
+    ///The trending topics, in order.
+    pub trends: Vec<Trend>,
+    ///When this list of trends was loaded.
+    pub as_of: chrono::DateTime<chrono::Utc>,
+    ///When this list of trends was created by Twitter.
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    ///The locations these trends apply to.
+    pub locations: Vec<TrendsLocation>,
+}
+
+///A location that a list of trends applies to.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TrendsLocation {
+    ///The name of the location.
+    pub name: String,
+    ///The "where on earth identifier" of the location.
+    pub woeid: u32,
+}
+
+///A trending topic.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Trend {
+    ///The text of the trend, as it would be displayed.
+    pub name: String,
+    ///A link to search results for the trend on twitter.com.
+    pub url: String,
+    ///The trend as a URL-encoded search query.
+    pub query: String,
+    ///If this trend is promoted, information about the promotion.
+    pub promoted_content: Option<serde_json::Value>,
+    ///The number of tweets about this trend in the last 24 hours, if Twitter has it.
+    pub tweet_volume: Option<u64>,
+}
+
+impl Trend {
+    ///Returns whether this trend is a hashtag rather than a phrase.
+    pub fn is_hashtag(&self) -> bool {
+        self.name.starts_with('#') || self.name.starts_with('＃')
+    }
+
+    ///Returns the tweet volume of this trend formatted for display, like "1.2K" or "34M", if
+    ///Twitter gave one.
+    ///
+    ///See `format_volume` for details.
+    pub fn format_volume(&self) -> Option<String> {
+        self.tweet_volume.map(format_volume)
+    }
+}
+
+///Formats the given tweet count for display, like Twitter does.
+///
+///Counts under one thousand are given as-is. Larger counts are shortened with a "K", "M", or "B"
+///suffix, with one decimal place if the shortened number is less than 100: 1234 becomes "1.2K",
+///56789 becomes "56.8K", and 123456 becomes "123K".
+///
+///```rust
+///use egg_mode::trend::format_volume;
+///
+///assert_eq!(format_volume(999), "999");
+///assert_eq!(format_volume(1234), "1.2K");
+///assert_eq!(format_volume(3_400_000), "3.4M");
+///```
+pub fn format_volume(volume: u64) -> String {
+    const UNITS: [(&str, u64); 3] = [("K", 1_000), ("M", 1_000_000), ("B", 1_000_000_000)];
+
+    if volume < 1_000 {
+        return volume.to_string();
+    }
+
+    for (idx, &(suffix, size)) in UNITS.iter().enumerate() {
+        let last = idx == UNITS.len() - 1;
+
+        if volume < 100 * size {
+            //round to tenths of the unit; this can round up to 100, which gets printed without a
+            //decimal point
+            let tenths = (volume + size / 20) / (size / 10);
+            let (whole, frac) = (tenths / 10, tenths % 10);
+            return if frac == 0 {
+                format!("{}{}", whole, suffix)
+            } else {
+                format!("{}.{}{}", whole, frac, suffix)
+            };
+        }
+
+        let whole = (volume + size / 2) / size;
+        if whole < 1_000 || last {
+            return format!("{}{}", whole, suffix);
+        }
+    }
+
+    unreachable!()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_volume, Trends};
+    use crate::common::tests::load_file;
+
+    #[test]
+    fn volume() {
+        assert_eq!(format_volume(0), "0");
+        assert_eq!(format_volume(1_000), "1K");
+        assert_eq!(format_volume(1_049), "1K");
+        assert_eq!(format_volume(1_050), "1.1K");
+        assert_eq!(format_volume(56_789), "56.8K");
+        assert_eq!(format_volume(99_960), "100K");
+        assert_eq!(format_volume(123_456), "123K");
+        assert_eq!(format_volume(999_600), "1M");
+        assert_eq!(format_volume(12_345_678), "12.3M");
+        assert_eq!(format_volume(2_500_000_000_000), "2500B");
+    }
+
+    #[test]
+    fn parse_trends() {
+        let contents = load_file("sample_payloads/trends-place.json");
+        let trends: Vec<Trends> = serde_json::from_str(&contents).unwrap();
+        let trends = &trends[0];
+
+        assert_eq!(trends.locations[0].woeid, super::woeid::WORLDWIDE);
+        assert_eq!(trends.trends.len(), 3);
+        assert!(trends.trends[0].is_hashtag());
+        assert!(!trends.trends[1].is_hashtag());
+        assert_eq!(trends.trends[0].format_volume().as_deref(), Some("12.3K"));
+        assert_eq!(trends.trends[2].format_volume(), None);
+    }
+}
@@ -12,4 +12,7 @@ pub struct RawTrendLocation {
     pub place_type: PlaceType,
     pub url: String,
     pub woeid: u32,
+    #[cfg(feature = "raw-fields")]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
@@ -0,0 +1,42 @@
+//! "Where on earth identifiers" for common trend locations.
+//!
+//! These can be given to `trend::place` without looking them up through `trend::available`
+//! first. Twitter only has trending topics for some locations, so WOEIDs from other sources may
+//! not work.
+
+///Trends across the whole world.
+pub const WORLDWIDE: u32 = 1;
+
+///Trends for the United States.
+pub const US: u32 = 23424977;
+///Trends for the United Kingdom.
+pub const UK: u32 = 23424975;
+///Trends for Canada.
+pub const CANADA: u32 = 23424775;
+///Trends for Australia.
+pub const AUSTRALIA: u32 = 23424748;
+///Trends for India.
+pub const INDIA: u32 = 23424848;
+///Trends for Japan.
+pub const JAPAN: u32 = 23424856;
+///Trends for Brazil.
+pub const BRAZIL: u32 = 23424768;
+///Trends for Germany.
+pub const GERMANY: u32 = 23424829;
+///Trends for France.
+pub const FRANCE: u32 = 23424819;
+///Trends for Spain.
+pub const SPAIN: u32 = 23424950;
+///Trends for Mexico.
+pub const MEXICO: u32 = 23424900;
+
+///Trends for New York City.
+pub const NEW_YORK: u32 = 2459115;
+///Trends for Los Angeles.
+pub const LOS_ANGELES: u32 = 2442047;
+///Trends for San Francisco.
+pub const SAN_FRANCISCO: u32 = 2487956;
+///Trends for London.
+pub const LONDON: u32 = 44418;
+///Trends for Tokyo.
+pub const TOKYO: u32 = 1118370;
@@ -0,0 +1,188 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Link preview cards attached to tweets.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::serde_via_string;
+
+/// The keys of image values in a card, from the largest and most specific to the least.
+const IMAGE_KEYS: &[&str] = &[
+    "photo_image_full_size_large",
+    "summary_photo_image_large",
+    "thumbnail_image_large",
+    "player_image_large",
+    "photo_image_full_size",
+    "summary_photo_image",
+    "thumbnail_image",
+    "player_image",
+];
+
+/// The link preview attached to a tweet, as Twitter renders it on its website.
+///
+/// A card describes the page behind a link in the tweet, using the metadata Twitter loaded from
+/// the page itself. Its contents are stored in `binding_values`, whose keys depend on the kind of
+/// card given in `name`. The most common values are available through the `title`,
+/// `description`, `domain`, and `image` methods.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Card {
+    /// The kind of card, like `summary` or `summary_large_image`.
+    pub name: String,
+    /// The link this card describes, usually a t.co link from the tweet.
+    pub url: String,
+    /// A URL identifying the kind of card, if Twitter included one.
+    #[serde(default)]
+    pub card_type_url: Option<String>,
+    /// The contents of the card.
+    #[serde(default)]
+    pub binding_values: HashMap<String, CardValue>,
+}
+
+impl Card {
+    /// Returns the text value with the given key, if the card has one.
+    pub fn string_value(&self, key: &str) -> Option<&str> {
+        match self.binding_values.get(key) {
+            Some(CardValue::String(s)) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the title of the linked page, if the card has one.
+    pub fn title(&self) -> Option<&str> {
+        self.string_value("title")
+    }
+
+    /// Returns the description of the linked page, if the card has one.
+    pub fn description(&self) -> Option<&str> {
+        self.string_value("description")
+    }
+
+    /// Returns the domain of the linked page, for display, if the card has one.
+    pub fn domain(&self) -> Option<&str> {
+        self.string_value("vanity_url")
+            .or_else(|| self.string_value("domain"))
+    }
+
+    /// Returns the largest preview image of the linked page, if the card has one.
+    pub fn image(&self) -> Option<&CardImage> {
+        IMAGE_KEYS
+            .iter()
+            .filter_map(|key| match self.binding_values.get(*key) {
+                Some(CardValue::Image(img)) => Some(img),
+                _ => None,
+            })
+            .next()
+    }
+}
+
+/// A single value in a `Card`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(from = "RawCardValue", into = "RawCardValue")]
+pub enum CardValue {
+    /// A text value, like a title or description.
+    String(String),
+    /// An image, like a preview of the linked page.
+    Image(CardImage),
+    /// A true/false value.
+    Boolean(bool),
+    /// A reference to a user, like the author of the linked page. The enclosed value is the
+    /// user's ID.
+    User(u64),
+    /// A value of a kind egg-mode doesn't load. The enclosed value is the kind of value, as given
+    /// by Twitter.
+    Other(String),
+}
+
+/// An image in a `Card`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CardImage {
+    /// The URL of the image.
+    pub url: String,
+    /// The width of the image, in pixels.
+    pub width: i32,
+    /// The height of the image, in pixels.
+    pub height: i32,
+    /// The alt text of the image, if the linked page gave any.
+    #[serde(default)]
+    pub alt: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct RawCardValue {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    string_value: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    image_value: Option<CardImage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    boolean_value: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    user_value: Option<RawCardUser>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct RawCardUser {
+    #[serde(with = "serde_via_string")]
+    id_str: u64,
+}
+
+impl From<RawCardValue> for CardValue {
+    fn from(raw: RawCardValue) -> CardValue {
+        match raw {
+            RawCardValue {
+                string_value: Some(s),
+                ..
+            } => CardValue::String(s),
+            RawCardValue {
+                image_value: Some(img),
+                ..
+            } => CardValue::Image(img),
+            RawCardValue {
+                boolean_value: Some(b),
+                ..
+            } => CardValue::Boolean(b),
+            RawCardValue {
+                user_value: Some(user),
+                ..
+            } => CardValue::User(user.id_str),
+            RawCardValue { kind, .. } => CardValue::Other(kind),
+        }
+    }
+}
+
+impl From<CardValue> for RawCardValue {
+    fn from(val: CardValue) -> RawCardValue {
+        let mut raw = RawCardValue {
+            kind: String::new(),
+            string_value: None,
+            image_value: None,
+            boolean_value: None,
+            user_value: None,
+        };
+        match val {
+            CardValue::String(s) => {
+                raw.kind = "STRING".into();
+                raw.string_value = Some(s);
+            }
+            CardValue::Image(img) => {
+                raw.kind = "IMAGE".into();
+                raw.image_value = Some(img);
+            }
+            CardValue::Boolean(b) => {
+                raw.kind = "BOOLEAN".into();
+                raw.boolean_value = Some(b);
+            }
+            CardValue::User(id) => {
+                raw.kind = "USER".into();
+                raw.user_value = Some(RawCardUser { id_str: id });
+            }
+            CardValue::Other(kind) => raw.kind = kind,
+        }
+        raw
+    }
+}
@@ -0,0 +1,139 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Storage for the content hashes used by `DraftTweet::send_once`.
+
+use std::collections::hash_map::{Entry, HashMap};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// A record of recently-sent drafts, used by [`DraftTweet::send_once`] to avoid posting the same
+/// tweet twice.
+///
+/// Drafts are identified by the hash returned from [`DraftTweet::content_hash`]. egg-mode provides
+/// [`MemoryDraftStore`], which remembers hashes for a fixed amount of time within a single process.
+/// To remember sent drafts across restarts or share them between processes, implement this trait
+/// on top of your own storage.
+///
+/// [`DraftTweet::send_once`]: struct.DraftTweet.html#method.send_once
+/// [`DraftTweet::content_hash`]: struct.DraftTweet.html#method.content_hash
+/// [`MemoryDraftStore`]: struct.MemoryDraftStore.html
+pub trait DraftStore {
+    /// Records that a draft with the given hash is about to be sent.
+    ///
+    /// Returns `false` if the hash was already recorded and hasn't expired yet, in which case the
+    /// draft will not be sent. This should check and record the hash in one step, so that two
+    /// copies of a draft sent at the same time can't both get through.
+    fn try_insert(&self, hash: u64) -> bool;
+
+    /// Forgets the given hash, so a draft with it can be sent again.
+    ///
+    /// This is called when sending a draft fails, so the draft can be retried.
+    fn remove(&self, hash: u64);
+}
+
+/// A `DraftStore` that remembers sent drafts in memory for a fixed amount of time.
+#[derive(Debug)]
+pub struct MemoryDraftStore {
+    ttl: Duration,
+    sent: Mutex<HashMap<u64, Instant>>,
+}
+
+impl MemoryDraftStore {
+    /// Creates a new `MemoryDraftStore` that remembers each draft for the given amount of time.
+    pub fn new(ttl: Duration) -> MemoryDraftStore {
+        MemoryDraftStore {
+            ttl,
+            sent: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl DraftStore for MemoryDraftStore {
+    fn try_insert(&self, hash: u64) -> bool {
+        let now = Instant::now();
+        let mut sent = self.sent.lock().unwrap_or_else(PoisonError::into_inner);
+        sent.retain(|_, expires| *expires > now);
+
+        match sent.entry(hash) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(now + self.ttl);
+                true
+            }
+        }
+    }
+
+    fn remove(&self, hash: u64) {
+        self.sent
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&hash);
+    }
+}
+
+/// Incrementally computes a 64-bit FNV-1a hash.
+///
+/// This is used instead of `std`'s `DefaultHasher` because its output is stable across processes
+/// and Rust versions, so the hashes can be saved by a `DraftStore`.
+pub(crate) struct Fnv(u64);
+
+impl Fnv {
+    pub(crate) fn new() -> Fnv {
+        Fnv(0xcbf2_9ce4_8422_2325)
+    }
+
+    /// Adds the given bytes to the hash, followed by a separator so that adjacent fields can't
+    /// run together.
+    pub(crate) fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes.iter().chain(&[0xff]) {
+            self.0 ^= u64::from(b);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    pub(crate) fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DraftStore, MemoryDraftStore};
+    use crate::tweet::DraftTweet;
+
+    use std::time::Duration;
+
+    #[test]
+    fn memory_store() {
+        let store = MemoryDraftStore::new(Duration::from_secs(60));
+        let hash = DraftTweet::new("hello").content_hash();
+
+        assert!(store.try_insert(hash));
+        assert!(!store.try_insert(hash));
+        store.remove(hash);
+        assert!(store.try_insert(hash));
+
+        let expired = MemoryDraftStore::new(Duration::from_secs(0));
+        assert!(expired.try_insert(hash));
+        assert!(expired.try_insert(hash));
+    }
+
+    #[test]
+    fn content_hash() {
+        let draft = DraftTweet::new("hello");
+        assert_eq!(
+            draft.content_hash(),
+            DraftTweet::new("hello").content_hash()
+        );
+        assert_ne!(
+            draft.content_hash(),
+            DraftTweet::new("hello").in_reply_to(1).content_hash()
+        );
+        assert_ne!(
+            draft.content_hash(),
+            DraftTweet::new("hello!").content_hash()
+        );
+    }
+}
@@ -4,21 +4,26 @@
 
 use std::collections::HashMap;
 
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+
 use crate::common::*;
 use crate::error::{Error::InvalidResponse, Result};
-use crate::user::UserID;
-use crate::{auth, cursor, links};
+use crate::user::{TwitterUser, UserID};
+use crate::{auth, cursor, error, links, user};
 use serde_json;
 
 use super::*;
 
 ///Lookup a single tweet by numeric ID.
-pub async fn show(id: u64, token: &auth::Token) -> Result<Response<Tweet>> {
+pub async fn show<T: Into<TweetId>>(id: T, token: &auth::Token) -> Result<Response<Tweet>> {
+    let id: TweetId = id.into();
     let params = ParamList::new()
         .extended_tweets()
         .add_param("id", id.to_string())
         .add_param("include_my_retweet", "true")
-        .add_param("include_ext_alt_text", "true");
+        .add_param("include_ext_alt_text", "true")
+        .add_param("include_ext_edit_control", "true")
+        .cards();
     let req = get(links::statuses::SHOW, token, Some(&params));
     request_with_json_response(req).await
 }
@@ -27,7 +32,21 @@ pub async fn show(id: u64, token: &auth::Token) -> Result<Response<Tweet>> {
 ///
 ///Use the `count` parameter to indicate how many retweets you would like to retrieve. If `count`
 ///is 0 or greater than 100, it will be defaulted to 100 before making the call.
-pub async fn retweets_of(id: u64, count: u32, token: &auth::Token) -> Result<Response<Vec<Tweet>>> {
+///
+///Twitter does not allow paging past the first 100 retweets with this method. To see everyone who
+///has retweeted a tweet, use `retweeters_of` for their IDs, or `retweeter_users` for their full
+///user information.
+///
+///There's no stream of every retweet to go with `retweeter_users`: the v1.1 API has no endpoint
+///that finds a retweet from its author's ID, so the retweets themselves can only be loaded for the
+///100 most recent retweeters. If you need more than that, use the retweeters' IDs or user
+///information instead.
+pub async fn retweets_of<T: Into<TweetId>>(
+    id: T,
+    count: u32,
+    token: &auth::Token,
+) -> Result<Response<Vec<Tweet>>> {
+    let id: TweetId = id.into();
     let params = ParamList::new().extended_tweets().add_param(
         "count",
         if count == 0 || count > 100 {
@@ -49,35 +68,221 @@ pub async fn retweets_of(id: u64, count: u32, token: &auth::Token) -> Result<Res
 ///set the page size. Calling `with_page_size` on the iterator returned by this function will not
 ///change the page size used by the network call. Setting `page_size` manually may result in an
 ///error from Twitter.
-pub fn retweeters_of(id: u64, token: &auth::Token) -> cursor::CursorIter<cursor::IDCursor> {
+pub fn retweeters_of<T: Into<TweetId>>(
+    id: T,
+    token: &auth::Token,
+) -> cursor::CursorIter<cursor::IDCursor> {
+    let id: TweetId = id.into();
     let params = ParamList::new().add_param("id", id.to_string());
     cursor::CursorIter::new(links::statuses::RETWEETERS_OF, token, Some(params), None)
 }
 
+///Lookup the user information for every account that has retweeted the given tweet.
+///
+///This combines `retweeters_of` with `user::lookup`, loading each page of retweeter IDs and then
+///loading the user information for that page before returning the users in the stream. This means
+///that every page of results performs two network calls, and counts against the rate limits of
+///both. The rate-limit information attached to each user is from the `user::lookup` call.
+///
+///If an error occurs while loading a page, it is returned from the stream, and the stream ends.
+///
+///```rust,no_run
+///# use egg_mode::Token;
+///use futures::TryStreamExt;
+///
+///# #[tokio::main]
+///# async fn main() {
+///# let token: Token = unimplemented!();
+///egg_mode::tweet::retweeter_users(1234567890, &token)
+///    .try_for_each(|user| {
+///        println!("retweeted by @{}", user.screen_name);
+///        futures::future::ready(Ok(()))
+///    })
+///    .await
+///    .unwrap();
+///# }
+///```
+pub fn retweeter_users<T: Into<TweetId>>(
+    id: T,
+    token: &auth::Token,
+) -> impl Stream<Item = Result<Response<TwitterUser>>> {
+    let token = token.clone();
+    let ids = retweeters_of(id, &token);
+
+    stream::try_unfold(Some(ids), move |ids| {
+        let token = token.clone();
+        async move {
+            let mut ids = match ids {
+                Some(ids) => ids,
+                None => return Ok::<_, error::Error>(None),
+            };
+
+            let page = ids.call().await?.response;
+            let users = if page.ids.is_empty() {
+                Vec::new()
+            } else {
+                user::lookup(page.ids, &token).await?.into_iter().collect()
+            };
+
+            ids.next_cursor = page.next_cursor;
+            let ids = if page.next_cursor == 0 {
+                None
+            } else {
+                Some(ids)
+            };
+
+            Ok(Some((users, ids)))
+        }
+    })
+    .map_ok(|page: Vec<Response<TwitterUser>>| stream::iter(page).map(Ok))
+    .try_flatten()
+}
+
 ///Lookup tweet information for the given list of tweet IDs.
 ///
 ///This function differs from `lookup_map` in how it handles protected or nonexistent tweets.
 ///`lookup` gives a Vec of just the tweets it could load, leaving out any that it couldn't find.
-pub async fn lookup<I: IntoIterator<Item = u64>>(
+pub async fn lookup<T, I>(ids: I, token: &auth::Token) -> Result<Response<Vec<Tweet>>>
+where
+    T: Into<TweetId>,
+    I: IntoIterator<Item = T>,
+{
+    lookup_with_options(ids, &PayloadOptions::new(), token).await
+}
+
+///Lookup tweet information for the given list of tweet IDs, trimmed with the given
+///`PayloadOptions`.
+///
+///This is the same as `lookup`, but with `trim_user`, the tweets are returned without their
+///`user`, and with `include_entities` set to `false`, without their `entities`.
+pub async fn lookup_with_options<T, I>(
     ids: I,
+    options: &PayloadOptions,
     token: &auth::Token,
-) -> Result<Response<Vec<Tweet>>> {
-    let id_param = ids.into_iter().fold(String::new(), |mut acc, x| {
-        if !acc.is_empty() {
-            acc.push(',');
-        }
-        acc.push_str(&x.to_string());
-        acc
-    });
+) -> Result<Response<Vec<Tweet>>>
+where
+    T: Into<TweetId>,
+    I: IntoIterator<Item = T>,
+{
+    let id_param = ids
+        .into_iter()
+        .map(Into::<TweetId>::into)
+        .fold(String::new(), |mut acc, x| {
+            if !acc.is_empty() {
+                acc.push(',');
+            }
+            acc.push_str(&x.to_string());
+            acc
+        });
     let params = ParamList::new()
         .extended_tweets()
         .add_param("id", id_param)
-        .add_param("include_ext_alt_text", "true");
+        .add_param("include_ext_alt_text", "true")
+        .add_param("include_ext_edit_control", "true")
+        .cards()
+        .payload_options(options);
 
     let req = post(links::statuses::LOOKUP, token, Some(&params));
     request_with_json_response(req).await
 }
 
+///Lookup every version of the given tweet, oldest first.
+///
+///This loads the given tweet with `show`, then loads the versions listed in its `edit_info` with
+///`lookup`. The ID of any version of the tweet can be given. If the tweet hasn't been edited, or
+///Twitter didn't return edit metadata for it, only the tweet itself is returned, without the
+///second call. Versions that Twitter doesn't return, for example because they were deleted, are
+///left out.
+///
+///The rate-limit information in the result is from the `lookup` call, if one was made.
+pub async fn edit_history<T: Into<TweetId>>(
+    id: T,
+    token: &auth::Token,
+) -> Result<Response<Vec<Tweet>>> {
+    let tweet = show(id, token).await?;
+    let ids = match &tweet.edit_info {
+        Some(info) if info.is_edited() => info.edit_tweet_ids.clone(),
+        _ => return Ok(Response::map(tweet, |tweet| vec![tweet])),
+    };
+
+    let mut versions = lookup(&ids, token).await?;
+    versions
+        .response
+        .sort_by_key(|version| ids.iter().position(|&id| id == version.id));
+    let mut partial_errors = tweet.partial_errors;
+    partial_errors.append(&mut versions.partial_errors);
+    versions.partial_errors = partial_errors;
+
+    Ok(versions)
+}
+
+///Lookup whether the authenticated user has liked or retweeted each of the given tweets.
+///
+///This loads the given tweets in groups of 100 with the smallest payload Twitter allows (without
+///user information or entities), and only reads the "perspectival" fields from them, so it's a
+///cheaper way to refresh the like/retweet state of tweets you've already loaded than calling
+///`lookup` again. This performs one call for every 100 IDs; the rate-limit information returned
+///is from the last call.
+///
+///Tweets that don't exist or can't be read by the authenticated user are left out of the returned
+///map.
+///
+///Note that bookmark status can't be loaded this way, as Twitter only makes it available through
+///OAuth 2.0 user authentication, which egg-mode does not support.
+pub async fn hydrate_interactions<T, I>(
+    ids: I,
+    token: &auth::Token,
+) -> Result<Response<HashMap<u64, TweetInteractions>>>
+where
+    T: Into<TweetId>,
+    I: IntoIterator<Item = T>,
+{
+    #[derive(Deserialize)]
+    struct RawInteractions {
+        id: u64,
+        favorited: Option<bool>,
+        retweeted: Option<bool>,
+        current_user_retweet: Option<raw::CurrentUserRetweet>,
+    }
+
+    let ids: Vec<TweetId> = ids.into_iter().map(Into::into).collect();
+    let mut map = HashMap::with_capacity(ids.len());
+    let mut rate_limit_status = None;
+
+    for chunk in ids.chunks(100) {
+        let id_param = chunk
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        let params = ParamList::new()
+            .add_param("id", id_param)
+            .add_param("trim_user", "true")
+            .add_param("include_entities", "false")
+            .add_param("include_my_retweet", "true");
+
+        let req = post(links::statuses::LOOKUP, token, Some(&params));
+        let resp = request_with_json_response::<Vec<RawInteractions>>(req).await?;
+
+        for tweet in &resp.response {
+            map.insert(
+                tweet.id,
+                TweetInteractions {
+                    favorited: tweet.favorited.unwrap_or(false),
+                    retweeted: tweet.retweeted.unwrap_or(false),
+                    current_user_retweet: tweet.current_user_retweet.as_ref().map(|rt| rt.id),
+                },
+            );
+        }
+        rate_limit_status = Some(resp.rate_limit_status);
+    }
+
+    Ok(Response::new(
+        rate_limit_status.unwrap_or(RateLimit::unknown()),
+        map,
+    ))
+}
+
 ///Lookup tweet information for the given list of tweet IDs, and return a map indicating which IDs
 ///couldn't be found.
 ///
@@ -85,11 +290,83 @@ pub async fn lookup<I: IntoIterator<Item = u64>>(
 ///`lookup_map` gives a map containing every ID in the input slice; tweets that don't exist or
 ///can't be read by the authenticated user store `None` in the map, whereas tweets that could be
 ///loaded store `Some` and the requested status.
-pub async fn lookup_map<I: IntoIterator<Item = u64>>(
+///
+///To find out why a tweet couldn't be loaded, use `lookup_map_detailed`.
+pub async fn lookup_map<T, I>(
     ids: I,
     token: &auth::Token,
+) -> Result<Response<HashMap<u64, Option<Tweet>>>>
+where
+    T: Into<TweetId>,
+    I: IntoIterator<Item = T>,
+{
+    let ids: Vec<TweetId> = ids.into_iter().map(Into::into).collect();
+    lookup_id_map(&ids, token).await
+}
+
+///Lookup tweet information for the given list of tweet IDs, keeping them in the order they were
+///given and saying why any of them couldn't be loaded.
+///
+///This returns every ID in the input once, in the order it first appeared, alongside either the
+///loaded tweet or a `MissReason`. The reasons come from the `errors` Twitter sends alongside the
+///tweets it could load; as those errors don't say which tweet they belong to, a reason is only
+///given when every missing tweet can be matched to the same kind of error, and is
+///`MissReason::Unknown` otherwise. The errors themselves are still available in the response's
+///`partial_errors`.
+///
+///This is useful for archiving tweets, as `MissReason::is_retryable` tells whether a missing tweet
+///is worth asking for again later.
+pub async fn lookup_map_detailed<T, I>(
+    ids: I,
+    token: &auth::Token,
+) -> Result<Response<Vec<(u64, std::result::Result<Tweet, MissReason>)>>>
+where
+    T: Into<TweetId>,
+    I: IntoIterator<Item = T>,
+{
+    let mut ids: Vec<TweetId> = ids.into_iter().map(Into::into).collect();
+    let mut seen = HashSet::new();
+    ids.retain(|id| seen.insert(id.0));
+
+    let resp = lookup_id_map(&ids, token).await?;
+    let missing = resp
+        .response
+        .values()
+        .filter(|tweet| tweet.is_none())
+        .count();
+    let reason = miss_reason(&resp.partial_errors, missing);
+
+    Ok(Response::map(resp, |mut map| {
+        ids.iter()
+            .map(|id| {
+                let tweet = map.remove(&id.0).flatten().ok_or(reason);
+                (id.0, tweet)
+            })
+            .collect()
+    }))
+}
+
+///Returns the reason that applies to all of the `missing` tweets of a `lookup_map_detailed` call,
+///judging by the `errors` that accompanied it.
+fn miss_reason(errors: &[error::PartialError], missing: usize) -> MissReason {
+    let mut reasons = errors.iter().map(|err| {
+        err.code()
+            .and_then(MissReason::from_error_code)
+            .unwrap_or(MissReason::Unknown)
+    });
+    match reasons.next() {
+        Some(first) if errors.len() == missing && reasons.all(|reason| reason == first) => first,
+        _ => MissReason::Unknown,
+    }
+}
+
+///Loads the given tweets with `map=true`, so that tweets that couldn't be loaded are returned as
+///`None`.
+async fn lookup_id_map(
+    ids: &[TweetId],
+    token: &auth::Token,
 ) -> Result<Response<HashMap<u64, Option<Tweet>>>> {
-    let id_param = ids.into_iter().fold(String::new(), |mut acc, x| {
+    let id_param = ids.iter().fold(String::new(), |mut acc, x| {
         if !acc.is_empty() {
             acc.push(',');
         }
@@ -178,6 +455,22 @@ pub fn user_timeline<T: Into<UserID>>(
     Timeline::new(links::statuses::USER_TIMELINE, Some(params), token)
 }
 
+///Make a `MergedTimeline` for navigating the tweets posted by all of the given accounts, newest
+///first.
+///
+///This loads the `user_timeline` of every account, including replies and retweets, a few at a
+///time, and merges them by the time each tweet was posted. See the [`MergedTimeline`] docs for
+///details.
+///
+///[`MergedTimeline`]: struct.MergedTimeline.html
+pub fn merged_timeline<T, I>(accts: I, token: &auth::Token) -> MergedTimeline
+where
+    T: Into<UserID>,
+    I: IntoIterator<Item = T>,
+{
+    MergedTimeline::new(accts, token)
+}
+
 ///Make a `Timeline` struct for navigating the collection of tweets posted by the authenticated
 ///user that have been retweeted by others.
 ///
@@ -186,9 +479,90 @@ pub fn retweets_of_me(token: &auth::Token) -> Timeline {
     Timeline::new(links::statuses::RETWEETS_OF_ME, None, token)
 }
 
+///Load the authenticated user's most recently retweeted tweets, along with who retweeted each of
+///them.
+///
+///This loads the first page of `retweets_of_me` with the given page size, and then calls
+///`retweets_of` for every tweet on that page, loading up to `retweeters` of its most recent
+///retweets. Like with `retweets_of`, `retweeters` is capped at 100, and a value of 0 is treated as
+///100. The calls for each tweet are made at the same time, so a page of 20 tweets performs 21
+///network calls in all, and counts against the rate limits of both endpoints. The rate-limit
+///information returned is from the `retweets_of_me` call.
+///
+///Retweets that were deleted or made by protected accounts aren't returned by `retweets_of`, so
+///`RetweetActivity::retweeters` can be shorter than `retweet_count`.
+///
+///```rust,no_run
+///# use egg_mode::Token;
+///# #[tokio::main]
+///# async fn main() {
+///# let token: Token = unimplemented!();
+///let activity = egg_mode::tweet::retweets_of_me_with_retweeters(20, 10, &token)
+///    .await
+///    .unwrap();
+///for item in activity.iter() {
+///    let names: Vec<_> = item.retweeters.iter().map(|u| &u.screen_name).collect();
+///    println!("{} retweeted by {:?}", item.tweet.id, names);
+///}
+///# }
+///```
+pub async fn retweets_of_me_with_retweeters(
+    page_size: i32,
+    retweeters: u32,
+    token: &auth::Token,
+) -> Result<Response<Vec<RetweetActivity>>> {
+    let (_, tweets) = retweets_of_me(token)
+        .with_page_size(page_size)
+        .start()
+        .await?;
+
+    let loads = tweets
+        .iter()
+        .map(|tweet| retweets_of(tweet.id, retweeters, token));
+    let retweets = futures::future::try_join_all(loads).await?;
+
+    Ok(Response::map(tweets, |tweets| {
+        retweet_activity(tweets, retweets)
+    }))
+}
+
+///Pairs each tweet with the users who posted the given retweets of it.
+fn retweet_activity(
+    tweets: Vec<Tweet>,
+    retweets: Vec<Response<Vec<Tweet>>>,
+) -> Vec<RetweetActivity> {
+    tweets
+        .into_iter()
+        .zip(retweets)
+        .map(|(tweet, retweets)| RetweetActivity {
+            tweet,
+            retweeters: retweets
+                .response
+                .into_iter()
+                .filter_map(|rt| rt.user.map(|user| *user))
+                .collect(),
+        })
+        .collect()
+}
+
 ///Make a `Timeline` struct for navigating the collection of tweets liked by the given user.
 ///
-///This method has a default page size of 20 tweets, with a maximum of 200.
+///This method has a default page size of 20 tweets, with a maximum of 200. The standard `Timeline`
+///options apply: tweets are loaded in extended mode, and `with_payload_options` can leave out
+///their entities or users.
+///
+///Unlike other timelines, likes are ordered by when each tweet was liked, not by tweet ID, while
+///Twitter still pages through them by tweet ID. This means:
+///
+///* `min_id` and `max_id` are the IDs of the last and first tweets on the page, not the smallest
+///  and largest.
+///* A page loaded with `older` can include tweets from earlier pages, if they have smaller IDs
+///  than the last tweet of the page before.
+///* A tweet that was liked a long time ago, but has a larger ID than the last tweet of a page,
+///  won't be on any later page.
+///
+///`Timeline::into_stream` leaves out the tweets that were already returned, so each liked tweet is
+///only returned once, but can't recover the ones Twitter skips.
 pub fn liked_by<T: Into<UserID>>(acct: T, token: &auth::Token) -> Timeline {
     let params = ParamList::new()
         .extended_tweets()
@@ -200,7 +574,8 @@ pub fn liked_by<T: Into<UserID>>(acct: T, token: &auth::Token) -> Timeline {
 ///
 ///On success, the future returned by this function yields the retweet, with the original status
 ///contained in `retweeted_status`.
-pub async fn retweet(id: u64, token: &auth::Token) -> Result<Response<Tweet>> {
+pub async fn retweet<T: Into<TweetId>>(id: T, token: &auth::Token) -> Result<Response<Tweet>> {
+    let id: TweetId = id.into();
     let params = ParamList::new().extended_tweets();
     let url = format!("{}/{}.json", links::statuses::RETWEET_STEM, id);
     let req = post(&url, token, Some(&params));
@@ -213,7 +588,8 @@ pub async fn retweet(id: u64, token: &auth::Token) -> Result<Response<Tweet>> {
 ///it.
 ///
 ///On success, the future returned by this function yields the original tweet.
-pub async fn unretweet(id: u64, token: &auth::Token) -> Result<Response<Tweet>> {
+pub async fn unretweet<T: Into<TweetId>>(id: T, token: &auth::Token) -> Result<Response<Tweet>> {
+    let id: TweetId = id.into();
     let params = ParamList::new().extended_tweets();
     let url = format!("{}/{}.json", links::statuses::UNRETWEET_STEM, id);
     let req = post(&url, token, Some(&params));
@@ -223,7 +599,8 @@ pub async fn unretweet(id: u64, token: &auth::Token) -> Result<Response<Tweet>>
 ///Like the given status as the authenticated user.
 ///
 ///On success, the future returned by this function yields the liked tweet.
-pub async fn like(id: u64, token: &auth::Token) -> Result<Response<Tweet>> {
+pub async fn like<T: Into<TweetId>>(id: T, token: &auth::Token) -> Result<Response<Tweet>> {
+    let id: TweetId = id.into();
     let params = ParamList::new()
         .extended_tweets()
         .add_param("id", id.to_string());
@@ -234,7 +611,8 @@ pub async fn like(id: u64, token: &auth::Token) -> Result<Response<Tweet>> {
 ///Clears a like of the given status as the authenticated user.
 ///
 ///On success, the future returned by this function yields the given tweet.
-pub async fn unlike(id: u64, token: &auth::Token) -> Result<Response<Tweet>> {
+pub async fn unlike<T: Into<TweetId>>(id: T, token: &auth::Token) -> Result<Response<Tweet>> {
+    let id: TweetId = id.into();
     let params = ParamList::new()
         .extended_tweets()
         .add_param("id", id.to_string());
@@ -242,12 +620,107 @@ pub async fn unlike(id: u64, token: &auth::Token) -> Result<Response<Tweet>> {
     request_with_json_response(req).await
 }
 
+///Removes the authenticated user's likes of tweets posted within the given range of dates.
+///
+///This returns an `UnlikeBatch`, which can be configured before calling `into_stream` to start it.
+///See the [`UnlikeBatch`] docs for details on how the unlikes are paced, and on the limits of what
+///Twitter allows it to find.
+///
+///[`UnlikeBatch`]: struct.UnlikeBatch.html
+pub fn unlike_all<R>(range: R, token: &auth::Token) -> UnlikeBatch
+where
+    R: RangeBounds<chrono::DateTime<chrono::Utc>>,
+{
+    UnlikeBatch::new(range, token)
+}
+
 ///Delete the given tweet. The authenticated user must be the user who posted the given tweet.
 ///
 ///On success, the future returned by this function yields the given tweet.
-pub async fn delete(id: u64, token: &auth::Token) -> Result<Response<Tweet>> {
+pub async fn delete<T: Into<TweetId>>(id: T, token: &auth::Token) -> Result<Response<Tweet>> {
+    let id: TweetId = id.into();
     let params = ParamList::new().extended_tweets();
     let url = format!("{}/{}.json", links::statuses::DELETE_STEM, id);
     let req = post(&url, token, Some(&params));
     request_with_json_response(req).await
 }
+
+///Begins deleting the given tweet, after checking that it's safe to do so.
+///
+///Unlike `delete`, this loads the tweet with `show` first, and only deletes it if it was posted by
+///the authenticated user and falls within any bounds set on the returned `CheckedDelete`. If a
+///check fails, the tweet is left alone and `Error::PreconditionFailed` is returned, describing
+///which check failed. See the [`CheckedDelete`] docs for details.
+///
+///[`CheckedDelete`]: struct.CheckedDelete.html
+pub fn delete_checked<T: Into<TweetId>>(id: T) -> CheckedDelete {
+    CheckedDelete {
+        id: id.into().0,
+        owner_id: None,
+        older_than: None,
+        newer_than: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{miss_reason, retweet_activity, MissReason, Tweet};
+    use crate::common::{tests::load_file, RateLimit};
+    use crate::error::{PartialError, TwitterErrorCode};
+    use crate::Response;
+
+    fn errors(codes: &[i32]) -> Vec<PartialError> {
+        codes
+            .iter()
+            .map(|&code| {
+                PartialError::Code(TwitterErrorCode {
+                    message: String::new(),
+                    code,
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn match_miss_reasons() {
+        assert_eq!(miss_reason(&errors(&[144, 144]), 2), MissReason::Deleted);
+        assert_eq!(miss_reason(&errors(&[179]), 1), MissReason::Protected);
+        assert_eq!(miss_reason(&errors(&[63]), 1), MissReason::Suspended);
+        // errors that can't be told apart, or don't cover every missing tweet
+        assert_eq!(miss_reason(&errors(&[144, 179]), 2), MissReason::Unknown);
+        assert_eq!(miss_reason(&errors(&[144]), 2), MissReason::Unknown);
+        assert_eq!(miss_reason(&errors(&[]), 1), MissReason::Unknown);
+        assert_eq!(miss_reason(&errors(&[88]), 1), MissReason::Unknown);
+
+        assert!(!MissReason::Deleted.is_retryable());
+        assert!(MissReason::Protected.is_retryable());
+    }
+
+    #[test]
+    fn pair_retweeters() {
+        let sample = load_file("sample_payloads/tweet_array.json");
+        let mut sample: Vec<Tweet> = ::serde_json::from_str(&sample).unwrap();
+        let mine = sample.split_off(18);
+
+        //retweets loaded with `trim_user` don't say who posted them
+        let mut trimmed = sample.split_off(15);
+        trimmed[0].user = None;
+        let expected: Vec<_> = trimmed[1..]
+            .iter()
+            .map(|rt| rt.user.as_ref().unwrap().id)
+            .collect();
+
+        let retweets = vec![
+            Response::new(RateLimit::unknown(), trimmed),
+            Response::new(RateLimit::unknown(), vec![]),
+        ];
+        let activity = retweet_activity(mine.clone(), retweets);
+
+        assert_eq!(activity.len(), 2);
+        assert_eq!(activity[0].tweet.id, mine[0].id);
+        let retweeters: Vec<_> = activity[0].retweeters.iter().map(|u| u.id).collect();
+        assert_eq!(retweeters, expected);
+        assert_eq!(activity[1].tweet.id, mine[1].id);
+        assert!(activity[1].retweeters.is_empty());
+    }
+}
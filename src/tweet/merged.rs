@@ -0,0 +1,249 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A timeline made of the user timelines of several accounts, for `merged_timeline`.
+
+use std::collections::VecDeque;
+
+use futures::stream::{self, StreamExt};
+
+use crate::common::*;
+use crate::error::Result;
+use crate::{auth, user};
+
+use super::{user_timeline, Timeline, Tweet};
+
+///A timeline of the tweets posted by several accounts, newest first, as returned by
+///`merged_timeline`.
+///
+///Each call to `older` loads the next page of the timelines that need one, a few at a time, and
+///returns every tweet that's known to be newer than anything still left to load. Since the
+///accounts tweet at different rates, a page may hold more or fewer tweets than the page size of
+///the underlying timelines, and can even be empty while some accounts still have tweets left.
+///Keep calling `older` until `is_done` returns true to load everything.
+///
+///```rust,no_run
+///# use egg_mode::Token;
+///# #[tokio::main]
+///# async fn main() {
+///# let token: Token = unimplemented!();
+///use egg_mode::tweet;
+///
+///let mut timeline = tweet::merged_timeline(vec!["rustlang", "ThisWeekInRust"], &token)
+///    .with_concurrency(2);
+///
+///while !timeline.is_done() {
+///    for tweet in timeline.older().await.unwrap().iter() {
+///        println!("<@{}> {}", tweet.user.as_ref().unwrap().screen_name, tweet.text);
+///    }
+///}
+///# }
+///```
+///
+///Like `user_timeline`, each account's timeline only goes back 3,200 tweets.
+#[must_use = "MergedTimeline is lazy and won't do anything unless `older` is called"]
+pub struct MergedTimeline {
+    feeds: Vec<MergedFeed>,
+    concurrency: usize,
+}
+
+///The timeline of a single account in a `MergedTimeline`.
+struct MergedFeed {
+    timeline: Timeline,
+    ///The tweets loaded from this timeline that haven't been returned yet, newest first.
+    buffer: VecDeque<Tweet>,
+    ///The `max_id` to load the next page with.
+    max_id: Option<u64>,
+    ///Whether this timeline has no more tweets to load.
+    done: bool,
+}
+
+impl MergedTimeline {
+    pub(crate) fn new<T, I>(users: I, token: &auth::Token) -> MergedTimeline
+    where
+        T: Into<user::UserID>,
+        I: IntoIterator<Item = T>,
+    {
+        let feeds = users
+            .into_iter()
+            .map(|user| MergedFeed {
+                timeline: user_timeline(user, true, true, token),
+                buffer: VecDeque::new(),
+                max_id: None,
+                done: false,
+            })
+            .collect();
+
+        MergedTimeline {
+            feeds,
+            concurrency: 4,
+        }
+    }
+
+    ///Sets the page size of each account's timeline. The default is 20, and the maximum is 200.
+    pub fn with_page_size(self, page_size: i32) -> Self {
+        let feeds = self
+            .feeds
+            .into_iter()
+            .map(|feed| MergedFeed {
+                timeline: feed.timeline.with_page_size(page_size),
+                ..feed
+            })
+            .collect();
+
+        MergedTimeline { feeds, ..self }
+    }
+
+    ///Sets the number of timelines to load at once. The default is 4.
+    pub fn with_concurrency(self, concurrency: usize) -> Self {
+        MergedTimeline {
+            concurrency: concurrency.max(1),
+            ..self
+        }
+    }
+
+    ///Returns whether every account's timeline has been loaded and returned in full.
+    pub fn is_done(&self) -> bool {
+        self.feeds
+            .iter()
+            .all(|feed| feed.done && feed.buffer.is_empty())
+    }
+
+    ///Loads the next page of tweets, newest first.
+    ///
+    ///The rate-limit information returned is from the call that has the fewest calls left, and
+    ///the partial errors of every call are kept. If loading a timeline fails, the error is
+    ///returned, and the failed timeline is loaded again on the next call; the pages of the other
+    ///timelines are kept until then.
+    pub async fn older(&mut self) -> Result<Response<Vec<Tweet>>> {
+        let loads = self
+            .feeds
+            .iter()
+            .enumerate()
+            .filter(|(_, feed)| feed.buffer.is_empty() && !feed.done)
+            .map(
+                |(index, feed)| async move { (index, feed.timeline.call(None, feed.max_id).await) },
+            );
+        let results = stream::iter(loads)
+            .buffer_unordered(self.concurrency)
+            .collect::<Vec<_>>()
+            .await;
+
+        let mut rate_limit_status: Option<RateLimit> = None;
+        let mut partial_errors = Vec::new();
+        let mut error = None;
+        for (index, result) in results {
+            let resp = match result {
+                Ok(resp) => resp,
+                Err(e) => {
+                    error = Some(e);
+                    continue;
+                }
+            };
+            let fewer_left = match rate_limit_status {
+                Some(r) => resp.rate_limit_status.remaining < r.remaining,
+                None => true,
+            };
+            if fewer_left {
+                rate_limit_status = Some(resp.rate_limit_status);
+            }
+            partial_errors.extend(resp.partial_errors);
+
+            let feed = &mut self.feeds[index];
+            match resp.response.iter().map(|tweet| tweet.id).min() {
+                Some(min_id) => feed.max_id = Some(min_id - 1),
+                None => feed.done = true,
+            }
+            feed.buffer.extend(resp.response);
+        }
+        if let Some(e) = error {
+            return Err(e);
+        }
+
+        let mut resp = Response::new(
+            rate_limit_status.unwrap_or(RateLimit::unknown()),
+            take_ready(&mut self.feeds),
+        );
+        resp.partial_errors = partial_errors;
+        Ok(resp)
+    }
+}
+
+///Returns the order the merged timeline is sorted in: by the time each tweet was posted, with
+///tweets posted in the same second sorted by ID.
+fn sort_key(tweet: &Tweet) -> (chrono::DateTime<chrono::Utc>, u64) {
+    (tweet.created_at, tweet.id)
+}
+
+///Takes the tweets out of the given timelines that are newer than any tweet still left to load,
+///merged into one list, newest first.
+fn take_ready(feeds: &mut [MergedFeed]) -> Vec<Tweet> {
+    // anything still to load from a timeline is older than the oldest tweet loaded from it
+    let cutoff = feeds
+        .iter()
+        .filter(|feed| !feed.done)
+        .filter_map(|feed| feed.buffer.back().map(sort_key))
+        .max();
+
+    feeds
+        .iter_mut()
+        .map(|feed| {
+            let ready = match cutoff {
+                Some(cutoff) => feed
+                    .buffer
+                    .iter()
+                    .take_while(|tweet| sort_key(tweet) >= cutoff)
+                    .count(),
+                None => feed.buffer.len(),
+            };
+            feed.buffer.drain(..ready).collect::<Vec<_>>()
+        })
+        .fold(Vec::new(), |merged, ready| {
+            merge_by(merged.into_iter(), ready.into_iter(), |a, b| {
+                sort_key(a) >= sort_key(b)
+            })
+            .collect()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::tests::load_file;
+
+    #[test]
+    fn merge_ready_tweets() {
+        let tweets: Vec<Tweet> =
+            serde_json::from_str(&load_file("sample_payloads/tweet_array.json")).unwrap();
+        let mut tweets = tweets.into_iter().take(5).collect::<Vec<_>>();
+        tweets.sort_by_key(|tweet| std::cmp::Reverse(sort_key(tweet)));
+        let ids = tweets.iter().map(|tweet| tweet.id).collect::<Vec<_>>();
+
+        let token = auth::Token::Bearer("token".into());
+        let feed = |indices: &[usize], done| MergedFeed {
+            timeline: user_timeline(1u64, true, true, &token),
+            buffer: indices.iter().map(|&i| tweets[i].clone()).collect(),
+            max_id: None,
+            done,
+        };
+
+        // the second timeline may still have tweets between its oldest and the first timeline's
+        let mut feeds = vec![feed(&[0, 3, 4], false), feed(&[1, 2], false)];
+        let ready = take_ready(&mut feeds);
+        assert_eq!(
+            ready.iter().map(|tweet| tweet.id).collect::<Vec<_>>(),
+            &ids[..3]
+        );
+        assert_eq!(feeds[0].buffer.len(), 2);
+        assert!(feeds[1].buffer.is_empty());
+
+        // once that timeline is done, everything else can be returned
+        feeds[1].done = true;
+        let ready = take_ready(&mut feeds);
+        assert_eq!(
+            ready.iter().map(|tweet| tweet.id).collect::<Vec<_>>(),
+            &ids[3..]
+        );
+    }
+}
@@ -19,8 +19,26 @@
 //! - `DraftTweet`: This is what you use to post a new tweet. At present, not all available options
 //!   are supported, but basics like marking the tweet as a reply and attaching a location
 //!   coordinate are available.
+//! - `ThreadDraft`: Splits text too long for one tweet into a thread of replies, with media
+//!   attached to any tweet in the thread and optional "1/4"-style numbering.
 //! - `Timeline`: Returned by several functions in this module, this is how you cursor through a
 //!   collection of tweets. See the struct-level documentation for details.
+//! - `MergedTimeline`: Returned by `merged_timeline`, this pages through the tweets of several
+//!   accounts at once, newest first.
+//! - `DraftStore`/`MemoryDraftStore`: Used by `DraftTweet::send_once` to remember which drafts
+//!   were sent recently, so the same tweet isn't posted twice.
+//! - `EditInfo`: Lists the versions of an edited tweet, as found in `Tweet::edit_info`.
+//! - `Card`/`CardValue`/`CardImage`: The link preview attached to a tweet, as found in
+//!   `Tweet::card`.
+//! - `TweetId`: The numeric ID of a tweet. Functions that take a tweet's ID accept anything that
+//!   converts into this type, including plain `u64`s.
+//! - `CheckedDelete`: Returned by `delete_checked`, this lets you set the conditions a tweet must
+//!   meet before it is deleted.
+//! - `UnlikeBatch`/`UnlikeProgress`: Returned by `unlike_all`, these types remove the
+//!   authenticated user's likes of tweets posted within a range of dates, reporting progress as
+//!   they go.
+//! - `FilterLevel`: The stream filter level a tweet passes, as found in `Tweet::filter_level`. It's
+//!   also used to filter streams in the `stream` module.
 //!
 //! ## Functions
 //!
@@ -29,8 +47,8 @@
 //! These functions perform actions on their given tweets. They require write access to the
 //! authenticated user's account.
 //!
-//! - `delete` (for creating a tweet, see `DraftTweet`)
-//! - `like`/`unlike`
+//! - `delete`/`delete_checked` (for creating a tweet, see `DraftTweet`)
+//! - `like`/`unlike`/`unlike_all`
 //! - `retweet`/`unretweet`
 //!
 //! ### Metadata lookup
@@ -39,8 +57,10 @@
 //! about the given tweet in a direct (non-`Timeline`) fashion.
 //!
 //! - `show`
-//! - `lookup`/`lookup_map` (for the differences between these functions, see their respective
-//!   documentations.)
+//! - `edit_history`
+//! - `lookup`/`lookup_map`/`lookup_map_detailed` (for the differences between these functions, see
+//!   their respective documentations.)
+//! - `lookup_with_options`
 //! - `retweeters_of`
 //! - `retweets_of`
 //!
@@ -52,28 +72,108 @@
 //!
 //! - `home_timeline`/`mentions_timeline`/`retweets_of_me`
 //! - `user_timeline`/`liked_by`
+//!
+//! `merged_timeline` combines the `user_timeline`s of several accounts into one `MergedTimeline`.
+//!
+//! ### v2 timelines
+//!
+//! The [`v2`] submodule loads a user's tweets and mentions from version 2 of the Twitter API, for
+//! apps whose access level doesn't include the v1.1 timelines.
+//!
+//! [`v2`]: v2/index.html
 
 use std::borrow::Cow;
+use std::collections::{HashSet, VecDeque};
 use std::convert::TryFrom;
+use std::fmt;
 use std::future::Future;
+use std::ops::{Bound, RangeBounds};
 use std::pin::Pin;
 use std::str::FromStr;
+use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
 use chrono;
+use futures::channel::mpsc;
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+use futures::SinkExt;
 use hyper::{Body, Request};
-use regex::Regex;
-use serde::{Deserialize, Deserializer, Serialize};
+use serde::{Deserialize, Serialize};
 
 use crate::common::*;
+use crate::entities::EntityKind;
 use crate::error::{Error::InvalidResponse, Result};
-use crate::stream::FilterLevel;
-use crate::{auth, entities, error, links, media, place, user};
+use crate::{auth, entities, error, links, media, place, snowflake, user};
 
+mod card;
+mod dedup;
 mod fun;
+mod merged;
 mod raw;
+mod thread;
+pub mod v2;
 
+pub use self::card::{Card, CardImage, CardValue};
+pub use self::dedup::{DraftStore, MemoryDraftStore};
 pub use self::fun::*;
+pub use self::merged::MergedTimeline;
+pub use self::thread::ThreadDraft;
+
+/// The numeric ID of a tweet.
+///
+/// Tweet IDs, user IDs, and direct message IDs are all plain numbers as far as Twitter is
+/// concerned, which makes them easy to mix up. Functions in this module that take the ID of a
+/// tweet accept any type that converts into `TweetId`, so that a tweet ID can be labeled as such
+/// while it's being passed around. Since `TweetId` can also be created from a `u64` or `&u64`,
+/// code that passes plain numbers to these functions continues to work.
+///
+/// The ID fields on `Tweet` and other structs are still plain `u64`s. To label one, convert it
+/// with `TweetId::from`; to get the number back, use the public field or `u64::from`.
+///
+/// # Example
+///
+/// ```rust
+/// use egg_mode::tweet::TweetId;
+///
+/// let id = TweetId::from(1234567890);
+/// assert_eq!(id.0, 1234567890);
+/// assert_eq!(u64::from(id), 1234567890);
+/// assert_eq!(id.to_string(), "1234567890");
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct TweetId(pub u64);
+
+impl From<u64> for TweetId {
+    fn from(id: u64) -> TweetId {
+        TweetId(id)
+    }
+}
+
+impl<'a> From<&'a u64> for TweetId {
+    fn from(id: &'a u64) -> TweetId {
+        TweetId(*id)
+    }
+}
+
+impl<'a> From<&'a TweetId> for TweetId {
+    fn from(id: &'a TweetId) -> TweetId {
+        *id
+    }
+}
+
+impl From<TweetId> for u64 {
+    fn from(id: TweetId) -> u64 {
+        id.0
+    }
+}
+
+impl fmt::Display for TweetId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
 
 round_trip! { raw::RawTweet,
     ///Represents a single status update.
@@ -115,6 +215,29 @@ round_trip! { raw::RawTweet,
     ///
     ///* `in_reply_to_user_id`/`in_reply_to_screen_name`
     ///* `in_reply_to_status_id`
+    ///* `conversation_id`, which is also present on tweets that begin a conversation, as long as
+    ///  Twitter includes it in the response
+    ///
+    ///## Edits
+    ///
+    ///When Twitter includes edit metadata with a tweet, it's collected into `edit_info`, which
+    ///lists every version of the tweet and whether it can still be edited. To load the earlier
+    ///versions themselves, use [`edit_history`].
+    ///
+    ///[`edit_history`]: fn.edit_history.html
+    ///
+    ///## Link previews
+    ///
+    ///When a tweet links to a page that Twitter can preview, the preview is loaded into `card`,
+    ///with the page's title, description, and image. `card_uri` identifies the card, and may be
+    ///present even when Twitter doesn't include the card itself.
+    ///
+    ///Cards are only included because egg-mode asks for them with the `include_cards` and
+    ///`cards_platform` parameters that Twitter's web client uses. These aren't documented, so
+    ///Twitter may stop honoring them without notice. Version 2 tweets carry the same information
+    ///on their links instead, which `egg_mode::v2::Tweet::card` loads into a `Card`.
+    ///
+    ///* `card`/`card_uri`
     ///
     ///## Retweets and Quote Tweets
     ///
@@ -150,8 +273,14 @@ round_trip! { raw::RawTweet,
         //If the user has contributors enabled, this will show which accounts contributed to this
         //tweet.
         //pub contributors: Option<Contributors>,
+        ///If the tweet links to a page that Twitter can preview, contains the preview.
+        pub card: Option<Card>,
+        ///If the tweet has a link preview, contains an identifier for it, like `card://1234`.
+        pub card_uri: Option<String>,
         ///If present, the location coordinate attached to the tweet, as a (latitude, longitude) pair.
         pub coordinates: Option<(f64, f64)>,
+        ///If present, the ID of the tweet that began the conversation this tweet is part of.
+        pub conversation_id: Option<u64>,
         ///UTC timestamp from when the tweet was posted.
         #[serde(with = "serde_datetime")]
         pub created_at: chrono::DateTime<chrono::Utc>,
@@ -159,9 +288,27 @@ round_trip! { raw::RawTweet,
         pub current_user_retweet: Option<u64>,
         ///If this tweet is an extended tweet with "hidden" metadata and entities, contains the byte
         ///offsets between which the "displayable" tweet text is.
+        ///
+        ///If you rewrite the tweet text before displaying it, use an [`OffsetMap`] to translate
+        ///this range (and the ranges in `entities`) into the new text.
+        ///
+        ///[`OffsetMap`]: ../entities/struct.OffsetMap.html
         pub display_text_range: Option<(usize, usize)>,
+        ///If Twitter returned edit metadata for this tweet, contains the IDs of every version of
+        ///it and whether it can still be edited.
+        pub edit_info: Option<EditInfo>,
         ///Link, hashtag, and user mention information extracted from the tweet text.
         pub entities: TweetEntities,
+        ///The entities whose ranges didn't match the tweet text when the tweet was loaded, and how
+        ///they were repaired.
+        ///
+        ///This is empty for tweets loaded from Twitter itself, but payloads from other sources,
+        ///like third-party archives, can have ranges that no longer line up with the text. See the
+        ///[`EntityRepair`] docs for details.
+        ///
+        ///[`EntityRepair`]: ../entities/struct.EntityRepair.html
+        #[serde(default)]
+        pub entity_repairs: Vec<entities::EntityRepair>,
         ///Extended media information attached to the tweet, if media is available.
         ///
         ///If a tweet has a photo, set of photos, gif, or video attached to it, this field will be
@@ -211,7 +358,13 @@ round_trip! { raw::RawTweet,
         ///who retweeted the status, as well as the original poster.
         pub retweeted_status: Option<Box<Tweet>>,
         ///The application used to post the tweet.
+        ///
+        ///This is `None` if the tweet didn't include a source, or if it couldn't be parsed. The
+        ///original value is available in `source_raw`.
         pub source: Option<TweetSource>,
+        ///The source of the tweet exactly as Twitter returned it, usually an HTML link to the
+        ///application used to post the tweet.
+        pub source_raw: Option<String>,
         ///The text of the tweet. For "extended" tweets, opening reply mentions and/or attached media
         ///or quoted tweet links do not count against character count, so this could be longer than 280
         ///characters in those situations.
@@ -220,8 +373,11 @@ round_trip! { raw::RawTweet,
         ///full text is longer than 280 characters.
         pub truncated: bool,
         ///The user who posted this tweet. This field will be absent on tweets included as part of a
-        ///`TwitterUser`.
+        ///`TwitterUser`, and on tweets loaded with the `trim_user` option of `PayloadOptions`.
         pub user: Option<Box<user::TwitterUser>>,
+        ///The ID of the user who posted this tweet. Unlike `user`, this is filled in when the tweet
+        ///was loaded with the `trim_user` option of `PayloadOptions`.
+        pub user_id: Option<u64>,
         ///If present and `true`, indicates that this tweet has been withheld due to a DMCA complaint.
         pub withheld_copyright: bool,
         ///If present, contains two-letter country codes indicating where this tweet is being withheld.
@@ -240,6 +396,7 @@ impl TryFrom<raw::RawTweet> for Tweet {
     type Error = error::Error;
 
     fn try_from(mut raw: raw::RawTweet) -> Result<Tweet> {
+        let edit_info = raw.edit_info();
         let extended_full_text = raw.extended_tweet.map(|xt| xt.full_text);
         let text = raw
             .full_text
@@ -248,37 +405,57 @@ impl TryFrom<raw::RawTweet> for Tweet {
             .ok_or(error::Error::MissingValue("text"))?;
         let current_user_retweet = raw.current_user_retweet.map(|cur| cur.id);
 
+        const HASHTAG: &[char] = &['#', '＃'];
+        const SYMBOL: &[char] = &['$', '＄'];
+        const MENTION: &[char] = &['@', '＠'];
+
+        let mut entity_repairs = Vec::new();
+        let mut check = |kind, i, range: &mut _, expected, prefixes: &[char]| {
+            let repairs = &mut entity_repairs;
+            entities::load_range(kind, i, range, &text, expected, prefixes, repairs)
+        };
         if let Some(ref mut range) = raw.display_text_range {
-            codepoints_to_bytes(range, &text);
+            check(EntityKind::DisplayTextRange, 0, range, None, &[]);
         }
-        for entity in &mut raw.entities.hashtags {
-            codepoints_to_bytes(&mut entity.range, &text);
+        for (i, entity) in raw.entities.hashtags.iter_mut().enumerate() {
+            let tag = Some(entity.text.as_str());
+            check(EntityKind::Hashtag, i, &mut entity.range, tag, HASHTAG);
         }
-        for entity in &mut raw.entities.symbols {
-            codepoints_to_bytes(&mut entity.range, &text);
+        for (i, entity) in raw.entities.symbols.iter_mut().enumerate() {
+            let symbol = Some(entity.text.as_str());
+            check(EntityKind::Symbol, i, &mut entity.range, symbol, SYMBOL);
         }
-        for entity in &mut raw.entities.urls {
-            codepoints_to_bytes(&mut entity.range, &text);
+        for (i, entity) in raw.entities.urls.iter_mut().enumerate() {
+            let url = Some(entity.url.as_str());
+            check(EntityKind::Url, i, &mut entity.range, url, &[]);
         }
-        for entity in &mut raw.entities.user_mentions {
-            codepoints_to_bytes(&mut entity.range, &text);
+        for (i, entity) in raw.entities.user_mentions.iter_mut().enumerate() {
+            let name = Some(entity.screen_name.as_str());
+            check(EntityKind::Mention, i, &mut entity.range, name, MENTION);
         }
         if let Some(ref mut media) = raw.entities.media {
-            for entity in media.iter_mut() {
-                codepoints_to_bytes(&mut entity.range, &text);
+            for (i, entity) in media.iter_mut().enumerate() {
+                let url = Some(entity.url.as_str());
+                check(EntityKind::Media, i, &mut entity.range, url, &[]);
             }
         }
         if let Some(ref mut entities) = raw.extended_entities {
-            for entity in entities.media.iter_mut() {
-                codepoints_to_bytes(&mut entity.range, &text);
+            for (i, entity) in entities.media.iter_mut().enumerate() {
+                let url = Some(entity.url.as_str());
+                check(EntityKind::ExtendedMedia, i, &mut entity.range, url, &[]);
             }
         }
 
         Ok(Tweet {
+            card: raw.card,
+            card_uri: raw.card_uri,
             coordinates: raw.coordinates.map(|coords| coords.coordinates),
+            conversation_id: raw.conversation_id_str,
             created_at: raw.created_at,
             display_text_range: raw.display_text_range,
+            edit_info,
             entities: raw.entities,
+            entity_repairs,
             extended_entities: raw.extended_entities,
             favorite_count: raw.favorite_count,
             favorited: raw.favorited,
@@ -295,18 +472,401 @@ impl TryFrom<raw::RawTweet> for Tweet {
             retweet_count: raw.retweet_count,
             retweeted: raw.retweeted,
             retweeted_status: raw.retweeted_status,
-            source: raw.source,
+            source: raw
+                .source_raw
+                .as_deref()
+                .and_then(|s| TweetSource::from_str(s).ok()),
+            source_raw: raw.source_raw,
             truncated: raw.truncated,
-            user: raw.user,
+            user_id: raw.user.as_ref().map(|user| user.id()),
+            user: raw.user.and_then(|user| match user {
+                raw::RawTweetUser::Full(user) => Some(user),
+                raw::RawTweetUser::Trimmed(_) => None,
+            }),
             withheld_copyright: raw.withheld_copyright,
             withheld_in_countries: raw.withheld_in_countries,
             withheld_scope: raw.withheld_scope,
+            #[cfg(feature = "raw-fields")]
+            extra: raw.extra,
             text,
             current_user_retweet,
         })
     }
 }
 
+impl Tweet {
+    ///Returns the time this tweet was posted, as read from its ID.
+    ///
+    ///This is the same time as `created_at`, but to the millisecond instead of the second. For
+    ///tweets posted before November 2010, whose IDs don't contain a timestamp, this falls back to
+    ///`created_at`. See the [`snowflake`] module for details.
+    ///
+    ///[`snowflake`]: ../snowflake/index.html
+    pub fn snowflake_time(&self) -> chrono::DateTime<chrono::Utc> {
+        if snowflake::is_snowflake(self.id) {
+            snowflake::timestamp(self.id)
+        } else {
+            self.created_at
+        }
+    }
+
+    ///Returns the part of `text` that is meant to be displayed.
+    ///
+    ///For extended tweets, this leaves out the leading reply mentions and the trailing media or
+    ///quoted tweet link, as given by `display_text_range`. If there's no such range, this is the
+    ///whole text.
+    pub fn display_text(&self) -> &str {
+        let (start, end) = self.display_range();
+        &self.text[start..end]
+    }
+
+    ///Returns every media item attached to this tweet, in order, without duplicates.
+    ///
+    ///The full list of media is in `extended_entities`, while `entities` only holds the first photo
+    ///or a thumbnail. This returns the media from `extended_entities`, followed by any media from
+    ///`entities` that wasn't already listed there.
+    pub fn all_media(&self) -> Vec<&entities::MediaEntity> {
+        let extended = self.extended_entities.iter().flat_map(|ext| &ext.media);
+        let compat = self.entities.media.iter().flatten();
+
+        let mut media: Vec<&entities::MediaEntity> = Vec::new();
+        for entity in extended.chain(compat) {
+            if !media.iter().any(|m| m.id == entity.id) {
+                media.push(entity);
+            }
+        }
+        media
+    }
+
+    ///Returns this tweet's entities as they apply to `display_text`.
+    ///
+    ///Hashtags, symbols, links, and mentions that fall outside the display range (like the leading
+    ///reply mentions of an extended tweet) are left out, and the ranges of the rest are moved so
+    ///they can be used to slice `display_text()` directly. `media` holds the full list from
+    ///`all_media`; since the link for attached media usually falls after the display range, media
+    ///whose link isn't inside the display text get an empty range at its end.
+    pub fn effective_entities(&self) -> TweetEntities {
+        let display = self.display_range();
+        let rebase = |range: (usize, usize)| -> Option<(usize, usize)> {
+            if range.0 >= display.0 && range.1 <= display.1 {
+                Some((range.0 - display.0, range.1 - display.0))
+            } else {
+                None
+            }
+        };
+
+        fn keep<T: Clone>(
+            entities: &[T],
+            range: fn(&mut T) -> &mut (usize, usize),
+            rebase: impl Fn((usize, usize)) -> Option<(usize, usize)>,
+        ) -> Vec<T> {
+            entities
+                .iter()
+                .filter_map(|entity| {
+                    let mut entity = entity.clone();
+                    *range(&mut entity) = rebase(*range(&mut entity))?;
+                    Some(entity)
+                })
+                .collect()
+        }
+
+        let end = display.1 - display.0;
+        let media = self
+            .all_media()
+            .into_iter()
+            .map(|entity| entities::MediaEntity {
+                range: rebase(entity.range).unwrap_or((end, end)),
+                ..entity.clone()
+            })
+            .collect::<Vec<_>>();
+
+        TweetEntities {
+            hashtags: keep(&self.entities.hashtags, |e| &mut e.range, rebase),
+            symbols: keep(&self.entities.symbols, |e| &mut e.range, rebase),
+            urls: keep(&self.entities.urls, |e| &mut e.range, rebase),
+            user_mentions: keep(&self.entities.user_mentions, |e| &mut e.range, rebase),
+            media: if media.is_empty() { None } else { Some(media) },
+        }
+    }
+
+    ///Returns whether this tweet mentions the given user.
+    ///
+    ///This checks the user mentions in `entities`, which include the leading mentions of a reply
+    ///as well as the ones in the tweet's text. To only check the mentions in `display_text`, look
+    ///through the `user_mentions` of `effective_entities` instead.
+    ///
+    ///Screen names are compared without regard to case, and can be given with or without a
+    ///leading `@`.
+    ///
+    ///```rust,no_run
+    ///# use egg_mode::Token;
+    ///# #[tokio::main]
+    ///# async fn main() {
+    ///# let token: Token = unimplemented!();
+    ///let me = egg_mode::auth::verify_tokens(&token).await.unwrap();
+    ///let (_, feed) = egg_mode::tweet::mentions_timeline(&token).start().await.unwrap();
+    ///
+    ///for tweet in feed.iter() {
+    ///    if tweet.is_reply_to(me.id) {
+    ///        println!("reply: {}", tweet.text);
+    ///    } else if tweet.mentions(me.id) {
+    ///        println!("mention: {}", tweet.text);
+    ///    }
+    ///}
+    ///# }
+    ///```
+    pub fn mentions<T: Into<user::UserID>>(&self, user: T) -> bool {
+        let user = user.into();
+        self.entities
+            .user_mentions
+            .iter()
+            .any(|mention| is_user(&user, Some(mention.id), Some(&mention.screen_name)))
+    }
+
+    ///Returns whether this tweet is a reply to a tweet posted by the given user.
+    ///
+    ///Screen names are compared without regard to case, and can be given with or without a
+    ///leading `@`.
+    pub fn is_reply_to<T: Into<user::UserID>>(&self, user: T) -> bool {
+        is_user(
+            &user.into(),
+            self.in_reply_to_user_id,
+            self.in_reply_to_screen_name.as_deref(),
+        )
+    }
+
+    ///Returns whether this tweet is a retweet of a tweet posted by the given user.
+    ///
+    ///Screen names are compared without regard to case, and can be given with or without a
+    ///leading `@`. If the retweeted tweet was loaded with the `trim_user` option of
+    ///`PayloadOptions`, only its author's ID is available, so this always returns `false` for
+    ///screen names.
+    pub fn is_retweet_of<T: Into<user::UserID>>(&self, user: T) -> bool {
+        match self.retweeted_status {
+            Some(ref rt) => rt.is_posted_by(&user.into()),
+            None => false,
+        }
+    }
+
+    ///Returns whether this tweet quotes a tweet posted by the given user.
+    ///
+    ///This can only check quoted tweets that Twitter included in `quoted_status`. Screen names are
+    ///compared the same way as in `is_retweet_of`.
+    pub fn is_quote_of<T: Into<user::UserID>>(&self, user: T) -> bool {
+        match self.quoted_status {
+            Some(ref quote) => quote.is_posted_by(&user.into()),
+            None => false,
+        }
+    }
+
+    ///Undoes the authenticated user's retweet of this tweet, if they've retweeted it.
+    ///
+    ///This works whether this tweet is the original or a retweet of it, by unretweeting the
+    ///original. Whether the authenticated user has retweeted it is read from `current_user_retweet`
+    ///and `retweeted`, so the tweet should have been loaded with the same token. If neither says
+    ///the tweet was retweeted, no call is made and this returns `None`, rather than asking Twitter
+    ///to undo a retweet that doesn't exist.
+    ///
+    ///On success, the future returned by this method yields the original tweet.
+    pub async fn undo_my_retweet(&self, token: &auth::Token) -> Result<Option<Response<Tweet>>> {
+        match self.my_retweet_target() {
+            Some(id) => Ok(Some(unretweet(id, token).await?)),
+            None => Ok(None),
+        }
+    }
+
+    ///Likes this tweet if the authenticated user hasn't liked it yet, or clears their like if they
+    ///have.
+    ///
+    ///Whether the tweet is liked is read from `favorited`, so the tweet should have been loaded with
+    ///the same token. If `favorited` is missing, the tweet is liked.
+    ///
+    ///On success, the future returned by this method yields the tweet, with `favorited` set to its
+    ///new state.
+    pub async fn toggle_like(&self, token: &auth::Token) -> Result<Response<Tweet>> {
+        if self.favorited == Some(true) {
+            unlike(self.id, token).await
+        } else {
+            like(self.id, token).await
+        }
+    }
+
+    ///Returns the ID of the tweet to unretweet for `undo_my_retweet`, or `None` if the
+    ///authenticated user hasn't retweeted this tweet.
+    fn my_retweet_target(&self) -> Option<u64> {
+        let original = self.retweeted_status.as_deref().unwrap_or(self);
+        let retweeted =
+            |tweet: &Tweet| tweet.current_user_retweet.is_some() || tweet.retweeted == Some(true);
+
+        if retweeted(self) || retweeted(original) {
+            Some(original.id)
+        } else {
+            None
+        }
+    }
+
+    ///Returns whether this tweet was posted by the given user.
+    fn is_posted_by(&self, user: &user::UserID) -> bool {
+        let screen_name = self.user.as_ref().map(|u| u.screen_name.as_str());
+        is_user(user, self.user_id, screen_name)
+    }
+
+    ///Returns `display_text_range`, or the range of the whole text if it's missing or doesn't fit
+    ///the text.
+    fn display_range(&self) -> (usize, usize) {
+        match self.display_text_range {
+            Some((start, end))
+                if start <= end
+                    && end <= self.text.len()
+                    && self.text.is_char_boundary(start)
+                    && self.text.is_char_boundary(end) =>
+            {
+                (start, end)
+            }
+            _ => (0, self.text.len()),
+        }
+    }
+}
+
+///Returns whether the given user matches the given ID or screen name, whichever is given.
+fn is_user(user: &user::UserID, id: Option<u64>, screen_name: Option<&str>) -> bool {
+    match user {
+        user::UserID::ID(user_id) => id == Some(*user_id),
+        user::UserID::ScreenName(name) => {
+            let name = name.strip_prefix('@').unwrap_or(name);
+            matches!(screen_name, Some(screen_name) if screen_name.eq_ignore_ascii_case(name))
+        }
+    }
+}
+
+///Information about the versions of an editable tweet.
+///
+///Every version of an edited tweet has its own ID. All of them carry the same list of IDs in
+///`edit_tweet_ids`, so any version can be used to find the others, for example with
+///[`edit_history`].
+///
+///[`edit_history`]: fn.edit_history.html
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct EditInfo {
+    ///The ID of the first version of the tweet.
+    pub initial_tweet_id: u64,
+    ///The IDs of every version of the tweet, oldest first. For a tweet that hasn't been edited,
+    ///this only contains the tweet's own ID.
+    pub edit_tweet_ids: Vec<u64>,
+    ///If present, the time after which the tweet can no longer be edited.
+    pub editable_until: Option<chrono::DateTime<chrono::Utc>>,
+    ///If present, the number of edits that can still be made to the tweet.
+    pub edits_remaining: Option<i32>,
+    ///If present, indicates whether the tweet could be edited when it was posted.
+    pub is_edit_eligible: Option<bool>,
+}
+
+impl EditInfo {
+    ///Returns whether the tweet has been edited, i.e. whether there's more than one version of it.
+    pub fn is_edited(&self) -> bool {
+        self.edit_tweet_ids.len() > 1
+    }
+
+    ///Returns the ID of the most recent version of the tweet.
+    pub fn latest_tweet_id(&self) -> u64 {
+        self.edit_tweet_ids
+            .last()
+            .copied()
+            .unwrap_or(self.initial_tweet_id)
+    }
+}
+
+///The authenticated user's interactions with a tweet, as returned by `hydrate_interactions`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TweetInteractions {
+    ///Indicates whether the authenticated user has liked this tweet.
+    pub favorited: bool,
+    ///Indicates whether the authenticated user has retweeted this tweet.
+    pub retweeted: bool,
+    ///If the authenticated user has retweeted this tweet, contains the ID of that retweet.
+    pub current_user_retweet: Option<u64>,
+}
+
+///One of the authenticated user's tweets, along with the users who recently retweeted it, as
+///returned by `retweets_of_me_with_retweeters`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RetweetActivity {
+    ///The tweet that was retweeted.
+    pub tweet: Tweet,
+    ///The users who retweeted the tweet, most recent first.
+    pub retweeters: Vec<user::TwitterUser>,
+}
+
+///The reason a tweet couldn't be loaded by `lookup_map_detailed`.
+///
+///Twitter doesn't say which missing tweet each of its errors belongs to, so a reason is only given
+///when every missing tweet in the call can be matched to the same kind of error. Otherwise the
+///reason is `Unknown`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum MissReason {
+    ///The tweet doesn't exist, or was deleted.
+    Deleted,
+    ///The tweet was posted by a protected account the authenticated user doesn't follow.
+    Protected,
+    ///The tweet was posted by an account that was suspended.
+    Suspended,
+    ///Twitter didn't say why the tweet couldn't be loaded.
+    Unknown,
+}
+
+impl MissReason {
+    ///Returns the reason that corresponds to the given Twitter error code, if it's one that
+    ///explains a missing tweet.
+    pub fn from_error_code(code: i32) -> Option<MissReason> {
+        match code {
+            34 | 144 => Some(MissReason::Deleted),
+            179 => Some(MissReason::Protected),
+            63 => Some(MissReason::Suspended),
+            _ => None,
+        }
+    }
+
+    ///Returns whether loading the tweet again later could succeed.
+    ///
+    ///Deleted tweets never come back, but protected accounts can be followed or made public,
+    ///suspensions can be lifted, and tweets missing for an unknown reason may have been caught by
+    ///a temporary problem.
+    pub fn is_retryable(self) -> bool {
+        self != MissReason::Deleted
+    }
+}
+
+///Represents the amount of filtering that can be done to streams on Twitter's side.
+///
+///According to Twitter's documentation, "When displaying a stream of Tweets to end users
+///(dashboards or live feeds at a presentation or conference, for example) it is suggested that
+///you set this value to medium."
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub enum FilterLevel {
+    ///No filtering.
+    #[serde(rename = "none")]
+    None,
+    ///A light amount of filtering.
+    #[serde(rename = "low")]
+    Low,
+    ///A medium amount of filtering.
+    #[serde(rename = "medium")]
+    Medium,
+}
+
+///`Display` impl to turn `FilterLevel` variants into the form needed for stream parameters. This
+///is basically "the variant name, in lowercase".
+// TODO Probably can remove this somehow
+impl ::std::fmt::Display for FilterLevel {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        match *self {
+            FilterLevel::None => write!(f, "none"),
+            FilterLevel::Low => write!(f, "low"),
+            FilterLevel::Medium => write!(f, "medium"),
+        }
+    }
+}
+
 ///Represents the app from which a specific tweet was posted.
 ///
 ///This struct is parsed out of the HTML anchor tag that Twitter returns as part of each tweet.
@@ -327,12 +887,6 @@ impl FromStr for TweetSource {
     type Err = error::Error;
 
     fn from_str(full: &str) -> Result<TweetSource> {
-        use lazy_static::lazy_static;
-        lazy_static! {
-            static ref RE_URL: Regex = Regex::new("href=\"(.*?)\"").unwrap();
-            static ref RE_NAME: Regex = Regex::new(">(.*)</a>").unwrap();
-        }
-
         if full == "web" {
             return Ok(TweetSource {
                 name: "Twitter Web Client".to_string(),
@@ -340,18 +894,20 @@ impl FromStr for TweetSource {
             });
         }
 
-        let url = RE_URL
-            .captures(full)
-            .and_then(|cap| cap.get(1))
-            .map(|m| m.as_str().to_string())
+        let url = full
+            .find("href=\"")
+            .map(|start| &full[start + 6..])
+            .and_then(|rest| rest.find('"').map(|end| rest[..end].to_string()))
             .ok_or_else(|| {
                 InvalidResponse("TweetSource had no link href", Some(full.to_string()))
             })?;
 
-        let name = RE_NAME
-            .captures(full)
-            .and_then(|cap| cap.get(1))
-            .map(|m| m.as_str().to_string())
+        let name = full
+            .find('>')
+            .and_then(|start| {
+                let rest = &full[start + 1..];
+                rest.rfind("</a>").map(|end| rest[..end].to_string())
+            })
             .ok_or_else(|| {
                 InvalidResponse("TweetSource had no link text", Some(full.to_string()))
             })?;
@@ -360,14 +916,6 @@ impl FromStr for TweetSource {
     }
 }
 
-fn deserialize_tweet_source<'de, D>(ser: D) -> std::result::Result<Option<TweetSource>, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let s = String::deserialize(ser)?;
-    Ok(TweetSource::from_str(&s).ok())
-}
-
 ///Container for URL, hashtag, mention, and media information associated with a tweet.
 ///
 ///If a tweet has no hashtags, financial symbols ("cashtags"), links, or mentions, those respective
@@ -376,7 +924,7 @@ where
 ///Note that for media attached to a tweet, this struct will only contain the first image of a
 ///photo set, or a thumbnail of a video or GIF. Full media information is available in the tweet's
 ///`extended_entities` field.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct TweetEntities {
     ///Collection of hashtags parsed from the tweet.
     pub hashtags: Vec<entities::HashtagEntity>,
@@ -483,6 +1031,25 @@ pub struct ExtendedTweetEntities {
 /// If you want to manually pull tweets between certain IDs, the baseline `call` function can do
 /// that for you. Keep in mind, though, that `call` doesn't update the `min_id` or `max_id` fields,
 /// so you'll have to set those yourself if you want to follow up with `older` or `newer`.
+///
+/// For interfaces where scroll latency matters more than rate-limit budget, `prefetch` will have
+/// the `Timeline` keep loading a few pages ahead in the background while you consume the current
+/// one. Subsequent calls to `older(None)` are then served from that buffer:
+///
+/// ```rust,no_run
+/// # use egg_mode::Token;
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let token: Token = unimplemented!();
+/// let timeline = egg_mode::tweet::home_timeline(&token).prefetch(2);
+///
+/// //loading the first page kicks off the background loads
+/// let (timeline, _feed) = timeline.start().await.unwrap();
+///
+/// //this page was (likely) already loaded while we were busy with the first one
+/// let (timeline, _feed) = timeline.older(None).await.unwrap();
+/// # }
+/// ```
 pub struct Timeline {
     ///The URL to request tweets from.
     link: &'static str,
@@ -498,13 +1065,125 @@ pub struct Timeline {
     pub max_id: Option<u64>,
     ///The smallest/oldest tweet ID returned in the last call to `start`, `older`, or `newer`.
     pub min_id: Option<u64>,
+    ///The number of pages to load ahead of the caller when paging with `older`.
+    prefetch: usize,
+    ///The pages loaded ahead of the caller, if a prefetch is running.
+    prefetched: Option<Prefetched>,
+    ///The filter applied to tweets before they're returned from `older` or `newer`.
+    filter: Option<TweetFilter>,
+    ///The IDs of the tweets already returned, if `dedup_by_id` is set.
+    seen: Option<SeenIds>,
+}
+
+///The tweets loaded by `Timeline::backfill_until`.
+#[derive(Debug)]
+pub struct Backfill {
+    ///The tweets posted since the requested time, newest first.
+    pub tweets: Vec<Tweet>,
+    ///Whether the timeline went back as far as the requested time. If this is `false`, Twitter
+    ///stopped returning older tweets first, so tweets between the requested time and the oldest
+    ///tweet in `tweets` may be missing.
+    pub reached: bool,
+}
+
+///Returns whether the tweet with the given ID, the oldest of a timeline page, was posted before
+///the given time.
+fn passed_time(min_id: Option<u64>, time: chrono::DateTime<chrono::Utc>) -> bool {
+    match min_id {
+        Some(id) if snowflake::is_snowflake(id) => snowflake::timestamp(id) < time,
+        // tweet IDs from before November 2010 don't contain a timestamp, but are certainly older
+        Some(_) => true,
+        None => false,
+    }
 }
 
+///A predicate that tweets must pass to be returned from a `Timeline` or search.
+#[derive(Clone)]
+pub(crate) struct TweetFilter(Arc<dyn Fn(&Tweet) -> bool + Send + Sync>);
+
+impl TweetFilter {
+    pub(crate) fn new<F>(filter: F) -> TweetFilter
+    where
+        F: Fn(&Tweet) -> bool + Send + Sync + 'static,
+    {
+        TweetFilter(Arc::new(filter))
+    }
+
+    ///Combines this filter with another one, so tweets must pass both.
+    pub(crate) fn and(self, other: TweetFilter) -> TweetFilter {
+        TweetFilter::new(move |tweet| (self.0)(tweet) && (other.0)(tweet))
+    }
+
+    ///Removes the tweets that don't pass this filter.
+    pub(crate) fn apply(&self, tweets: &mut Vec<Tweet>) {
+        tweets.retain(|tweet| (self.0)(tweet));
+    }
+}
+
+impl fmt::Debug for TweetFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("TweetFilter")
+    }
+}
+
+///The number of tweet IDs a `Timeline` remembers when `dedup_by_id` is set.
+const SEEN_IDS_LIMIT: usize = 10_000;
+
+///The most recently returned tweet IDs of a `Timeline`, used to leave out tweets it already
+///returned.
+#[derive(Debug, Default)]
+struct SeenIds {
+    ids: HashSet<u64>,
+    order: VecDeque<u64>,
+}
+
+impl SeenIds {
+    ///Records the given ID, returning whether it was new. Once more than `SEEN_IDS_LIMIT` IDs are
+    ///recorded, the oldest ones are forgotten.
+    fn insert(&mut self, id: u64) -> bool {
+        if !self.ids.insert(id) {
+            return false;
+        }
+
+        self.order.push_back(id);
+        if self.order.len() > SEEN_IDS_LIMIT {
+            if let Some(old) = self.order.pop_front() {
+                self.ids.remove(&old);
+            }
+        }
+        true
+    }
+
+    ///Removes the tweets that were already seen, and records the rest.
+    fn apply(&mut self, tweets: &mut Vec<Tweet>) {
+        tweets.retain(|tweet| self.insert(tweet.id));
+    }
+}
+
+///A background task loading older pages of a `Timeline` ahead of time.
+struct Prefetched {
+    ///The `since_id` the pages are being loaded with.
+    since_id: Option<u64>,
+    ///The `min_id` the timeline needs to have for the next buffered page to follow on from it.
+    after: u64,
+    ///The loaded pages, in order. The channel's capacity bounds how far ahead the task runs.
+    pages: Arc<futures::lock::Mutex<PageReceiver>>,
+}
+
+type PageReceiver = mpsc::Receiver<Result<Response<Vec<Tweet>>>>;
+
 impl Timeline {
     ///Clear the saved IDs on this timeline.
+    ///
+    ///This also discards any pages loaded by `prefetch`, and forgets the tweets returned so far if
+    ///`dedup_by_id` is set.
     pub fn reset(&mut self) {
         self.max_id = None;
         self.min_id = None;
+        self.prefetched = None;
+        if let Some(seen) = &mut self.seen {
+            *seen = SeenIds::default();
+        }
     }
 
     ///Clear the saved IDs on this timeline, and return the most recent set of tweets.
@@ -516,13 +1195,36 @@ impl Timeline {
 
     ///Return the set of tweets older than the last set pulled, optionally placing a minimum tweet
     ///ID to bound with.
-    pub fn older(self, since_id: Option<u64>) -> TimelineFuture {
-        let req = self.request(since_id, self.min_id.map(|id| id - 1));
-        let loader = Box::pin(request_with_json_response(req));
+    ///
+    ///If `prefetch` is set and this call follows on from the previous one, the page is taken from
+    ///the ones already loaded in the background.
+    pub fn older(mut self, since_id: Option<u64>) -> TimelineFuture {
+        let buffered = match &self.prefetched {
+            Some(prefetched) => {
+                prefetched.since_id == since_id && Some(prefetched.after) == self.min_id
+            }
+            None => false,
+        };
+
+        let loader: FutureResponse<Vec<Tweet>> = if buffered {
+            let pages = self.prefetched.as_ref().unwrap().pages.clone();
+            Box::pin(async move {
+                match pages.lock().await.next().await {
+                    Some(page) => page,
+                    None => Err(error::Error::FutureAlreadyCompleted),
+                }
+            })
+        } else {
+            //dropping the old receiver stops any background task still loading for it
+            self.prefetched = None;
+            let req = self.request(since_id, self.min_id.map(|id| id - 1));
+            Box::pin(request_with_json_response(req))
+        };
 
         TimelineFuture {
             timeline: Some(self),
             loader,
+            since_id: Some(since_id),
         }
     }
 
@@ -535,6 +1237,7 @@ impl Timeline {
         TimelineFuture {
             timeline: Some(self),
             loader,
+            since_id: None,
         }
     }
 
@@ -563,6 +1266,8 @@ impl Timeline {
             .add_param("count", self.count.to_string())
             .add_param("tweet_mode", "extended")
             .add_param("include_ext_alt_text", "true")
+            .add_param("include_ext_edit_control", "true")
+            .cards()
             .add_opt_param("since_id", since_id.map(|v| v.to_string()))
             .add_opt_param("max_id", max_id.map(|v| v.to_string()));
 
@@ -573,66 +1278,390 @@ impl Timeline {
     pub fn with_page_size(self, page_size: i32) -> Self {
         Timeline {
             count: page_size,
+            prefetched: None,
             ..self
         }
     }
 
-    ///With the returned slice of Tweets, set the min_id and max_id on self.
-    fn map_ids(&mut self, resp: &[Tweet]) {
-        self.max_id = resp.first().map(|status| status.id);
-        self.min_id = resp.last().map(|status| status.id);
+    ///Builder function to trim the tweets loaded by this `Timeline` with the given
+    ///`PayloadOptions`. With `trim_user`, tweets are returned without their `user`, but still
+    ///have their `user_id`.
+    pub fn with_payload_options(self, options: PayloadOptions) -> Self {
+        let params = self
+            .params_base
+            .clone()
+            .unwrap_or_default()
+            .payload_options(&options);
+        Timeline {
+            params_base: Some(params),
+            prefetched: None,
+            ..self
+        }
     }
 
-    ///Create an instance of `Timeline` with the given link and tokens.
-    pub(crate) fn new(
-        link: &'static str,
-        params_base: Option<ParamList>,
-        token: &auth::Token,
-    ) -> Self {
+    ///Builder function to load up to `pages` pages ahead when paging with `older`.
+    ///
+    ///After each page returned by `older` (or `start`), the `Timeline` keeps loading the pages
+    ///after it in the background, so the next call to `older(None)` can be answered without
+    ///waiting on the network. This spends rate-limit budget on pages that may never be read: if
+    ///the caller stops paging, calls `newer`, or calls `reset`, the loaded pages are thrown away.
+    ///Errors from a background load are returned from the `older` call that would have loaded that
+    ///page.
+    ///
+    ///The background loads are spawned onto the current Tokio runtime. A value of 0 (the default)
+    ///disables prefetching.
+    pub fn prefetch(self, pages: usize) -> Self {
         Timeline {
-            link,
-            token: token.clone(),
-            params_base,
-            count: 20,
-            max_id: None,
-            min_id: None,
+            prefetch: pages,
+            prefetched: None,
+            ..self
         }
     }
-}
-
-/// `Future` which represents loading from a `Timeline`.
-///
-/// When this future completes, it will either return the tweets given by Twitter (after having
-/// updated the IDs in the parent `Timeline`) or the error encountered when loading or parsing the
-/// response.
-#[must_use = "futures do nothing unless polled"]
-pub struct TimelineFuture {
-    timeline: Option<Timeline>,
-    loader: FutureResponse<Vec<Tweet>>,
-}
 
-impl Future for TimelineFuture {
-    type Output = Result<(Timeline, Response<Vec<Tweet>>)>;
-
-    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
-        match Pin::new(&mut self.loader).poll(cx) {
-            Poll::Pending => Poll::Pending,
-            Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
-            Poll::Ready(Ok(resp)) => {
-                if let Some(mut timeline) = self.timeline.take() {
-                    timeline.map_ids(&resp.response);
-                    Poll::Ready(Ok((timeline, resp)))
-                } else {
-                    Poll::Ready(Err(error::Error::FutureAlreadyCompleted))
-                }
-            }
+    ///Builder function to only return tweets that pass the given predicate from `older` and
+    ///`newer`.
+    ///
+    ///The `Timeline` tracks `min_id` and `max_id` from the full page Twitter returned, before
+    ///the filter is applied, so paging continues from where the last page left off even when the
+    ///filter removes its oldest or newest tweets. Note that this means a page can come back with
+    ///fewer tweets than the page size, or none at all, even when there are more tweets to load;
+    ///keep paging until `min_id` is `None` to reach the end of the timeline.
+    ///
+    ///Calling this more than once (or with `filter_lang`) requires tweets to pass every filter.
+    ///The filter isn't applied to `call`, which returns the page as Twitter sent it.
+    ///
+    ///```rust,no_run
+    ///# use egg_mode::Token;
+    ///# #[tokio::main]
+    ///# async fn main() {
+    ///# let token: Token = unimplemented!();
+    ///let timeline = egg_mode::tweet::home_timeline(&token)
+    ///    .filter_tweets(|tweet| tweet.retweeted_status.is_none());
+    ///
+    ///let (timeline, originals) = timeline.start().await.unwrap();
+    ///# }
+    ///```
+    pub fn filter_tweets<F>(self, filter: F) -> Self
+    where
+        F: Fn(&Tweet) -> bool + Send + Sync + 'static,
+    {
+        let filter = TweetFilter::new(filter);
+        Timeline {
+            filter: Some(match self.filter {
+                Some(existing) => existing.and(filter),
+                None => filter,
+            }),
+            ..self
         }
     }
-}
 
-/// Represents an in-progress tweet before it is sent.
-///
-/// This is your entry point to posting new tweets to Twitter. To begin, make a new `DraftTweet` by
+    ///Builder function to only return tweets that Twitter detected as being written in the given
+    ///language, like `"en"`.
+    ///
+    ///Languages are compared without regard to case. Tweets whose language couldn't be detected
+    ///are left out. See `filter_tweets` for how this affects paging.
+    pub fn filter_lang(self, lang: impl Into<String>) -> Self {
+        let lang = lang.into();
+        self.filter_tweets(move |tweet| {
+            matches!(tweet.lang, Some(ref tweet_lang) if tweet_lang.eq_ignore_ascii_case(&lang))
+        })
+    }
+
+    ///Builder function to leave out tweets that this `Timeline` already returned from `older` or
+    ///`newer`.
+    ///
+    ///Twitter pages through timelines by tweet ID, but some timelines don't keep a fixed order
+    ///between calls: in a list's statuses or the home timeline, retweets are placed by when they
+    ///were retweeted, so paging quickly while new retweets arrive can return the same tweet on
+    ///more than one page. With this set, the `Timeline` remembers the IDs of the last 10,000
+    ///tweets it returned, and removes them from later pages. As with `filter_tweets`, `min_id` and
+    ///`max_id` still track the full page, so paging isn't affected.
+    ///
+    ///The remembered IDs are cleared by `start` and `reset`. `call` returns the page as Twitter
+    ///sent it.
+    ///
+    ///```rust,no_run
+    ///# use egg_mode::Token;
+    ///# #[cfg(not(feature = "lists"))] fn main() {}
+    ///# #[cfg(feature = "lists")]
+    ///# #[tokio::main]
+    ///# async fn main() {
+    ///# let token: Token = unimplemented!();
+    ///let timeline = egg_mode::list::statuses(egg_mode::list::ListID::from_id(1234), true, &token)
+    ///    .dedup_by_id(true);
+    ///
+    ///let (timeline, _feed) = timeline.start().await.unwrap();
+    ///let (timeline, _feed) = timeline.older(None).await.unwrap();
+    ///# }
+    ///```
+    pub fn dedup_by_id(self, dedup: bool) -> Self {
+        let seen = match (dedup, self.seen) {
+            (true, Some(seen)) => Some(seen),
+            (true, None) => Some(SeenIds::default()),
+            (false, _) => None,
+        };
+        Timeline { seen, ..self }
+    }
+
+    ///Turns this `Timeline` into a `Stream` that loads every tweet it can reach, newest first.
+    ///
+    ///The stream starts from the newest page, like `start`, and keeps calling `older` until
+    ///Twitter returns an empty page. Filters, the page size, and `prefetch` are kept. Since each
+    ///tweet is returned as it's read from the page, the rate-limit information in each `Response`
+    ///is from the call that loaded its page.
+    ///
+    ///For `liked_by`, whose pages are ordered by when each tweet was liked rather than by tweet ID,
+    ///`dedup_by_id` is always set, so tweets that were already returned are left out of later
+    ///pages. See `liked_by` for details.
+    ///
+    ///```rust,no_run
+    ///# use egg_mode::Token;
+    ///# #[tokio::main]
+    ///# async fn main() {
+    ///# let token: Token = unimplemented!();
+    ///use futures::TryStreamExt;
+    ///
+    ///let likes = egg_mode::tweet::liked_by("rustlang", &token)
+    ///    .with_page_size(200)
+    ///    .into_stream()
+    ///    .try_collect::<Vec<_>>()
+    ///    .await
+    ///    .unwrap();
+    ///# }
+    ///```
+    pub fn into_stream(self) -> impl Stream<Item = Result<Response<Tweet>>> + Unpin + Send {
+        let pages = stream::try_unfold(
+            (Some(self.for_stream()), true),
+            |(timeline, first)| async move {
+                let timeline = match timeline {
+                    Some(timeline) => timeline,
+                    None => return Ok::<_, error::Error>(None),
+                };
+                let (timeline, page) = if first {
+                    timeline.start().await?
+                } else {
+                    timeline.older(None).await?
+                };
+
+                let next = if timeline.min_id.is_some() {
+                    Some(timeline)
+                } else {
+                    None
+                };
+
+                Ok(Some((page, (next, false))))
+            },
+        );
+
+        Box::pin(
+            pages
+                .map_ok(|page| stream::iter(page).map(Ok))
+                .try_flatten(),
+        )
+    }
+
+    ///Sets up this timeline for `into_stream`, turning on `dedup_by_id` for `liked_by`.
+    fn for_stream(self) -> Self {
+        if self.link == links::statuses::LIKES_OF {
+            self.dedup_by_id(true)
+        } else {
+            self
+        }
+    }
+
+    ///Load pages of this timeline, starting from the most recent tweets, until reaching tweets
+    ///posted before the given time.
+    ///
+    ///This is meant for catching up on a timeline since the last time it was loaded: save the
+    ///time of the newest tweet from one run, and give it to `backfill_until` in the next. The
+    ///pages are put together, newest tweet first, leaving out the tweets posted before `time`.
+    ///
+    ///Twitter only lets you page so far back in a timeline (about 800 tweets for the home
+    ///timeline, and 3,200 for user timelines), so if the app hasn't run for a while, the timeline
+    ///can run out before reaching `time`. `Backfill::reached` says whether it was reached, which
+    ///means no tweets were missed. Each page counts against the rate limit; the rate-limit
+    ///information returned is from the last page loaded, and the partial errors of every page are
+    ///kept. If any page fails to load, the error is returned and the tweets loaded so far are
+    ///lost.
+    ///
+    ///Filters set with `filter_tweets` or `filter_lang` still apply. This isn't meaningful for
+    ///`liked_by`, whose tweets aren't ordered by when they were posted.
+    ///
+    ///```rust,no_run
+    ///# use egg_mode::Token;
+    ///# #[tokio::main]
+    ///# async fn main() {
+    ///# let token: Token = unimplemented!();
+    ///# let last_run: chrono::DateTime<chrono::Utc> = unimplemented!();
+    ///let timeline = egg_mode::tweet::home_timeline(&token).with_page_size(200);
+    ///let (_timeline, backfill) = timeline.backfill_until(last_run).await.unwrap();
+    ///
+    ///if !backfill.reached {
+    ///    println!("some tweets since the last run could not be loaded");
+    ///}
+    ///for tweet in backfill.tweets.iter().rev() {
+    ///    println!("{}", tweet.text);
+    ///}
+    ///# }
+    ///```
+    pub async fn backfill_until(
+        self,
+        time: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(Self, Response<Backfill>)> {
+        let (mut timeline, page) = self.start().await?;
+        let mut reached = passed_time(timeline.min_id, time);
+        let mut backfill = Response::map(page, |tweets| Backfill {
+            tweets,
+            reached: false,
+        });
+
+        while !reached && timeline.min_id.is_some() {
+            let (next, page) = timeline.older(None).await?;
+            timeline = next;
+            reached = passed_time(timeline.min_id, time);
+
+            backfill.rate_limit_status = page.rate_limit_status;
+            backfill.partial_errors.extend(page.partial_errors);
+            backfill.tweets.extend(page.response);
+        }
+
+        backfill
+            .tweets
+            .retain(|tweet| tweet.snowflake_time() >= time);
+        backfill.reached = reached;
+        Ok((timeline, backfill))
+    }
+
+    ///With the returned slice of Tweets, set the min_id and max_id on self.
+    fn map_ids(&mut self, resp: &[Tweet]) {
+        self.max_id = resp.first().map(|status| status.id);
+        self.min_id = resp.last().map(|status| status.id);
+    }
+
+    ///After a page has been loaded with `older`, make sure pages after it are being loaded in
+    ///the background.
+    fn start_prefetch(&mut self, since_id: Option<u64>) {
+        let after = match self.min_id {
+            Some(id) if self.prefetch > 0 => id,
+            _ => {
+                self.prefetched = None;
+                return;
+            }
+        };
+
+        if let Some(prefetched) = &mut self.prefetched {
+            if prefetched.since_id == since_id {
+                prefetched.after = after;
+                return;
+            }
+        }
+
+        //the sender itself takes up one slot in the channel
+        let (mut sender, pages) = mpsc::channel(self.prefetch - 1);
+        let loader = Timeline::new(self.link, self.params_base.clone(), &self.token)
+            .with_page_size(self.count);
+        let mut max_id = after - 1;
+        tokio::spawn(async move {
+            loop {
+                let page = loader.call(since_id, Some(max_id)).await;
+                let next = match &page {
+                    Ok(page) => page.last().map(|status| status.id - 1),
+                    Err(_) => None,
+                };
+                if sender.send(page).await.is_err() {
+                    break;
+                }
+                match next {
+                    Some(id) => max_id = id,
+                    None => break,
+                }
+            }
+        });
+
+        self.prefetched = Some(Prefetched {
+            since_id,
+            after,
+            pages: Arc::new(futures::lock::Mutex::new(pages)),
+        });
+    }
+
+    ///Create an instance of `Timeline` with the given link and tokens.
+    pub(crate) fn new(
+        link: &'static str,
+        params_base: Option<ParamList>,
+        token: &auth::Token,
+    ) -> Self {
+        Timeline {
+            link,
+            token: token.clone(),
+            params_base,
+            count: 20,
+            max_id: None,
+            min_id: None,
+            prefetch: 0,
+            prefetched: None,
+            filter: None,
+            seen: None,
+        }
+    }
+}
+
+/// `Future` which represents loading from a `Timeline`.
+///
+/// When this future completes, it will either return the tweets given by Twitter (after having
+/// updated the IDs in the parent `Timeline`) or the error encountered when loading or parsing the
+/// response.
+#[must_use = "futures do nothing unless polled"]
+pub struct TimelineFuture {
+    timeline: Option<Timeline>,
+    loader: FutureResponse<Vec<Tweet>>,
+    ///The `since_id` given to `older`, or `None` if this future came from `newer`.
+    since_id: Option<Option<u64>>,
+}
+
+impl Future for TimelineFuture {
+    type Output = Result<(Timeline, Response<Vec<Tweet>>)>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        match Pin::new(&mut self.loader).poll(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+            Poll::Ready(Ok(mut resp)) => {
+                if let Some(mut timeline) = self.timeline.take() {
+                    timeline.map_ids(&resp.response);
+                    match self.since_id {
+                        Some(since_id) => timeline.start_prefetch(since_id),
+                        None => timeline.prefetched = None,
+                    }
+                    if let Some(filter) = &timeline.filter {
+                        filter.apply(&mut resp.response);
+                    }
+                    if let Some(seen) = &mut timeline.seen {
+                        seen.apply(&mut resp.response);
+                    }
+                    Poll::Ready(Ok((timeline, resp)))
+                } else {
+                    Poll::Ready(Err(error::Error::FutureAlreadyCompleted))
+                }
+            }
+        }
+    }
+}
+
+///Picks the place whose type is closest to the given one, preferring an exact match, then smaller
+///areas, then larger ones. Among equally good places, the first one is picked.
+#[cfg(feature = "places")]
+fn best_place(places: &[place::Place], granularity: place::PlaceType) -> Option<&place::Place> {
+    let target = granularity.rank();
+    places.iter().min_by_key(|place| {
+        let rank = place.place_type.rank();
+        (rank.max(target) - rank.min(target), rank > target)
+    })
+}
+
+/// Represents an in-progress tweet before it is sent.
+///
+/// This is your entry point to posting new tweets to Twitter. To begin, make a new `DraftTweet` by
 /// calling `new` with your desired status text:
 ///
 /// ```rust,no_run
@@ -741,9 +1770,10 @@ impl DraftTweet {
     ///
     ///Note that this will only properly take effect if the user who posted the given status is
     ///@mentioned in the status text, or if the given status was posted by the authenticated user.
-    pub fn in_reply_to(self, in_reply_to: u64) -> Self {
+    pub fn in_reply_to<T: Into<TweetId>>(self, in_reply_to: T) -> Self {
+        let in_reply_to: TweetId = in_reply_to.into();
         DraftTweet {
-            in_reply_to: Some(in_reply_to),
+            in_reply_to: Some(in_reply_to.0),
             ..self
         }
     }
@@ -777,6 +1807,66 @@ impl DraftTweet {
         }
     }
 
+    ///Marks this draft tweet as replying to everyone in the given tweet, leaving out the given
+    ///users.
+    ///
+    ///This sets `in_reply_to` to the given tweet and turns on `auto_populate_reply_metadata`, so
+    ///Twitter fills in the reply mentions from the parent tweet. Users in `ignore` that are
+    ///mentioned in the parent tweet are passed to `exclude_reply_user_ids`; they can be given by ID
+    ///or by screen name, which is matched against the parent tweet's mentions without regard to
+    ///case. The author of the parent tweet can't be left out of the reply, so they're never
+    ///excluded.
+    ///
+    ///If the given tweet is a retweet, the reply is made to the original tweet, since that's what
+    ///Twitter does when replying to a retweet from its apps.
+    ///
+    ///```rust,no_run
+    ///# use egg_mode::Token;
+    ///# #[tokio::main]
+    ///# async fn main() {
+    ///# let token: Token = unimplemented!();
+    ///use egg_mode::tweet::{self, DraftTweet};
+    ///
+    ///let parent = tweet::show(1261253754969640960, &token).await.unwrap();
+    ///DraftTweet::new("sounds good!")
+    ///    .reply_all(&parent, vec!["some_bot"])
+    ///    .send(&token)
+    ///    .await
+    ///    .unwrap();
+    ///# }
+    ///```
+    pub fn reply_all<I, T>(self, parent: &Tweet, ignore: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<user::UserID>,
+    {
+        let parent = parent.retweeted_status.as_deref().unwrap_or(parent);
+        let author = parent.user.as_ref().map(|user| user.id);
+        let ignore = ignore.into_iter().map(Into::into).collect::<Vec<_>>();
+
+        let mut excluded = Vec::new();
+        for mention in &parent.entities.user_mentions {
+            if Some(mention.id) == author || excluded.contains(&mention.id) {
+                continue;
+            }
+            if ignore
+                .iter()
+                .any(|user| is_user(user, Some(mention.id), Some(&mention.screen_name)))
+            {
+                excluded.push(mention.id);
+            }
+        }
+
+        let draft = self
+            .in_reply_to(parent.id)
+            .auto_populate_reply_metadata(true);
+        if excluded.is_empty() {
+            draft
+        } else {
+            draft.exclude_reply_user_ids(excluded)
+        }
+    }
+
     ///Attaches the given tweet URL or [DM deep link][] to the tweet draft, which lets it be used
     ///outside the 280 character text limit.
     ///
@@ -818,6 +1908,53 @@ impl DraftTweet {
         }
     }
 
+    ///Looks up the place at the given coordinate and attaches it to this tweet.
+    ///
+    ///This calls `place::reverse_geocode` with the given `granularity` and picks the result whose
+    ///type matches it. If no result matches exactly, the result with the closest type is used,
+    ///preferring smaller areas over larger ones. The chosen place's ID is set as this draft's
+    ///`place_id`; the coordinate itself is not attached unless you also call `coordinates`.
+    ///
+    ///If Twitter doesn't return any places for the coordinate, this returns
+    ///`Error::NoPlaceFound`.
+    ///
+    ///Location fields will be ignored unless the user has enabled geolocation from their profile.
+    ///
+    ///# Example
+    ///
+    ///```rust,no_run
+    ///# use egg_mode::Token;
+    ///# #[tokio::main]
+    ///# async fn main() {
+    ///# let token: Token = unimplemented!();
+    ///use egg_mode::place::PlaceType;
+    ///use egg_mode::tweet::DraftTweet;
+    ///
+    ///let draft = DraftTweet::new("Hello from Seattle!")
+    ///    .place_from_coordinates(47.6062, -122.3321, PlaceType::City, &token)
+    ///    .await
+    ///    .unwrap();
+    ///draft.send(&token).await.unwrap();
+    ///# }
+    ///```
+    #[cfg(feature = "places")]
+    pub async fn place_from_coordinates(
+        self,
+        latitude: f64,
+        longitude: f64,
+        granularity: place::PlaceType,
+        token: &auth::Token,
+    ) -> Result<Self> {
+        let search = place::reverse_geocode(latitude, longitude)
+            .granularity(granularity)
+            .call(token)
+            .await?;
+        let best = best_place(&search.response.results, granularity)
+            .ok_or(error::Error::NoPlaceFound(latitude, longitude))?;
+
+        Ok(self.place_id(best.id.clone()))
+    }
+
     ///Attaches the given media ID(s) to this tweet. If more than four IDs are in this slice, only
     ///the first four will be attached. Note that Twitter will only allow one GIF, one video, or up
     ///to four images to be attached to a single tweet.
@@ -839,6 +1976,86 @@ impl DraftTweet {
         }
     }
 
+    ///Computes a hash of the content of this draft, used by `send_once` to recognize drafts that
+    ///were already sent.
+    ///
+    ///The hash covers the text, reply target, attachment URL, location, and attached media of the
+    ///draft. It is stable across processes and versions of egg-mode, so it can be saved by a
+    ///`DraftStore` and compared later.
+    pub fn content_hash(&self) -> u64 {
+        let mut hash = dedup::Fnv::new();
+        hash.write(self.text.as_bytes());
+        hash.write(self.in_reply_to.map_string().unwrap_or_default().as_bytes());
+        hash.write(
+            self.attachment_url
+                .as_deref()
+                .unwrap_or_default()
+                .as_bytes(),
+        );
+        hash.write(self.place_id.as_deref().unwrap_or_default().as_bytes());
+        if let Some((lat, long)) = self.coordinates {
+            hash.write(format!("{},{}", lat, long).as_bytes());
+        } else {
+            hash.write(&[]);
+        }
+        for media in &self.media_ids {
+            hash.write(media.0.as_bytes());
+        }
+        hash.finish()
+    }
+
+    ///Send the assembled tweet as the authenticated user, unless the same draft was sent
+    ///recently.
+    ///
+    ///Before sending, this records the draft's `content_hash` in the given `DraftStore`. If the
+    ///store already holds that hash, the draft isn't sent and `Error::DuplicateDraft` is returned
+    ///instead. If Twitter rejects the tweet as a duplicate (error code 187), that is also returned
+    ///as `Error::DuplicateDraft`. If sending fails for any other reason, the hash is removed from
+    ///the store so the draft can be retried.
+    ///
+    ///This is useful when a tweet may be sent more than once by mistake, for example when a job
+    ///that posts tweets is retried after a timeout.
+    ///
+    ///```rust,no_run
+    ///# use egg_mode::Token;
+    ///# #[tokio::main]
+    ///# async fn main() {
+    ///# let token: Token = unimplemented!();
+    ///use std::time::Duration;
+    ///use egg_mode::tweet::{DraftTweet, MemoryDraftStore};
+    ///
+    ///let store = MemoryDraftStore::new(Duration::from_secs(60 * 60));
+    ///
+    ///DraftTweet::new("Hey Twitter!").send_once(&store, &token).await.unwrap();
+    ///// this won't reach Twitter:
+    ///let again = DraftTweet::new("Hey Twitter!").send_once(&store, &token).await;
+    ///assert!(again.is_err());
+    ///# }
+    ///```
+    pub async fn send_once(
+        &self,
+        store: &(impl DraftStore + ?Sized),
+        token: &auth::Token,
+    ) -> Result<Response<Tweet>> {
+        let hash = self.content_hash();
+        if !store.try_insert(hash) {
+            return Err(error::Error::DuplicateDraft(hash));
+        }
+
+        match self.send(token).await {
+            Ok(tweet) => Ok(tweet),
+            Err(error::Error::TwitterError(_, ref terrs))
+                if terrs.errors.iter().any(|e| e.code == 187) =>
+            {
+                Err(error::Error::DuplicateDraft(hash))
+            }
+            Err(e) => {
+                store.remove(hash);
+                Err(e)
+            }
+        }
+    }
+
     ///Send the assembled tweet as the authenticated user.
     pub async fn send(&self, token: &auth::Token) -> Result<Response<Tweet>> {
         let mut params = ParamList::new()
@@ -885,10 +2102,408 @@ impl DraftTweet {
     }
 }
 
+/// Represents a pending deletion of a tweet, which will only go through if the tweet passes the
+/// configured checks.
+///
+/// This is returned by [`delete_checked`]. Before deleting the tweet, `send` loads it and checks
+/// that it was posted by the authenticated user. The bounds set with `older_than` and `newer_than`
+/// are checked as well, which helps when deleting old tweets in bulk, so that a tweet posted after
+/// the cleanup began is never removed by mistake.
+///
+/// On success, `send` returns the deleted tweet as Twitter returned it, so it can be archived.
+///
+/// [`delete_checked`]: fn.delete_checked.html
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # use egg_mode::Token;
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let token: Token = unimplemented!();
+/// use chrono::{Duration, Utc};
+/// use egg_mode::tweet;
+///
+/// let cutoff = Utc::now() - Duration::days(365);
+/// let deleted = tweet::delete_checked(1234567890)
+///     .older_than(cutoff)
+///     .send(&token)
+///     .await
+///     .unwrap();
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+#[must_use = "CheckedDelete is a builder; call `send` to delete the tweet"]
+pub struct CheckedDelete {
+    id: u64,
+    owner_id: Option<u64>,
+    older_than: Option<chrono::DateTime<chrono::Utc>>,
+    newer_than: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl CheckedDelete {
+    /// Sets the ID of the authenticated user, to compare against the tweet's author.
+    ///
+    /// If this isn't set, `send` calls `auth::verify_tokens` to load the authenticated user. If
+    /// you're deleting many tweets, setting this saves a call for each one.
+    pub fn owner_id(self, owner_id: u64) -> Self {
+        CheckedDelete {
+            owner_id: Some(owner_id),
+            ..self
+        }
+    }
+
+    /// Only deletes the tweet if it was posted before the given time.
+    pub fn older_than(self, time: chrono::DateTime<chrono::Utc>) -> Self {
+        CheckedDelete {
+            older_than: Some(time),
+            ..self
+        }
+    }
+
+    /// Only deletes the tweet if it was posted after the given time.
+    pub fn newer_than(self, time: chrono::DateTime<chrono::Utc>) -> Self {
+        CheckedDelete {
+            newer_than: Some(time),
+            ..self
+        }
+    }
+
+    /// Checks the tweet and, if all the checks pass, deletes it.
+    pub async fn send(self, token: &auth::Token) -> Result<Response<Tweet>> {
+        let owner_id = match self.owner_id {
+            Some(id) => id,
+            None => auth::verify_tokens(token).await?.id,
+        };
+
+        let tweet = show(self.id, token).await?;
+        self.check(owner_id, &tweet)?;
+
+        delete(self.id, token).await
+    }
+
+    /// Checks the loaded tweet against the authenticated user's ID and the configured bounds.
+    fn check(&self, owner_id: u64, tweet: &Tweet) -> Result<()> {
+        let author_id = match tweet.user_id {
+            Some(id) => id,
+            None => return Err(error::Error::MissingValue("user")),
+        };
+
+        if author_id != owner_id {
+            return Err(error::PreconditionError::NotOwner {
+                tweet_id: self.id,
+                author_id,
+            }
+            .into());
+        }
+
+        match (self.older_than, self.newer_than) {
+            (Some(bound), _) if tweet.created_at >= bound => {
+                Err(error::PreconditionError::TooNew {
+                    tweet_id: self.id,
+                    created_at: tweet.created_at,
+                }
+                .into())
+            }
+            (_, Some(bound)) if tweet.created_at <= bound => {
+                Err(error::PreconditionError::TooOld {
+                    tweet_id: self.id,
+                    created_at: tweet.created_at,
+                }
+                .into())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Represents a set of likes to remove, paced to avoid hitting Twitter's limits.
+///
+/// This struct is returned by [`unlike_all`]. Use its builder methods to configure how the batch is
+/// paced, then call `into_stream` to start it. The resulting stream walks through the authenticated
+/// user's likes with [`liked_by`], and unlikes each tweet that was posted within the range given to
+/// `unlike_all`, yielding an [`UnlikeProgress`] as it goes.
+///
+/// [`unlike_all`]: fn.unlike_all.html
+/// [`liked_by`]: fn.liked_by.html
+/// [`UnlikeProgress`]: enum.UnlikeProgress.html
+///
+/// Note that Twitter doesn't say when a tweet was liked, so the range is compared against the time
+/// each tweet was *posted*. Twitter also only returns a user's most recent likes (roughly the
+/// last 3000) from `liked_by`, so older likes may be out of reach until newer ones are removed;
+/// running the batch again after it finishes can find more.
+///
+/// ## Pacing
+///
+/// The batch waits between each unlike (1 second by default; change this with `with_delay`), and
+/// can stop after a set number of unlikes (unlimited by default; change this with `with_limit`).
+/// If the rate limit for loading likes or unliking tweets is hit, the batch waits for it to reset,
+/// then picks up where it left off.
+///
+/// ```rust,no_run
+/// # use egg_mode::Token;
+/// use chrono::{Duration, Utc};
+/// use egg_mode::tweet::{self, UnlikeProgress};
+/// use futures::TryStreamExt;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let token: Token = unimplemented!();
+/// let cutoff = Utc::now() - Duration::days(365);
+/// let mut batch = tweet::unlike_all(..cutoff, &token).into_stream();
+///
+/// while let Some(progress) = batch.try_next().await.unwrap() {
+///     match progress {
+///         UnlikeProgress::PageLoaded { scanned, matched } => {
+///             println!("checked {} likes, {} to remove", scanned, matched)
+///         }
+///         UnlikeProgress::Done(tweet) => println!("unliked {}", tweet.id),
+///         UnlikeProgress::Failed(id, err) => println!("couldn't unlike {}: {}", id, err),
+///         UnlikeProgress::Paused { until } => println!("rate limited until {}", until),
+///         UnlikeProgress::LimitReached => println!("limit reached"),
+///     }
+/// }
+/// # }
+/// ```
+#[must_use = "unlike batches do nothing unless turned into a stream and polled"]
+pub struct UnlikeBatch {
+    token: auth::Token,
+    range: (
+        Bound<chrono::DateTime<chrono::Utc>>,
+        Bound<chrono::DateTime<chrono::Utc>>,
+    ),
+    user_id: Option<u64>,
+    delay: Duration,
+    limit: Option<usize>,
+}
+
+impl UnlikeBatch {
+    pub(crate) fn new<R>(range: R, token: &auth::Token) -> UnlikeBatch
+    where
+        R: RangeBounds<chrono::DateTime<chrono::Utc>>,
+    {
+        UnlikeBatch {
+            token: token.clone(),
+            range: (range.start_bound().cloned(), range.end_bound().cloned()),
+            user_id: None,
+            delay: Duration::from_secs(1),
+            limit: None,
+        }
+    }
+
+    /// Sets the amount of time to wait between each unlike. Defaults to 1 second.
+    pub fn with_delay(self, delay: Duration) -> Self {
+        UnlikeBatch { delay, ..self }
+    }
+
+    /// Sets the maximum number of tweets to unlike before stopping. `None` removes the limit, which
+    /// is the default.
+    pub fn with_limit(self, limit: Option<usize>) -> Self {
+        UnlikeBatch { limit, ..self }
+    }
+
+    /// Sets the ID of the authenticated user, whose likes are loaded.
+    ///
+    /// If this isn't set, the batch calls `auth::verify_tokens` to load the authenticated user
+    /// before it starts.
+    pub fn user_id(self, user_id: u64) -> Self {
+        UnlikeBatch {
+            user_id: Some(user_id),
+            ..self
+        }
+    }
+
+    /// Starts the batch, returning a `Stream` that loads likes, unlikes tweets within the range,
+    /// and yields its progress.
+    ///
+    /// An error is only returned from the stream if loading the authenticated user or a page of
+    /// likes fails for a reason other than the rate limit, after which the stream ends. Errors from
+    /// unliking individual tweets are returned as `UnlikeProgress::Failed`, and the batch continues
+    /// with the next tweet.
+    pub fn into_stream(self) -> impl Stream<Item = Result<UnlikeProgress>> + Unpin + Send {
+        let state = UnlikeState::new(self);
+
+        Box::pin(stream::unfold(state, |mut state| async move {
+            let progress = state.next().await?;
+            Some((progress, state))
+        }))
+    }
+}
+
+struct UnlikeState {
+    batch: UnlikeBatch,
+    timeline: Option<Timeline>,
+    max_id: Option<u64>,
+    seen: SeenIds,
+    queue: VecDeque<Tweet>,
+    scanned: usize,
+    matched: usize,
+    exhausted: bool,
+    performed: usize,
+    wait_until: Option<i32>,
+    finished: bool,
+}
+
+impl UnlikeState {
+    fn new(batch: UnlikeBatch) -> UnlikeState {
+        UnlikeState {
+            batch,
+            timeline: None,
+            max_id: None,
+            seen: SeenIds::default(),
+            queue: VecDeque::new(),
+            scanned: 0,
+            matched: 0,
+            exhausted: false,
+            performed: 0,
+            wait_until: None,
+            finished: false,
+        }
+    }
+
+    async fn next(&mut self) -> Option<Result<UnlikeProgress>> {
+        if self.finished {
+            return None;
+        }
+
+        if self.queue.is_empty() {
+            if self.exhausted {
+                return None;
+            }
+            return Some(self.load_page().await);
+        }
+
+        if matches!(self.batch.limit, Some(limit) if self.performed >= limit) {
+            self.finished = true;
+            return Some(Ok(UnlikeProgress::LimitReached));
+        }
+
+        let tweet = self.queue.pop_front()?;
+        self.wait().await;
+
+        match unlike(tweet.id, &self.batch.token).await {
+            Ok(tweet) => {
+                self.performed += 1;
+                Some(Ok(UnlikeProgress::Done(tweet)))
+            }
+            Err(error::Error::RateLimit(reset)) => {
+                self.queue.push_front(tweet);
+                self.wait_until = Some(reset);
+                Some(Ok(UnlikeProgress::Paused { until: reset }))
+            }
+            Err(e) => Some(Ok(UnlikeProgress::Failed(tweet.id, e))),
+        }
+    }
+
+    async fn load_page(&mut self) -> Result<UnlikeProgress> {
+        self.wait().await;
+
+        let timeline = match self.timeline.take() {
+            Some(timeline) => timeline,
+            None => {
+                let user_id = match self.batch.user_id {
+                    Some(id) => id,
+                    None => match auth::verify_tokens(&self.batch.token).await {
+                        Ok(user) => user.id,
+                        Err(e) => {
+                            self.finished = true;
+                            return Err(e);
+                        }
+                    },
+                };
+                liked_by(user_id, &self.batch.token).with_page_size(200)
+            }
+        };
+
+        let result = timeline.call(None, self.max_id).await;
+        self.timeline = Some(timeline);
+
+        let page = match result {
+            Ok(page) => page.response,
+            Err(error::Error::RateLimit(reset)) => {
+                self.wait_until = Some(reset);
+                return Ok(UnlikeProgress::Paused { until: reset });
+            }
+            Err(e) => {
+                self.finished = true;
+                return Err(e);
+            }
+        };
+
+        Ok(self.add_page(page))
+    }
+
+    /// Queues the tweets in the given page of likes that fall within the batch's range, and moves
+    /// `max_id` past the page.
+    ///
+    /// Likes are ordered by when they were liked rather than by tweet ID, so like `Timeline`, the
+    /// next page is loaded from the ID of the last tweet on this one rather than the smallest.
+    /// Later pages can then repeat tweets from earlier ones, which are left out.
+    fn add_page(&mut self, mut page: Vec<Tweet>) -> UnlikeProgress {
+        match page.last() {
+            Some(last) => self.max_id = Some(last.id - 1),
+            None => self.exhausted = true,
+        }
+        self.seen.apply(&mut page);
+        self.scanned += page.len();
+        for tweet in page {
+            if self.batch.range.contains(&tweet.created_at) {
+                self.matched += 1;
+                self.queue.push_back(tweet);
+            }
+        }
+
+        UnlikeProgress::PageLoaded {
+            scanned: self.scanned,
+            matched: self.matched,
+        }
+    }
+
+    async fn wait(&mut self) {
+        if let Some(reset) = self.wait_until.take() {
+            let now = chrono::Utc::now().timestamp();
+            let secs = (i64::from(reset) - now).max(0) as u64 + 1;
+            tokio::time::sleep(Duration::from_secs(secs)).await;
+        } else if self.performed > 0 {
+            tokio::time::sleep(self.batch.delay).await;
+        }
+    }
+}
+
+/// Represents the progress of an `UnlikeBatch`.
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum UnlikeProgress {
+    /// A page of likes was loaded. Contains the total number of likes checked so far, and how many
+    /// of those were posted within the batch's range.
+    PageLoaded {
+        /// The number of likes loaded so far.
+        scanned: usize,
+        /// The number of loaded likes that were posted within the batch's range.
+        matched: usize,
+    },
+    /// The tweet was unliked. Contains the tweet as Twitter returned it.
+    Done(Response<Tweet>),
+    /// Unliking the tweet with the given ID failed. The batch will continue with the next tweet.
+    Failed(u64, error::Error),
+    /// The rate limit for loading likes or unliking tweets was reached. The batch will wait until
+    /// the given UTC Unix timestamp before trying again.
+    Paused {
+        /// The time the rate limit resets, as a UTC Unix timestamp.
+        until: i32,
+    },
+    /// The batch stopped because its limit was reached. No more progress will be yielded after
+    /// this.
+    LimitReached,
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Tweet;
+    use super::{
+        delete_checked, passed_time, CardValue, DraftTweet, SeenIds, Tweet, TweetId, SEEN_IDS_LIMIT,
+    };
     use crate::common::tests::load_file;
+    use crate::error::{Error, PreconditionError};
 
     use chrono::{Datelike, Timelike, Weekday};
 
@@ -897,6 +2512,64 @@ mod tests {
         ::serde_json::from_str(&sample).unwrap()
     }
 
+    #[test]
+    fn tweet_ids() {
+        let tweet = load_tweet("sample_payloads/sample-reply.json");
+        let id = TweetId::from(tweet.id);
+        assert_eq!(id, TweetId::from(&tweet.id));
+        assert_eq!(u64::from(id), tweet.id);
+        assert_eq!(id.to_string(), tweet.id.to_string());
+
+        let parsed: TweetId = serde_json::from_str(&tweet.id.to_string()).unwrap();
+        assert_eq!(parsed, id);
+        assert_eq!(serde_json::to_string(&id).unwrap(), tweet.id.to_string());
+
+        let reply = DraftTweet::new("reply").in_reply_to(id);
+        assert_eq!(reply.in_reply_to, Some(tweet.id));
+        let reply = DraftTweet::new("reply").in_reply_to(tweet.id);
+        assert_eq!(reply.in_reply_to, Some(tweet.id));
+    }
+
+    #[test]
+    fn delete_checks() {
+        let tweet = load_tweet("sample_payloads/sample-extended-onepic.json");
+        let author_id = tweet.user_id.unwrap();
+        let before = tweet.created_at - chrono::Duration::days(1);
+        let after = tweet.created_at + chrono::Duration::days(1);
+
+        let delete = delete_checked(tweet.id);
+        assert!(delete.check(author_id, &tweet).is_ok());
+        assert!(matches!(
+            delete.check(author_id + 1, &tweet),
+            Err(Error::PreconditionFailed(PreconditionError::NotOwner { author_id: id, .. }))
+                if id == author_id
+        ));
+
+        let delete = delete_checked(tweet.id)
+            .older_than(after)
+            .newer_than(before);
+        assert!(delete.check(author_id, &tweet).is_ok());
+        assert!(matches!(
+            delete_checked(tweet.id)
+                .older_than(before)
+                .check(author_id, &tweet),
+            Err(Error::PreconditionFailed(PreconditionError::TooNew { .. }))
+        ));
+        assert!(matches!(
+            delete_checked(tweet.id)
+                .newer_than(after)
+                .check(author_id, &tweet),
+            Err(Error::PreconditionFailed(PreconditionError::TooOld { .. }))
+        ));
+
+        let mut anonymous = tweet.clone();
+        anonymous.user_id = None;
+        assert!(matches!(
+            delete.check(author_id, &anonymous),
+            Err(Error::MissingValue("user"))
+        ));
+    }
+
     #[test]
     fn parse_basic() {
         let sample = load_tweet("sample_payloads/sample-extended-onepic.json");
@@ -939,7 +2612,7 @@ mod tests {
         assert_eq!(&sample.text[range.0..range.1],
                    ".@Serrayak said he’d use what-ev-er I came up with as his Halloween avatar so I’m just making sure you all know he said that"
         );
-        assert_eq!(sample.truncated, false);
+        assert!(!sample.truncated);
     }
 
     #[test]
@@ -967,6 +2640,64 @@ mod tests {
         assert_eq!(sample.in_reply_to_status_id, Some(782643731665080322));
     }
 
+    #[test]
+    fn draft_reply_all() {
+        let sample = load_tweet("sample_payloads/sample-retweet.json");
+
+        let draft = DraftTweet::new("hi").reply_all(&sample, Vec::<u64>::new());
+        assert_eq!(draft.in_reply_to, Some(782770881193992193));
+        assert_eq!(draft.auto_populate_reply_metadata, Some(true));
+        assert!(draft.exclude_reply_user_ids.is_none());
+
+        // the original author can't be excluded, and only mentioned users are
+        let draft = DraftTweet::new("hi")
+            .reply_all(&sample, vec!["ANDREWHUANGBOT", "@andrewismusic", "nobody"]);
+        assert_eq!(
+            draft.exclude_reply_user_ids.as_deref(),
+            Some(&[781705823168958464][..])
+        );
+    }
+
+    #[test]
+    fn repair_entity_ranges() {
+        use crate::entities::{EntityKind, RepairMethod};
+
+        let sample = load_tweet("sample_payloads/sample-reply.json");
+        assert!(sample.entity_repairs.is_empty());
+
+        // this payload's text was mangled into mojibake, which moved the link
+        let sample = load_tweet("sample_payloads/compatibilityplus_classic_13994.json");
+        assert_eq!(sample.entity_repairs.len(), 1);
+        let repair = &sample.entity_repairs[0];
+        assert_eq!(repair.kind, EntityKind::Url);
+        assert_eq!(repair.method, RepairMethod::Relocated);
+        assert_eq!(repair.range, sample.entities.urls[0].range);
+        let (start, end) = repair.range;
+        assert_eq!(&sample.text[start..end], sample.entities.urls[0].url);
+
+        let mut json: serde_json::Value =
+            serde_json::from_str(&load_file("sample_payloads/sample-reply.json")).unwrap();
+        json["entities"]["hashtags"] =
+            serde_json::json!([{ "text": "gone", "indices": [500, 505] }]);
+        json["display_text_range"] = serde_json::json!([0, 500]);
+        let tweet: Tweet = serde_json::from_value(json).unwrap();
+        let kinds = tweet
+            .entity_repairs
+            .iter()
+            .map(|repair| (repair.kind, repair.method))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                (EntityKind::DisplayTextRange, RepairMethod::Clamped),
+                (EntityKind::Hashtag, RepairMethod::Clamped),
+            ]
+        );
+        let len = tweet.text.len();
+        assert_eq!(tweet.display_text_range, Some((0, len)));
+        assert_eq!(tweet.entities.hashtags[0].range, (len, len));
+    }
+
     #[test]
     fn parse_quote() {
         let sample = load_tweet("sample_payloads/sample-quote.json");
@@ -986,6 +2717,23 @@ mod tests {
                    "it's working: follow @andrewhuangbot for a random lyric of mine every hour. we'll call this version 0.1.0. wanna get line breaks in there");
     }
 
+    #[test]
+    fn my_retweet_target() {
+        let mut sample = load_tweet("sample_payloads/sample-retweet.json");
+        let original_id = sample.retweeted_status.as_ref().unwrap().id;
+        assert_eq!(sample.my_retweet_target(), Some(original_id));
+
+        sample.retweeted = Some(false);
+        let mut original = *sample.retweeted_status.take().unwrap();
+        original.retweeted = Some(false);
+        assert_eq!(original.my_retweet_target(), None);
+
+        original.current_user_retweet = Some(sample.id);
+        assert_eq!(original.my_retweet_target(), Some(original_id));
+        sample.retweeted_status = Some(Box::new(original));
+        assert_eq!(sample.my_retweet_target(), Some(original_id));
+    }
+
     #[test]
     fn parse_image_alt_text() {
         let sample = load_tweet("sample_payloads/sample-image-alt-text.json");
@@ -997,6 +2745,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn lenient_source() {
+        let sample = load_file("sample_payloads/sample-extended-onepic.json");
+        let mut json: serde_json::Value = serde_json::from_str(&sample).unwrap();
+
+        json["source"] = "<b>not a link</b>".into();
+        let tweet: Tweet = serde_json::from_value(json.clone()).unwrap();
+        assert!(tweet.source.is_none());
+        assert_eq!(tweet.source_raw.as_deref(), Some("<b>not a link</b>"));
+
+        json["source"] = serde_json::Value::Null;
+        let tweet: Tweet = serde_json::from_value(json.clone()).unwrap();
+        assert!(tweet.source.is_none());
+        assert!(tweet.source_raw.is_none());
+
+        json.as_object_mut().unwrap().remove("source");
+        let tweet: Tweet = serde_json::from_value(json).unwrap();
+        assert!(tweet.source.is_none());
+    }
+
     #[test]
     fn roundtrip_deser() {
         let sample = load_file("sample_payloads/tweet_array.json");
@@ -1007,4 +2775,403 @@ mod tests {
 
         assert_eq!(json1, json2);
     }
+
+    #[test]
+    fn dedup_seen_ids() {
+        let sample = load_file("sample_payloads/tweet_array.json");
+        let tweets: Vec<Tweet> = serde_json::from_str(&sample).unwrap();
+        let mut seen = SeenIds::default();
+
+        let mut page = tweets.clone();
+        seen.apply(&mut page);
+        assert_eq!(page.len(), tweets.len());
+
+        let mut page = tweets.clone();
+        seen.apply(&mut page);
+        assert!(page.is_empty());
+
+        for id in 0..SEEN_IDS_LIMIT as u64 {
+            seen.insert(id);
+        }
+        assert_eq!(seen.ids.len(), SEEN_IDS_LIMIT);
+        let mut page = tweets.clone();
+        seen.apply(&mut page);
+        assert_eq!(page.len(), tweets.len());
+    }
+
+    #[test]
+    fn effective_entities() {
+        let sample = load_tweet("sample_payloads/sample-extended-onepic.json");
+        assert!(sample.display_text().ends_with("he said that"));
+        assert_eq!(sample.all_media().len(), 1);
+
+        let entities = sample.effective_entities();
+        assert_eq!(entities.user_mentions[0].range, (1, 10));
+        let media = entities.media.unwrap();
+        let end = sample.display_text().len();
+        assert_eq!(media[0].range, (end, end));
+
+        // pretend the leading mention was an automatic reply mention
+        let content = load_file("sample_payloads/sample-extended-onepic.json");
+        let mut json: serde_json::Value = serde_json::from_str(&content).unwrap();
+        json["display_text_range"] = serde_json::json!([11, 124]);
+        let reply: Tweet = serde_json::from_value(json).unwrap();
+        assert!(reply.display_text().starts_with("said he"));
+        let entities = reply.effective_entities();
+        assert!(entities.user_mentions.is_empty());
+        assert_eq!(entities.media.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn timeline_filters() {
+        let sample = load_file("sample_payloads/tweet_array.json");
+        let tweets: Vec<Tweet> = serde_json::from_str(&sample).unwrap();
+        let token = crate::auth::Token::Bearer("token".into());
+        let timeline = super::Timeline::new(crate::links::statuses::HOME_TIMELINE, None, &token);
+
+        let english = timeline
+            .filter_lang("EN")
+            .filter_tweets(|tweet| tweet.retweeted_status.is_none());
+        let mut filtered = tweets.clone();
+        english.filter.as_ref().unwrap().apply(&mut filtered);
+        let ids = filtered.iter().map(|t| t.id).collect::<Vec<_>>();
+        assert_eq!(ids, vec![1267519582505512960, 1267519580756422659]);
+
+        let french = english.filter_lang("fr");
+        let mut filtered = tweets;
+        french.filter.as_ref().unwrap().apply(&mut filtered);
+        assert!(filtered.is_empty());
+    }
+
+    #[test]
+    fn parse_trimmed_tweet() {
+        let sample = load_file("sample_payloads/sample-extended-onepic.json");
+        let full: Tweet = serde_json::from_str(&sample).unwrap();
+        let author_id = full.user.as_ref().unwrap().id;
+        assert_eq!(full.user_id, Some(author_id));
+
+        let mut json: serde_json::Value = serde_json::from_str(&sample).unwrap();
+        json["user"] = serde_json::json!({ "id": author_id, "id_str": author_id.to_string() });
+        json.as_object_mut().unwrap().remove("entities");
+        let tweet: Tweet = serde_json::from_value(json).unwrap();
+
+        assert!(tweet.user.is_none());
+        assert_eq!(tweet.user_id, Some(author_id));
+        assert!(tweet.entities.urls.is_empty());
+        assert!(tweet.entities.media.is_none());
+        assert_eq!(tweet.text, full.text);
+
+        let round_trip: Tweet =
+            serde_json::from_str(&serde_json::to_string(&tweet).unwrap()).unwrap();
+        assert_eq!(round_trip.user_id, Some(author_id));
+
+        let mut json: serde_json::Value = serde_json::from_str(&sample).unwrap();
+        json["user"]["followers_count"] = "many".into();
+        assert!(serde_json::from_value::<Tweet>(json).is_err());
+
+        let params =
+            crate::common::ParamList::new().payload_options(&crate::PayloadOptions::minimal());
+        assert_eq!(
+            params.to_urlencoded(),
+            "trim_user=true&skip_status=true&include_entities=false"
+        );
+    }
+
+    #[test]
+    fn classify_interactions() {
+        let sample = load_file("sample_payloads/extended_extended_14001.json");
+        let reply: Tweet = serde_json::from_str(&sample).unwrap();
+        assert!(reply.mentions(2244994945));
+        assert!(reply.mentions("twitterdev"));
+        assert!(reply.mentions("@TwitterDev"));
+        assert!(!reply.mentions("rustlang"));
+        assert!(reply.is_reply_to(783214));
+        assert!(reply.is_reply_to("@Twitter"));
+        assert!(!reply.is_reply_to(2244994945));
+        assert!(!reply.is_retweet_of(783214));
+
+        let sample = load_file("sample_payloads/sample-retweet.json");
+        let retweet: Tweet = serde_json::from_str(&sample).unwrap();
+        assert!(retweet.is_retweet_of(26053487));
+        assert!(retweet.is_retweet_of("AndrewIsMusic"));
+        assert!(!retweet.is_retweet_of("andrewhuangbot"));
+        assert!(!retweet.is_reply_to(26053487));
+
+        let sample = load_file("sample_payloads/sample-quote.json");
+        let quote: Tweet = serde_json::from_str(&sample).unwrap();
+        assert!(quote.is_quote_of("QuietMisdreavus"));
+        assert!(!quote.is_retweet_of("QuietMisdreavus"));
+    }
+
+    #[test]
+    fn parse_card() {
+        let sample = load_file("sample_payloads/sample-extended-onepic.json");
+        let mut json: serde_json::Value = serde_json::from_str(&sample).unwrap();
+        json["card_uri"] = "https://t.co/abc123".into();
+        json["card"] = serde_json::json!({
+            "name": "summary_large_image",
+            "url": "https://t.co/abc123",
+            "card_type_url": "http://card-type-url-is-deprecated.invalid",
+            "binding_values": {
+                "title": { "type": "STRING", "string_value": "A page" },
+                "description": { "type": "STRING", "string_value": "About the page" },
+                "domain": { "type": "STRING", "string_value": "example.com" },
+                "thumbnail_image": {
+                    "type": "IMAGE",
+                    "image_value": { "url": "https://pbs.twimg.com/small.jpg", "width": 144, "height": 72 }
+                },
+                "summary_photo_image_large": {
+                    "type": "IMAGE",
+                    "image_value": { "url": "https://pbs.twimg.com/large.jpg", "width": 1200, "height": 600 }
+                },
+                "site": { "type": "USER", "user_value": { "id_str": "783214", "path": [] } },
+                "theme_color": { "type": "IMAGE_COLOR", "image_color_value": {} }
+            }
+        });
+
+        let tweet: Tweet = serde_json::from_value(json).unwrap();
+        assert_eq!(tweet.card_uri.as_deref(), Some("https://t.co/abc123"));
+        let card = tweet.card.as_ref().unwrap();
+        assert_eq!(card.name, "summary_large_image");
+        assert_eq!(card.title(), Some("A page"));
+        assert_eq!(card.description(), Some("About the page"));
+        assert_eq!(card.domain(), Some("example.com"));
+        assert_eq!(card.image().unwrap().width, 1200);
+        assert_eq!(card.binding_values["site"], CardValue::User(783214));
+        assert_eq!(
+            card.binding_values["theme_color"],
+            CardValue::Other("IMAGE_COLOR".into())
+        );
+
+        let roundtrip: Tweet =
+            serde_json::from_value(serde_json::to_value(&tweet).unwrap()).unwrap();
+        assert_eq!(roundtrip.card.as_ref(), Some(card));
+
+        let plain = load_tweet("sample_payloads/sample-extended-onepic.json");
+        assert!(plain.card.is_none());
+    }
+
+    #[test]
+    fn parse_edit_info() {
+        let sample = load_file("sample_payloads/sample-extended-onepic.json");
+        let mut json: serde_json::Value = serde_json::from_str(&sample).unwrap();
+        json["conversation_id_str"] = "1560000000000000000".into();
+        json["edit_history"] = serde_json::json!({
+            "initial_tweet_id": "1560000000000000000",
+            "edit_tweet_ids": ["1560000000000000000", "1560000000000000001"]
+        });
+        json["edit_controls"] = serde_json::json!({
+            "edits_remaining": 4,
+            "is_edit_eligible": true,
+            "editable_until_ms": 1660000000000i64
+        });
+
+        let tweet: Tweet = serde_json::from_value(json).unwrap();
+        assert_eq!(tweet.conversation_id, Some(1560000000000000000));
+        let info = tweet.edit_info.as_ref().unwrap();
+        assert!(info.is_edited());
+        assert_eq!(info.latest_tweet_id(), 1560000000000000001);
+        assert_eq!(info.edits_remaining, Some(4));
+        assert_eq!(info.editable_until.unwrap().timestamp(), 1660000000);
+
+        let roundtrip: Tweet =
+            serde_json::from_value(serde_json::to_value(&tweet).unwrap()).unwrap();
+        assert_eq!(roundtrip.edit_info.as_ref(), Some(info));
+
+        let plain = load_tweet("sample_payloads/sample-extended-onepic.json");
+        assert!(plain.edit_info.is_none());
+    }
+
+    #[cfg(feature = "places")]
+    #[test]
+    fn pick_place() {
+        use super::best_place;
+        use crate::place::{Place, PlaceType};
+
+        let sample = load_tweet("sample_payloads/sample-retweet.json");
+        let city = sample.retweeted_status.unwrap().place.unwrap();
+        let with_type = |id: &str, place_type| Place {
+            id: id.to_string(),
+            place_type,
+            ..city.clone()
+        };
+        let places = vec![
+            with_type("country", PlaceType::Country),
+            with_type("admin", PlaceType::Admin),
+            with_type("neighborhood", PlaceType::Neighborhood),
+            with_type("city", PlaceType::City),
+        ];
+
+        let best = |granularity| best_place(&places, granularity).map(|p| p.id.as_str());
+        assert_eq!(best(PlaceType::City), Some("city"));
+        assert_eq!(best(PlaceType::Admin), Some("admin"));
+        assert_eq!(best(PlaceType::PointOfInterest), Some("neighborhood"));
+        assert_eq!(
+            best_place(&places[..2], PlaceType::City).unwrap().id,
+            "admin"
+        );
+        assert!(best_place(&[], PlaceType::City).is_none());
+    }
+
+    #[cfg(feature = "raw-fields")]
+    #[test]
+    fn extra_fields() {
+        let sample = load_tweet("sample_payloads/sample-extended-onepic.json");
+
+        assert!(sample.extra.contains_key("contributors"));
+        assert!(!sample.extra.contains_key("created_at"));
+
+        let json = serde_json::to_value(&sample).unwrap();
+        let roundtrip: Tweet = serde_json::from_value(json).unwrap();
+        assert_eq!(sample.extra, roundtrip.extra);
+    }
+
+    #[test]
+    fn backfill_stops_at_time() {
+        use chrono::TimeZone;
+
+        let time = chrono::Utc.timestamp_opt(1_600_000_000, 0).unwrap();
+        let at_time = crate::snowflake::min_id_at(time);
+        assert!(!passed_time(Some(at_time), time));
+        assert!(passed_time(Some(at_time - (1 << 22)), time));
+        assert!(passed_time(Some(20), time));
+        assert!(!passed_time(None, time));
+    }
+
+    #[test]
+    fn unlike_batch_filters_page() {
+        use super::{UnlikeBatch, UnlikeProgress, UnlikeState};
+        use chrono::TimeZone;
+
+        let sample = load_file("sample_payloads/tweet_array.json");
+        let page: Vec<Tweet> = ::serde_json::from_str(&sample).unwrap();
+
+        let cutoff = chrono::Utc.with_ymd_and_hms(2020, 5, 28, 0, 0, 0).unwrap();
+        let token = crate::Token::Bearer("token".to_string());
+        let mut state = UnlikeState::new(UnlikeBatch::new(..cutoff, &token));
+
+        match state.add_page(page) {
+            UnlikeProgress::PageLoaded { scanned, matched } => {
+                assert_eq!(scanned, 20);
+                assert_eq!(matched, 9);
+            }
+            other => panic!("unexpected progress: {:?}", other),
+        }
+        assert_eq!(state.queue.len(), 9);
+        assert!(state.queue.iter().all(|tweet| tweet.created_at < cutoff));
+        assert_eq!(state.queue[0].id, 1265787353878212609);
+        assert_eq!(state.max_id, Some(1265628849343168512));
+        assert!(!state.exhausted);
+
+        match state.add_page(vec![]) {
+            UnlikeProgress::PageLoaded { scanned, matched } => {
+                assert_eq!(scanned, 20);
+                assert_eq!(matched, 9);
+            }
+            other => panic!("unexpected progress: {:?}", other),
+        }
+        assert!(state.exhausted);
+    }
+
+    #[test]
+    fn unlike_batch_pages_by_like_order() {
+        use super::{UnlikeBatch, UnlikeState};
+
+        let sample = load_file("sample_payloads/tweet_array.json");
+        let mut likes: Vec<Tweet> = ::serde_json::from_str(&sample).unwrap();
+        let token = crate::Token::Bearer("token".to_string());
+        let mut state = UnlikeState::new(UnlikeBatch::new(.., &token));
+
+        // a recent like of an old tweet comes first, ahead of likes of newer tweets
+        let old_tweet = likes.pop().unwrap();
+        let mut page = likes.split_off(10);
+        page.insert(0, old_tweet.clone());
+        state.add_page(page.clone());
+        assert_eq!(state.max_id, Some(page.last().unwrap().id - 1));
+        assert!(state.max_id.unwrap() > old_tweet.id);
+        assert_eq!(state.queue.len(), 10);
+
+        // tweets repeated on a later page are only queued once
+        likes.push(old_tweet);
+        state.add_page(likes);
+        assert_eq!(state.scanned, 20);
+        assert_eq!(state.queue.len(), 20);
+        assert_eq!(state.max_id, Some(page[0].id - 1));
+    }
+
+    #[test]
+    fn prefetched_pages() {
+        use super::{Prefetched, Timeline};
+        use crate::{common::RateLimit, links, Response};
+        use futures::{channel::mpsc, executor::block_on};
+        use std::sync::Arc;
+
+        let sample = load_file("sample_payloads/tweet_array.json");
+        let page: Vec<Tweet> = ::serde_json::from_str(&sample).unwrap();
+        let (first, second) = page.split_at(10);
+
+        let token = crate::Token::Bearer("token".to_string());
+        let mut timeline = Timeline::new(links::statuses::HOME_TIMELINE, None, &token).prefetch(2);
+        timeline.map_ids(first);
+
+        let (mut sender, pages) = mpsc::channel(1);
+        sender
+            .try_send(Ok(Response::new(RateLimit::unknown(), second.to_vec())))
+            .unwrap();
+        timeline.prefetched = Some(Prefetched {
+            since_id: None,
+            after: timeline.min_id.unwrap(),
+            pages: Arc::new(futures::lock::Mutex::new(pages)),
+        });
+
+        //the next page comes from the buffer instead of the network
+        let (mut timeline, loaded) = block_on(timeline.older(None)).unwrap();
+        assert_eq!(loaded.len(), 10);
+        assert_eq!(loaded[0].id, second[0].id);
+        assert_eq!(timeline.max_id, Some(second[0].id));
+        assert_eq!(timeline.min_id, Some(second[9].id));
+
+        //the running prefetch is kept, and now follows on from the page just returned
+        let prefetched = timeline.prefetched.as_ref().unwrap();
+        assert_eq!(prefetched.after, second[9].id);
+
+        //turning prefetching off drops the buffer
+        timeline.prefetch = 0;
+        timeline.start_prefetch(None);
+        assert!(timeline.prefetched.is_none());
+    }
+
+    #[test]
+    fn stream_liked_by() {
+        use super::{home_timeline, liked_by};
+        use crate::common::PayloadOptions;
+
+        let token = crate::Token::Bearer("token".to_string());
+        let likes = liked_by(1234, &token)
+            .with_page_size(200)
+            .with_payload_options(PayloadOptions::new().trim_user(true));
+
+        let uri = likes.request(None, Some(5678)).uri().to_string();
+        assert!(uri.contains("user_id=1234"));
+        assert!(uri.contains("tweet_mode=extended"));
+        assert!(uri.contains("trim_user=true"));
+        assert!(uri.contains("count=200"));
+        assert!(uri.contains("max_id=5678"));
+
+        //likes are ordered by when they were liked, so repeated tweets are left out of the stream
+        let mut likes = likes.for_stream();
+        assert!(home_timeline(&token).for_stream().seen.is_none());
+        let seen = likes.seen.as_mut().unwrap();
+
+        let sample = load_file("sample_payloads/tweet_array.json");
+        let mut page: Vec<Tweet> = ::serde_json::from_str(&sample).unwrap();
+        let mut repeated = page.split_off(10);
+        seen.apply(&mut page);
+        assert_eq!(page.len(), 10);
+
+        repeated.push(page[0].clone());
+        seen.apply(&mut repeated);
+        assert_eq!(repeated.len(), 10);
+        assert!(repeated.iter().all(|tweet| tweet.id != page[0].id));
+    }
 }
@@ -1,20 +1,26 @@
 use crate::{place, user};
-use chrono;
-use serde::Deserialize;
+use chrono::{self, TimeZone};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
 
-use crate::common::serde_datetime;
+use crate::common::{serde_datetime, serde_opt_via_string, serde_vec_via_string};
 
-use super::{
-    deserialize_tweet_source, ExtendedTweetEntities, FilterLevel, Tweet, TweetEntities, TweetSource,
-};
+use super::{Card, EditInfo, ExtendedTweetEntities, FilterLevel, Tweet, TweetEntities};
 
 #[derive(Debug, Clone, Deserialize)]
 pub(crate) struct RawTweet {
+    pub card: Option<Card>,
+    pub card_uri: Option<String>,
     pub coordinates: Option<RawCoordinates>,
+    #[serde(default, with = "serde_opt_via_string")]
+    pub conversation_id_str: Option<u64>,
     #[serde(with = "serde_datetime")]
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub current_user_retweet: Option<CurrentUserRetweet>,
     pub display_text_range: Option<(usize, usize)>,
+    pub edit_controls: Option<RawEditControls>,
+    pub edit_history: Option<RawEditHistory>,
+    #[serde(default)]
     pub entities: TweetEntities,
     pub extended_entities: Option<ExtendedTweetEntities>,
     pub extended_tweet: Option<RawExtendedTweet>,
@@ -33,29 +39,75 @@ pub(crate) struct RawTweet {
     pub retweet_count: i32,
     pub retweeted: Option<bool>,
     pub retweeted_status: Option<Box<Tweet>>,
-    #[serde(deserialize_with = "deserialize_tweet_source")]
-    pub source: Option<TweetSource>,
+    #[serde(default, rename = "source")]
+    pub source_raw: Option<String>,
     pub text: Option<String>,
     pub full_text: Option<String>,
     pub truncated: bool,
-    pub user: Option<Box<user::TwitterUser>>,
+    #[serde(default, deserialize_with = "deser_tweet_user")]
+    pub user: Option<RawTweetUser>,
     #[serde(default)]
     pub withheld_copyright: bool,
     pub withheld_in_countries: Option<Vec<String>>,
     pub withheld_scope: Option<String>,
+    #[cfg(feature = "raw-fields")]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
+#[allow(dead_code)]
 pub(crate) struct RawExtendedTweet {
     pub full_text: String,
     pub display_text_range: Option<(usize, usize)>,
+    #[serde(default)]
     pub entities: TweetEntities,
     pub extended_entities: Option<ExtendedTweetEntities>,
 }
 
+///The author of a tweet, which is cut down to their ID when the tweet was loaded with `trim_user`.
+#[derive(Debug, Clone)]
+pub(crate) enum RawTweetUser {
+    Full(Box<user::TwitterUser>),
+    Trimmed(u64),
+}
+
+impl RawTweetUser {
+    pub fn id(&self) -> u64 {
+        match self {
+            RawTweetUser::Full(user) => user.id,
+            RawTweetUser::Trimmed(id) => *id,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TrimmedUser {
+    id: u64,
+}
+
+///Loads a tweet's user, telling trimmed users apart by their missing `screen_name`. Checking for
+///the field first (instead of trying both shapes) keeps the errors from malformed full users.
+fn deser_tweet_user<'de, D>(ser: D) -> Result<Option<RawTweetUser>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = match Option::<serde_json::Value>::deserialize(ser)? {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    let user = if value.get("screen_name").is_some() {
+        serde_json::from_value(value).map(RawTweetUser::Full)
+    } else {
+        serde_json::from_value(value).map(|user: TrimmedUser| RawTweetUser::Trimmed(user.id))
+    };
+    user.map(Some).map_err(D::Error::custom)
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub(crate) struct RawCoordinates {
     #[serde(rename = "type")]
+    #[allow(dead_code)]
     pub kind: String,
     pub coordinates: (f64, f64),
 }
@@ -64,3 +116,56 @@ pub(crate) struct RawCoordinates {
 pub(crate) struct CurrentUserRetweet {
     pub id: u64,
 }
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct RawEditHistory {
+    #[serde(with = "serde_opt_via_string", default)]
+    pub initial_tweet_id: Option<u64>,
+    #[serde(with = "serde_vec_via_string", default)]
+    pub edit_tweet_ids: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct RawEditControls {
+    pub edits_remaining: Option<i32>,
+    pub is_edit_eligible: Option<bool>,
+    ///Twitter has been seen to send this both as a number and as a string.
+    pub editable_until_ms: Option<serde_json::Value>,
+}
+
+impl RawTweet {
+    ///Combines the edit metadata into an `EditInfo`, if Twitter sent any.
+    pub fn edit_info(&self) -> Option<EditInfo> {
+        if self.edit_history.is_none() && self.edit_controls.is_none() {
+            return None;
+        }
+
+        let (initial_tweet_id, edit_tweet_ids) = match &self.edit_history {
+            Some(history) if !history.edit_tweet_ids.is_empty() => (
+                history
+                    .initial_tweet_id
+                    .unwrap_or(history.edit_tweet_ids[0]),
+                history.edit_tweet_ids.clone(),
+            ),
+            Some(history) => (history.initial_tweet_id.unwrap_or(self.id), vec![self.id]),
+            None => (self.id, vec![self.id]),
+        };
+        let controls = self.edit_controls.as_ref();
+        let editable_until = controls
+            .and_then(|c| c.editable_until_ms.as_ref())
+            .and_then(|ms| match ms {
+                serde_json::Value::Number(n) => n.as_i64(),
+                serde_json::Value::String(s) => s.parse().ok(),
+                _ => None,
+            })
+            .and_then(|ms| chrono::Utc.timestamp_millis_opt(ms).single());
+
+        Some(EditInfo {
+            initial_tweet_id,
+            edit_tweet_ids,
+            editable_until,
+            edits_remaining: controls.and_then(|c| c.edits_remaining),
+            is_edit_eligible: controls.and_then(|c| c.is_edit_eligible),
+        })
+    }
+}
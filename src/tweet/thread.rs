@@ -0,0 +1,338 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Splitting long text into a thread of tweets, for `ThreadDraft`.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::common::*;
+use crate::error::ThreadError;
+use crate::{auth, media};
+
+use super::{DraftTweet, Tweet, TweetId};
+
+///The weighted length Twitter allows in a single tweet.
+const MAX_WEIGHT: usize = 280;
+
+///The weight of a URL in tweet text, since Twitter wraps every link with t.co.
+const URL_WEIGHT: usize = 23;
+
+///A long piece of text to post as a thread of replies, split into tweets that fit Twitter's
+///character limit.
+///
+///The text is split between words, so each tweet holds as many whole words as will fit. Words
+///too long for a single tweet are split between grapheme clusters, so that characters like emoji
+///with skin tone modifiers or flags aren't broken apart. Tweets are measured the way Twitter
+///counts them: links count as 23 characters no matter their length, and characters outside the
+///Latin, Greek, Cyrillic, and other common ranges (such as CJK text and most emoji) count as two.
+///
+///Media can be attached to any tweet in the thread with `with_media_at`, and `numbered` adds a
+///"1/4"-style suffix to each tweet, which is accounted for when the text is split.
+///
+///```rust,no_run
+///# use egg_mode::Token;
+///# #[tokio::main]
+///# async fn main() {
+///# let token: Token = unimplemented!();
+///# let long_text = String::new();
+///# let chart: egg_mode::media::MediaId = unimplemented!();
+///use egg_mode::tweet::ThreadDraft;
+///
+///let thread = ThreadDraft::new(long_text)
+///    .numbered(true)
+///    .with_media_at(1, chart);
+///
+///match thread.send(&token).await {
+///    Ok(tweets) => println!("posted {} tweets", tweets.len()),
+///    Err(e) => println!("{}, after posting {} tweets", e.error, e.posted.len()),
+///}
+///# }
+///```
+#[derive(Debug, Clone)]
+pub struct ThreadDraft {
+    text: Cow<'static, str>,
+    in_reply_to: Option<u64>,
+    numbered: bool,
+    media: BTreeMap<usize, Vec<media::MediaId>>,
+}
+
+impl ThreadDraft {
+    ///Creates a new `ThreadDraft` with the given text, to be split into tweets when it's sent.
+    pub fn new<S: Into<Cow<'static, str>>>(text: S) -> ThreadDraft {
+        ThreadDraft {
+            text: text.into(),
+            in_reply_to: None,
+            numbered: false,
+            media: BTreeMap::new(),
+        }
+    }
+
+    ///Posts the first tweet of the thread as a reply to the given tweet, to continue an existing
+    ///thread.
+    pub fn in_reply_to<T: Into<TweetId>>(self, in_reply_to: T) -> ThreadDraft {
+        ThreadDraft {
+            in_reply_to: Some(in_reply_to.into().0),
+            ..self
+        }
+    }
+
+    ///Sets whether to append a " 1/4"-style suffix to each tweet in the thread.
+    ///
+    ///The suffix is counted against each tweet's character limit when the text is split, so
+    ///numbered threads may be split into more tweets than unnumbered ones. A thread that fits in
+    ///one tweet isn't numbered.
+    pub fn numbered(self, numbered: bool) -> ThreadDraft {
+        ThreadDraft { numbered, ..self }
+    }
+
+    ///Attaches the given media to the tweet at the given index in the thread, counting from
+    ///zero.
+    ///
+    ///As with `DraftTweet::add_media`, only the last four media attached to a tweet are kept. If
+    ///the text is split into fewer tweets than `index` implies, the media is attached to the last
+    ///tweet of the thread instead.
+    pub fn with_media_at(mut self, index: usize, media_id: media::MediaId) -> ThreadDraft {
+        self.media.entry(index).or_default().push(media_id);
+        self
+    }
+
+    ///Returns the text of each tweet in the thread, including its number if `numbered` was set.
+    pub fn chunks(&self) -> Vec<String> {
+        let text = self.text.trim();
+        if !self.numbered {
+            return split_text(text, MAX_WEIGHT);
+        }
+
+        //the suffix takes more room as the thread gets longer, so split with room for the widest
+        //suffix and try again if the thread ends up needing more digits than that
+        let mut digits = 1;
+        let chunks = loop {
+            let chunks = split_text(text, MAX_WEIGHT - (2 * digits + 2));
+            if chunks.len().to_string().len() <= digits {
+                break chunks;
+            }
+            digits += 1;
+        };
+
+        if chunks.len() < 2 {
+            return chunks;
+        }
+
+        let total = chunks.len();
+        chunks
+            .into_iter()
+            .enumerate()
+            .map(|(idx, chunk)| format!("{} {}/{}", chunk, idx + 1, total))
+            .collect()
+    }
+
+    ///Returns the `DraftTweet`s that make up the thread, with their media attached.
+    ///
+    ///Only the first draft is marked as a reply, if `in_reply_to` was set. When sending these
+    ///yourself, mark each of the others as a reply to the tweet posted before it.
+    pub fn drafts(&self) -> Vec<DraftTweet> {
+        let mut drafts = self
+            .chunks()
+            .into_iter()
+            .map(DraftTweet::new)
+            .collect::<Vec<_>>();
+
+        if let (Some(first), Some(id)) = (drafts.first_mut(), self.in_reply_to) {
+            first.in_reply_to = Some(id);
+        }
+
+        let last = drafts.len().saturating_sub(1);
+        for (&index, media) in &self.media {
+            if let Some(draft) = drafts.get_mut(index.min(last)) {
+                for media_id in media {
+                    draft.add_media(media_id.clone());
+                }
+            }
+        }
+
+        drafts
+    }
+
+    ///Posts the thread as the authenticated user, each tweet replying to the one before it.
+    ///
+    ///The tweets are returned in the order they were posted, along with the rate-limit
+    ///information from the last one. If posting a tweet fails, the tweets posted before it are
+    ///left in place, and returned in the `ThreadError` along with the error, so the rest of the
+    ///thread can be retried by replying to the last of them.
+    pub async fn send(&self, token: &auth::Token) -> Result<Response<Vec<Tweet>>, ThreadError> {
+        let mut drafts = self.drafts().into_iter();
+        //text without any words is still sent as one tweet, so Twitter can report why it's wrong
+        let first = drafts
+            .next()
+            .unwrap_or_else(|| DraftTweet::new(self.text.clone()));
+        let first = first.send(token).await.map_err(|error| ThreadError {
+            posted: Vec::new(),
+            error,
+        })?;
+        let mut thread = Response::map(first, |tweet| vec![tweet]);
+
+        for mut draft in drafts {
+            draft.in_reply_to = thread.response.last().map(|tweet| tweet.id);
+            match draft.send(token).await {
+                Ok(tweet) => {
+                    let (rate_limit, tweet) = Response::into_parts(tweet);
+                    thread.rate_limit_status = rate_limit;
+                    thread.response.push(tweet);
+                }
+                Err(error) => {
+                    return Err(ThreadError {
+                        posted: thread.response,
+                        error,
+                    })
+                }
+            }
+        }
+
+        Ok(thread)
+    }
+}
+
+///Splits the given text into pieces no heavier than `budget`, breaking between words where
+///possible.
+fn split_text(text: &str, budget: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut weight = 0;
+
+    for (gap, word) in words(text) {
+        let word_weight = weighted_len(word);
+        let gap_weight = weighted_len(gap);
+
+        if !current.is_empty() && weight + gap_weight + word_weight <= budget {
+            current.push_str(gap);
+            current.push_str(word);
+            weight += gap_weight + word_weight;
+            continue;
+        }
+
+        if !current.is_empty() {
+            chunks.push(std::mem::take(&mut current));
+        }
+
+        if word_weight <= budget {
+            current.push_str(word);
+            weight = word_weight;
+        } else {
+            //words that don't fit in a tweet on their own are broken between grapheme clusters
+            weight = 0;
+            for grapheme in word.graphemes(true) {
+                let grapheme_weight = grapheme.chars().map(char_weight).sum::<usize>();
+                if weight + grapheme_weight > budget && !current.is_empty() {
+                    chunks.push(std::mem::take(&mut current));
+                    weight = 0;
+                }
+                current.push_str(grapheme);
+                weight += grapheme_weight;
+            }
+        }
+    }
+
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+
+    chunks
+}
+
+///Returns the words of the given text, each with the whitespace that came before it.
+fn words(text: &str) -> impl Iterator<Item = (&str, &str)> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        let start = rest.find(|ch: char| !ch.is_whitespace())?;
+        let end = rest[start..]
+            .find(char::is_whitespace)
+            .map_or(rest.len(), |end| start + end);
+        let (gap, word) = (&rest[..start], &rest[start..end]);
+        rest = &rest[end..];
+        Some((gap, word))
+    })
+}
+
+///Returns the length Twitter counts for a single word of tweet text.
+fn weighted_len(text: &str) -> usize {
+    if text.starts_with("http://") || text.starts_with("https://") {
+        URL_WEIGHT
+    } else {
+        text.chars().map(char_weight).sum()
+    }
+}
+
+///Returns the weight Twitter gives the given character.
+fn char_weight(ch: char) -> usize {
+    match ch as u32 {
+        0x0000..=0x10FF | 0x2000..=0x200D | 0x2010..=0x201F | 0x2032..=0x2037 => 1,
+        _ => 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_thread() {
+        let word = "abcdefghi";
+        let text = vec![word; 60].join(" ");
+        let chunks = ThreadDraft::new(text.clone()).chunks();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].len(), 279);
+        assert_eq!(chunks.join(" "), text);
+
+        let numbered = ThreadDraft::new(text).numbered(true).chunks();
+        assert_eq!(numbered.len(), 3);
+        assert!(numbered[0].ends_with(" 1/3"));
+        assert!(numbered.iter().all(|chunk| chunk.len() <= MAX_WEIGHT));
+
+        let links = vec!["https://example.com/a/very/long/path/to/somewhere"; 20].join(" ");
+        assert_eq!(ThreadDraft::new(links).chunks().len(), 2);
+
+        let wide = "あ".repeat(300);
+        let chunks = ThreadDraft::new(wide).chunks();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].chars().count(), 140);
+
+        //a family emoji is several characters joined into one grapheme, and is never split
+        let family = "👨\u{200d}👩\u{200d}👧".repeat(40);
+        let chunks = ThreadDraft::new(family.clone()).chunks();
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|chunk| chunk.starts_with('👨')));
+        assert!(chunks.iter().all(|chunk| chunk.ends_with('👧')));
+        assert_eq!(chunks.concat(), family);
+
+        let short = ThreadDraft::new("hello").numbered(true);
+        assert_eq!(short.chunks(), vec!["hello".to_string()]);
+    }
+
+    #[test]
+    fn thread_media() {
+        let text = vec!["abcdefghi"; 60].join(" ");
+        let media = |id: &str| media::MediaId(id.to_string());
+        let drafts = ThreadDraft::new(text)
+            .in_reply_to(1234)
+            .with_media_at(1, media("1"))
+            .with_media_at(7, media("7"))
+            .drafts();
+
+        assert_eq!(drafts[0].in_reply_to, Some(1234));
+        assert_eq!(drafts[1].in_reply_to, None);
+        assert!(drafts[0].media_ids.is_empty());
+        let ids = |draft: &DraftTweet| {
+            draft
+                .media_ids
+                .iter()
+                .map(|id| id.0.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids(&drafts[1]), vec!["1"]);
+        assert_eq!(ids(&drafts[2]), vec!["7"]);
+    }
+}
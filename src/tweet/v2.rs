@@ -0,0 +1,239 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Timelines loaded from version 2 of the Twitter API.
+//!
+//! Some levels of API access can't load the v1.1 `user_timeline` and `mentions_timeline`. The
+//! functions in this module load the same timelines from the v2 API instead. They return tweets in
+//! the v2 shape from the [`v2`] module, a page at a time, with the tweets' authors included
+//! alongside them.
+//!
+//! [`v2`]: ../../v2/index.html
+
+use chrono;
+use futures::stream::{self, Stream};
+
+use crate::common::*;
+use crate::{auth, error, links, v2};
+
+/// Begins loading the tweets posted by the given user, most recent first.
+///
+/// This wraps the v2 `/2/users/:id/tweets` endpoint, which can load up to the 3,200 most recent
+/// tweets from the user. See the [`Timeline`] docs for the available options.
+///
+/// [`Timeline`]: struct.Timeline.html
+pub fn user_tweets(user_id: u64, token: &auth::Token) -> Timeline {
+    Timeline::new(
+        format!("{}/{}/tweets", links::statuses::USERS_V2_STEM, user_id),
+        token.clone(),
+    )
+}
+
+/// Begins loading the tweets that mention the given user, most recent first.
+///
+/// This wraps the v2 `/2/users/:id/mentions` endpoint, which can load up to the 800 most recent
+/// mentions of the user. See the [`Timeline`] docs for the available options. Note that Twitter
+/// does not accept the `exclude` option for this timeline.
+///
+/// [`Timeline`]: struct.Timeline.html
+pub fn user_mentions(user_id: u64, token: &auth::Token) -> Timeline {
+    Timeline::new(
+        format!("{}/{}/mentions", links::statuses::USERS_V2_STEM, user_id),
+        token.clone(),
+    )
+}
+
+/// Kinds of tweets that can be left out of a v2 user timeline.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Exclude {
+    /// Leave out retweets.
+    Retweets,
+    /// Leave out replies.
+    Replies,
+}
+
+impl Exclude {
+    fn as_str(self) -> &'static str {
+        match self {
+            Exclude::Retweets => "retweets",
+            Exclude::Replies => "replies",
+        }
+    }
+}
+
+/// A helper struct to navigate a timeline from the v2 API.
+///
+/// v2 timelines are paged with an opaque token instead of tweet IDs. Like [`direct::Timeline`],
+/// this struct saves the token it received with the last page, so calling `next_page` repeatedly
+/// walks back through the timeline, and `into_stream` does so automatically.
+///
+/// [`direct::Timeline`]: ../../direct/struct.Timeline.html
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # use egg_mode::Token;
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let token: Token = unimplemented!();
+/// use egg_mode::tweet::v2::{self, Exclude};
+///
+/// let mut timeline = v2::user_tweets(2244994945, &token)
+///     .exclude(&[Exclude::Retweets, Exclude::Replies])
+///     .with_page_size(100);
+///
+/// let page = timeline.start().await.unwrap();
+/// for tweet in &page.tweets {
+///     println!("{}", tweet.text);
+/// }
+///
+/// if timeline.next_token.is_some() {
+///     let older = timeline.next_page().await.unwrap();
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Timeline {
+    link: String,
+    token: auth::Token,
+    exclude: Vec<Exclude>,
+    start_time: Option<chrono::DateTime<chrono::Utc>>,
+    end_time: Option<chrono::DateTime<chrono::Utc>>,
+    /// The number of tweets to request in a single page. The default is 10; Twitter accepts
+    /// values from 5 to 100.
+    pub count: u32,
+    /// The token that can be used to load the next page of results. A value of `None` indicates
+    /// that either no tweets have been loaded yet, or that the most recently loaded page is the
+    /// last page of tweets available.
+    pub next_token: Option<String>,
+    /// Whether this `Timeline` has been called yet.
+    pub loaded: bool,
+}
+
+impl Timeline {
+    fn new(link: String, token: auth::Token) -> Timeline {
+        Timeline {
+            link,
+            token,
+            exclude: vec![],
+            start_time: None,
+            end_time: None,
+            count: 10,
+            next_token: None,
+            loaded: false,
+        }
+    }
+
+    /// Builder function to set the page size. The default value for the page size is 10; Twitter
+    /// accepts values from 5 to 100.
+    pub fn with_page_size(self, count: u32) -> Self {
+        Timeline { count, ..self }
+    }
+
+    /// Builder function to leave the given kinds of tweets out of the timeline.
+    pub fn exclude(self, exclude: &[Exclude]) -> Self {
+        Timeline {
+            exclude: exclude.to_vec(),
+            ..self
+        }
+    }
+
+    /// Builder function to only load tweets posted at or after the given time.
+    pub fn start_time(self, start_time: chrono::DateTime<chrono::Utc>) -> Self {
+        Timeline {
+            start_time: Some(start_time),
+            ..self
+        }
+    }
+
+    /// Builder function to only load tweets posted before the given time.
+    pub fn end_time(self, end_time: chrono::DateTime<chrono::Utc>) -> Self {
+        Timeline {
+            end_time: Some(end_time),
+            ..self
+        }
+    }
+
+    /// Clears the saved pagination token on this `Timeline`.
+    pub fn reset(&mut self) {
+        self.next_token = None;
+        self.loaded = false;
+    }
+
+    /// Clears the saved pagination token on this timeline, then returns the most recent page of
+    /// tweets.
+    pub async fn start(&mut self) -> Result<Response<v2::TweetPage>, error::Error> {
+        self.reset();
+        self.next_page().await
+    }
+
+    /// Loads the next page of tweets, saving the pagination token received from Twitter.
+    ///
+    /// If the call fails, the pagination token is kept, so calling this again retries the same
+    /// page.
+    pub async fn next_page(&mut self) -> Result<Response<v2::TweetPage>, error::Error> {
+        let req = get(&self.link, &self.token, Some(&self.page_params()));
+        let resp: Response<v2::TweetPage> = request_with_json_response(req).await?;
+
+        self.loaded = true;
+        self.next_token = resp.meta.next_token.clone();
+        Ok(resp)
+    }
+
+    /// Assembles the parameters to load the next page with.
+    fn page_params(&self) -> ParamList {
+        let format_time = |time: chrono::DateTime<chrono::Utc>| {
+            time.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+        };
+        let exclude = self
+            .exclude
+            .iter()
+            .map(|ex| ex.as_str())
+            .collect::<Vec<_>>()
+            .join(",");
+        v2::add_fields(ParamList::new())
+            .add_param("max_results", self.count.to_string())
+            .add_opt_param("pagination_token", self.next_token.clone())
+            .add_opt_param("start_time", self.start_time.map(format_time))
+            .add_opt_param("end_time", self.end_time.map(format_time))
+            .add_opt_param("exclude", Some(exclude).filter(|ex| !ex.is_empty()))
+    }
+
+    /// Converts this `Timeline` into a `Stream` of pages, which automatically loads the next page
+    /// until the timeline runs out.
+    pub fn into_stream(self) -> impl Stream<Item = Result<Response<v2::TweetPage>, error::Error>> {
+        stream::try_unfold(self, |mut timeline| async move {
+            if timeline.loaded && timeline.next_token.is_none() {
+                Ok::<_, error::Error>(None)
+            } else {
+                let page = timeline.next_page().await?;
+                Ok(Some((page, timeline)))
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{user_tweets, Exclude};
+
+    #[test]
+    fn page_params_keep_token() {
+        let token = crate::Token::Bearer("token".to_string());
+        let mut timeline = user_tweets(783214, &token)
+            .with_page_size(50)
+            .exclude(&[Exclude::Retweets]);
+        timeline.next_token = Some("7140dibdnow9c7btw3w29grvxfcgvpb9n9coehpk7xz5i".to_string());
+
+        let params = timeline.page_params();
+        assert_eq!(params.get("max_results").unwrap(), "50");
+        assert_eq!(params.get("exclude").unwrap(), "retweets");
+        assert_eq!(
+            params.get("pagination_token").unwrap(),
+            "7140dibdnow9c7btw3w29grvxfcgvpb9n9coehpk7xz5i"
+        );
+        // building the request leaves the token in place, in case the call fails
+        assert!(timeline.next_token.is_some());
+    }
+}
@@ -2,6 +2,8 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::collections::HashMap;
+
 use crate::common::*;
 use crate::error::Result;
 use crate::{auth, cursor, links};
@@ -74,6 +76,23 @@ use super::*;
 /// # }
 /// ```
 pub async fn lookup<T, I>(accts: I, token: &auth::Token) -> Result<Response<Vec<TwitterUser>>>
+where
+    T: Into<UserID>,
+    I: IntoIterator<Item = T>,
+{
+    lookup_with_options(accts, &PayloadOptions::new(), token).await
+}
+
+/// Look up profile information for several Twitter users, trimmed with the given
+/// `PayloadOptions`.
+///
+/// This is the same as `lookup`, but with `skip_status`, the users are returned without their
+/// `status`, and with `include_entities` set to `false`, without their `entities`.
+pub async fn lookup_with_options<T, I>(
+    accts: I,
+    options: &PayloadOptions,
+    token: &auth::Token,
+) -> Result<Response<Vec<TwitterUser>>>
 where
     T: Into<UserID>,
     I: IntoIterator<Item = T>,
@@ -83,7 +102,8 @@ where
     let params = ParamList::new()
         .extended_tweets()
         .add_param("user_id", id_param)
-        .add_param("screen_name", name_param);
+        .add_param("screen_name", name_param)
+        .payload_options(options);
 
     let req = post(links::users::LOOKUP, token, Some(&params));
 
@@ -116,15 +136,7 @@ where
     F: Into<UserID>,
     T: Into<UserID>,
 {
-    let mut params = match from.into() {
-        UserID::ID(id) => ParamList::new().add_param("source_id", id.to_string()),
-        UserID::ScreenName(name) => ParamList::new().add_param("source_screen_name", name),
-    };
-    match to.into() {
-        UserID::ID(id) => params.add_param_ref("target_id", id.to_string()),
-        UserID::ScreenName(name) => params.add_param_ref("target_screen_name", name),
-    };
-
+    let params = relation_params(from.into(), to.into());
     let req = get(links::users::FRIENDSHIP_SHOW, token, Some(&params));
 
     // the relationship returned by Twitter is actually contained within a `"relationship"` field,
@@ -139,6 +151,18 @@ where
     Ok(Response::map(resp, |r| r.relationship))
 }
 
+fn relation_params(from: UserID, to: UserID) -> ParamList {
+    let mut params = match from {
+        UserID::ID(id) => ParamList::new().add_param("source_id", id.to_string()),
+        UserID::ScreenName(name) => ParamList::new().add_param("source_screen_name", name),
+    };
+    match to {
+        UserID::ID(id) => params.add_param_ref("target_id", id.to_string()),
+        UserID::ScreenName(name) => params.add_param_ref("target_screen_name", name),
+    };
+    params
+}
+
 /// Lookup the relations between the authenticated user and the given accounts.
 pub async fn relation_lookup<T, I>(
     accts: I,
@@ -159,6 +183,86 @@ where
     request_with_json_response(req).await
 }
 
+/// Lookup the relations between the authenticated user and any number of accounts.
+///
+/// `relation_lookup` can only look up 100 accounts at once. This function splits the given
+/// accounts into groups of 100 and looks up each group in turn, collecting the results into a
+/// `RelationSet`, which has helpers to sort the accounts by how they're connected to the
+/// authenticated user. This performs one call for every 100 accounts; the rate-limit information
+/// returned is from the last call, and the partial errors of every call are kept.
+///
+/// ```rust,no_run
+/// # use egg_mode::Token;
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let token: Token = unimplemented!();
+/// use egg_mode::user::{self, Connection};
+///
+/// # let follower_ids: Vec<u64> = vec![];
+/// let relations = user::relation_lookup_all(follower_ids, &token).await.unwrap();
+/// let (followed, not_followed) = relations.partition_by(Connection::Following);
+/// println!("{} followers followed back, {} not", followed.len(), not_followed.len());
+/// # }
+/// ```
+pub async fn relation_lookup_all<T, I>(
+    accts: I,
+    token: &auth::Token,
+) -> Result<Response<RelationSet>>
+where
+    T: Into<UserID>,
+    I: IntoIterator<Item = T>,
+{
+    let accts: Vec<UserID> = accts.into_iter().map(Into::into).collect();
+    let mut relations = Vec::with_capacity(accts.len());
+    let mut rate_limit_status = RateLimit::unknown();
+    let mut partial_errors = Vec::new();
+
+    for chunk in accts.chunks(100) {
+        let resp = relation_lookup(chunk.iter().cloned(), token).await?;
+        rate_limit_status = resp.rate_limit_status;
+        partial_errors.extend(resp.partial_errors);
+        relations.extend(resp.response);
+    }
+
+    let mut resp = Response::new(rate_limit_status, RelationSet::from(relations));
+    resp.partial_errors = partial_errors;
+    Ok(resp)
+}
+
+/// Lookup whether the first account follows the second.
+///
+/// This is a shortcut for `relation` that only returns `source.following`, for when that's all you
+/// need to know. It makes the same call, and shares its rate limit.
+pub async fn follows<F, T>(from: F, to: T, token: &auth::Token) -> Result<Response<bool>>
+where
+    F: Into<UserID>,
+    T: Into<UserID>,
+{
+    let resp = relation(from, to, token).await?;
+    Ok(Response::map(resp, |r| r.source.following))
+}
+
+/// Lookup whether each of the given accounts follows the authenticated user.
+///
+/// This uses `relation_lookup_all`, so it can check any number of accounts, 100 per call, rather
+/// than calling `follows` for each one. The returned map has an entry for every account Twitter
+/// found, keyed by its numeric ID; accounts that don't exist or are suspended are left out.
+pub async fn follows_me<T, I>(accts: I, token: &auth::Token) -> Result<Response<HashMap<u64, bool>>>
+where
+    T: Into<UserID>,
+    I: IntoIterator<Item = T>,
+{
+    let resp = relation_lookup_all(accts, token).await?;
+    Ok(Response::map(resp, |set| followed_by(&set)))
+}
+
+fn followed_by(set: &RelationSet) -> HashMap<u64, bool> {
+    set.relations
+        .iter()
+        .map(|relation| (relation.id, relation.has(Connection::FollowedBy)))
+        .collect()
+}
+
 //---Cursored collections---
 
 /// Lookup users based on the given search term.
@@ -232,6 +336,67 @@ pub fn followers_ids<T: Into<UserID>>(
     cursor::CursorIter::new(links::users::FOLLOWERS_IDS, token, Some(params), Some(500))
 }
 
+/// Write every follower of the given account to the given writer, one row per user.
+///
+/// Followers are loaded a page at a time and written out as soon as each page arrives, so the
+/// whole follower list is never held in memory. When Twitter's rate limit is reached, this waits
+/// until the limit resets and continues from the same page, so exporting a large account can take
+/// a long time. Any other error stops the export; rows written before the error remain in the
+/// writer.
+///
+/// The writer is not buffered by this function, so wrapping files in a `BufWriter` is recommended.
+/// Upon success, the future returned by this function yields the number of users written.
+///
+/// ## Example
+///
+/// ```rust,no_run
+/// # use egg_mode::Token;
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let token: Token = unimplemented!();
+/// use egg_mode::user::ExportFormat;
+/// use std::fs::File;
+/// use std::io::BufWriter;
+///
+/// let file = BufWriter::new(File::create("followers.csv").unwrap());
+/// let count = egg_mode::user::export_followers("rustlang", file, ExportFormat::Csv, &token)
+///     .await
+///     .unwrap();
+/// println!("exported {} followers", count);
+/// # }
+/// ```
+pub async fn export_followers<T: Into<UserID>, W: io::Write>(
+    acct: T,
+    mut writer: W,
+    format: ExportFormat,
+    token: &auth::Token,
+) -> Result<usize> {
+    use futures::StreamExt;
+
+    let mut followers = followers_of(acct, token).with_page_size(200);
+    let mut written = 0;
+
+    format.write_header(&mut writer)?;
+    while let Some(user) = followers.next().await {
+        match user {
+            Ok(user) => {
+                format.write_user(&mut writer, &user)?;
+                written += 1;
+            }
+            Err(error::Error::RateLimit(reset)) => {
+                writer.flush()?;
+                let now = chrono::Utc::now().timestamp();
+                let secs = (i64::from(reset) - now).max(0) as u64 + 1;
+                tokio::time::sleep(Duration::from_secs(secs)).await;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    writer.flush()?;
+
+    Ok(written)
+}
+
 /// Lookup the users that have been blocked by the authenticated user.
 ///
 /// Note that while loading a user's blocks list is a cursored search, it does not allow you to set
@@ -334,6 +499,65 @@ pub async fn unfollow<T: Into<UserID>>(
     request_with_json_response(req).await
 }
 
+/// Follow each of the given accounts with the authenticated user, pacing the follows to stay within
+/// Twitter's limits.
+///
+/// This returns a `FollowBatch`, which can be configured before calling `into_stream` to start it.
+/// See the [`FollowBatch`] docs for details on how the follows are paced.
+///
+/// [`FollowBatch`]: struct.FollowBatch.html
+pub fn follow_all<T, I>(accts: I, token: &auth::Token) -> FollowBatch
+where
+    T: Into<UserID>,
+    I: IntoIterator<Item = T>,
+{
+    FollowBatch::new(
+        accts.into_iter().map(Into::into).collect(),
+        BatchAction::Follow,
+        token,
+    )
+}
+
+/// Unfollow each of the given accounts with the authenticated user, pacing the unfollows to avoid
+/// hitting Twitter's limits.
+///
+/// This returns a `FollowBatch`, which can be configured before calling `into_stream` to start it.
+/// See the [`FollowBatch`] docs for details on how the unfollows are paced.
+///
+/// [`FollowBatch`]: struct.FollowBatch.html
+pub fn unfollow_all<T, I>(accts: I, token: &auth::Token) -> FollowBatch
+where
+    T: Into<UserID>,
+    I: IntoIterator<Item = T>,
+{
+    FollowBatch::new(
+        accts.into_iter().map(Into::into).collect(),
+        BatchAction::Unfollow,
+        token,
+    )
+}
+
+/// Report each of the given accounts for spam with the authenticated user, pacing the reports to
+/// avoid hitting Twitter's limits.
+///
+/// Like `report_spam`, this also blocks each account, unless `FollowBatch::with_block(false)` is
+/// set, in which case the accounts are only reported, like `report_spam_only`. This returns a
+/// `FollowBatch`, which can be configured before calling `into_stream` to start it. See the
+/// [`FollowBatch`] docs for details on how the reports are paced.
+///
+/// [`FollowBatch`]: struct.FollowBatch.html
+pub fn report_spam_many<T, I>(accts: I, token: &auth::Token) -> FollowBatch
+where
+    T: Into<UserID>,
+    I: IntoIterator<Item = T>,
+{
+    FollowBatch::new(
+        accts.into_iter().map(Into::into).collect(),
+        BatchAction::ReportSpam,
+        token,
+    )
+}
+
 /// Update notification settings and reweet visibility for the given user.
 ///
 /// Calling this for an account the authenticated user does not already follow will not cause them
@@ -375,13 +599,36 @@ pub async fn report_spam<T: Into<UserID>>(
     acct: T,
     token: &auth::Token,
 ) -> Result<Response<TwitterUser>> {
-    let params = ParamList::new()
-        .extended_tweets()
-        .add_user_param(acct.into());
+    let params = report_spam_params(acct.into(), true);
     let req = post(links::users::REPORT_SPAM, token, Some(&params));
     request_with_json_response(req).await
 }
 
+/// Report the given account for spam with the authenticated user, without blocking it.
+///
+/// Upon success, the future returned by this function yields the given user.
+pub async fn report_spam_only<T: Into<UserID>>(
+    acct: T,
+    token: &auth::Token,
+) -> Result<Response<TwitterUser>> {
+    let params = report_spam_params(acct.into(), false);
+    let req = post(links::users::REPORT_SPAM, token, Some(&params));
+    request_with_json_response(req).await
+}
+
+/// Assembles the parameters for `report_spam` and `report_spam_only`.
+///
+/// Twitter blocks reported accounts unless `perform_block` is set to `false`, so it's only sent
+/// when the account shouldn't be blocked.
+pub(crate) fn report_spam_params(acct: UserID, perform_block: bool) -> ParamList {
+    let params = ParamList::new().extended_tweets().add_user_param(acct);
+    if perform_block {
+        params
+    } else {
+        params.add_param("perform_block", "false")
+    }
+}
+
 /// Unblock the given user with the authenticated user.
 ///
 /// Upon success, the future returned by this function yields the given user.
@@ -420,3 +667,35 @@ pub async fn unmute<T: Into<UserID>>(
     let req = post(links::users::UNMUTE, token, Some(&params));
     request_with_json_response(req).await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{followed_by, relation_params};
+    use crate::user::{RelationLookup, RelationSet, UserID};
+
+    #[test]
+    fn follows_params() {
+        let params = relation_params(UserID::from(1234), "rustlang".into());
+        assert_eq!(params.get("source_id").unwrap(), "1234");
+        assert_eq!(params.get("target_screen_name").unwrap(), "rustlang");
+        assert_eq!(params.len(), 2);
+    }
+
+    #[test]
+    fn follows_me_map() {
+        let relations = serde_json::from_str::<Vec<RelationLookup>>(
+            r#"[
+                {"name": "a", "screen_name": "a", "id": 1, "connections": ["following", "followed_by"]},
+                {"name": "b", "screen_name": "b", "id": 2, "connections": ["following"]},
+                {"name": "c", "screen_name": "c", "id": 3, "connections": ["none"]}
+            ]"#,
+        )
+        .unwrap();
+
+        let map = followed_by(&RelationSet::from(relations));
+        assert_eq!(map.len(), 3);
+        assert!(map[&1]);
+        assert!(!map[&2]);
+        assert!(!map[&3]);
+    }
+}
@@ -10,16 +10,24 @@
 //! ## Types
 //!
 //! - `UserID`: used as a generic input to many functions, this enum allows you to refer to a user
-//!   by a numeric ID or by their screen name.
+//!   by a numeric ID or by their screen name. Screen names typed by people can be cleaned up with
+//!   `UserID::normalized` or `normalize_screen_name`, and checked with `is_valid_screen_name`.
 //! - `Relationship`/`RelationSource`/`RelationTarget`: returned by `relation`, these types
 //!   (`Relationship` contains the other two) show the ways two accounts relate to each other.
 //! - `RelationLookup`/`Connection`: returned as part of a collection by `relation_lookup`, these
 //!   types (`RelationLookup` contains a `Vec<Connection>`) shows the ways the authenticated user
 //!   relates to a specific account.
+//! - `RelationSet`: returned by `relation_lookup_all`, this collection of `RelationLookup`s can sort
+//!   accounts by how they're connected to the authenticated user.
 //! - `TwitterUser`/`UserEntities`/`UserEntityDetail`: returned by many functions in this module,
 //!   these types (`TwitterUser` contains the other two) describe the content of a user's profile,
 //!   and a handful of settings relating to how their profile is displayed.
-//! - `UserSearch`: returned by `search`, this is a stream of search results.
+//! - `UserSearch`: returned by `search`, this is a stream of search results. Its position can be
+//!   saved as a `UserSearchState` to continue the search later.
+//! - `FollowBatch`/`BatchProgress`: returned by `follow_all`, `unfollow_all`, and
+//!   `report_spam_many`, these types follow, unfollow, or report many accounts at once, pacing the
+//!   actions to stay within Twitter's limits.
+//! - `ExportFormat`: given to `export_followers` to choose how each user is written out.
 //!
 //! ## Functions
 //!
@@ -28,8 +36,9 @@
 //! These functions perform actions to the user's account. Their use requires that your application
 //! request write access to authenticated accounts.
 //!
-//! - `block`/`report_spam`/`unblock`
+//! - `block`/`report_spam`/`report_spam_only`/`unblock`
 //! - `follow`/`unfollow`/`update_follow`
+//! - `follow_all`/`unfollow_all`/`report_spam_many`
 //! - `mute`/`unmute`
 //!
 //! ### Direct lookup
@@ -38,9 +47,10 @@
 //! results.
 //!
 //! - `show`
-//! - `lookup`/`lookup_ids`/`lookup_names`
+//! - `lookup`/`lookup_ids`/`lookup_names`/`lookup_with_options`
 //! - `friends_no_retweets`
-//! - `relation`/`relation_lookup`
+//! - `relation`/`relation_lookup`/`relation_lookup_all`
+//! - `follows`/`follows_me`
 //!
 //! ### Cursored lookup
 //!
@@ -54,14 +64,22 @@
 //! - `blocks`/`blocks_ids`
 //! - `mutes`/`mutes_ids`
 //! - `incoming_requests`/`outgoing_requests`
+//!
+//! ### Export
+//!
+//! - `export_followers`: writes every follower of an account to a file or other writer as they
+//!   are loaded, waiting out rate limits as needed.
 
+use std::collections::{HashSet, VecDeque};
 use std::future::Future;
+use std::io;
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::time::Duration;
 use std::vec::IntoIter as VecIter;
 
 use chrono;
-use futures::Stream;
+use futures::stream::{self, Stream};
 use serde::{Deserialize, Serialize};
 
 use crate::common::*;
@@ -97,7 +115,86 @@ pub enum UserID {
     ScreenName(CowStr),
 }
 
-impl<'a> From<&'static str> for UserID {
+impl UserID {
+    /// Cleans up a screen name the way a person would have typed it, by trimming surrounding
+    /// whitespace and a leading `@`. Numeric IDs are returned unchanged.
+    ///
+    /// Twitter doesn't recognize screen names like `"@rustlang"` or `" rustlang"`, and returns a
+    /// "user not found" error for them. Call this on names that came from user input before
+    /// passing them to egg-mode.
+    ///
+    /// ```rust
+    /// use egg_mode::user::UserID;
+    ///
+    /// let id = UserID::from(" @rustlang\n").normalized();
+    /// assert!(matches!(id, UserID::ScreenName(ref name) if name == "rustlang"));
+    /// ```
+    pub fn normalized(self) -> UserID {
+        match self {
+            UserID::ScreenName(name) => {
+                let normalized = normalize_screen_name(&name);
+                if normalized.len() == name.len() {
+                    UserID::ScreenName(name)
+                } else {
+                    UserID::ScreenName(normalized.to_string().into())
+                }
+            }
+            id => id,
+        }
+    }
+
+    /// Returns whether this could refer to an account: either it's a numeric ID, or a screen name
+    /// that passes `is_valid_screen_name`.
+    ///
+    /// This doesn't check whether the account exists, only whether Twitter could possibly accept
+    /// it, so that obviously broken input can be rejected before making a call.
+    pub fn is_valid(&self) -> bool {
+        match self {
+            UserID::ID(_) => true,
+            UserID::ScreenName(name) => is_valid_screen_name(name),
+        }
+    }
+}
+
+/// Returns whether the given text is a valid screen name: between 1 and 15 characters, all of
+/// them ASCII letters, digits, or underscores.
+///
+/// The name shouldn't include the leading `@`; use `normalize_screen_name` to remove it first.
+/// Note that some old accounts have screen names longer than 15 characters, which Twitter no
+/// longer allows for new accounts, so this can reject a handful of real accounts.
+///
+/// ```rust
+/// use egg_mode::user::is_valid_screen_name;
+///
+/// assert!(is_valid_screen_name("rust_lang2"));
+/// assert!(!is_valid_screen_name("@rustlang"));
+/// assert!(!is_valid_screen_name("rust-lang"));
+/// assert!(!is_valid_screen_name(""));
+/// ```
+pub fn is_valid_screen_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 15
+        && name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_')
+}
+
+/// Trims surrounding whitespace and a leading `@` (or the full-width `＠`) from the given screen
+/// name.
+///
+/// ```rust
+/// use egg_mode::user::normalize_screen_name;
+///
+/// assert_eq!(normalize_screen_name("  @rustlang "), "rustlang");
+/// assert_eq!(normalize_screen_name("rustlang"), "rustlang");
+/// ```
+pub fn normalize_screen_name(name: &str) -> &str {
+    let name = name.trim();
+    name.strip_prefix('@')
+        .or_else(|| name.strip_prefix('＠'))
+        .unwrap_or(name)
+        .trim_start()
+}
+
+impl From<&'static str> for UserID {
     fn from(name: &'static str) -> UserID {
         UserID::ScreenName(name.into())
     }
@@ -353,8 +450,65 @@ impl From<raw::RawTwitterUser> for TwitterUser {
             verified: raw.verified,
             withheld_in_countries: raw.withheld_in_countries,
             withheld_scope: raw.withheld_scope,
+            #[cfg(feature = "raw-fields")]
+            extra: raw.extra,
+        }
+    }
+}
+
+impl TwitterUser {
+    /// Returns the full URL of the link on this user's profile, if they have one.
+    ///
+    /// The `url` field holds the t.co link that Twitter wraps around the user's URL. This returns
+    /// the URL the user entered instead, falling back to the t.co link if Twitter didn't include
+    /// the expanded version.
+    pub fn expanded_url(&self) -> Option<&str> {
+        let expanded = self
+            .entities
+            .url
+            .as_ref()
+            .and_then(|detail| detail.urls.first())
+            .and_then(|entity| entity.expanded_url.as_deref());
+
+        expanded.or(self.url.as_deref())
+    }
+
+    /// Returns this user's `description`, with each t.co link replaced by the full URL it points
+    /// to.
+    ///
+    /// Links are replaced using the URL entities in `entities.description`. A link is left as-is
+    /// if Twitter didn't include its expanded URL, or if its entity doesn't line up with the
+    /// text. Returns `None` if the user has no description.
+    pub fn description_with_expanded_urls(&self) -> Option<String> {
+        let description = self.description.as_ref()?;
+        Some(expand_urls(description, &self.entities.description.urls))
+    }
+}
+
+/// Replaces the t.co links in the given text with their expanded URLs, using the given entities.
+fn expand_urls(text: &str, urls: &[entities::UrlEntity]) -> String {
+    let mut urls = urls.iter().collect::<Vec<_>>();
+    urls.sort_by_key(|entity| entity.range);
+
+    let mut output = String::with_capacity(text.len());
+    let mut last = 0;
+    for entity in urls {
+        let (start, end) = entity.range;
+        let expanded = match entity.expanded_url {
+            Some(ref expanded) => expanded,
+            None => continue,
+        };
+        if start < last || text.get(start..end) != Some(&*entity.url) {
+            continue;
         }
+
+        output.push_str(&text[last..start]);
+        output.push_str(expanded);
+        last = end;
     }
+    output.push_str(&text[last..]);
+
+    output
 }
 
 /// Container for URL entity information that may be paired with a user's profile.
@@ -437,6 +591,34 @@ pub struct UserEntityDetail {
 /// re-initiate the late network call; this way, you can wait for your network connection to return
 /// or for your rate limit to refresh and try again from the same position.
 ///
+/// ## Resuming a search
+///
+/// `UserSearch` keeps track of how many users it has returned from the current page. To continue a
+/// search later, for example after the process restarts, save the value returned by
+/// `resume_state` and give it to `from_state`. The new search will load the same page again, and
+/// skip the users that were already returned from it.
+///
+/// ```rust,no_run
+/// # use egg_mode::Token;
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let token: Token = unimplemented!();
+/// use futures::StreamExt;
+/// use egg_mode::user::{self, UserSearch};
+///
+/// let mut search = user::search("rustlang", &token);
+/// while let Some(user) = search.next().await {
+///     if user.is_err() {
+///         break;
+///     }
+/// }
+/// let state = serde_json::to_string(&search.resume_state()).unwrap();
+///
+/// // ...later...
+/// let search = UserSearch::from_state(serde_json::from_str(&state).unwrap(), &token);
+/// # }
+/// ```
+///
 /// ## Manual paging
 ///
 /// The `Stream` implementation works by loading in a page of results (with size set by default or
@@ -478,6 +660,10 @@ pub struct UserSearch {
     pub page_size: i32,
     current_loader: Option<FutureResponse<Vec<TwitterUser>>>,
     current_results: Option<VecIter<TwitterUser>>,
+    /// The number of users on the current page.
+    page_len: usize,
+    /// The number of users already returned from the current page.
+    page_offset: usize,
 }
 
 impl UserSearch {
@@ -490,6 +676,7 @@ impl UserSearch {
             page_size,
             current_loader: None,
             current_results: None,
+            page_offset: 0,
             ..self
         }
     }
@@ -503,10 +690,36 @@ impl UserSearch {
             page_num,
             current_loader: None,
             current_results: None,
+            page_offset: 0,
             ..self
         }
     }
 
+    /// Returns the position of this search, to continue it later with `from_state`.
+    pub fn resume_state(&self) -> UserSearchState {
+        UserSearchState {
+            query: self.query.to_string(),
+            page_num: self.page_num,
+            page_size: self.page_size,
+            page_offset: self.page_offset,
+        }
+    }
+
+    /// Creates a `UserSearch` that continues from the given position, as saved from
+    /// `resume_state`.
+    ///
+    /// The search starts by loading the saved page again, and skips the users that were already
+    /// returned from it. If the search results changed in the meantime, this may skip or repeat
+    /// some users.
+    pub fn from_state(state: UserSearchState, token: &auth::Token) -> UserSearch {
+        UserSearch {
+            page_num: state.page_num,
+            page_size: state.page_size,
+            page_offset: state.page_offset,
+            ..UserSearch::new(state.query, token)
+        }
+    }
+
     /// Performs the search for the current page of results.
     ///
     /// This will automatically be called if you use the `UserSearch` as an iterator. This method is
@@ -531,10 +744,41 @@ impl UserSearch {
             page_size: 10,
             current_loader: None,
             current_results: None,
+            page_len: 0,
+            page_offset: 0,
+        }
+    }
+
+    /// Sets the given page of results as the current one, skipping the users that were already
+    /// returned from it.
+    fn load_page(&mut self, users: Vec<TwitterUser>) {
+        self.page_len = users.len();
+        let mut results = users.into_iter();
+        if self.page_offset > 0 {
+            results.nth(self.page_offset - 1);
         }
+        self.current_results = Some(results);
     }
 }
 
+/// The position of a `UserSearch`, as returned by `resume_state`.
+///
+/// This can be serialized to continue a search after the process restarts. See the
+/// [`UserSearch`] documentation for details.
+///
+/// [`UserSearch`]: struct.UserSearch.html
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserSearchState {
+    /// The search query.
+    pub query: String,
+    /// The page of results being returned, starting at 1.
+    pub page_num: i32,
+    /// The number of user records per page of results.
+    pub page_size: i32,
+    /// The number of users already returned from the page.
+    pub page_offset: usize,
+}
+
 impl Stream for UserSearch {
     type Item = Result<TwitterUser, error::Error>;
 
@@ -545,7 +789,7 @@ impl Stream for UserSearch {
                     self.current_loader = Some(fut);
                     return Poll::Pending;
                 }
-                Poll::Ready(Ok(res)) => self.current_results = Some(res.response.into_iter()),
+                Poll::Ready(Ok(res)) => self.load_page(res.response),
                 Poll::Ready(Err(e)) => {
                     //Invalidate current results so we don't increment the page number again
                     self.current_results = None;
@@ -556,11 +800,13 @@ impl Stream for UserSearch {
 
         if let Some(ref mut results) = self.current_results {
             if let Some(user) = results.next() {
+                self.page_offset += 1;
                 return Poll::Ready(Some(Ok(user)));
-            } else if (results.len() as i32) < self.page_size {
+            } else if (self.page_len as i32) < self.page_size {
                 return Poll::Ready(None);
             } else {
                 self.page_num += 1;
+                self.page_offset = 0;
             }
         }
 
@@ -569,6 +815,302 @@ impl Stream for UserSearch {
     }
 }
 
+/// Represents a set of accounts to follow, unfollow, or report, paced to stay within Twitter's
+/// limits.
+///
+/// This struct is returned by [`follow_all`], [`unfollow_all`], and [`report_spam_many`]. Use its
+/// builder methods to configure how the batch is paced, then call `into_stream` to start it. The
+/// resulting stream performs one action at a time, and yields a [`BatchProgress`] for each account
+/// as it goes.
+///
+/// [`follow_all`]: fn.follow_all.html
+/// [`unfollow_all`]: fn.unfollow_all.html
+/// [`report_spam_many`]: fn.report_spam_many.html
+/// [`BatchProgress`]: enum.BatchProgress.html
+///
+/// ## Pacing
+///
+/// Twitter limits how many accounts a user can follow in a day (400 at the time of this writing),
+/// and following accounts in quick succession can get an account flagged as spam. To keep clear of
+/// both, the batch:
+///
+/// - waits between each action (3 seconds by default; change this with `with_delay`);
+/// - stops after a set number of actions (400 by default for `follow_all`, unlimited for
+///   `unfollow_all`; change this with `with_limit`);
+/// - stops if Twitter reports that the user can't follow any more accounts right now (error code
+///   161), or can't report any more accounts right now (error code 205);
+/// - waits for the rate limit to reset if it's hit, then picks up where it left off.
+///
+/// Spam reports are paced the same way, except that the delay defaults to 10 seconds and there's
+/// no default limit.
+///
+/// By default, follow and unfollow batches also check the authenticated user's relationship with
+/// every account before starting, using [`relation_lookup`], and skip accounts that are already
+/// followed (or already not followed, for `unfollow_all`). This costs one extra call for every 100
+/// accounts, but skipped accounts don't count against the pacing. Turn this off with
+/// `skip_existing(false)`.
+///
+/// [`relation_lookup`]: fn.relation_lookup.html
+///
+/// ```rust,no_run
+/// # use egg_mode::Token;
+/// use std::time::Duration;
+/// use futures::TryStreamExt;
+/// use egg_mode::user::BatchProgress;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let token: Token = unimplemented!();
+/// let mut batch = egg_mode::user::follow_all(vec!["rustlang", "tokio_rs"], &token)
+///     .with_delay(Duration::from_secs(10))
+///     .into_stream();
+///
+/// while let Some(progress) = batch.try_next().await.unwrap() {
+///     match progress {
+///         BatchProgress::Done(user) => println!("followed @{}", user.screen_name),
+///         BatchProgress::Skipped(_) => (),
+///         BatchProgress::Failed(acct, err) => println!("couldn't follow {:?}: {}", acct, err),
+///         BatchProgress::Paused { until } => println!("rate limited until {}", until),
+///         BatchProgress::LimitReached => println!("follow limit reached, try again tomorrow"),
+///     }
+/// }
+/// # }
+/// ```
+#[must_use = "follow batches do nothing unless turned into a stream and polled"]
+pub struct FollowBatch {
+    token: auth::Token,
+    accts: Vec<UserID>,
+    action: BatchAction,
+    notifications: bool,
+    block: bool,
+    delay: Duration,
+    limit: Option<usize>,
+    skip_existing: bool,
+}
+
+/// The action a `FollowBatch` performs on each account.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum BatchAction {
+    Follow,
+    Unfollow,
+    ReportSpam,
+}
+
+impl FollowBatch {
+    fn new(accts: Vec<UserID>, action: BatchAction, token: &auth::Token) -> FollowBatch {
+        let (delay, limit) = match action {
+            BatchAction::Follow => (3, Some(400)),
+            BatchAction::Unfollow => (3, None),
+            BatchAction::ReportSpam => (10, None),
+        };
+        FollowBatch {
+            token: token.clone(),
+            accts,
+            action,
+            notifications: false,
+            block: true,
+            delay: Duration::from_secs(delay),
+            limit,
+            skip_existing: action != BatchAction::ReportSpam,
+        }
+    }
+
+    /// Sets the amount of time to wait between each action. Defaults to 3 seconds for follows and
+    /// unfollows, and 10 seconds for spam reports.
+    pub fn with_delay(self, delay: Duration) -> Self {
+        FollowBatch { delay, ..self }
+    }
+
+    /// Sets the maximum number of accounts to act on before stopping. `None` removes the limit.
+    ///
+    /// Defaults to 400 for `follow_all`, Twitter's daily follow limit, and `None` for
+    /// `unfollow_all` and `report_spam_many`. Skipped accounts don't count towards this limit.
+    pub fn with_limit(self, limit: Option<usize>) -> Self {
+        FollowBatch { limit, ..self }
+    }
+
+    /// Sets whether to enable device notifications for newly-followed accounts. Defaults to
+    /// `false`. Has no effect for `unfollow_all`.
+    pub fn with_notifications(self, notifications: bool) -> Self {
+        FollowBatch {
+            notifications,
+            ..self
+        }
+    }
+
+    /// Sets whether to block each account as well as reporting it for spam. Defaults to `true`, as
+    /// with `report_spam`; with `false`, each account is only reported, as with
+    /// `report_spam_only`. Has no effect for `follow_all` and `unfollow_all`.
+    pub fn with_block(self, block: bool) -> Self {
+        FollowBatch { block, ..self }
+    }
+
+    /// Sets whether to check the relationship with each account first, and skip accounts that
+    /// are already followed (or not followed, for `unfollow_all`). Defaults to `true`. Has no
+    /// effect for `report_spam_many`.
+    pub fn skip_existing(self, skip_existing: bool) -> Self {
+        FollowBatch {
+            skip_existing,
+            ..self
+        }
+    }
+
+    /// Starts the batch, returning a `Stream` that performs each action and yields its progress.
+    ///
+    /// An error is only returned from the stream if the initial relationship lookup fails, after
+    /// which the stream ends. Errors from following or unfollowing individual accounts are returned
+    /// as `BatchProgress::Failed`, and the batch continues with the next account.
+    pub fn into_stream(self) -> impl Stream<Item = error::Result<BatchProgress>> + Unpin + Send {
+        Box::pin(stream::unfold(
+            BatchState::new(self),
+            |mut state| async move {
+                let progress = state.next().await?;
+                Some((progress, state))
+            },
+        ))
+    }
+}
+
+struct BatchState {
+    batch: FollowBatch,
+    queue: VecDeque<UserID>,
+    checked: bool,
+    skip_ids: HashSet<u64>,
+    skip_names: HashSet<String>,
+    performed: usize,
+    wait_until: Option<i32>,
+    finished: bool,
+}
+
+impl BatchState {
+    fn new(batch: FollowBatch) -> BatchState {
+        BatchState {
+            queue: batch.accts.iter().cloned().collect(),
+            batch,
+            checked: false,
+            skip_ids: HashSet::new(),
+            skip_names: HashSet::new(),
+            performed: 0,
+            wait_until: None,
+            finished: false,
+        }
+    }
+
+    async fn next(&mut self) -> Option<error::Result<BatchProgress>> {
+        if self.finished {
+            return None;
+        }
+
+        let reporting = self.batch.action == BatchAction::ReportSpam;
+        if !self.checked && self.batch.skip_existing && !reporting {
+            self.checked = true;
+            if let Err(e) = self.check_relations().await {
+                self.finished = true;
+                return Some(Err(e));
+            }
+        }
+
+        let acct = self.queue.pop_front()?;
+
+        if self.is_skipped(&acct) {
+            return Some(Ok(BatchProgress::Skipped(acct)));
+        }
+
+        if matches!(self.batch.limit, Some(limit) if self.performed >= limit) {
+            self.finished = true;
+            return Some(Ok(BatchProgress::LimitReached));
+        }
+
+        if let Some(reset) = self.wait_until.take() {
+            let now = chrono::Utc::now().timestamp();
+            let secs = (i64::from(reset) - now).max(0) as u64 + 1;
+            tokio::time::sleep(Duration::from_secs(secs)).await;
+        } else if self.performed > 0 {
+            tokio::time::sleep(self.batch.delay).await;
+        }
+
+        let token = &self.batch.token;
+        let result = match self.batch.action {
+            BatchAction::Follow => follow(acct.clone(), self.batch.notifications, token).await,
+            BatchAction::Unfollow => unfollow(acct.clone(), token).await,
+            BatchAction::ReportSpam if self.batch.block => report_spam(acct.clone(), token).await,
+            BatchAction::ReportSpam => report_spam_only(acct.clone(), token).await,
+        };
+
+        match result {
+            Ok(user) => {
+                self.performed += 1;
+                Some(Ok(BatchProgress::Done(user)))
+            }
+            Err(error::Error::RateLimit(reset)) => {
+                self.queue.push_front(acct);
+                self.wait_until = Some(reset);
+                Some(Ok(BatchProgress::Paused { until: reset }))
+            }
+            Err(error::Error::TwitterError(_, ref errors))
+                if errors.errors.iter().any(|e| e.code == 161 || e.code == 205) =>
+            {
+                self.finished = true;
+                Some(Ok(BatchProgress::LimitReached))
+            }
+            Err(e) => Some(Ok(BatchProgress::Failed(acct, e))),
+        }
+    }
+
+    async fn check_relations(&mut self) -> error::Result<()> {
+        let accts: Vec<UserID> = self.queue.iter().cloned().collect();
+        let relations = relation_lookup_all(accts, &self.batch.token).await?;
+        self.skip_existing(relations.response.relations);
+
+        Ok(())
+    }
+
+    /// Marks the accounts that the batch doesn't need to act on, given their relationships with
+    /// the authenticated user.
+    fn skip_existing(&mut self, relations: Vec<RelationLookup>) {
+        for relation in relations {
+            let following =
+                relation.has(Connection::Following) || relation.has(Connection::FollowingRequested);
+
+            if following == (self.batch.action == BatchAction::Follow) {
+                self.skip_ids.insert(relation.id);
+                self.skip_names.insert(relation.screen_name.to_lowercase());
+            }
+        }
+    }
+
+    fn is_skipped(&self, acct: &UserID) -> bool {
+        match acct {
+            UserID::ID(id) => self.skip_ids.contains(id),
+            UserID::ScreenName(name) => self.skip_names.contains(&name.to_lowercase()),
+        }
+    }
+}
+
+/// Represents the progress of a `FollowBatch`, yielded for each account in the batch.
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum BatchProgress {
+    /// The account was followed, unfollowed, or reported. Contains the user information Twitter
+    /// returned for the account.
+    Done(Response<TwitterUser>),
+    /// The account was skipped, because the authenticated user already follows it (for
+    /// `follow_all`) or already doesn't follow it (for `unfollow_all`).
+    Skipped(UserID),
+    /// Acting on the account failed. The batch will continue with the next account.
+    Failed(UserID, error::Error),
+    /// The rate limit for the batch's action was reached. The batch will wait until the given
+    /// UTC Unix timestamp before trying the same account again.
+    Paused {
+        /// The time the rate limit resets, as a UTC Unix timestamp.
+        until: i32,
+    },
+    /// The batch stopped because its limit was reached, or because Twitter reported that the user
+    /// can't follow or report any more accounts right now. No more progress will be yielded after
+    /// this.
+    LimitReached,
+}
+
 /// Represents relationship settings between two Twitter accounts.
 #[derive(Debug, Deserialize)]
 pub struct Relationship {
@@ -630,6 +1172,7 @@ pub struct RelationSource {
     ///
     /// Note that there is no mechanism with which to toggle this setting, at least none that this
     /// author could find, either through the API or through the official site.
+    #[allow(dead_code)]
     all_replies: Option<bool>,
     /// Indicates whether this source account has decided to show retweets from the target account.
     /// If the source account is not the authenticated user, holds `None` instead.
@@ -658,8 +1201,89 @@ pub struct RelationLookup {
     pub connections: Vec<Connection>,
 }
 
+impl RelationLookup {
+    /// Returns whether the target account is connected to the authenticated user in the given way.
+    pub fn has(&self, connection: Connection) -> bool {
+        self.connections.contains(&connection)
+    }
+}
+
+/// A collection of relations between the authenticated user and other accounts, as returned by
+/// `relation_lookup_all`.
+///
+/// Besides the relations themselves, this has helpers to pick out accounts by how they're
+/// connected to the authenticated user, for example to find the followers the user doesn't follow
+/// back.
+#[derive(Debug, Default)]
+pub struct RelationSet {
+    /// The relations in this set, in the order Twitter returned them.
+    pub relations: Vec<RelationLookup>,
+}
+
+impl From<Vec<RelationLookup>> for RelationSet {
+    fn from(relations: Vec<RelationLookup>) -> RelationSet {
+        RelationSet { relations }
+    }
+}
+
+impl RelationSet {
+    /// Returns the relations of the accounts that are connected to the authenticated user in the
+    /// given way.
+    pub fn with(&self, connection: Connection) -> Vec<&RelationLookup> {
+        self.relations
+            .iter()
+            .filter(|relation| relation.has(connection))
+            .collect()
+    }
+
+    /// Splits the relations into the ones that are connected to the authenticated user in the given
+    /// way, and the ones that aren't.
+    pub fn partition_by(
+        &self,
+        connection: Connection,
+    ) -> (Vec<&RelationLookup>, Vec<&RelationLookup>) {
+        self.relations
+            .iter()
+            .partition(|relation| relation.has(connection))
+    }
+
+    /// Returns the IDs of the accounts that are connected to the authenticated user in the given
+    /// way.
+    pub fn ids_with(&self, connection: Connection) -> HashSet<u64> {
+        self.relations
+            .iter()
+            .filter(|relation| relation.has(connection))
+            .map(|relation| relation.id)
+            .collect()
+    }
+
+    /// Returns the IDs of the accounts that follow the authenticated user and are followed by them.
+    pub fn mutuals(&self) -> HashSet<u64> {
+        self.ids_where(|r| r.has(Connection::Following) && r.has(Connection::FollowedBy))
+    }
+
+    /// Returns the IDs of the accounts the authenticated user follows that don't follow them back.
+    pub fn not_following_back(&self) -> HashSet<u64> {
+        self.ids_where(|r| r.has(Connection::Following) && !r.has(Connection::FollowedBy))
+    }
+
+    /// Returns the IDs of the accounts that follow the authenticated user that they don't follow
+    /// back.
+    pub fn not_followed_back(&self) -> HashSet<u64> {
+        self.ids_where(|r| r.has(Connection::FollowedBy) && !r.has(Connection::Following))
+    }
+
+    fn ids_where(&self, pred: impl Fn(&RelationLookup) -> bool) -> HashSet<u64> {
+        self.relations
+            .iter()
+            .filter(|relation| pred(relation))
+            .map(|relation| relation.id)
+            .collect()
+    }
+}
+
 /// Represents the ways a target account can be connected to another account.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Deserialize)]
 pub enum Connection {
     /// The target account has no relation.
     #[serde(rename = "none")]
@@ -684,10 +1308,168 @@ pub enum Connection {
     Muting,
 }
 
+/// The format used by `export_followers` to write out each user.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Writes a header row, then one row of comma-separated values per user, with the columns
+    /// listed in `ExportFormat::CSV_COLUMNS`.
+    Csv,
+    /// Writes each user as a complete JSON object on its own line.
+    NdJson,
+}
+
+impl ExportFormat {
+    /// The fields of `TwitterUser` written as columns by `ExportFormat::Csv`, in order.
+    pub const CSV_COLUMNS: &'static [&'static str] = &[
+        "id",
+        "screen_name",
+        "name",
+        "description",
+        "location",
+        "url",
+        "created_at",
+        "protected",
+        "verified",
+        "followers_count",
+        "friends_count",
+        "statuses_count",
+        "favourites_count",
+        "listed_count",
+    ];
+
+    /// Writes whatever needs to come before the first user.
+    fn write_header<W: io::Write>(self, writer: &mut W) -> error::Result<()> {
+        if self == ExportFormat::Csv {
+            writeln!(writer, "{}", Self::CSV_COLUMNS.join(","))?;
+        }
+        Ok(())
+    }
+
+    /// Writes a single user as one row.
+    fn write_user<W: io::Write>(self, writer: &mut W, user: &TwitterUser) -> error::Result<()> {
+        match self {
+            ExportFormat::NdJson => serde_json::to_writer(&mut *writer, user)?,
+            ExportFormat::Csv => {
+                let value = serde_json::to_value(user)?;
+                for (idx, column) in Self::CSV_COLUMNS.iter().enumerate() {
+                    if idx > 0 {
+                        writer.write_all(b",")?;
+                    }
+                    match value.get(column) {
+                        None | Some(serde_json::Value::Null) => (),
+                        Some(serde_json::Value::String(s)) => write_csv_field(writer, s)?,
+                        Some(other) => write_csv_field(writer, &other.to_string())?,
+                    }
+                }
+            }
+        }
+        writer.write_all(b"\n")?;
+        Ok(())
+    }
+}
+
+/// Writes the given value as a CSV field, quoting it if necessary.
+fn write_csv_field<W: io::Write>(writer: &mut W, field: &str) -> io::Result<()> {
+    if field.contains([',', '"', '\n', '\r']) {
+        write!(writer, "\"{}\"", field.replace('"', "\"\""))
+    } else {
+        writer.write_all(field.as_bytes())
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::TwitterUser;
+    use super::{
+        follow_all, is_valid_screen_name, report_spam_many, report_spam_params, unfollow_all,
+        BatchAction, BatchState, Connection, ExportFormat, RelationLookup, RelationSet,
+        TwitterUser, UserID, UserSearch, UserSearchState,
+    };
     use crate::common::tests::load_file;
+    use crate::entities::UrlEntity;
+
+    #[test]
+    fn follow_batch_skips() {
+        let token = crate::auth::Token::Bearer("token".to_string());
+        let accts = vec![UserID::from(1), "B".into(), 3.into()];
+        let relations = || {
+            serde_json::from_str::<Vec<RelationLookup>>(
+                r#"[
+                    {"name": "a", "screen_name": "a", "id": 1, "connections": ["following"]},
+                    {"name": "b", "screen_name": "b", "id": 2, "connections": ["following_requested"]},
+                    {"name": "c", "screen_name": "c", "id": 3, "connections": ["followed_by"]}
+                ]"#,
+            )
+            .unwrap()
+        };
+
+        let batch = follow_all(accts.clone(), &token);
+        assert_eq!(batch.action, BatchAction::Follow);
+        assert_eq!(batch.delay, std::time::Duration::from_secs(3));
+        assert_eq!(batch.limit, Some(400));
+        assert!(batch.skip_existing);
+        let mut state = BatchState::new(batch);
+        state.skip_existing(relations());
+        assert!(state.is_skipped(&1.into()));
+        assert!(state.is_skipped(&"b".into()));
+        assert!(!state.is_skipped(&3.into()));
+
+        let batch = unfollow_all(accts, &token);
+        assert_eq!(batch.limit, None);
+        let mut state = BatchState::new(batch);
+        state.skip_existing(relations());
+        assert!(!state.is_skipped(&1.into()));
+        assert!(!state.is_skipped(&"B".into()));
+        assert!(state.is_skipped(&3.into()));
+    }
+
+    #[test]
+    fn report_spam_batch() {
+        let token = crate::auth::Token::Bearer("token".to_string());
+        let batch = report_spam_many(vec![1234u64, 5678], &token);
+        assert_eq!(batch.action, BatchAction::ReportSpam);
+        assert_eq!(batch.accts.len(), 2);
+        assert_eq!(batch.delay, std::time::Duration::from_secs(10));
+        assert_eq!(batch.limit, None);
+        assert!(!batch.skip_existing);
+        assert!(batch.block);
+        assert!(!batch.with_block(false).block);
+
+        let params = report_spam_params("spammer".into(), true);
+        assert_eq!(params.get("screen_name").unwrap(), "spammer");
+        assert!(!params.contains_key("perform_block"));
+        let params = report_spam_params(1234.into(), false);
+        assert_eq!(params.get("user_id").unwrap(), "1234");
+        assert_eq!(params.get("perform_block").unwrap(), "false");
+    }
+
+    #[tokio::test]
+    async fn empty_report_spam_batch() {
+        use futures::StreamExt;
+
+        // reports don't look up relationships first, so an empty batch ends without any calls
+        let token = crate::auth::Token::Bearer("token".to_string());
+        let mut stream = report_spam_many(Vec::<u64>::new(), &token).into_stream();
+        assert!(stream.next().await.is_none());
+    }
+
+    #[test]
+    fn screen_name_cleanup() {
+        let name = |id: UserID| match id {
+            UserID::ScreenName(name) => name.into_owned(),
+            UserID::ID(id) => panic!("unexpected ID {}", id),
+        };
+        assert_eq!(name(UserID::from("@rustlang").normalized()), "rustlang");
+        assert_eq!(name(UserID::from("＠ rustlang\t").normalized()), "rustlang");
+        assert_eq!(name(UserID::from("@@rustlang").normalized()), "@rustlang");
+        assert!(matches!(UserID::from(20u64).normalized(), UserID::ID(20)));
+
+        assert!(UserID::from("rustlang").is_valid());
+        assert!(!UserID::from("@rustlang").is_valid());
+        assert!(UserID::from(20u64).is_valid());
+        assert!(is_valid_screen_name("___"));
+        assert!(!is_valid_screen_name("sixteen_chars_xx"));
+        assert!(!is_valid_screen_name("café"));
+    }
 
     #[test]
     fn roundtrip_deser() {
@@ -699,4 +1481,116 @@ mod tests {
 
         assert_eq!(json1, json2);
     }
+
+    #[test]
+    fn resume_search() {
+        let sample = load_file("sample_payloads/user_array.json");
+        let users: Vec<TwitterUser> = serde_json::from_str(&sample).unwrap();
+        let token = crate::Token::Bearer(String::new());
+
+        let state = UserSearchState {
+            query: "rustlang".into(),
+            page_num: 3,
+            page_size: 4,
+            page_offset: 2,
+        };
+        let mut search = UserSearch::from_state(state.clone(), &token);
+        assert_eq!(search.resume_state(), state);
+
+        search.load_page(users.clone());
+        let rest = search.current_results.take().unwrap().collect::<Vec<_>>();
+        assert_eq!(rest.len(), 2);
+        assert_eq!(rest[0].id, users[2].id);
+
+        let search = search.start_at_page(1);
+        assert_eq!(search.resume_state().page_offset, 0);
+    }
+
+    #[test]
+    fn relation_sets() {
+        let relations: RelationSet = serde_json::from_str::<Vec<_>>(
+            r#"[
+                {"name": "a", "screen_name": "a", "id": 1, "connections": ["following", "followed_by"]},
+                {"name": "b", "screen_name": "b", "id": 2, "connections": ["following"]},
+                {"name": "c", "screen_name": "c", "id": 3, "connections": ["followed_by", "muting"]},
+                {"name": "d", "screen_name": "d", "id": 4, "connections": ["none"]}
+            ]"#,
+        )
+        .unwrap()
+        .into();
+
+        let (following, rest) = relations.partition_by(Connection::Following);
+        assert_eq!(following.iter().map(|r| r.id).collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(rest.iter().map(|r| r.id).collect::<Vec<_>>(), [3, 4]);
+        assert_eq!(relations.with(Connection::Muting)[0].id, 3);
+        assert!(relations.ids_with(Connection::Blocking).is_empty());
+
+        assert_eq!(relations.mutuals(), [1].iter().cloned().collect());
+        assert_eq!(
+            relations.not_following_back(),
+            [2].iter().cloned().collect()
+        );
+        assert_eq!(relations.not_followed_back(), [3].iter().cloned().collect());
+    }
+
+    #[test]
+    fn expand_profile_urls() {
+        let sample = load_file("sample_payloads/user_array.json");
+        let mut users: Vec<TwitterUser> = serde_json::from_str(&sample).unwrap();
+        let mut user = users.remove(0);
+
+        assert_eq!(user.url.as_deref(), Some("https://t.co/TAXQpspyHn"));
+        assert_eq!(user.expanded_url(), Some("https://about.twitter.com/"));
+
+        user.description = Some("🦀 see https://t.co/aaa and https://t.co/bbb".into());
+        let link = |url: &str, expanded: Option<&str>, range| UrlEntity {
+            display_url: String::new(),
+            expanded_url: expanded.map(String::from),
+            range,
+            url: url.into(),
+        };
+        user.entities.description.urls = vec![
+            link("https://t.co/bbb", Some("https://example.com/b"), (30, 46)),
+            link("https://t.co/aaa", Some("https://example.com/a"), (9, 25)),
+        ];
+        assert_eq!(
+            user.description_with_expanded_urls().unwrap(),
+            "🦀 see https://example.com/a and https://example.com/b"
+        );
+
+        user.entities.description.urls[0].expanded_url = None;
+        user.entities.description.urls[1].range = (8, 24);
+        assert_eq!(
+            user.description_with_expanded_urls().unwrap(),
+            "🦀 see https://t.co/aaa and https://t.co/bbb"
+        );
+    }
+
+    #[test]
+    fn export_rows() {
+        let sample = load_file("sample_payloads/user_array.json");
+        let users: Vec<TwitterUser> = serde_json::from_str(&sample).unwrap();
+
+        let mut csv = Vec::new();
+        ExportFormat::Csv.write_header(&mut csv).unwrap();
+        for user in &users {
+            ExportFormat::Csv.write_user(&mut csv, user).unwrap();
+        }
+        let csv = String::from_utf8(csv).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next().unwrap(), ExportFormat::CSV_COLUMNS.join(","));
+        let first = lines.next().unwrap();
+        assert!(first.starts_with(&format!("{},{},", users[0].id, users[0].screen_name)));
+
+        let mut ndjson = Vec::new();
+        for user in &users {
+            ExportFormat::NdJson.write_user(&mut ndjson, user).unwrap();
+        }
+        let ndjson = String::from_utf8(ndjson).unwrap();
+        assert_eq!(ndjson.lines().count(), users.len());
+        for (line, user) in ndjson.lines().zip(&users) {
+            let parsed: TwitterUser = serde_json::from_str(line).unwrap();
+            assert_eq!(parsed.id, user.id);
+        }
+    }
 }
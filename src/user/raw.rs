@@ -144,4 +144,8 @@ pub struct RawTwitterUser {
     pub withheld_in_countries: Option<Vec<String>>,
     /// When present, indicates whether the content being withheld is a "status" or "user".
     pub withheld_scope: Option<String>,
+    /// Any fields returned by Twitter that aren't loaded into the other fields.
+    #[cfg(feature = "raw-fields")]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
@@ -0,0 +1,638 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Data structures returned by version 2 of the Twitter API.
+//!
+//! Most of egg-mode talks to version 1.1 of the Twitter API, but some newer endpoints are only
+//! available in version 2, which returns its data in a different shape. Rather than a single large
+//! object with every field filled in, a v2 object only carries the fields that were asked for in
+//! the request, and related objects (like the author of a tweet) are returned alongside it in a
+//! separate `includes` section rather than being nested inside it.
+//!
+//! egg-mode asks for every field that the types in this module can hold whenever it calls a v2
+//! endpoint, and asks for the authors of tweets to be included, so the `Option` fields on these
+//! types will generally be filled in. They're still `Option`s because Twitter may leave fields out
+//! of objects it can't fully load, and because these types may be deserialized from v2 responses
+//! loaded elsewhere.
+//!
+//! Photos, videos, and GIFs attached to tweets are returned in the `includes` section as [`Media`]
+//! objects. If your rendering code already works with the v1.1 [`entities::MediaEntity`], you can
+//! convert them with `From`, or collect a tweet's attachments with [`Includes::media_entities`].
+//!
+//! [`Media`]: struct.Media.html
+//! [`entities::MediaEntity`]: ../entities/struct.MediaEntity.html
+//! [`Includes::media_entities`]: struct.Includes.html#method.media_entities
+
+use chrono;
+use serde::{Deserialize, Serialize};
+
+use crate::common::*;
+use crate::entities;
+use crate::tweet::{Card, CardImage, CardValue};
+
+/// The `tweet.fields` requested from Twitter when egg-mode calls a v2 endpoint.
+pub(crate) const TWEET_FIELDS: &str = "attachments,author_id,conversation_id,created_at,\
+                                       edit_controls,edit_history_tweet_ids,entities,\
+                                       in_reply_to_user_id,lang,possibly_sensitive,source";
+
+/// The `user.fields` requested from Twitter when egg-mode calls a v2 endpoint.
+pub(crate) const USER_FIELDS: &str = "created_at,description,protected,verified";
+
+/// The `expansions` requested from Twitter when egg-mode calls a v2 endpoint.
+pub(crate) const EXPANSIONS: &str = "author_id,attachments.media_keys";
+
+/// The `media.fields` requested from Twitter when egg-mode calls a v2 endpoint.
+pub(crate) const MEDIA_FIELDS: &str =
+    "alt_text,duration_ms,height,preview_image_url,type,url,variants,width";
+
+/// Adds the fields and expansions that egg-mode uses for v2 calls to the given `ParamList`.
+pub(crate) fn add_fields(params: ParamList) -> ParamList {
+    params
+        .add_param("tweet.fields", TWEET_FIELDS)
+        .add_param("user.fields", USER_FIELDS)
+        .add_param("media.fields", MEDIA_FIELDS)
+        .add_param("expansions", EXPANSIONS)
+}
+
+/// A tweet, as returned by version 2 of the Twitter API.
+///
+/// This is a much smaller structure than [`tweet::Tweet`], reflecting the smaller default payload
+/// of the v2 API. Only `id` and `text` are always present.
+///
+/// [`tweet::Tweet`]: ../tweet/struct.Tweet.html
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Tweet {
+    /// Numeric ID for this tweet.
+    #[serde(with = "serde_via_string")]
+    pub id: u64,
+    /// The text of the tweet.
+    pub text: String,
+    /// The numeric ID of the user who posted this tweet. The user's information will be in the
+    /// `users` field of the accompanying `Includes`, if Twitter was able to load it.
+    #[serde(default, with = "serde_opt_via_string")]
+    pub author_id: Option<u64>,
+    /// The numeric ID of the tweet that began the conversation this tweet is part of.
+    #[serde(default, with = "serde_opt_via_string")]
+    pub conversation_id: Option<u64>,
+    /// UTC timestamp from when the tweet was posted.
+    #[serde(default)]
+    pub created_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// If this tweet is a reply, the numeric ID of the user it is replying to.
+    #[serde(default, with = "serde_opt_via_string")]
+    pub in_reply_to_user_id: Option<u64>,
+    /// The language Twitter detected this tweet to be written in, as a [BCP 47][] language tag.
+    ///
+    /// [BCP 47]: https://tools.ietf.org/html/bcp47
+    #[serde(default)]
+    pub lang: Option<String>,
+    /// Indicates whether this tweet contains a link that may point to sensitive content.
+    #[serde(default)]
+    pub possibly_sensitive: Option<bool>,
+    /// The name of the app used to post this tweet.
+    #[serde(default)]
+    pub source: Option<String>,
+    /// The media attached to this tweet, if any.
+    #[serde(default)]
+    pub attachments: Option<Attachments>,
+    /// The links in this tweet, along with the previews Twitter loaded for them.
+    #[serde(default)]
+    pub entities: Option<TweetEntities>,
+    /// The IDs of every version of this tweet, oldest first. For a tweet that hasn't been edited,
+    /// this only contains the tweet's own ID.
+    #[serde(default, with = "serde_vec_via_string")]
+    pub edit_history_tweet_ids: Vec<u64>,
+    /// Whether this tweet can still be edited, and until when.
+    #[serde(default)]
+    pub edit_controls: Option<EditControls>,
+}
+
+impl Tweet {
+    /// Returns the link preview for this tweet, if Twitter loaded one for any of its links.
+    ///
+    /// Version 2 of the API doesn't return cards. Instead, the title, description, and images of
+    /// a linked page are attached to the link in `entities`, and this method gathers the first
+    /// of those into the same [`Card`] that version 1.1 tweets carry in `Tweet::card`, so the
+    /// same rendering code can be used for both.
+    ///
+    /// [`Card`]: ../tweet/struct.Card.html
+    pub fn card(&self) -> Option<Card> {
+        self.entities
+            .as_ref()?
+            .urls
+            .iter()
+            .find_map(UrlEntity::card)
+    }
+}
+
+/// Information about whether a tweet can still be edited.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct EditControls {
+    /// The number of edits that can still be made to the tweet.
+    pub edits_remaining: i32,
+    /// Indicates whether the tweet could be edited when it was posted.
+    pub is_edit_eligible: bool,
+    /// The time after which the tweet can no longer be edited.
+    pub editable_until: chrono::DateTime<chrono::Utc>,
+}
+
+/// References to the objects attached to a tweet.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Attachments {
+    /// The keys of the media attached to the tweet. The media themselves will be in the `media`
+    /// field of the accompanying `Includes`, if Twitter was able to load them.
+    #[serde(default)]
+    pub media_keys: Vec<String>,
+}
+
+/// The entities found in a tweet's text, as returned by version 2 of the Twitter API.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct TweetEntities {
+    /// The links in the tweet.
+    #[serde(default)]
+    pub urls: Vec<UrlEntity>,
+}
+
+/// A link in a tweet, as returned by version 2 of the Twitter API.
+///
+/// Besides the link itself, this carries the preview Twitter loaded for the linked page, if any.
+/// `Tweet::card` turns that preview into a [`Card`].
+///
+/// [`Card`]: ../tweet/struct.Card.html
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct UrlEntity {
+    /// The character offset in the tweet's text where the link starts.
+    pub start: usize,
+    /// The character offset in the tweet's text just past the end of the link.
+    pub end: usize,
+    /// The t.co link as it appears in the tweet's text.
+    pub url: String,
+    /// The link as the author wrote it.
+    #[serde(default)]
+    pub expanded_url: Option<String>,
+    /// A shortened form of the link, for display.
+    #[serde(default)]
+    pub display_url: Option<String>,
+    /// The address the link ends up at after following any redirects, if Twitter loaded it.
+    #[serde(default)]
+    pub unwound_url: Option<String>,
+    /// The title of the linked page.
+    #[serde(default)]
+    pub title: Option<String>,
+    /// The description of the linked page.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Preview images of the linked page, in different sizes.
+    #[serde(default)]
+    pub images: Vec<UrlImage>,
+}
+
+impl UrlEntity {
+    /// Returns the preview of the linked page as a `Card`, or `None` if Twitter didn't load a
+    /// title, description, or image for it.
+    fn card(&self) -> Option<Card> {
+        let mut values = std::collections::HashMap::new();
+        if let Some(title) = &self.title {
+            values.insert("title".to_string(), CardValue::String(title.clone()));
+        }
+        if let Some(description) = &self.description {
+            let value = CardValue::String(description.clone());
+            values.insert("description".to_string(), value);
+        }
+        if let Some(image) = self.images.iter().max_by_key(|img| img.width * img.height) {
+            let image = CardValue::Image(CardImage {
+                url: image.url.clone(),
+                width: image.width,
+                height: image.height,
+                alt: None,
+            });
+            values.insert("summary_photo_image_large".to_string(), image);
+        }
+        if values.is_empty() {
+            return None;
+        }
+        if let Some(domain) = self
+            .display_url
+            .as_ref()
+            .and_then(|url| url.split('/').next())
+        {
+            values.insert("domain".to_string(), CardValue::String(domain.to_string()));
+        }
+
+        let name = if self.images.is_empty() {
+            "summary"
+        } else {
+            "summary_large_image"
+        };
+        Some(Card {
+            name: name.to_string(),
+            url: self.url.clone(),
+            card_type_url: None,
+            binding_values: values,
+        })
+    }
+}
+
+/// A preview image of a linked page, as returned by version 2 of the Twitter API.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct UrlImage {
+    /// The URL of the image.
+    pub url: String,
+    /// The width of the image, in pixels.
+    pub width: i32,
+    /// The height of the image, in pixels.
+    pub height: i32,
+}
+
+/// A photo, video, or GIF attached to a tweet, as returned by version 2 of the Twitter API.
+///
+/// Unlike the v1.1 [`entities::MediaEntity`], this doesn't carry the t.co link that was added to
+/// the tweet's text for it. It can still be converted into a `MediaEntity` with `From`; see that
+/// impl for how the missing fields are filled in.
+///
+/// [`entities::MediaEntity`]: ../entities/struct.MediaEntity.html
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Media {
+    /// The key identifying this media, which is referenced from a tweet's `attachments`.
+    pub media_key: String,
+    /// The type of media being represented.
+    #[serde(rename = "type")]
+    pub media_type: entities::MediaType,
+    /// For photos, a URL pointing directly to the image file.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// For videos and GIFs, a URL pointing to a still image from the video.
+    #[serde(default)]
+    pub preview_image_url: Option<String>,
+    /// The width of the original media file, in pixels.
+    #[serde(default)]
+    pub width: Option<i32>,
+    /// The height of the original media file, in pixels.
+    #[serde(default)]
+    pub height: Option<i32>,
+    /// For videos, the duration of the video, in milliseconds.
+    #[serde(default)]
+    pub duration_ms: Option<i32>,
+    /// Alt text for the media, if the author added any.
+    #[serde(default)]
+    pub alt_text: Option<String>,
+    /// For videos and GIFs, the encodings available for the video.
+    #[serde(default)]
+    pub variants: Vec<MediaVariant>,
+}
+
+impl Media {
+    /// Returns the numeric ID of this media, as used by version 1.1 of the Twitter API.
+    ///
+    /// Media keys are made up of a type prefix and the numeric ID, like `3_1264976393473728512`.
+    /// Returns `None` if the key isn't in that form.
+    pub fn id(&self) -> Option<u64> {
+        self.media_key.rsplit('_').next()?.parse().ok()
+    }
+}
+
+/// One encoding of a video or GIF, as returned by version 2 of the Twitter API.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MediaVariant {
+    /// The bitrate of the video, if known. This is not given for streaming playlists.
+    #[serde(default)]
+    pub bit_rate: Option<i32>,
+    /// The file format of the video variant.
+    #[serde(with = "serde_via_string")]
+    pub content_type: mime::Mime,
+    /// The URL for the video variant.
+    pub url: String,
+}
+
+/// Converts a v2 media object into the v1.1 entity shape.
+///
+/// Version 2 of the API leaves out several fields that `MediaEntity` requires, so they're filled
+/// in as follows:
+///
+/// * `id` is parsed from the media key, or 0 if that fails.
+/// * `media_url_https` is the photo's `url`, or the `preview_image_url` for videos and GIFs, and
+///   `media_url` is the same URL using HTTP.
+/// * `sizes` is computed from the original dimensions with [`MediaSizes::from_dimensions`].
+/// * `video_info` takes its aspect ratio from the original dimensions.
+/// * `display_url`, `expanded_url`, and `url` are empty, `range` is `(0, 0)`, and
+///   `source_status_id` is `None`, since v2 media objects don't carry the link that was added to
+///   the tweet's text.
+/// * `additional_media_info` is `None`.
+///
+/// [`MediaSizes::from_dimensions`]: ../entities/struct.MediaSizes.html#method.from_dimensions
+impl From<&Media> for entities::MediaEntity {
+    fn from(media: &Media) -> entities::MediaEntity {
+        let width = media.width.unwrap_or(0);
+        let height = media.height.unwrap_or(0);
+        let media_url_https = media
+            .url
+            .as_ref()
+            .or(media.preview_image_url.as_ref())
+            .cloned()
+            .unwrap_or_default();
+        let media_url = match media_url_https.strip_prefix("https://") {
+            Some(rest) => format!("http://{}", rest),
+            None => media_url_https.clone(),
+        };
+        let video_info = match media.media_type {
+            entities::MediaType::Photo => None,
+            entities::MediaType::Video | entities::MediaType::Gif => Some(entities::VideoInfo {
+                aspect_ratio: aspect_ratio(width, height),
+                duration_millis: media.duration_ms,
+                variants: media
+                    .variants
+                    .iter()
+                    .map(|variant| entities::VideoVariant {
+                        bitrate: variant.bit_rate,
+                        content_type: variant.content_type.clone(),
+                        url: variant.url.clone(),
+                    })
+                    .collect(),
+            }),
+        };
+
+        entities::MediaEntity {
+            display_url: String::new(),
+            expanded_url: String::new(),
+            id: media.id().unwrap_or(0),
+            range: (0, 0),
+            media_url,
+            media_url_https,
+            sizes: entities::MediaSizes::from_dimensions(width, height),
+            source_status_id: None,
+            media_type: media.media_type,
+            url: String::new(),
+            video_info,
+            ext_alt_text: media.alt_text.clone(),
+            additional_media_info: None,
+        }
+    }
+}
+
+impl From<Media> for entities::MediaEntity {
+    fn from(media: Media) -> entities::MediaEntity {
+        entities::MediaEntity::from(&media)
+    }
+}
+
+/// Reduces the given dimensions to an aspect ratio, like `(16, 9)`.
+fn aspect_ratio(width: i32, height: i32) -> (i32, i32) {
+    let (mut a, mut b) = (width.abs(), height.abs());
+    while b != 0 {
+        let rem = a % b;
+        a = b;
+        b = rem;
+    }
+    if a == 0 {
+        (width, height)
+    } else {
+        (width / a, height / a)
+    }
+}
+
+/// A user, as returned by version 2 of the Twitter API.
+///
+/// This is a much smaller structure than [`user::TwitterUser`], reflecting the smaller default
+/// payload of the v2 API. Only `id`, `name`, and `username` are always present.
+///
+/// [`user::TwitterUser`]: ../user/struct.TwitterUser.html
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct User {
+    /// Numeric ID for this user.
+    #[serde(with = "serde_via_string")]
+    pub id: u64,
+    /// The user-entered display name.
+    pub name: String,
+    /// The screen name or handle identifying this user.
+    pub username: String,
+    /// The UTC timestamp for when this user account was created on Twitter.
+    #[serde(default)]
+    pub created_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// The user-defined string describing their account.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Indicates whether the user is a protected account.
+    #[serde(default)]
+    pub protected: Option<bool>,
+    /// Indicates whether this user is a verified account.
+    #[serde(default)]
+    pub verified: Option<bool>,
+}
+
+/// Objects that are referenced by the main data in a v2 response.
+///
+/// When a v2 call asks for "expansions", the related objects aren't nested inside the objects that
+/// reference them. Instead, they're collected into this struct, and can be matched up with the
+/// main data by ID.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Includes {
+    /// Tweets referenced by the main data, such as quoted tweets or tweets being replied to.
+    #[serde(default)]
+    pub tweets: Vec<Tweet>,
+    /// Users referenced by the main data, such as the authors of tweets.
+    #[serde(default)]
+    pub users: Vec<User>,
+    /// Media attached to the tweets in the main data.
+    #[serde(default)]
+    pub media: Vec<Media>,
+}
+
+impl Includes {
+    /// Returns the user with the given ID, if it was included.
+    pub fn user(&self, id: u64) -> Option<&User> {
+        self.users.iter().find(|user| user.id == id)
+    }
+
+    /// Returns the tweet with the given ID, if it was included.
+    pub fn tweet(&self, id: u64) -> Option<&Tweet> {
+        self.tweets.iter().find(|tweet| tweet.id == id)
+    }
+
+    /// Returns the media with the given key, if it was included.
+    pub fn media(&self, media_key: &str) -> Option<&Media> {
+        self.media.iter().find(|media| media.media_key == media_key)
+    }
+
+    /// Returns the media attached to the given tweet as v1.1 `MediaEntity`s, in the order they
+    /// were attached.
+    ///
+    /// Media that weren't included are skipped. See the `From<&Media>` impl on `MediaEntity` for
+    /// how the fields v2 doesn't return are filled in.
+    pub fn media_entities(&self, tweet: &Tweet) -> Vec<entities::MediaEntity> {
+        tweet
+            .attachments
+            .iter()
+            .flat_map(|attachments| &attachments.media_keys)
+            .filter_map(|key| self.media(key))
+            .map(entities::MediaEntity::from)
+            .collect()
+    }
+}
+
+/// A single page of tweets loaded from a paginated v2 endpoint.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct TweetPage {
+    /// The tweets on this page. This is empty if there were no tweets to load.
+    #[serde(rename = "data", default)]
+    pub tweets: Vec<Tweet>,
+    /// The objects referenced by the tweets on this page, like their authors.
+    #[serde(default)]
+    pub includes: Includes,
+    /// Information about this page, including the token needed to load the next one.
+    #[serde(default)]
+    pub meta: PageMeta,
+}
+
+/// Information about a page of results loaded from a paginated v2 endpoint.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct PageMeta {
+    /// The number of results on this page.
+    #[serde(default)]
+    pub result_count: u32,
+    /// The ID of the newest tweet on this page, if any.
+    #[serde(default, with = "serde_opt_via_string")]
+    pub newest_id: Option<u64>,
+    /// The ID of the oldest tweet on this page, if any.
+    #[serde(default, with = "serde_opt_via_string")]
+    pub oldest_id: Option<u64>,
+    /// The token used to load the next page of results, if there is one.
+    #[serde(default)]
+    pub next_token: Option<String>,
+    /// The token used to load the previous page of results, if there is one.
+    #[serde(default)]
+    pub previous_token: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TweetPage;
+    use crate::common::tests::load_file;
+    use crate::entities::{MediaType, ResizeMode};
+    use crate::tweet::CardValue;
+
+    #[test]
+    fn parse_tweet_page() {
+        let content = load_file("sample_payloads/v2-user-tweets.json");
+        let page = ::serde_json::from_str::<TweetPage>(&content).unwrap();
+
+        assert_eq!(page.tweets.len(), 2);
+        assert_eq!(page.meta.result_count, 2);
+        assert_eq!(page.meta.newest_id, Some(1338971066773905408));
+        assert_eq!(
+            page.meta.next_token.as_deref(),
+            Some("7140dibdnow9c7btw3w29grvxfcgvpb9n9coehpk7xz5i")
+        );
+
+        let author = page.tweets[0].author_id.unwrap();
+        assert_eq!(page.includes.user(author).unwrap().username, "TwitterDev");
+
+        let empty = ::serde_json::from_str::<TweetPage>(r#"{"meta":{"result_count":0}}"#).unwrap();
+        assert!(empty.tweets.is_empty());
+        assert!(empty.meta.next_token.is_none());
+    }
+
+    #[test]
+    fn media_entities() {
+        let content = r#"{
+            "data": [{
+                "id": "1263145271946551300",
+                "text": "Testing https://t.co/Z4fVcSXBr6",
+                "attachments": {"media_keys": ["13_1263145212760805376", "3_1263145212760805377"]}
+            }],
+            "includes": {"media": [
+                {
+                    "media_key": "13_1263145212760805376",
+                    "type": "video",
+                    "preview_image_url": "https://pbs.twimg.com/media/EYeX7akWsAIP1_1.jpg",
+                    "width": 1920,
+                    "height": 1080,
+                    "duration_ms": 46947,
+                    "variants": [
+                        {"bit_rate": 632000, "content_type": "video/mp4", "url": "https://video.twimg.com/a.mp4"},
+                        {"content_type": "application/x-mpegURL", "url": "https://video.twimg.com/a.m3u8"}
+                    ]
+                },
+                {
+                    "media_key": "3_1263145212760805377",
+                    "type": "photo",
+                    "url": "https://pbs.twimg.com/media/EYeX7akWsAIP1_2.jpg",
+                    "width": 4096,
+                    "height": 1024,
+                    "alt_text": "A wide photo"
+                }
+            ]}
+        }"#;
+        let page = ::serde_json::from_str::<TweetPage>(content).unwrap();
+        let entities = page.includes.media_entities(&page.tweets[0]);
+        assert_eq!(entities.len(), 2);
+
+        let video = &entities[0];
+        assert_eq!(video.id, 1263145212760805376);
+        assert_eq!(video.media_type, MediaType::Video);
+        assert_eq!(
+            video.media_url,
+            "http://pbs.twimg.com/media/EYeX7akWsAIP1_1.jpg"
+        );
+        let info = video.video_info.as_ref().unwrap();
+        assert_eq!(info.aspect_ratio, (16, 9));
+        assert_eq!(info.duration_millis, Some(46947));
+        assert_eq!(
+            video.best_video(None).unwrap().url,
+            "https://video.twimg.com/a.mp4"
+        );
+
+        let photo = &entities[1];
+        assert_eq!(photo.media_type, MediaType::Photo);
+        assert!(photo.video_info.is_none());
+        assert_eq!(photo.ext_alt_text.as_deref(), Some("A wide photo"));
+        assert_eq!((photo.sizes.large.w, photo.sizes.large.h), (2048, 512));
+        assert_eq!((photo.sizes.small.w, photo.sizes.small.h), (680, 170));
+        assert_eq!(photo.sizes.thumb.resize, ResizeMode::Crop);
+    }
+
+    #[test]
+    fn url_cards() {
+        let content = r#"{
+            "data": [{
+                "id": "1445078208190291968",
+                "text": "Read more https://t.co/Ap9VLmkSNw",
+                "entities": {"urls": [
+                    {
+                        "start": 10,
+                        "end": 33,
+                        "url": "https://t.co/Ap9VLmkSNw",
+                        "expanded_url": "https://example.com/posts/1",
+                        "display_url": "example.com/posts/1",
+                        "title": "A post",
+                        "description": "What the post is about",
+                        "images": [
+                            {"url": "https://pbs.twimg.com/news_img/1?format=jpg&name=orig", "width": 1200, "height": 630},
+                            {"url": "https://pbs.twimg.com/news_img/1?format=jpg&name=150x150", "width": 150, "height": 150}
+                        ]
+                    }
+                ]}
+            }, {
+                "id": "1445078208190291969",
+                "text": "No preview https://t.co/Bq9VLmkSNw",
+                "entities": {"urls": [
+                    {"start": 11, "end": 34, "url": "https://t.co/Bq9VLmkSNw"}
+                ]}
+            }]
+        }"#;
+        let page = ::serde_json::from_str::<TweetPage>(content).unwrap();
+
+        let card = page.tweets[0].card().unwrap();
+        assert_eq!(card.name, "summary_large_image");
+        assert_eq!(card.url, "https://t.co/Ap9VLmkSNw");
+        assert_eq!(card.title(), Some("A post"));
+        assert_eq!(card.description(), Some("What the post is about"));
+        assert_eq!(card.domain(), Some("example.com"));
+        assert_eq!(card.image().unwrap().width, 1200);
+        assert!(matches!(card.binding_values["title"], CardValue::String(_)));
+
+        assert!(page.tweets[1].card().is_none());
+        let plain = load_file("sample_payloads/v2-user-tweets.json");
+        let plain = ::serde_json::from_str::<TweetPage>(&plain).unwrap();
+        assert!(plain.tweets[0].card().is_none());
+    }
+}